@@ -1,13 +1,44 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::error::RfqError;
+use crate::market::types::{DEFAULT_MAX_SNAPSHOT_AGE_MS, RfqAmount, RfqRequest};
+use crate::planner::types::AllocationMode;
+
 #[derive(Clone, Debug)]
 pub struct AppConfig {
     pub stonfi_http_endpoint: String,
+
+    /// Omniston RFQ feed for the pair's slippage pulse; `None` (no
+    /// `OMNISTON_WS_URL`) runs the STON.fi poller alone.
+    pub omniston: Option<OmnistonFeedConfig>,
+
     pub max_slippage_bps: f64,
     pub min_warm_up: u64,
+
+    /// Minimum number of samples the rolling pulses (trend, slippage) need
+    /// before their output is valid, in addition to `min_warm_up`.
+    pub min_warm_up_samples: usize,
     pub window_size: usize,
 
+    /// Minimum cumulative drop (bps) over the trend window before a
+    /// downtrend is reported. Smaller moves are treated as noise.
+    pub trend_min_drop_bps: f64,
+
+    /// Minimum average rate of a drop (bps per minute) before a
+    /// downtrend is reported. Filters slow drifts.
+    pub trend_min_slope_bps_per_min: f64,
+
     /// Database connection string.
     pub database_url: String,
 
+    /// Optional read-replica connection string for candidate loading.
+    /// When unset, all reads go to `database_url`.
+    pub database_read_url: Option<String>,
+
+    /// Pool sizing and timeouts, applied to the primary and the replica.
+    pub db: DbConfig,
+
     // =========================
     // Scheduler configuration
     // =========================
@@ -47,6 +78,57 @@ pub struct AppConfig {
     /// - reduce blast radius on failures
     pub scheduler_max_users_per_batch: usize,
 
+    /// Rotate the starting index of the selected intents each tick
+    /// before planning.
+    ///
+    /// The planner is first-fit, so whoever lands first can take the
+    /// whole tick budget. Rotating spreads that advantage over time.
+    pub scheduler_rotate_plan_start: bool,
+
+    /// Shuffle selected intents (seeded from the tick time) before planning
+    /// instead of rotating them. Enabled with `SCHEDULER_SHUFFLE_PLAN_ORDER=1`.
+    pub scheduler_shuffle_plan_order: bool,
+
+    /// Plan every tick but never reserve, execute or write fairness state;
+    /// plans are only logged. Enabled with `SCHEDULER_DRY_RUN=1`.
+    pub scheduler_dry_run: bool,
+
+    /// Record why each candidate was or was not served in the latest tick,
+    /// served at `GET /scheduler/trace`. Enabled with `SCHEDULER_TRACE=1`.
+    pub scheduler_trace: bool,
+
+    /// How the planner divides a tick's budget across selected users.
+    /// `PLANNER_ALLOCATION_MODE=proportional` shares it by demand instead of
+    /// first-fit.
+    pub planner_allocation_mode: AllocationMode,
+
+    /// Optional cap on the total bid selected per scheduler tick.
+    /// Throttles a pair independently of market depth.
+    /// Set with `SCHEDULER_MAX_TOTAL_BID_PER_TICK`.
+    pub scheduler_max_total_bid_per_tick: Option<u128>,
+
+    /// Sessions unserved for longer than this (ms) receive a DRR credit
+    /// boost proportional to their wait, so a small quantum cannot starve
+    /// behind richer sessions. 0 disables the watchdog.
+    pub starvation_ms: u64,
+
+    /// Optional absolute cap on a session's DRR deficit, bounding how many
+    /// chunks a long-starved session can claim in a row once eligible.
+    /// Set with `SCHEDULER_MAX_DEFICIT`.
+    pub scheduler_max_deficit: Option<u128>,
+
+    /// Per-pair executor queue depth at which the scheduler stops reserving
+    /// new batches until the worker catches up.
+    pub max_inflight_batches_per_pair: usize,
+
+    /// How often (ms) sessions past their `expires_at_ms` are deactivated.
+    pub session_expiry_sweep_ms: u64,
+
+    /// Per-pair scheduler loops start at a random phase and each tick moves
+    /// by up to this many ms, so pairs do not hit the DB in lockstep.
+    /// 0 ticks every pair on a fixed, aligned cadence. Set with `TICK_JITTER_MS`.
+    pub tick_jitter_ms: u64,
+
     // =========================
     // Execution configuration
     // =========================
@@ -65,6 +147,98 @@ pub struct AppConfig {
     /// - protect the chain and executor
     /// - allow market conditions to change
     pub default_failure_cooldown_ms: u64,
+
+    /// How often (in chunks) the executor re-reads the market snapshot
+    /// for Gate B while working through a batch.
+    ///
+    /// 1 = before every chunk. Larger values trade freshness for fewer
+    /// store reads on long batches.
+    pub exec_market_refresh_every_chunks: usize,
+
+    /// Maximum age (ms) of a market snapshot accepted by Gate A and Gate B.
+    ///
+    /// If the market feed stalls, the last snapshot stays in the view
+    /// store; once it is older than this, both gates fail closed.
+    pub max_snapshot_age_ms: u64,
+
+    /// Number of past market snapshots kept per pair in the view store.
+    ///
+    /// 0 disables the history; only the latest snapshot is kept.
+    pub market_history_len: usize,
+
+    /// Maximum number of chunks of one user allocation executed in parallel.
+    ///
+    /// 1 keeps strict sequential execution. Only raise this if the chain
+    /// executor supports parallel submission.
+    pub exec_max_concurrent_chunks: usize,
+
+    /// Maximum number of users of one batch executed in parallel.
+    ///
+    /// Each user's chunks remain sequential. 1 executes users one at a time.
+    pub exec_max_parallel_users: usize,
+
+    /// Safe mode for canary deployments.
+    ///
+    /// Scheduling, reservation, Gate B and commit all run as usual, but
+    /// no swap is ever submitted; chunks are committed as SIMULATED.
+    /// Enabled with `KASKADE_SAFE_MODE=1`.
+    pub safe_mode: bool,
+
+    /// Chain executor that submits swaps; see `build_executor`.
+    /// Selected with `EXECUTOR_KIND=dummy|ton|emc` (default `dummy`).
+    pub executor: ExecutorKind,
+
+    /// Attempts per chunk for transient swap failures (1 = no retry),
+    /// and the initial backoff between attempts (doubles each retry).
+    pub exec_retry_max_attempts: u32,
+    pub exec_retry_base_backoff_ms: u64,
+
+    /// Per-pair circuit breaker: once at least `exec_breaker_min_samples`
+    /// batches in the last `exec_breaker_window_ms` have a failure ratio of
+    /// `exec_breaker_failure_ratio` or more, or after
+    /// `exec_breaker_consecutive_failures` batches in a row failed on every
+    /// chunk, the pair's batches are skipped for `default_failure_cooldown_ms`:
+    /// they are committed as `Skipped { "CircuitOpen" }` without any swap, so
+    /// their bid returns to the sessions instead of staying RESERVED.
+    pub exec_breaker_window_ms: u64,
+    pub exec_breaker_min_samples: usize,
+    pub exec_breaker_failure_ratio: f64,
+    pub exec_breaker_consecutive_failures: u32,
+
+    /// Failed enqueue attempts (worker queue closed) after which a batch
+    /// is aborted and dead-lettered instead of retried.
+    pub exec_max_enqueue_attempts: u32,
+
+    /// `commit_batch` attempts per batch before the worker dead-letters it
+    /// for manual reconciliation.
+    pub exec_max_commit_attempts: u32,
+
+    /// Upper bound (ms) on a single swap call. Past it the worker stops
+    /// waiting, asks the executor whether the swap landed, and otherwise
+    /// fails the chunk with `Timeout`.
+    pub swap_timeout_ms: u64,
+
+    /// Per-pair overrides of `swap_timeout_ms`.
+    /// Set with `SWAP_TIMEOUT_MS_BY_PAIR="TON/STON=5000,TON/USDT=8000"`.
+    pub swap_timeout_ms_by_pair: HashMap<String, u64>,
+
+    /// Batches RESERVED for longer than this (ms) are reconciled at runtime
+    /// instead of waiting for a restart; 0 disables. Batches a worker is
+    /// executing are left alone. Set with `STALE_BATCH_RECOVERY_MS`.
+    pub stale_batch_recovery_ms: u64,
+
+    /// Cached sessions neither served nor reloaded for this long (ms) are
+    /// dropped from the candidate ring; 0 disables.
+    /// Set with `SESSION_CACHE_MAX_IDLE_MS`.
+    pub session_cache_max_idle_ms: u64,
+
+    /// Listen address of the `/metrics` endpoint.
+    /// Set with `METRICS_ADDR`.
+    pub metrics_addr: String,
+
+    /// Upper bound (ms) main waits on shutdown for the scheduler to stop,
+    /// the router to drain worker queues and market pollers to exit.
+    pub shutdown_timeout_ms: u64,
 }
 
 impl AppConfig {
@@ -72,25 +246,443 @@ impl AppConfig {
         let database_url =
             std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite://kaskade_dev.db".to_string());
 
+        let database_read_url = std::env::var("DATABASE_READ_URL").ok();
+
+        let safe_mode = matches!(
+            std::env::var("KASKADE_SAFE_MODE").as_deref(),
+            Ok("1") | Ok("true")
+        );
+
+        let swap_timeout_ms_by_pair = std::env::var("SWAP_TIMEOUT_MS_BY_PAIR")
+            .map(|v| parse_pair_overrides(&v))
+            .unwrap_or_default();
+
+        let scheduler_shuffle_plan_order = matches!(
+            std::env::var("SCHEDULER_SHUFFLE_PLAN_ORDER").as_deref(),
+            Ok("1") | Ok("true")
+        );
+
+        let scheduler_dry_run = matches!(
+            std::env::var("SCHEDULER_DRY_RUN").as_deref(),
+            Ok("1") | Ok("true")
+        );
+
+        let scheduler_trace = matches!(
+            std::env::var("SCHEDULER_TRACE").as_deref(),
+            Ok("1") | Ok("true")
+        );
+
+        let planner_allocation_mode = match std::env::var("PLANNER_ALLOCATION_MODE").as_deref() {
+            Ok("proportional") => AllocationMode::Proportional,
+            _ => AllocationMode::FirstFit,
+        };
+
+        let scheduler_max_total_bid_per_tick = std::env::var("SCHEDULER_MAX_TOTAL_BID_PER_TICK")
+            .ok()
+            .and_then(|v| v.parse().ok());
+
+        let scheduler_max_deficit = std::env::var("SCHEDULER_MAX_DEFICIT")
+            .ok()
+            .and_then(|v| v.parse().ok());
+
+        let executor = match std::env::var("EXECUTOR_KIND").as_deref() {
+            Ok("ton") => ExecutorKind::Ton {
+                rpc_url: std::env::var("TON_RPC_URL").unwrap_or_default(),
+                wallet_address: std::env::var("TON_WALLET_ADDRESS").unwrap_or_default(),
+                api_key: std::env::var("TON_API_KEY").ok(),
+                connect_timeout_ms: env_u64("TON_CONNECT_TIMEOUT_MS", 5_000),
+                request_timeout_ms: env_u64("TON_REQUEST_TIMEOUT_MS", 5_000),
+                confirm_timeout_ms: env_u64("TON_CONFIRM_TIMEOUT_MS", 15_000),
+            },
+            Ok("emc") => ExecutorKind::Emc {
+                rpc_url: std::env::var("EMC_RPC_URL").unwrap_or_default(),
+                account: std::env::var("EMC_ACCOUNT").unwrap_or_default(),
+            },
+            _ => ExecutorKind::Dummy,
+        };
+
+        let exec_max_parallel_users = std::env::var("EXEC_MAX_PARALLEL_USERS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1);
+
+        let metrics_addr =
+            std::env::var("METRICS_ADDR").unwrap_or_else(|_| "127.0.0.1:9100".to_string());
+
         let stonfi_http_endpoint = std::env::var("STONFI_HTTP_URL")
             .unwrap_or_else(|_| "https://api.ston.fi/v1".to_string());
 
+        let omniston = OmnistonFeedConfig::from_env();
+
         Self {
             database_url,
+            database_read_url,
+            db: DbConfig::from_env(),
             stonfi_http_endpoint,
+            omniston,
             // Scheduler defaults:
             // - scan widely for fairness (DRR)
             // - schedule conservatively per tick
             scheduler_candidate_min: 200,
             scheduler_max_attempts: 5_000,
             scheduler_max_users_per_batch: 64,
+            scheduler_rotate_plan_start: true,
+            scheduler_shuffle_plan_order,
+            scheduler_dry_run,
+            scheduler_trace,
+            planner_allocation_mode,
+            scheduler_max_total_bid_per_tick,
+            scheduler_max_deficit,
+            starvation_ms: 60_000,
+            max_inflight_batches_per_pair: 4,
+            session_expiry_sweep_ms: 60_000,
+            tick_jitter_ms: env_u64("TICK_JITTER_MS", 25),
 
             // Execution defaults:
             exec_queue_capacity: 256,
             default_failure_cooldown_ms: 10_000,
+            exec_market_refresh_every_chunks: 1,
+            max_snapshot_age_ms: DEFAULT_MAX_SNAPSHOT_AGE_MS,
+            market_history_len: 0,
+            exec_max_concurrent_chunks: 1,
+            exec_max_parallel_users,
+            safe_mode,
+            executor,
+            exec_retry_max_attempts: 3,
+            exec_retry_base_backoff_ms: 200,
+            exec_breaker_window_ms: 60_000,
+            exec_breaker_min_samples: 5,
+            exec_breaker_failure_ratio: 0.5,
+            exec_breaker_consecutive_failures: 5,
+            exec_max_enqueue_attempts: 3,
+            exec_max_commit_attempts: 5,
+            swap_timeout_ms: 30_000,
+            swap_timeout_ms_by_pair,
+            stale_batch_recovery_ms: env_u64("STALE_BATCH_RECOVERY_MS", 0),
+            session_cache_max_idle_ms: env_u64("SESSION_CACHE_MAX_IDLE_MS", 0),
+            metrics_addr,
+            shutdown_timeout_ms: 30_000,
             max_slippage_bps: 75.0,
             min_warm_up: 20_000,
+            min_warm_up_samples: 2,
             window_size: 10,
+            trend_min_drop_bps: 10.0,
+            trend_min_slope_bps_per_min: 2.0,
+        }
+    }
+}
+
+/// Which `SwapExecutor` the router drives. Missing settings are left empty
+/// here and rejected by `build_executor` at startup.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExecutorKind {
+    /// Acknowledges every swap without touching a chain (development only).
+    Dummy,
+
+    /// TON HTTP JSON-RPC endpoint (see `execution::ton`). Set with
+    /// `TON_RPC_URL`, `TON_WALLET_ADDRESS`, the optional `TON_API_KEY` and
+    /// `TON_{CONNECT,REQUEST,CONFIRM}_TIMEOUT_MS`.
+    Ton {
+        rpc_url: String,
+        wallet_address: String,
+        api_key: Option<String>,
+        connect_timeout_ms: u64,
+        request_timeout_ms: u64,
+        /// Bound on waiting for a submitted swap to confirm. Together with
+        /// two requests it must fit within every swap timeout, or the worker
+        /// abandons swaps the executor is still confirming.
+        confirm_timeout_ms: u64,
+    },
+
+    /// EMC node. Set with `EMC_RPC_URL` and `EMC_ACCOUNT`. Swap submission
+    /// is not implemented yet, so `build_executor` rejects it.
+    Emc { rpc_url: String, account: String },
+}
+
+impl ExecutorKind {
+    /// Short name for logs; never includes settings such as API keys.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExecutorKind::Dummy => "dummy",
+            ExecutorKind::Ton { .. } => "ton",
+            ExecutorKind::Emc { .. } => "emc",
+        }
+    }
+}
+
+/// Omniston quote subscription. Set with `OMNISTON_WS_URL`,
+/// `OMNISTON_BID_ASSET` and `OMNISTON_ASK_ASSET`. Invalid assets or amounts
+/// are rejected at startup (see [`OmnistonFeedConfig::rfq`]).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OmnistonFeedConfig {
+    pub ws_url: String,
+    pub bid_asset: String,
+    pub ask_asset: String,
+
+    /// Quoted amount for pairs without an override. `OMNISTON_BID_UNITS`
+    /// (default one TON), or an exact ask amount with `OMNISTON_ASK_UNITS`,
+    /// which takes precedence.
+    pub amount: RfqAmount,
+
+    /// Per-pair amounts, each `bid:<units>` or `ask:<units>`.
+    /// Set with `OMNISTON_AMOUNT_BY_PAIR="TON/STON=ask:5000000000"`.
+    pub amount_by_pair: HashMap<String, RfqAmount>,
+}
+
+impl OmnistonFeedConfig {
+    /// `None` unless `OMNISTON_WS_URL` is set.
+    pub fn from_env() -> Option<Self> {
+        Self::from_vars(|key| std::env::var(key).ok())
+    }
+
+    /// Reads settings through `var`; malformed per-pair amounts are ignored.
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        let ws_url = var("OMNISTON_WS_URL").filter(|url| !url.trim().is_empty())?;
+
+        let amount = match var("OMNISTON_ASK_UNITS") {
+            Some(units) => RfqAmount::AskUnits(units),
+            None => RfqAmount::BidUnits(
+                var("OMNISTON_BID_UNITS").unwrap_or_else(|| "1000000000".to_string()),
+            ),
+        };
+
+        Some(Self {
+            ws_url,
+            bid_asset: var("OMNISTON_BID_ASSET").unwrap_or_default(),
+            ask_asset: var("OMNISTON_ASK_ASSET").unwrap_or_default(),
+            amount,
+            amount_by_pair: var("OMNISTON_AMOUNT_BY_PAIR")
+                .map(|v| parse_pair_overrides(&v))
+                .unwrap_or_default(),
+        })
+    }
+
+    /// The quote request `pair_id` subscribes with, or why it is invalid
+    /// (e.g. an asset left unset).
+    pub fn rfq(&self, pair_id: &str) -> Result<RfqRequest, RfqError> {
+        let rfq = RfqRequest {
+            bid_asset: self.bid_asset.clone(),
+            ask_asset: self.ask_asset.clone(),
+            amount: self
+                .amount_by_pair
+                .get(pair_id)
+                .unwrap_or(&self.amount)
+                .clone(),
+        };
+        rfq.validate()?;
+        Ok(rfq)
+    }
+}
+
+/// Connection pool settings for `Db::connect`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DbConfig {
+    /// Must be at least 1; `Db::connect` rejects 0. `DB_MAX_CONNECTIONS`.
+    pub max_connections: u32,
+
+    /// How long a query waits for a free connection. `DB_ACQUIRE_TIMEOUT_MS`.
+    pub acquire_timeout: Duration,
+
+    /// Idle connections are closed after this long; `None` keeps them open.
+    /// `DB_IDLE_TIMEOUT_MS`, 0 = never.
+    pub idle_timeout: Option<Duration>,
+}
+
+impl Default for DbConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: 16,
+            acquire_timeout: Duration::from_secs(30),
+            idle_timeout: Some(Duration::from_secs(600)),
         }
     }
 }
+
+impl DbConfig {
+    pub fn from_env() -> Self {
+        Self::from_vars(|key| std::env::var(key).ok())
+    }
+
+    /// Reads settings through `var`; unset or unparsable values keep their
+    /// defaults.
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let num = |key: &str| var(key).and_then(|v| v.trim().parse::<u64>().ok());
+        let defaults = Self::default();
+
+        Self {
+            max_connections: var("DB_MAX_CONNECTIONS")
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(defaults.max_connections),
+            acquire_timeout: num("DB_ACQUIRE_TIMEOUT_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.acquire_timeout),
+            idle_timeout: match num("DB_IDLE_TIMEOUT_MS") {
+                Some(0) => None,
+                Some(ms) => Some(Duration::from_millis(ms)),
+                None => defaults.idle_timeout,
+            },
+        }
+    }
+}
+
+fn env_u64(key: &str, default: u64) -> u64 {
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Parses `PAIR=VALUE` entries separated by commas. Malformed entries are ignored.
+fn parse_pair_overrides<T: std::str::FromStr>(s: &str) -> HashMap<String, T> {
+    s.split(',')
+        .filter_map(|entry| {
+            let (pair, value) = entry.split_once('=')?;
+            Some((pair.trim().to_string(), value.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db_config(vars: &[(&str, &str)]) -> DbConfig {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        DbConfig::from_vars(|key| vars.get(key).cloned())
+    }
+
+    #[test]
+    fn db_config_defaults_when_unset() {
+        let cfg = db_config(&[]);
+
+        assert_eq!(cfg, DbConfig::default());
+        assert_eq!(cfg.max_connections, 16);
+        assert_eq!(cfg.acquire_timeout, Duration::from_secs(30));
+        assert_eq!(cfg.idle_timeout, Some(Duration::from_secs(600)));
+    }
+
+    #[test]
+    fn db_config_respects_overrides() {
+        let cfg = db_config(&[
+            ("DB_MAX_CONNECTIONS", "2"),
+            ("DB_ACQUIRE_TIMEOUT_MS", "1500"),
+            ("DB_IDLE_TIMEOUT_MS", "0"),
+        ]);
+        assert_eq!(
+            cfg,
+            DbConfig {
+                max_connections: 2,
+                acquire_timeout: Duration::from_millis(1_500),
+                idle_timeout: None,
+            }
+        );
+
+        let cfg = db_config(&[
+            ("DB_MAX_CONNECTIONS", "many"),
+            ("DB_IDLE_TIMEOUT_MS", "60000"),
+        ]);
+        assert_eq!(cfg.max_connections, 16);
+        assert_eq!(cfg.idle_timeout, Some(Duration::from_secs(60)));
+    }
+
+    const TON: &str = "EQCxE6mUtQJKFnGfaROTKOt1lZbDiiX1kCixRv7Nw2Id_sDs";
+    const STON: &str = "EQA2kCVNwVsil2EM2mB0SkXytxCqQjS4mttjDpnXmwG9T6bO";
+
+    fn omniston_feed(vars: &[(&str, &str)]) -> Option<OmnistonFeedConfig> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        OmnistonFeedConfig::from_vars(|key| vars.get(key).cloned())
+    }
+
+    #[test]
+    fn omniston_feed_is_off_without_url() {
+        assert_eq!(omniston_feed(&[]), None);
+        assert_eq!(omniston_feed(&[("OMNISTON_WS_URL", " ")]), None);
+    }
+
+    #[test]
+    fn omniston_feed_defaults_to_one_ton_of_bid() {
+        let feed = omniston_feed(&[
+            ("OMNISTON_WS_URL", "wss://omni-ws.ston.fi"),
+            ("OMNISTON_BID_ASSET", TON),
+            ("OMNISTON_ASK_ASSET", STON),
+        ])
+        .unwrap();
+
+        assert_eq!(feed.amount, RfqAmount::BidUnits("1000000000".into()));
+        assert!(feed.amount_by_pair.is_empty());
+
+        let rfq = feed.rfq("TON/STON").unwrap();
+        assert_eq!(rfq.bid_asset, TON);
+        assert_eq!(rfq.ask_asset, STON);
+        assert_eq!(rfq.amount, feed.amount);
+    }
+
+    #[test]
+    fn omniston_feed_reads_ask_units_and_per_pair_amounts() {
+        let feed = omniston_feed(&[
+            ("OMNISTON_WS_URL", "wss://omni-ws.ston.fi"),
+            ("OMNISTON_BID_ASSET", TON),
+            ("OMNISTON_ASK_ASSET", STON),
+            ("OMNISTON_BID_UNITS", "5"),
+            ("OMNISTON_ASK_UNITS", "7000"),
+            (
+                "OMNISTON_AMOUNT_BY_PAIR",
+                "TON/STON=bid:2000000000, TON/USDT=ask:300,TON/ETH=sell:1,TON/BTC",
+            ),
+        ])
+        .unwrap();
+
+        assert_eq!(feed.amount, RfqAmount::AskUnits("7000".into()));
+        assert_eq!(
+            feed.amount_by_pair,
+            HashMap::from([
+                (
+                    "TON/STON".to_string(),
+                    RfqAmount::BidUnits("2000000000".into())
+                ),
+                ("TON/USDT".to_string(), RfqAmount::AskUnits("300".into())),
+            ])
+        );
+
+        assert_eq!(
+            feed.rfq("TON/STON").unwrap().amount,
+            RfqAmount::BidUnits("2000000000".into())
+        );
+        assert_eq!(
+            feed.rfq("TON/ETH").unwrap().amount,
+            RfqAmount::AskUnits("7000".into())
+        );
+    }
+
+    #[test]
+    fn omniston_feed_rejects_unset_assets_and_bad_amounts() {
+        let unset = omniston_feed(&[
+            ("OMNISTON_WS_URL", "wss://omni-ws.ston.fi"),
+            ("OMNISTON_BID_ASSET", TON),
+        ])
+        .unwrap();
+        assert!(matches!(
+            unset.rfq("TON/STON"),
+            Err(RfqError::EmptyAddress { field: "ask_asset" })
+        ));
+
+        let zero = omniston_feed(&[
+            ("OMNISTON_WS_URL", "wss://omni-ws.ston.fi"),
+            ("OMNISTON_BID_ASSET", TON),
+            ("OMNISTON_ASK_ASSET", STON),
+            ("OMNISTON_AMOUNT_BY_PAIR", "TON/STON=ask:0"),
+        ])
+        .unwrap();
+        assert!(zero.rfq("TON/USDT").is_ok());
+        assert!(matches!(
+            zero.rfq("TON/STON"),
+            Err(RfqError::InvalidAmount(_))
+        ));
+    }
+}
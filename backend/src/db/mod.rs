@@ -4,15 +4,24 @@ use std::sync::Arc;
 use sqlx::AnyPool;
 use sqlx::any::AnyPoolOptions;
 
+use crate::config::DbConfig;
+
 #[derive(Clone)]
 pub struct Db {
     pub pool: Arc<AnyPool>,
 }
 
 impl Db {
-    pub async fn connect(database_url: &str) -> anyhow::Result<Self> {
+    pub async fn connect(database_url: &str, cfg: &DbConfig) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            cfg.max_connections >= 1,
+            "DB_MAX_CONNECTIONS must be at least 1"
+        );
+
         let pool = AnyPoolOptions::new()
-            .max_connections(16)
+            .max_connections(cfg.max_connections)
+            .acquire_timeout(cfg.acquire_timeout)
+            .idle_timeout(cfg.idle_timeout)
             .connect(database_url)
             .await?;
 
@@ -25,3 +34,19 @@ impl Db {
         schema::migrate(&self.pool).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn connect_rejects_an_empty_pool() {
+        let cfg = DbConfig {
+            max_connections: 0,
+            ..DbConfig::default()
+        };
+
+        let err = Db::connect("sqlite::memory:", &cfg).await.err().unwrap();
+        assert!(err.to_string().contains("at least 1"), "{err}");
+    }
+}
@@ -1,7 +1,83 @@
+//! Schema migrations.
+//!
+//! Scripts live in `migrations/` as `<version>_<name>.sql` and are embedded at
+//! build time. Applied versions and their checksums are recorded in
+//! `_sqlx_migrations`; startup fails if an applied script was edited since,
+//! so a schema change always ships as a new version.
+
 use sqlx::AnyPool;
+use sqlx::migrate::Migrator;
+
+static MIGRATOR: Migrator = sqlx::migrate!("../migrations");
 
+/// Applies every pending migration in version order.
 pub async fn migrate(pool: &AnyPool) -> anyhow::Result<()> {
-    sqlx::migrate!("../migrations").run(pool).await?;
+    MIGRATOR.run(pool).await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::any::AnyPoolOptions;
+
+    async fn memory_pool() -> AnyPool {
+        sqlx::any::install_default_drivers();
+        AnyPoolOptions::new()
+            .max_connections(1)
+            .connect(&format!(
+                "sqlite:file:{}?mode=memory&cache=shared",
+                uuid::Uuid::new_v4()
+            ))
+            .await
+            .unwrap()
+    }
+
+    async fn applied_versions(pool: &AnyPool) -> Vec<i64> {
+        sqlx::query_scalar("SELECT version FROM _sqlx_migrations ORDER BY version")
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn migrate_is_idempotent() {
+        let pool = memory_pool().await;
+
+        migrate(&pool).await.unwrap();
+        let first = applied_versions(&pool).await;
+        migrate(&pool).await.unwrap();
+
+        assert_eq!(first[0], 1, "base schema is version 1");
+        assert_eq!(first.len(), MIGRATOR.iter().count());
+        assert_eq!(applied_versions(&pool).await, first);
+
+        // The migrated schema carries every column the repository reads.
+        sqlx::query(
+            "SELECT failure_cooldown_ms, expires_at_ms, twap_interval_ms, quantum_weight, active_windows \
+             FROM sessions",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn migrate_refuses_edited_migration() {
+        let pool = memory_pool().await;
+        migrate(&pool).await.unwrap();
+
+        sqlx::query("UPDATE _sqlx_migrations SET checksum = X'00' WHERE version = 1")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let err = migrate(&pool).await.unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("previously applied but has been modified"),
+            "{err}"
+        );
+    }
+}
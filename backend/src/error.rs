@@ -1,4 +1,5 @@
 use thiserror::Error;
+use uuid::Uuid;
 
 #[derive(Error, Debug)]
 pub enum AppError {
@@ -11,3 +12,163 @@ pub enum AppError {
     #[error("scheduler invariant violated: {0}")]
     SchedulerInvariant(String),
 }
+
+/// Failure reported by a `SessionRepository`.
+///
+/// Lets callers tell a lost race (`Conflict`) or a missing row apart from
+/// a database failure without matching on strings.
+#[derive(Error, Debug)]
+pub enum RepositoryError {
+    #[error("not found: {0}")]
+    NotFound(String),
+
+    /// A value does not fit the storage (or domain) integer type.
+    #[error("numeric overflow: {0}")]
+    Overflow(String),
+
+    /// A CAS-guarded write lost to a concurrent writer.
+    #[error("conflict: {0}")]
+    Conflict(String),
+
+    #[error("unexpected batch status: {0}")]
+    UnexpectedBatchStatus(String),
+
+    /// A stored row cannot be decoded (poison row).
+    #[error("invalid row: {0}")]
+    InvalidRow(String),
+
+    #[error(transparent)]
+    Db(#[from] sqlx::Error),
+}
+
+/// Reasons `reassign_pair` refuses to move a session to another pair.
+#[derive(Error, Debug)]
+pub enum ReassignPairError {
+    #[error("session not found: {0}")]
+    SessionNotFound(Uuid),
+
+    #[error("session {0} has a pending batch")]
+    PendingBatch(Uuid),
+
+    #[error("session {session_id} has in-flight bid {in_flight_bid}")]
+    InFlight {
+        session_id: Uuid,
+        in_flight_bid: i64,
+    },
+
+    #[error(transparent)]
+    Db(#[from] sqlx::Error),
+}
+
+/// Failure reported by a `SwapExecutor`.
+///
+/// The executor worker maps each variant to a bounded, deterministic
+/// `ChunkStatus::Failed` reason.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SwapError {
+    #[error("MarketNotOpen")]
+    MarketNotOpen,
+
+    #[error("Slippage")]
+    Slippage,
+
+    #[error("InsufficientLiquidity")]
+    InsufficientLiquidity,
+
+    #[error("Rejected (code {code})")]
+    Rejected { code: u32 },
+
+    #[error("Timeout")]
+    Timeout,
+
+    #[error("{0}")]
+    Other(String),
+}
+
+impl SwapError {
+    /// True for transient failures worth retrying on the same chunk.
+    ///
+    /// Only `Timeout` qualifies: market/slippage/liquidity outcomes will not
+    /// change within a backoff window, and `Rejected`/`Other` are not known to
+    /// be safe to resubmit. Executors must treat `chunk_id` as an idempotency
+    /// key, since a timed-out swap may still have landed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, SwapError::Timeout)
+    }
+}
+
+/// Backward compatibility for executors that still produce `anyhow` errors:
+/// a wrapped `SwapError` is recovered as-is, legacy messages are matched by
+/// their well-known codes, anything else becomes `Other`.
+impl From<anyhow::Error> for SwapError {
+    fn from(e: anyhow::Error) -> Self {
+        if let Some(err) = e.downcast_ref::<SwapError>() {
+            return err.clone();
+        }
+
+        let s = e.to_string();
+        if s.contains("MarketNotOpen") {
+            SwapError::MarketNotOpen
+        } else if s.contains("InsufficientLiquidity") {
+            SwapError::InsufficientLiquidity
+        } else if s.contains("Slippage") {
+            SwapError::Slippage
+        } else {
+            SwapError::Other(s)
+        }
+    }
+}
+
+/// Invalid `AppConfig::executor` settings, reported by `build_executor`
+/// at startup instead of on the first swap.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ExecutorConfigError {
+    #[error("{executor} executor: {field} is required")]
+    MissingField {
+        executor: &'static str,
+        field: &'static str,
+    },
+
+    #[error("{executor} executor: {field} is not a valid http(s) URL: {reason}")]
+    InvalidUrl {
+        executor: &'static str,
+        field: &'static str,
+        reason: String,
+    },
+
+    #[error("{executor} executor is not implemented yet")]
+    Unsupported { executor: &'static str },
+
+    #[error("{executor} executor: cannot build HTTP client: {reason}")]
+    HttpClient {
+        executor: &'static str,
+        reason: String,
+    },
+
+    #[error(
+        "{executor} executor: a swap can take {needed_ms}ms, longer than {setting} ({swap_timeout_ms}ms)"
+    )]
+    ExceedsSwapTimeout {
+        executor: &'static str,
+        needed_ms: u64,
+        setting: String,
+        swap_timeout_ms: u64,
+    },
+}
+
+/// An `RfqRequest` the server would accept but never answer, rejected
+/// before subscribing.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum RfqError {
+    #[error("{field} is empty")]
+    EmptyAddress { field: &'static str },
+
+    #[error("{field} is not a TON address: {address}")]
+    InvalidAddress {
+        field: &'static str,
+        address: String,
+    },
+
+    #[error("amount must be a positive integer, got `{0}`")]
+    InvalidAmount(String),
+}
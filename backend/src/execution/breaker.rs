@@ -0,0 +1,387 @@
+//! Per-pair circuit breaker for the execution layer.
+//!
+//! The breaker watches batch outcomes for one trading pair and trips
+//! **open** when either:
+//! - the failure ratio over a sliding time window crosses a threshold, or
+//! - a run of consecutive batches failed on every executed chunk.
+//!
+//! Lifecycle:
+//! - `Closed`   – batches execute, outcomes are sampled
+//! - `Open`     – the worker short-circuits batches until `open_ms` elapses:
+//!   each one is committed with every chunk `Skipped { "CircuitOpen" }`,
+//!   releasing its reservation, and no swap is attempted
+//! - `HalfOpen` – exactly one probe batch is let through; success closes
+//!   the breaker, failure re-opens it
+//!
+//! Short-circuited batches are committed rather than left RESERVED. A
+//! withheld batch would keep its sessions' bid in flight and block them
+//! until restart recovery, while a skipped one hands the bid straight back
+//! to the scheduler, which re-reserves it once the breaker closes.
+//!
+//! Time is taken from `tokio::time::Instant` so the breaker follows
+//! virtual time in tests. All methods take `now` explicitly.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use tokio::time::Instant;
+
+/// Tunables for the per-pair circuit breaker.
+#[derive(Clone, Debug)]
+pub struct BreakerConfig {
+    /// Sliding window over which batch outcomes are counted.
+    pub window_ms: u64,
+
+    /// Minimum outcomes in the window before the ratio rule may trip.
+    /// Prevents a single failure on a quiet pair from opening it.
+    pub min_samples: usize,
+
+    /// Failure ratio in `[0, 1]` at or above which the breaker trips.
+    pub failure_ratio: f64,
+
+    /// Consecutive all-failed batches that trip the breaker (0 = disabled).
+    /// Any successful chunk resets the run.
+    pub consecutive_failures: u32,
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        Self {
+            window_ms: 60_000,
+            min_samples: 5,
+            failure_ratio: 0.5,
+            consecutive_failures: 5,
+        }
+    }
+}
+
+/// Observable breaker state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BreakerState {
+    Closed,
+    /// Open until the given instant.
+    Open {
+        until: Instant,
+    },
+    /// Cool-off elapsed; the next batch is a probe.
+    HalfOpen,
+}
+
+/// Outcome of one executed batch, as seen by the breaker.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BatchOutcome {
+    /// At least one chunk executed and none failed.
+    Success,
+    /// Some chunks succeeded, at least one failed.
+    PartialFailure,
+    /// Every executed chunk failed.
+    Failure,
+    /// No chunk was executed (e.g. all skipped by Gate B).
+    Inconclusive,
+}
+
+struct Inner {
+    state: BreakerState,
+    /// Set while the half-open probe batch is being executed.
+    probe_in_flight: bool,
+    /// Current run of `BatchOutcome::Failure`.
+    consecutive: u32,
+    /// `(at, failed)` per recorded batch, oldest first.
+    samples: VecDeque<(Instant, bool)>,
+}
+
+/// Failure-rate / consecutive-failure circuit breaker for one pair.
+pub struct CircuitBreaker {
+    cfg: BreakerConfig,
+    open_for: Duration,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    /// `open_ms` is how long the breaker stays open once tripped.
+    pub fn new(cfg: BreakerConfig, open_ms: u64) -> Self {
+        Self {
+            cfg,
+            open_for: Duration::from_millis(open_ms),
+            inner: Mutex::new(Inner {
+                state: BreakerState::Closed,
+                probe_in_flight: false,
+                consecutive: 0,
+                samples: VecDeque::new(),
+            }),
+        }
+    }
+
+    /// Current state (for observability). An expired open breaker is
+    /// reported as half-open: the next batch will be let through.
+    pub fn state(&self, now: Instant) -> BreakerState {
+        match self.inner.lock().state {
+            BreakerState::Open { until } if now >= until => BreakerState::HalfOpen,
+            s => s,
+        }
+    }
+
+    /// True while batches are being short-circuited.
+    pub fn is_open(&self, now: Instant) -> bool {
+        matches!(self.state(now), BreakerState::Open { .. })
+    }
+
+    /// Decides whether a batch may execute now.
+    ///
+    /// An expired open breaker moves to half-open and admits a single probe;
+    /// further batches are refused until that probe is recorded.
+    pub fn allow(&self, now: Instant) -> bool {
+        let mut g = self.inner.lock();
+        match g.state {
+            BreakerState::Closed => true,
+            BreakerState::Open { until } if now < until => false,
+            BreakerState::Open { .. } | BreakerState::HalfOpen => {
+                if g.probe_in_flight {
+                    return false;
+                }
+                g.state = BreakerState::HalfOpen;
+                g.probe_in_flight = true;
+                true
+            }
+        }
+    }
+
+    /// Records one batch outcome. Returns `true` if it tripped (or
+    /// re-opened) the breaker.
+    pub fn record(&self, outcome: BatchOutcome, now: Instant) -> bool {
+        let mut g = self.inner.lock();
+
+        if g.state == BreakerState::HalfOpen {
+            return match outcome {
+                BatchOutcome::Success => {
+                    g.state = BreakerState::Closed;
+                    g.probe_in_flight = false;
+                    g.consecutive = 0;
+                    g.samples.clear();
+                    false
+                }
+                BatchOutcome::PartialFailure | BatchOutcome::Failure => {
+                    self.trip(&mut g, now);
+                    true
+                }
+                // Says nothing about executor health: the next batch probes.
+                BatchOutcome::Inconclusive => {
+                    g.probe_in_flight = false;
+                    false
+                }
+            };
+        }
+
+        let failed = match outcome {
+            BatchOutcome::Inconclusive => return false,
+            BatchOutcome::Success => {
+                g.consecutive = 0;
+                false
+            }
+            BatchOutcome::PartialFailure => {
+                g.consecutive = 0;
+                true
+            }
+            BatchOutcome::Failure => {
+                g.consecutive += 1;
+                true
+            }
+        };
+        self.push_sample(&mut g, now, failed);
+
+        if g.state != BreakerState::Closed {
+            return false;
+        }
+
+        let consecutive_trip =
+            self.cfg.consecutive_failures > 0 && g.consecutive >= self.cfg.consecutive_failures;
+
+        let ratio_trip = g.samples.len() >= self.cfg.min_samples && {
+            let failures = g.samples.iter().filter(|(_, failed)| *failed).count();
+            failures as f64 >= self.cfg.failure_ratio * g.samples.len() as f64
+        };
+
+        if consecutive_trip || ratio_trip {
+            self.trip(&mut g, now);
+            return true;
+        }
+        false
+    }
+
+    fn push_sample(&self, g: &mut Inner, now: Instant, failed: bool) {
+        g.samples.push_back((now, failed));
+        let window = Duration::from_millis(self.cfg.window_ms);
+        while g
+            .samples
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > window)
+        {
+            g.samples.pop_front();
+        }
+    }
+
+    fn trip(&self, g: &mut Inner, now: Instant) {
+        g.state = BreakerState::Open {
+            until: now + self.open_for,
+        };
+        g.probe_in_flight = false;
+        g.consecutive = 0;
+        g.samples.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    fn ratio_breaker() -> CircuitBreaker {
+        CircuitBreaker::new(
+            BreakerConfig {
+                window_ms: 1_000,
+                min_samples: 4,
+                failure_ratio: 0.5,
+                consecutive_failures: 0,
+            },
+            5_000,
+        )
+    }
+
+    fn consecutive_breaker() -> CircuitBreaker {
+        CircuitBreaker::new(
+            BreakerConfig {
+                window_ms: 1_000,
+                min_samples: usize::MAX,
+                failure_ratio: 1.0,
+                consecutive_failures: 3,
+            },
+            5_000,
+        )
+    }
+
+    fn trip(b: &CircuitBreaker, t0: Instant) {
+        for i in 0..4 {
+            b.record(BatchOutcome::Failure, t0 + ms(i));
+        }
+        assert!(b.is_open(t0 + ms(4)));
+    }
+
+    #[test]
+    fn stays_closed_below_min_samples() {
+        let b = ratio_breaker();
+        let t0 = Instant::now();
+        for i in 0..3 {
+            assert!(!b.record(BatchOutcome::Failure, t0 + ms(i)));
+        }
+        assert_eq!(b.state(t0), BreakerState::Closed);
+        assert!(b.allow(t0 + ms(10)));
+    }
+
+    #[test]
+    fn trips_once_failure_ratio_crossed() {
+        let b = ratio_breaker();
+        let t0 = Instant::now();
+        b.record(BatchOutcome::Success, t0);
+        b.record(BatchOutcome::Success, t0 + ms(1));
+        assert!(!b.record(BatchOutcome::Failure, t0 + ms(2)));
+        assert!(b.record(BatchOutcome::PartialFailure, t0 + ms(3)));
+
+        assert_eq!(
+            b.state(t0),
+            BreakerState::Open {
+                until: t0 + ms(5_003)
+            }
+        );
+        assert!(!b.allow(t0 + ms(5_002)));
+    }
+
+    #[test]
+    fn old_samples_fall_out_of_window() {
+        let b = ratio_breaker();
+        let t0 = Instant::now();
+        for i in 0..3 {
+            b.record(BatchOutcome::Failure, t0 + ms(i));
+        }
+
+        // Far outside the window: only this sample remains.
+        assert!(!b.record(BatchOutcome::Failure, t0 + ms(10_000)));
+        assert!(!b.is_open(t0 + ms(10_000)));
+    }
+
+    #[test]
+    fn trips_after_consecutive_failed_batches() {
+        let b = consecutive_breaker();
+        let t0 = Instant::now();
+        assert!(!b.record(BatchOutcome::Failure, t0));
+        assert!(!b.record(BatchOutcome::Failure, t0));
+        assert!(b.record(BatchOutcome::Failure, t0));
+        assert!(b.is_open(t0));
+    }
+
+    #[test]
+    fn successful_chunk_resets_consecutive_run() {
+        let b = consecutive_breaker();
+        let t0 = Instant::now();
+        b.record(BatchOutcome::Failure, t0);
+        b.record(BatchOutcome::Failure, t0);
+        b.record(BatchOutcome::PartialFailure, t0);
+        b.record(BatchOutcome::Failure, t0);
+        assert!(!b.record(BatchOutcome::Failure, t0));
+        assert!(!b.is_open(t0));
+    }
+
+    #[test]
+    fn reports_half_open_once_cooldown_elapses() {
+        let b = ratio_breaker();
+        let t0 = Instant::now();
+        trip(&b, t0);
+
+        assert!(b.is_open(t0 + ms(5_002)));
+        assert_eq!(b.state(t0 + ms(5_003)), BreakerState::HalfOpen);
+    }
+
+    #[test]
+    fn half_open_admits_single_probe_and_success_closes() {
+        let b = ratio_breaker();
+        let t0 = Instant::now();
+        trip(&b, t0);
+
+        assert!(b.allow(t0 + ms(6_000)));
+        assert!(!b.allow(t0 + ms(6_001)), "only one probe while half-open");
+
+        b.record(BatchOutcome::Success, t0 + ms(6_002));
+        assert_eq!(b.state(t0 + ms(6_002)), BreakerState::Closed);
+        assert!(b.allow(t0 + ms(6_003)));
+    }
+
+    #[test]
+    fn failed_probe_reopens() {
+        let b = ratio_breaker();
+        let t0 = Instant::now();
+        trip(&b, t0);
+
+        assert!(b.allow(t0 + ms(6_000)));
+        assert!(b.record(BatchOutcome::Failure, t0 + ms(6_001)));
+        assert_eq!(
+            b.state(t0),
+            BreakerState::Open {
+                until: t0 + ms(11_001)
+            }
+        );
+    }
+
+    #[test]
+    fn inconclusive_probe_releases_slot() {
+        let b = ratio_breaker();
+        let t0 = Instant::now();
+        trip(&b, t0);
+
+        assert!(b.allow(t0 + ms(6_000)));
+        b.record(BatchOutcome::Inconclusive, t0 + ms(6_000));
+        assert!(b.allow(t0 + ms(6_001)));
+    }
+}
@@ -0,0 +1,270 @@
+//! Chain executors and the startup factory that selects one from `AppConfig`.
+
+use async_trait::async_trait;
+use reqwest::Url;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::{AppConfig, ExecutorKind};
+use crate::error::{ExecutorConfigError, SwapError};
+use crate::execution::executor::SwapExecutor;
+use crate::execution::ton::{TonRpcConfig, TonSwapExecutor};
+use crate::execution::types::{SwapCall, SwapReceipt};
+
+/// Builds the executor selected by `cfg.executor`.
+///
+/// Required settings are validated here so a misconfigured deployment fails
+/// at startup rather than on its first swap.
+pub fn build_executor(cfg: &AppConfig) -> Result<Arc<dyn SwapExecutor>, ExecutorConfigError> {
+    match &cfg.executor {
+        ExecutorKind::Dummy => Ok(Arc::new(DummySwapExecutor)),
+        ExecutorKind::Ton {
+            rpc_url,
+            wallet_address,
+            api_key,
+            connect_timeout_ms,
+            request_timeout_ms,
+            confirm_timeout_ms,
+        } => {
+            // One submit request, then polling for up to `confirm_timeout`
+            // plus a last status request that may run past it.
+            fits_swap_timeout(
+                "ton",
+                confirm_timeout_ms.saturating_add(request_timeout_ms.saturating_mul(2)),
+                cfg,
+            )?;
+
+            let rpc = TonRpcConfig {
+                api_key: api_key.clone().filter(|k| !k.trim().is_empty()),
+                connect_timeout: Duration::from_millis(*connect_timeout_ms),
+                request_timeout: Duration::from_millis(*request_timeout_ms),
+                confirm_timeout: Duration::from_millis(*confirm_timeout_ms),
+                ..TonRpcConfig::new(
+                    require_url("ton", "TON_RPC_URL", rpc_url)?,
+                    require("ton", "TON_WALLET_ADDRESS", wallet_address)?,
+                )
+            };
+            let exec = TonSwapExecutor::new(rpc).map_err(|e| ExecutorConfigError::HttpClient {
+                executor: "ton",
+                reason: e.to_string(),
+            })?;
+            Ok(Arc::new(exec))
+        }
+        ExecutorKind::Emc { .. } => Err(ExecutorConfigError::Unsupported { executor: "emc" }),
+    }
+}
+
+/// Rejects an executor that can outlast the worker's swap timeout: the
+/// worker would give up on swaps the executor is still confirming.
+fn fits_swap_timeout(
+    executor: &'static str,
+    needed_ms: u64,
+    cfg: &AppConfig,
+) -> Result<(), ExecutorConfigError> {
+    let global = ("SWAP_TIMEOUT_MS".to_string(), cfg.swap_timeout_ms);
+    let per_pair = cfg
+        .swap_timeout_ms_by_pair
+        .iter()
+        .map(|(pair, ms)| (format!("SWAP_TIMEOUT_MS_BY_PAIR[{pair}]"), *ms));
+
+    for (setting, swap_timeout_ms) in std::iter::once(global).chain(per_pair) {
+        if needed_ms >= swap_timeout_ms {
+            return Err(ExecutorConfigError::ExceedsSwapTimeout {
+                executor,
+                needed_ms,
+                setting,
+                swap_timeout_ms,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn require(
+    executor: &'static str,
+    field: &'static str,
+    value: &str,
+) -> Result<String, ExecutorConfigError> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(ExecutorConfigError::MissingField { executor, field });
+    }
+    Ok(value.to_string())
+}
+
+fn require_url(
+    executor: &'static str,
+    field: &'static str,
+    value: &str,
+) -> Result<Url, ExecutorConfigError> {
+    let invalid = |reason: String| ExecutorConfigError::InvalidUrl {
+        executor,
+        field,
+        reason,
+    };
+
+    let url = Url::parse(&require(executor, field, value)?).map_err(|e| invalid(e.to_string()))?;
+    match url.scheme() {
+        "http" | "https" => Ok(url),
+        other => Err(invalid(format!("unsupported scheme `{other}`"))),
+    }
+}
+
+/// Acknowledges every swap without submitting anything (development only).
+#[derive(Clone)]
+pub struct DummySwapExecutor;
+
+#[async_trait]
+impl SwapExecutor for DummySwapExecutor {
+    async fn execute_swap(&self, call: SwapCall) -> Result<SwapReceipt, SwapError> {
+        // Echo the key so callers can verify it reached the executor.
+        Ok(SwapReceipt {
+            tx_id: "dummy_tx".to_string(),
+            idempotency_key: Some(call.idempotency_key),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn cfg(executor: ExecutorKind) -> AppConfig {
+        AppConfig {
+            executor,
+            ..AppConfig::from_env()
+        }
+    }
+
+    fn ton(rpc_url: &str, wallet_address: &str) -> ExecutorKind {
+        ExecutorKind::Ton {
+            rpc_url: rpc_url.into(),
+            wallet_address: wallet_address.into(),
+            api_key: None,
+            connect_timeout_ms: 1_000,
+            request_timeout_ms: 1_000,
+            confirm_timeout_ms: 1_000,
+        }
+    }
+
+    #[tokio::test]
+    async fn dummy_executor_echoes_the_idempotency_key() {
+        let exec = build_executor(&cfg(ExecutorKind::Dummy)).unwrap();
+
+        let receipt = exec
+            .execute_swap(SwapCall {
+                pair_id: "TON/USDT".into(),
+                session_id: Uuid::new_v4(),
+                bid: 100,
+                chunk_id: Uuid::new_v4(),
+                deadline_ms: 0,
+                idempotency_key: "batch:chunk".into(),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(receipt.tx_id, "dummy_tx");
+        assert_eq!(receipt.idempotency_key.as_deref(), Some("batch:chunk"));
+    }
+
+    #[test]
+    fn ton_requires_rpc_url_and_wallet() {
+        let err = build_executor(&cfg(ton("", "EQwallet"))).err().unwrap();
+        assert_eq!(
+            err,
+            ExecutorConfigError::MissingField {
+                executor: "ton",
+                field: "TON_RPC_URL"
+            }
+        );
+
+        let err = build_executor(&cfg(ton("https://toncenter.com/api/v2", "  ")))
+            .err()
+            .unwrap();
+        assert_eq!(
+            err,
+            ExecutorConfigError::MissingField {
+                executor: "ton",
+                field: "TON_WALLET_ADDRESS"
+            }
+        );
+
+        assert!(build_executor(&cfg(ton("https://toncenter.com/api/v2", "EQwallet"))).is_ok());
+    }
+
+    #[test]
+    fn rpc_url_must_be_http() {
+        for bad in ["not a url", "ftp://node.example"] {
+            let err = build_executor(&cfg(ton(bad, "EQwallet"))).err().unwrap();
+            assert!(
+                matches!(
+                    err,
+                    ExecutorConfigError::InvalidUrl {
+                        field: "TON_RPC_URL",
+                        ..
+                    }
+                ),
+                "{bad}: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn ton_timeouts_must_fit_every_swap_timeout() {
+        let ton = |confirm_timeout_ms| ExecutorKind::Ton {
+            rpc_url: "https://toncenter.com/api/v2".into(),
+            wallet_address: "EQwallet".into(),
+            api_key: None,
+            connect_timeout_ms: 1_000,
+            request_timeout_ms: 5_000,
+            confirm_timeout_ms,
+        };
+        let cfg = |confirm_timeout_ms, by_pair: &[(&str, u64)]| AppConfig {
+            executor: ton(confirm_timeout_ms),
+            swap_timeout_ms: 30_000,
+            swap_timeout_ms_by_pair: by_pair
+                .iter()
+                .map(|(pair, ms)| (pair.to_string(), *ms))
+                .collect(),
+            ..AppConfig::from_env()
+        };
+
+        assert!(build_executor(&cfg(15_000, &[])).is_ok());
+
+        let err = build_executor(&cfg(60_000, &[])).err().unwrap();
+        assert_eq!(
+            err,
+            ExecutorConfigError::ExceedsSwapTimeout {
+                executor: "ton",
+                needed_ms: 70_000,
+                setting: "SWAP_TIMEOUT_MS".into(),
+                swap_timeout_ms: 30_000,
+            }
+        );
+
+        let err = build_executor(&cfg(15_000, &[("TON/USDT", 8_000)]))
+            .err()
+            .unwrap();
+        assert!(
+            matches!(
+                &err,
+                ExecutorConfigError::ExceedsSwapTimeout { setting, .. }
+                    if setting == "SWAP_TIMEOUT_MS_BY_PAIR[TON/USDT]"
+            ),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn emc_is_rejected_until_implemented() {
+        let err = build_executor(&cfg(ExecutorKind::Emc {
+            rpc_url: "http://127.0.0.1:8545".into(),
+            account: "emc-account".into(),
+        }))
+        .err()
+        .unwrap();
+
+        assert_eq!(err, ExecutorConfigError::Unsupported { executor: "emc" });
+    }
+}
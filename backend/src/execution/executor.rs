@@ -17,19 +17,33 @@
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use async_trait::async_trait;
+use futures::StreamExt;
+use futures::stream::FuturesUnordered;
 use tokio::sync::mpsc::{Receiver, Sender};
 use tokio::sync::{Mutex, mpsc};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 use tracing::{Instrument, debug, error, info, info_span, warn};
 
-use crate::execution::commit_batch;
+use crate::error::SwapError;
+use crate::execution::breaker::{BatchOutcome, BreakerConfig, BreakerState, CircuitBreaker};
 use crate::execution::types::{
-    ChunkResult, ChunkStatus, ExecutionEvent, ReservedBatch, UserResult,
+    ChunkResult, ChunkStatus, CommitSummary, ExecutionEvent, ReservedBatch, ReservedChunk,
+    ReservedUser, UserResult,
+};
+use crate::execution::{
+    abort_batch, claim_batch, commit_batch, dead_letter_batch, record_commit_failure,
 };
 use crate::market::market_view_store::MarketViewStore;
+use crate::market::types::{DEFAULT_MAX_SNAPSHOT_AGE_MS, MarketMetricsView};
+use crate::metrics::counters::Counters;
 use crate::session::model::Session;
 use crate::session::store::SessionStore;
+use crate::time::now_ms;
+use uuid::Uuid;
 
 /// Abstraction over the on-chain execution layer.
 ///
@@ -38,13 +52,157 @@ use crate::session::store::SessionStore;
 /// - RPC details
 /// - error formats
 ///
-/// Errors must be normalized into stable strings by the implementation.
+/// Failures are reported as a [`SwapError`]; `anyhow` errors convert via `?`
+/// for executors that have not been ported to typed variants yet.
+///
+/// Timeouts: the worker abandons a call after `swap_timeout_ms`, which is
+/// also `SwapCall::deadline_ms`. Implementations must make the submission
+/// expire at the deadline (e.g. the message's `valid_until`), so nothing can
+/// land once the worker has given up. A swap may still have landed just
+/// before it without its confirmation being seen, so once the deadline has
+/// passed the worker asks `query_status`: a landed swap is committed as
+/// SUCCESS, anything else as FAILED (returning the bid to `remaining_bid`).
+/// A `SwapError::Timeout` returned by the executor is handled the same way.
+/// If the lookup fails the chunk is left PENDING (`ChunkStatus::Unknown`)
+/// for recovery, rather than risk spending the same bid twice.
+///
+/// Idempotency: `SwapCall::idempotency_key` is identical for every attempt at
+/// the same chunk. A worker can crash after a swap lands but before
+/// `commit_batch`; implementations must dedupe on the key so a resubmitted
+/// chunk returns the original receipt instead of swapping again.
+///
+/// Recovery: before restart recovery unwinds a chunk that has no recorded
+/// outcome, it asks `query_status` whether the chunk landed anyway.
 #[async_trait]
 pub trait SwapExecutor: Send + Sync + 'static {
     async fn execute_swap(
         &self,
         call: super::types::SwapCall,
-    ) -> anyhow::Result<super::types::SwapReceipt>;
+    ) -> Result<super::types::SwapReceipt, SwapError>;
+
+    /// Receipt of an already executed swap for `chunk_id`, or `None` if no
+    /// swap for it landed. The default reports `None` (nothing to look up).
+    async fn query_status(
+        &self,
+        chunk_id: Uuid,
+    ) -> Result<Option<super::types::SwapReceipt>, SwapError> {
+        let _ = chunk_id;
+        Ok(None)
+    }
+}
+
+/// Read-only view of how far behind execution is, per pair.
+///
+/// The scheduler consults this before reserving so a slow pair does not
+/// accumulate RESERVED batches it cannot execute.
+#[async_trait]
+pub trait ExecutorBacklog: Send + Sync {
+    /// Batches queued for `pair_id` but not yet picked up by its worker.
+    /// `None` if the pair has no live worker.
+    async fn queue_depth(&self, pair_id: &str) -> Option<usize>;
+}
+
+/// Bounded per-chunk retry for transient (`SwapError::is_retryable`) failures.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Total attempts per chunk, including the first (1 = no retry).
+    pub max_attempts: u32,
+    /// Backoff before the 2nd attempt; doubles for every further attempt.
+    pub base_backoff_ms: u64,
+}
+
+impl RetryPolicy {
+    /// Backoff to sleep after failed attempt number `attempt` (1-based).
+    fn backoff_ms(&self, attempt: u32) -> u64 {
+        let exp = attempt.saturating_sub(1).min(16);
+        self.base_backoff_ms.saturating_mul(1u64 << exp)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            base_backoff_ms: 200,
+        }
+    }
+}
+
+/// Tunables shared by all per-pair executor workers.
+#[derive(Clone, Debug)]
+pub struct WorkerConfig {
+    /// Cooldown applied to a session after a failed chunk.
+    pub default_failure_cooldown_ms: u64,
+
+    /// Gate B re-reads the market snapshot every N chunks (1 = before every chunk).
+    /// Batches execute sequentially, so a snapshot taken at batch start goes stale.
+    pub market_refresh_every_chunks: usize,
+
+    /// Market snapshots older than this fail Gate B (treated as missing).
+    pub max_snapshot_age_ms: u64,
+
+    /// Opt-in parallelism: chunks of one allocation issued concurrently.
+    /// 1 (default) keeps strictly sequential execution with per-chunk Gate B.
+    pub max_concurrent_chunks: usize,
+
+    /// Opt-in parallelism: users of one batch executed concurrently. Each
+    /// user's chunks stay sequential and stop on that user's first failure.
+    /// 1 (default) executes users one after another.
+    pub max_parallel_users: usize,
+
+    /// Safe mode (canary): run the full worker path, including Gate B and
+    /// commit, but never call the `SwapExecutor`. Chunks that would have been
+    /// submitted are logged and committed as `ChunkStatus::Simulated`.
+    pub safe_mode: bool,
+
+    /// Retry policy for transient swap failures.
+    pub retry: RetryPolicy,
+
+    /// Per-pair circuit breaker. Trips open for `default_failure_cooldown_ms`,
+    /// during which the worker commits batches as `Skipped { "CircuitOpen" }`.
+    pub breaker: BreakerConfig,
+
+    /// Failed enqueue attempts after which the router dead-letters a batch.
+    pub max_enqueue_attempts: u32,
+
+    /// `commit_batch` calls per batch before the worker gives up and
+    /// dead-letters it with its results. Retries back off per `retry`.
+    pub max_commit_attempts: u32,
+
+    /// Upper bound (ms) on a single `execute_swap` call. A hung call would
+    /// otherwise block the pair's worker; on expiry the chunk fails with
+    /// `Timeout`.
+    pub swap_timeout_ms: u64,
+
+    /// Per-pair overrides of `swap_timeout_ms`, keyed by pair_id.
+    pub swap_timeout_ms_by_pair: HashMap<String, u64>,
+}
+
+impl Default for WorkerConfig {
+    fn default() -> Self {
+        Self {
+            default_failure_cooldown_ms: 10_000,
+            market_refresh_every_chunks: 1,
+            max_snapshot_age_ms: DEFAULT_MAX_SNAPSHOT_AGE_MS,
+            max_concurrent_chunks: 1,
+            max_parallel_users: 1,
+            safe_mode: false,
+            retry: RetryPolicy::default(),
+            breaker: BreakerConfig::default(),
+            max_enqueue_attempts: 3,
+            max_commit_attempts: 3,
+            swap_timeout_ms: 30_000,
+            swap_timeout_ms_by_pair: HashMap::new(),
+        }
+    }
+}
+
+/// Router-side handle to a live pair worker.
+#[derive(Clone)]
+struct WorkerHandle {
+    tx: Sender<ReservedBatch>,
+    /// Batches sent but not yet picked up by the worker.
+    queued: Arc<AtomicUsize>,
 }
 
 /// Routes RESERVED batches into per-pair worker queues.
@@ -55,40 +213,158 @@ pub trait SwapExecutor: Send + Sync + 'static {
 /// - bounded memory via per-pair channel capacity
 ///
 /// Failure handling:
-/// - if a worker dies, its sender is removed
+/// - if a worker dies, its sender is removed and delivery is retried
+/// - batches that repeatedly fail to enqueue are aborted and dead-lettered
+/// - while a pair's circuit breaker is open, its worker commits batches as
+///   `Skipped { "CircuitOpen" }` without executing them (see `breaker`)
 /// - RESERVED batches remain recoverable via DB recovery
-pub struct PairExecutorRouter<E: SwapExecutor> {
+pub struct PairExecutorRouter<E: SwapExecutor + ?Sized> {
     store: Arc<SessionStore>,
     market_view: MarketViewStore,
     exec: Arc<E>,
-    default_failure_cooldown_ms: u64,
+    cfg: WorkerConfig,
 
     /// Maximum backlog per trading pair.
     /// This provides backpressure against over-reserving.
     per_pair_capacity: usize,
 
     /// Active worker channels keyed by pair_id.
-    pair_txs: Mutex<HashMap<String, Sender<ReservedBatch>>>,
+    pair_txs: Mutex<HashMap<String, WorkerHandle>>,
+
+    /// Worker tasks, awaited on shutdown so queued batches finish executing.
+    worker_tasks: parking_lot::Mutex<Vec<JoinHandle<()>>>,
+
+    /// Circuit breakers keyed by pair_id. Outlive worker restarts.
+    breakers: parking_lot::Mutex<HashMap<String, Arc<CircuitBreaker>>>,
+
+    /// Failed enqueue attempts keyed by batch_id.
+    enqueue_failures: parking_lot::Mutex<HashMap<Uuid, u32>>,
+
+    /// Receives batches that exhausted `max_enqueue_attempts`.
+    dead_letter: Option<Sender<ReservedBatch>>,
+
+    /// Handed to every worker for `ExecutionEvent::Committed`.
+    events: Option<Sender<ExecutionEvent>>,
+
+    /// Handed to every worker for execution counts.
+    counters: Counters,
+
+    /// Starts each new pair worker on its queue; `tokio::spawn` by default.
+    spawn_worker: WorkerSpawner<E>,
 }
 
-impl<E: SwapExecutor> PairExecutorRouter<E> {
+/// Starts a pair worker consuming the given queue (see
+/// `PairExecutorRouter::with_worker_spawner`).
+pub type WorkerSpawner<E> =
+    Arc<dyn Fn(ExecutorWorker<E>, Receiver<ReservedBatch>) -> JoinHandle<()> + Send + Sync>;
+
+#[async_trait]
+impl<E: SwapExecutor + ?Sized> ExecutorBacklog for PairExecutorRouter<E> {
+    async fn queue_depth(&self, pair_id: &str) -> Option<usize> {
+        PairExecutorRouter::queue_depth(self, pair_id).await
+    }
+}
+
+impl<E: SwapExecutor + ?Sized> PairExecutorRouter<E> {
     pub fn new(
         store: Arc<SessionStore>,
         market_view: MarketViewStore,
         exec: Arc<E>,
-        default_failure_cooldown_ms: u64,
+        cfg: WorkerConfig,
         per_pair_capacity: usize,
     ) -> Self {
         Self {
             store,
             market_view,
             exec,
-            default_failure_cooldown_ms,
+            cfg,
             per_pair_capacity: per_pair_capacity.max(8),
             pair_txs: Mutex::new(HashMap::new()),
+            worker_tasks: parking_lot::Mutex::new(Vec::new()),
+            breakers: parking_lot::Mutex::new(HashMap::new()),
+            enqueue_failures: parking_lot::Mutex::new(HashMap::new()),
+            dead_letter: None,
+            events: None,
+            counters: Counters::default(),
+            spawn_worker: Arc::new(|worker, rx| tokio::spawn(worker.run(rx))),
         }
     }
 
+    /// Starts pair workers through `spawn` instead of `tokio::spawn`, e.g.
+    /// to run them on a dedicated runtime. The returned task is awaited on
+    /// shutdown.
+    pub fn with_worker_spawner(mut self, spawn: WorkerSpawner<E>) -> Self {
+        self.spawn_worker = spawn;
+        self
+    }
+
+    /// Workers publish `ExecutionEvent::Committed` to `events` after each commit.
+    pub fn with_events(mut self, events: Sender<ExecutionEvent>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Workers record committed batch and chunk counts into `counters`.
+    pub fn with_counters(mut self, counters: Counters) -> Self {
+        self.counters = counters;
+        self
+    }
+
+    /// Routes undeliverable batches to `sink` after they are aborted.
+    pub fn with_dead_letter(mut self, sink: Sender<ReservedBatch>) -> Self {
+        self.dead_letter = Some(sink);
+        self
+    }
+
+    /// Number of batches waiting in a pair's worker queue, or `None` if the
+    /// pair has no live worker.
+    ///
+    /// Lets callers hold back reservations when a pair's executor is behind.
+    pub async fn queue_depth(&self, pair_id: &str) -> Option<usize> {
+        self.pair_txs
+            .lock()
+            .await
+            .get(pair_id)
+            .filter(|h| !h.tx.is_closed())
+            .map(|h| h.queued.load(Ordering::SeqCst))
+    }
+
+    /// Pairs that currently have a live worker.
+    pub async fn active_pairs(&self) -> Vec<String> {
+        let mut pairs: Vec<String> = self
+            .pair_txs
+            .lock()
+            .await
+            .iter()
+            .filter(|(_, h)| !h.tx.is_closed())
+            .map(|(pair_id, _)| pair_id.clone())
+            .collect();
+        pairs.sort();
+        pairs
+    }
+
+    /// Circuit breaker state for a pair, or `None` if the pair has not
+    /// received any batch yet.
+    pub fn breaker_state(&self, pair_id: &str) -> Option<BreakerState> {
+        self.breakers
+            .lock()
+            .get(pair_id)
+            .map(|b| b.state(tokio::time::Instant::now()))
+    }
+
+    fn breaker_for(&self, pair_id: &str) -> Arc<CircuitBreaker> {
+        self.breakers
+            .lock()
+            .entry(pair_id.to_string())
+            .or_insert_with(|| {
+                Arc::new(CircuitBreaker::new(
+                    self.cfg.breaker.clone(),
+                    self.cfg.default_failure_cooldown_ms,
+                ))
+            })
+            .clone()
+    }
+
     /// Main router loop.
     ///
     /// This function never mutates session state and never executes swaps.
@@ -101,56 +377,199 @@ impl<E: SwapExecutor> PairExecutorRouter<E> {
         );
 
         while let Some(ev) = rx.recv().await {
-            match ev {
-                ExecutionEvent::Reserved(batch) => {
-                    let pair_id = batch.pair_id.clone();
-                    let batch_id = batch.batch_id;
-
-                    let tx = match self.get_or_spawn_worker(&pair_id).await {
-                        Ok(tx) => tx,
-                        Err(e) => {
-                            error!(
-                                component = "router",
-                                event = "worker_spawn_failure",
-                                %pair_id,
-                                %batch_id,
-                                error = ?e,
-                                "Failed to acquire worker; batch left for recovery"
-                            );
-                            continue;
-                        }
-                    };
+            self.route(ev).await;
+        }
 
-                    debug!(%pair_id, %batch_id, "Routing batch to worker");
+        warn!(
+            component = "router",
+            event = "shutdown",
+            "Router channel closed"
+        );
+    }
 
-                    if let Err(_) = tx.send(batch).await {
-                        // Worker died; remove sender so it can be recreated.
-                        warn!(
-                            component = "router",
-                            event = "worker_send_error",
-                            %pair_id,
-                            "Worker channel closed; purging sender"
-                        );
-                        self.pair_txs.lock().await.remove(&pair_id);
+    /// Like `run`, but stops gracefully once `shutdown` is cancelled.
+    ///
+    /// Shutdown sequence:
+    /// - close the input so no new batches are accepted
+    /// - deliver every batch already buffered in the channel
+    /// - close worker queues and wait for workers to finish their backlog
+    ///
+    /// Batches that could not be sent after the input closed are already
+    /// RESERVED in the DB and are unwound by recovery on restart.
+    pub async fn run_with_shutdown(
+        self: Arc<Self>,
+        mut rx: Receiver<ExecutionEvent>,
+        shutdown: CancellationToken,
+    ) {
+        info!(
+            component = "router",
+            event = "startup",
+            "Execution router started"
+        );
+
+        loop {
+            tokio::select! {
+                ev = rx.recv() => match ev {
+                    Some(ev) => self.route(ev).await,
+                    None => break,
+                },
+                _ = shutdown.cancelled() => break,
+            }
+        }
+
+        rx.close();
+        while let Some(ev) = rx.recv().await {
+            self.route(ev).await;
+        }
+
+        self.drain_workers().await;
+
+        info!(
+            component = "router",
+            event = "shutdown",
+            "Execution router drained"
+        );
+    }
+
+    async fn route(&self, ev: ExecutionEvent) {
+        match ev {
+            ExecutionEvent::Reserved(batch) => self.deliver(batch).await,
+            ExecutionEvent::Committed { batch_id, .. } => {
+                // Outbound only; never routed to workers.
+                debug!(%batch_id, "Ignoring committed event on router input");
+            }
+        }
+    }
+
+    /// Closes every worker queue and waits for the workers to execute what
+    /// they already hold.
+    async fn drain_workers(&self) {
+        self.pair_txs.lock().await.clear();
+
+        let tasks = std::mem::take(&mut *self.worker_tasks.lock());
+        for task in tasks {
+            if let Err(e) = task.await {
+                error!(
+                    component = "router",
+                    event = "worker_join_failure",
+                    error = ?e,
+                    "Worker task failed during shutdown"
+                );
+            }
+        }
+    }
+
+    /// Delivers one batch to its pair worker.
+    ///
+    /// If the worker queue is closed, the sender is purged and delivery is
+    /// retried on a fresh worker. After `max_enqueue_attempts` failures the
+    /// batch is dead-lettered instead of bouncing forever.
+    async fn deliver(&self, mut batch: ReservedBatch) {
+        let pair_id = batch.pair_id.clone();
+        let batch_id = batch.batch_id;
+
+        loop {
+            let worker = match self.get_or_spawn_worker(&pair_id).await {
+                Ok(worker) => worker,
+                Err(e) => {
+                    error!(
+                        component = "router",
+                        event = "worker_spawn_failure",
+                        %pair_id,
+                        %batch_id,
+                        error = ?e,
+                        "Failed to acquire worker; batch left for recovery"
+                    );
+                    return;
+                }
+            };
+
+            debug!(%pair_id, %batch_id, "Routing batch to worker");
+
+            // Counted before sending so the worker's decrement never
+            // observes the batch before its increment.
+            worker.queued.fetch_add(1, Ordering::SeqCst);
+
+            match worker.tx.send(batch).await {
+                Ok(()) => {
+                    self.enqueue_failures.lock().remove(&batch_id);
+                    return;
+                }
+                Err(mpsc::error::SendError(returned)) => {
+                    worker.queued.fetch_sub(1, Ordering::SeqCst);
+                    batch = returned;
+
+                    // Worker died; remove sender so it can be recreated.
+                    warn!(
+                        component = "router",
+                        event = "worker_send_error",
+                        %pair_id,
+                        %batch_id,
+                        "Worker channel closed; purging sender"
+                    );
+                    self.pair_txs.lock().await.remove(&pair_id);
+
+                    let attempts = {
+                        let mut failures = self.enqueue_failures.lock();
+                        let n = failures.entry(batch_id).or_insert(0);
+                        *n += 1;
+                        *n
+                    };
+
+                    if attempts >= self.cfg.max_enqueue_attempts {
+                        self.enqueue_failures.lock().remove(&batch_id);
+                        self.dead_letter(batch, attempts).await;
+                        return;
                     }
                 }
             }
         }
+    }
 
-        warn!(
+    /// Aborts an undeliverable batch in the DB and hands it to the
+    /// dead-letter sink, if one is configured.
+    async fn dead_letter(&self, batch: ReservedBatch, attempts: u32) {
+        error!(
             component = "router",
-            event = "shutdown",
-            "Router channel closed"
+            event = "dead_letter",
+            pair_id = %batch.pair_id,
+            batch_id = %batch.batch_id,
+            attempts,
+            "Batch could not be enqueued; dead-lettering"
         );
+
+        if let Err(e) = abort_batch(self.store.as_ref(), &batch, "dead_letter").await {
+            error!(
+                component = "router",
+                event = "dead_letter_abort_failure",
+                batch_id = %batch.batch_id,
+                error = ?e,
+                "Failed to abort dead-lettered batch; left for recovery"
+            );
+        }
+
+        if let Some(sink) = &self.dead_letter
+            && sink.send(batch).await.is_err()
+        {
+            warn!(
+                component = "router",
+                event = "dead_letter_sink_closed",
+                "Dead-letter sink closed; batch dropped after abort"
+            );
+        }
     }
 
-    /// Returns an existing worker sender or spawns a new worker for this pair.
-    async fn get_or_spawn_worker(&self, pair_id: &str) -> anyhow::Result<Sender<ReservedBatch>> {
-        if let Some(tx) = self.pair_txs.lock().await.get(pair_id) {
-            return Ok(tx.clone());
+    /// Returns an existing worker handle or spawns a new worker for this pair.
+    async fn get_or_spawn_worker(&self, pair_id: &str) -> anyhow::Result<WorkerHandle> {
+        if let Some(h) = self.pair_txs.lock().await.get(pair_id) {
+            return Ok(h.clone());
         }
 
         let (tx, rx) = mpsc::channel(self.per_pair_capacity);
+        let handle = WorkerHandle {
+            tx,
+            queued: Arc::new(AtomicUsize::new(0)),
+        };
 
         self.pair_txs
             .lock()
@@ -161,50 +580,95 @@ impl<E: SwapExecutor> PairExecutorRouter<E> {
                     self.store.clone(),
                     self.market_view.clone(),
                     self.exec.clone(),
-                    self.default_failure_cooldown_ms,
+                    self.cfg.clone(),
                     pair_id.to_string(),
-                );
-
-                tokio::spawn(async move {
-                    worker.run(rx).await;
-                });
-
-                tx.clone()
+                )
+                .with_breaker(self.breaker_for(pair_id))
+                .with_queue_depth(handle.queued.clone())
+                .with_counters(self.counters.clone());
+                let worker = match &self.events {
+                    Some(events) => worker.with_events(events.clone()),
+                    None => worker,
+                };
+
+                let task = (self.spawn_worker)(worker, rx);
+                let mut tasks = self.worker_tasks.lock();
+                tasks.retain(|t| !t.is_finished());
+                tasks.push(task);
+
+                handle.clone()
             });
 
         info!(component = "router", %pair_id, "Spawned new pair worker");
-        Ok(tx)
+        Ok(handle)
     }
 }
 
 /// Executes batches for a single trading pair sequentially.
 ///
 /// This is the **only place** where swaps are executed.
-pub struct ExecutorWorker<E: SwapExecutor> {
+pub struct ExecutorWorker<E: SwapExecutor + ?Sized> {
     store: Arc<SessionStore>,
     market_view: MarketViewStore,
     exec: Arc<E>,
-    default_failure_cooldown_ms: u64,
+    cfg: WorkerConfig,
     pair_id: String,
+    breaker: Option<Arc<CircuitBreaker>>,
+    events: Option<Sender<ExecutionEvent>>,
+    queued: Option<Arc<AtomicUsize>>,
+    counters: Counters,
 }
 
-impl<E: SwapExecutor> ExecutorWorker<E> {
+impl<E: SwapExecutor + ?Sized> ExecutorWorker<E> {
     pub fn new(
         store: Arc<SessionStore>,
         market_view: MarketViewStore,
         exec: Arc<E>,
-        default_failure_cooldown_ms: u64,
+        mut cfg: WorkerConfig,
         pair_id: String,
     ) -> Self {
+        cfg.market_refresh_every_chunks = cfg.market_refresh_every_chunks.max(1);
+        cfg.max_parallel_users = cfg.max_parallel_users.max(1);
+        if let Some(&ms) = cfg.swap_timeout_ms_by_pair.get(&pair_id) {
+            cfg.swap_timeout_ms = ms;
+        }
         Self {
             store,
             market_view,
             exec,
-            default_failure_cooldown_ms,
+            cfg,
             pair_id,
+            breaker: None,
+            events: None,
+            queued: None,
+            counters: Counters::default(),
         }
     }
 
+    /// Decrements the router's queue-depth counter as batches are picked up.
+    pub fn with_queue_depth(mut self, queued: Arc<AtomicUsize>) -> Self {
+        self.queued = Some(queued);
+        self
+    }
+
+    /// Reports batch outcomes to the pair's circuit breaker.
+    pub fn with_breaker(mut self, breaker: Arc<CircuitBreaker>) -> Self {
+        self.breaker = Some(breaker);
+        self
+    }
+
+    /// Records committed batch and chunk counts.
+    pub fn with_counters(mut self, counters: Counters) -> Self {
+        self.counters = counters;
+        self
+    }
+
+    /// Publishes `ExecutionEvent::Committed` after every successful commit.
+    pub fn with_events(mut self, events: Sender<ExecutionEvent>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
     /// Worker loop.
     ///
     /// Executes batches sequentially and never panics.
@@ -212,6 +676,10 @@ impl<E: SwapExecutor> ExecutorWorker<E> {
         info!(component = "worker", %self.pair_id, event = "startup");
 
         while let Some(batch) = rx.recv().await {
+            if let Some(queued) = &self.queued {
+                queued.fetch_sub(1, Ordering::SeqCst);
+            }
+
             let span = info_span!(
                 "batch_execution",
                 pair_id = %self.pair_id,
@@ -229,171 +697,667 @@ impl<E: SwapExecutor> ExecutorWorker<E> {
     /// Executes a single RESERVED batch.
     ///
     /// Invariants:
+    /// - the batch is claimed (`claim_batch`) before anything else; a batch
+    ///   that can no longer be claimed was recovered and is dropped
     /// - no state mutation before `commit_batch`
-    /// - no retries inside a batch
+    /// - no batch-level retries (transient chunk failures follow `RetryPolicy`);
+    ///   only the commit is retried, never execution
     /// - stop on first failure per user
+    /// - one `UserResult` per reserved user, in batch order, even when users
+    ///   run in parallel (`max_parallel_users`)
+    /// - while the pair's breaker is open, every chunk is skipped unexecuted
+    /// - Gate B sees a snapshot at most `market_refresh_every_chunks` chunks old
     async fn execute_batch(&self, batch: ReservedBatch) -> anyhow::Result<()> {
+        if !claim_batch(self.store.as_ref(), &batch).await? {
+            warn!(
+                component = "worker",
+                event = "batch_not_claimed",
+                pair_id = %self.pair_id,
+                batch_id = %batch.batch_id,
+                "Batch no longer RESERVED; dropped unexecuted"
+            );
+            return Ok(());
+        }
+
+        if let Some(breaker) = &self.breaker
+            && !breaker.allow(tokio::time::Instant::now())
+        {
+            warn!(
+                component = "worker",
+                event = "circuit_open",
+                pair_id = %self.pair_id,
+                batch_id = %batch.batch_id,
+                "Circuit open; batch short-circuited"
+            );
+            let results: Vec<_> = batch
+                .users
+                .iter()
+                .map(|u| skip_user(u, "CircuitOpen", None))
+                .collect();
+            self.commit_with_retry(&batch, &results).await?;
+            self.on_committed(&batch, &results);
+            return Ok(());
+        }
+
+        let mut sessions = self.load_sessions(&batch).await;
         let market = self.market_view.get(&batch.pair_id).await;
 
-        let mut results = Vec::with_capacity(batch.users.len());
+        let results = if self.cfg.max_parallel_users > 1 {
+            // Every user tracks its own Gate B refresh cadence; results are
+            // put back in batch order so the commit is independent of timing.
+            let batch = &batch;
+            let mut users = Vec::with_capacity(batch.users.len());
+            for (i, u) in batch.users.iter().enumerate() {
+                let session = sessions.remove(&u.session_id);
+                let mut gate = (market.clone(), 0usize);
+                users.push(
+                    async move { (i, self.execute_user(batch, u, session, &mut gate).await) },
+                );
+            }
+            let mut indexed: Vec<(usize, UserResult)> = futures::stream::iter(users)
+                .buffer_unordered(self.cfg.max_parallel_users)
+                .collect()
+                .await;
+            indexed.sort_by_key(|(i, _)| *i);
+            indexed.into_iter().map(|(_, r)| r).collect()
+        } else {
+            let mut gate = (market, 0usize);
+            let mut results = Vec::with_capacity(batch.users.len());
+            for u in &batch.users {
+                let session = sessions.remove(&u.session_id);
+                results.push(self.execute_user(&batch, u, session, &mut gate).await);
+            }
+            results
+        };
+
+        if let Some(breaker) = &self.breaker {
+            self.record_outcome(breaker, &results);
+        }
 
-        for u in &batch.users {
-            let session = match self.load_session(u.session_id).await {
-                Ok(s) => s,
-                Err(_) => {
-                    results.push(UserResult {
-                        session_id: u.session_id,
-                        chunk_results: u
-                            .chunks
-                            .iter()
-                            .map(|c| ChunkResult {
-                                chunk_id: c.chunk_id,
-                                status: ChunkStatus::Skipped {
-                                    reason: "SESSION_NOT_FOUND".into(),
-                                },
-                            })
-                            .collect(),
-                        cooldown_ms: Some(5_000),
-                    });
-                    continue;
-                }
+        // Single, idempotent DB mutation point
+        self.commit_with_retry(&batch, &results).await?;
+
+        let unresolved = results
+            .iter()
+            .flat_map(|u| &u.chunk_results)
+            .any(|c| matches!(c.status, ChunkStatus::Unknown { .. }));
+        if unresolved {
+            // `commit_batch` recorded what is known and left the batch
+            // RESERVED; recovery settles it.
+            warn!(
+                component = "worker",
+                event = "batch_unresolved",
+                pair_id = %self.pair_id,
+                batch_id = %batch.batch_id,
+                "Chunk outcome unknown; batch left RESERVED for recovery"
+            );
+            return Ok(());
+        }
+
+        self.on_committed(&batch, &results);
+        Ok(())
+    }
+
+    /// Commits `results`, retrying failed attempts with backoff.
+    ///
+    /// Chunks have already executed at this point, so a batch left RESERVED
+    /// would be unwound by restart recovery and lose its on-chain effect.
+    /// After `max_commit_attempts` failures the batch is dead-lettered with
+    /// its results for manual reconciliation instead.
+    async fn commit_with_retry(
+        &self,
+        batch: &ReservedBatch,
+        results: &[UserResult],
+    ) -> anyhow::Result<()> {
+        let mut attempt = 1u32;
+        loop {
+            let started = std::time::Instant::now();
+            let res = commit_batch(self.store.as_ref(), batch, results).await;
+            self.counters.commit_batch_latency.record(started.elapsed());
+            let err = match res {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
             };
 
-            if !session.active {
-                results.push(UserResult {
-                    session_id: u.session_id,
-                    chunk_results: u
-                        .chunks
-                        .iter()
-                        .map(|c| ChunkResult {
-                            chunk_id: c.chunk_id,
-                            status: ChunkStatus::Skipped {
-                                reason: "SESSION_INACTIVE".into(),
-                            },
-                        })
-                        .collect(),
-                    cooldown_ms: None,
-                });
-                continue;
+            if let Err(e) = record_commit_failure(self.store.as_ref(), batch).await {
+                warn!(
+                    component = "worker",
+                    event = "commit_attempt_not_recorded",
+                    batch_id = %batch.batch_id,
+                    error = %e,
+                    "Failed to record commit attempt"
+                );
+            }
+
+            if attempt >= self.cfg.max_commit_attempts {
+                error!(
+                    component = "worker",
+                    event = "commit_dead_letter",
+                    batch_id = %batch.batch_id,
+                    attempts = attempt,
+                    error = %err,
+                    "Commit failed repeatedly; dead-lettering batch"
+                );
+                if let Err(e) =
+                    dead_letter_batch(self.store.as_ref(), batch, results, &err.to_string()).await
+                {
+                    error!(
+                        component = "worker",
+                        event = "commit_dead_letter_failure",
+                        batch_id = %batch.batch_id,
+                        error = %e,
+                        "Failed to dead-letter batch; it stays RESERVED"
+                    );
+                }
+                return Err(err.into());
             }
 
+            self.counters
+                .exec_commit_retries
+                .fetch_add(1, Ordering::Relaxed);
+            let backoff_ms = self.cfg.retry.backoff_ms(attempt);
+            warn!(
+                component = "worker",
+                event = "commit_retry",
+                batch_id = %batch.batch_id,
+                attempt,
+                backoff_ms,
+                error = %err,
+                "Commit failed; retrying"
+            );
+            tokio::time::sleep(std::time::Duration::from_millis(backoff_ms)).await;
+            attempt += 1;
+        }
+    }
+
+    /// Executes one user's chunks in order and stops on their first failure.
+    ///
+    /// `gate` holds the Gate B snapshot and the number of chunks since it was
+    /// read; sequential execution shares it across the batch's users.
+    async fn execute_user(
+        &self,
+        batch: &ReservedBatch,
+        u: &ReservedUser,
+        session: Option<Session>,
+        gate: &mut (Option<MarketMetricsView>, usize),
+    ) -> UserResult {
+        let Some(session) = session else {
+            return skip_user(u, "SESSION_NOT_FOUND", Some(5_000));
+        };
+
+        if !session.active {
+            return skip_user(u, "SESSION_INACTIVE", None);
+        }
+
+        let (chunk_results, failed) = if self.cfg.max_concurrent_chunks > 1 {
+            self.execute_chunks_concurrently(&batch.pair_id, batch.batch_id, u, &session)
+                .await
+        } else {
+            let (market, chunks_since_refresh) = gate;
             let mut chunk_results = Vec::new();
             let mut failed = false;
 
             for ch in &u.chunks {
-                if !gate_b_ok(&session, market.as_ref()) {
-                    chunk_results.push(ChunkResult {
-                        chunk_id: ch.chunk_id,
-                        status: ChunkStatus::Skipped {
-                            reason: "GATE_B_CONSTRAINTS".into(),
-                        },
-                    });
+                if *chunks_since_refresh >= self.cfg.market_refresh_every_chunks {
+                    *market = self.market_view.get(&batch.pair_id).await;
+                    *chunks_since_refresh = 0;
+                }
+                *chunks_since_refresh += 1;
+
+                if !gate_b_ok(
+                    &session,
+                    market.as_ref(),
+                    now_ms(),
+                    self.cfg.max_snapshot_age_ms,
+                ) {
+                    chunk_results.push(gate_b_skipped(ch));
                     break;
                 }
 
-                match self
-                    .exec
-                    .execute_swap(super::types::SwapCall {
-                        pair_id: batch.pair_id.clone(),
-                        session_id: u.session_id,
-                        bid: ch.bid,
-                        chunk_id: ch.chunk_id,
-                    })
-                    .await
-                {
-                    Ok(rcpt) => {
-                        chunk_results.push(ChunkResult {
-                            chunk_id: ch.chunk_id,
-                            status: ChunkStatus::Success { tx_id: rcpt.tx_id },
-                        });
-                    }
-                    Err(e) => {
-                        failed = true;
-                        chunk_results.push(ChunkResult {
-                            chunk_id: ch.chunk_id,
-                            status: ChunkStatus::Failed {
-                                reason: classify_error(&e),
-                            },
-                        });
-                        break;
-                    }
+                let res = self
+                    .swap_chunk(&batch.pair_id, batch.batch_id, u.session_id, ch)
+                    .await;
+                failed = stops_user(&res.status);
+                chunk_results.push(res);
+
+                if failed {
+                    break;
                 }
             }
 
-            results.push(UserResult {
-                session_id: u.session_id,
-                chunk_results,
-                cooldown_ms: failed.then_some(self.default_failure_cooldown_ms),
-            });
+            (chunk_results, failed)
+        };
+
+        UserResult {
+            session_id: u.session_id,
+            chunk_results,
+            cooldown_ms: failed.then(|| {
+                session
+                    .intent
+                    .failure_cooldown_ms
+                    .unwrap_or(self.cfg.default_failure_cooldown_ms)
+            }),
         }
+    }
 
-        // Single, idempotent DB mutation point
-        commit_batch(self.store.as_ref(), &batch, &results).await?;
-        Ok(())
+    /// Records counters and publishes the committed event.
+    /// Best-effort; never blocks or fails execution.
+    fn on_committed(&self, batch: &ReservedBatch, results: &[UserResult]) {
+        let summary = CommitSummary::from_results(batch, results);
+
+        let c = &self.counters;
+        c.exec_batches_committed.fetch_add(1, Ordering::Relaxed);
+        c.exec_chunks_executed.fetch_add(
+            (summary.succeeded + summary.simulated) as u64,
+            Ordering::Relaxed,
+        );
+        c.exec_chunks_failed
+            .fetch_add(summary.failed as u64, Ordering::Relaxed);
+        c.exec_chunks_skipped
+            .fetch_add(summary.skipped as u64, Ordering::Relaxed);
+        for cr in results.iter().flat_map(|u| &u.chunk_results) {
+            let (outcome, reason) = match &cr.status {
+                ChunkStatus::Success { .. } => ("success", ""),
+                ChunkStatus::Simulated => ("simulated", ""),
+                ChunkStatus::Failed { reason } => ("failed", reason.as_str()),
+                ChunkStatus::Skipped { reason } => ("skipped", reason.as_str()),
+                ChunkStatus::Unknown { reason } => ("unknown", reason.as_str()),
+            };
+            c.record_chunk_outcome(&batch.pair_id, outcome, reason);
+        }
+
+        let Some(events) = &self.events else {
+            return;
+        };
+
+        let ev = ExecutionEvent::Committed {
+            batch_id: batch.batch_id,
+            pair_id: batch.pair_id.clone(),
+            results_summary: summary,
+        };
+
+        if let Err(e) = events.try_send(ev) {
+            warn!(
+                component = "worker",
+                event = "committed_event_dropped",
+                batch_id = %batch.batch_id,
+                error = %e,
+                "Committed event not delivered"
+            );
+        }
     }
 
-    async fn load_session(&self, session_id: uuid::Uuid) -> anyhow::Result<Session> {
-        if let Some(s) = self.store.get_cached(&session_id) {
-            return Ok(s);
+    /// Feeds one batch outcome into the circuit breaker.
+    fn record_outcome(&self, breaker: &CircuitBreaker, results: &[UserResult]) {
+        let statuses = || {
+            results
+                .iter()
+                .flat_map(|u| &u.chunk_results)
+                .map(|c| &c.status)
+        };
+
+        let failed = statuses().any(stops_user);
+        let succeeded =
+            statuses().any(|s| matches!(s, ChunkStatus::Success { .. } | ChunkStatus::Simulated));
+
+        let outcome = match (failed, succeeded) {
+            (true, true) => BatchOutcome::PartialFailure,
+            (true, false) => BatchOutcome::Failure,
+            (false, true) => BatchOutcome::Success,
+            (false, false) => BatchOutcome::Inconclusive,
+        };
+
+        if breaker.record(outcome, tokio::time::Instant::now()) {
+            warn!(
+                component = "worker",
+                event = "circuit_tripped",
+                pair_id = %self.pair_id,
+                ?outcome,
+                "Pair failing persistently; circuit opened"
+            );
         }
-        let s = self.store.load_by_id(&session_id).await?;
-        self.store.upsert_cache(s.clone());
-        Ok(s)
     }
-}
 
-/// Gate B: final constraint enforcement right before execution.
-/// Missing market data fails closed.
-fn gate_b_ok(session: &Session, market: Option<&crate::market::types::MarketMetricsView>) -> bool {
-    let m = match market {
-        Some(m) => m,
-        None => return false,
-    };
+    /// Runs up to `max_concurrent_chunks` swaps of one allocation in parallel.
+    ///
+    /// Gate B is evaluated once, against a fresh snapshot, before any chunk is
+    /// issued. After the first failure no further chunks are issued; swaps that
+    /// are already submitted are awaited so their outcome is still recorded.
+    /// Results are ordered by `chunk_id`.
+    async fn execute_chunks_concurrently(
+        &self,
+        pair_id: &str,
+        batch_id: uuid::Uuid,
+        u: &ReservedUser,
+        session: &Session,
+    ) -> (Vec<ChunkResult>, bool) {
+        let market = self.market_view.get(pair_id).await;
+        if !gate_b_ok(
+            session,
+            market.as_ref(),
+            now_ms(),
+            self.cfg.max_snapshot_age_ms,
+        ) {
+            return (
+                u.chunks.first().map(gate_b_skipped).into_iter().collect(),
+                false,
+            );
+        }
 
-    m.spread_bps <= session.intent.constraints.max_spread_bps
-        && m.trend_drop_bps <= session.intent.constraints.max_trend_drop_bps
-}
+        let mut not_issued = u.chunks.iter();
+        let mut in_flight = FuturesUnordered::new();
+        let mut chunk_results = Vec::with_capacity(u.chunks.len());
+        let mut failed = false;
 
-/// Normalizes executor errors into stable bounded strings.
-fn classify_error(e: &anyhow::Error) -> String {
-    let s = e.to_string();
-    if s.contains("MarketNotOpen") {
-        return "MarketNotOpen".into();
-    }
-    if s.contains("Slippage") {
-        return "Slippage".into();
-    }
-    if s.contains("InsufficientLiquidity") {
-        return "InsufficientLiquidity".into();
-    }
+        loop {
+            while !failed && in_flight.len() < self.cfg.max_concurrent_chunks {
+                match not_issued.next() {
+                    Some(ch) => {
+                        in_flight.push(self.swap_chunk(pair_id, batch_id, u.session_id, ch))
+                    }
+                    None => break,
+                }
+            }
 
-    const MAX: usize = 160;
-    if s.len() > MAX {
-        format!("ERR:{}", &s[..MAX])
-    } else {
-        s
-    }
-}
+            match in_flight.next().await {
+                Some(res) => {
+                    failed |= stops_user(&res.status);
+                    chunk_results.push(res);
+                }
+                None => break,
+            }
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::sync::Arc;
-    use std::sync::atomic::{AtomicUsize, Ordering};
-    use tokio::sync::mpsc;
-    use tokio::time::{Duration, sleep};
-    use uuid::Uuid;
+        chunk_results.sort_by_key(|r| r.chunk_id);
+        (chunk_results, failed)
+    }
 
-    use async_trait::async_trait;
+    /// Executes one chunk and maps the outcome into a `ChunkResult`.
+    /// Each call is bounded by `swap_timeout_ms`. A timeout, whether hit here
+    /// or reported by the executor, is settled via `resolve_timed_out` and
+    /// retried per `RetryPolicy` (with exponential backoff) only if the swap
+    /// is confirmed not to have landed.
+    /// In safe mode the call is only recorded, never submitted.
+    async fn swap_chunk(
+        &self,
+        pair_id: &str,
+        batch_id: uuid::Uuid,
+        session_id: uuid::Uuid,
+        ch: &ReservedChunk,
+    ) -> ChunkResult {
+        let mut call = super::types::SwapCall {
+            pair_id: pair_id.to_string(),
+            session_id,
+            bid: ch.bid,
+            chunk_id: ch.chunk_id,
+            deadline_ms: 0,
+            idempotency_key: super::types::SwapCall::idempotency_key_for(batch_id, ch.chunk_id),
+        };
+
+        if self.cfg.safe_mode {
+            info!(
+                component = "worker",
+                event = "simulated_swap",
+                pair_id = %call.pair_id,
+                session_id = %call.session_id,
+                chunk_id = %call.chunk_id,
+                bid = %call.bid,
+                "Safe mode: swap not submitted"
+            );
+            return ChunkResult {
+                chunk_id: ch.chunk_id,
+                status: ChunkStatus::Simulated,
+            };
+        }
 
-    use crate::execution::types::{ReservedChunk, ReservedUser};
-    use crate::execution::types::{SwapCall, SwapReceipt};
-    use crate::market::market_view_store::MarketViewStore;
-    use crate::session::model::{Session, SessionIntent, SessionState, UserConstraints};
-    use crate::session::repository::SessionRepository;
-    use crate::session::store::SessionStore;
+        let mut attempt = 1u32;
+        let timeout = std::time::Duration::from_millis(self.cfg.swap_timeout_ms);
+        let status = loop {
+            call.deadline_ms = now_ms().saturating_add(self.cfg.swap_timeout_ms);
 
-    /// Create a cache-only SessionStore with a single session.
+            let err =
+                match tokio::time::timeout(timeout, self.exec.execute_swap(call.clone())).await {
+                    Ok(Ok(rcpt)) => break ChunkStatus::Success { tx_id: rcpt.tx_id },
+                    Ok(Err(e)) => e,
+                    Err(_) => {
+                        warn!(
+                            component = "worker",
+                            event = "swap_timeout",
+                            chunk_id = %ch.chunk_id,
+                            timeout_ms = self.cfg.swap_timeout_ms,
+                            "Swap did not complete in time; checking whether it landed"
+                        );
+                        SwapError::Timeout
+                    }
+                };
+
+            // A timed-out swap may still have landed, so it is only resubmitted
+            // once `query_status` has confirmed it did not.
+            let status = match err {
+                SwapError::Timeout => self.resolve_timed_out(&call).await,
+                ref e => ChunkStatus::Failed {
+                    reason: classify_error(e),
+                },
+            };
+            if !(err.is_retryable()
+                && matches!(status, ChunkStatus::Failed { .. })
+                && attempt < self.cfg.retry.max_attempts)
+            {
+                break status;
+            }
+
+            self.counters
+                .exec_swap_retries
+                .fetch_add(1, Ordering::Relaxed);
+            let backoff_ms = self.cfg.retry.backoff_ms(attempt);
+            warn!(
+                component = "worker",
+                event = "swap_retry",
+                chunk_id = %ch.chunk_id,
+                attempt,
+                backoff_ms,
+                error = %err,
+                "Transient swap failure; retrying chunk"
+            );
+            tokio::time::sleep(std::time::Duration::from_millis(backoff_ms)).await;
+            attempt += 1;
+        };
+
+        ChunkResult {
+            chunk_id: ch.chunk_id,
+            status,
+        }
+    }
+
+    /// Outcome of a swap abandoned at its deadline.
+    ///
+    /// Nothing can land after `deadline_ms`, so once it has passed
+    /// `query_status` is authoritative: a landed swap is a success, anything
+    /// else a `Timeout` failure. A lookup that errors or hangs leaves the
+    /// outcome unknown, for recovery to settle.
+    async fn resolve_timed_out(&self, call: &super::types::SwapCall) -> ChunkStatus {
+        let wait_ms = call.deadline_ms.saturating_sub(now_ms());
+        if wait_ms > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(wait_ms)).await;
+        }
+
+        let timeout = std::time::Duration::from_millis(self.cfg.swap_timeout_ms);
+        let err = match tokio::time::timeout(timeout, self.exec.query_status(call.chunk_id)).await {
+            Ok(Ok(Some(rcpt))) => {
+                info!(
+                    component = "worker",
+                    event = "timed_out_swap_landed",
+                    chunk_id = %call.chunk_id,
+                    tx_id = %rcpt.tx_id,
+                    "Timed-out swap had landed"
+                );
+                return ChunkStatus::Success { tx_id: rcpt.tx_id };
+            }
+            Ok(Ok(None)) => {
+                return ChunkStatus::Failed {
+                    reason: "Timeout".into(),
+                };
+            }
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => "status lookup timed out".to_string(),
+        };
+
+        warn!(
+            component = "worker",
+            event = "swap_outcome_unknown",
+            chunk_id = %call.chunk_id,
+            error = %err,
+            "Timed-out swap status unknown; leaving chunk for recovery"
+        );
+        ChunkStatus::Unknown {
+            reason: "Timeout".into(),
+        }
+    }
+
+    /// Resolves every session in the batch before the chunk loop: cached
+    /// sessions are used as-is and the rest are fetched in one round-trip.
+    /// Sessions that cannot be loaded are absent (=> `SESSION_NOT_FOUND`).
+    async fn load_sessions(&self, batch: &ReservedBatch) -> HashMap<Uuid, Session> {
+        let mut sessions = HashMap::with_capacity(batch.users.len());
+        let mut missing = Vec::new();
+
+        for u in &batch.users {
+            match self.store.get_cached(&u.session_id) {
+                Some(s) => {
+                    sessions.insert(u.session_id, s);
+                }
+                None => missing.push(u.session_id),
+            }
+        }
+
+        if missing.is_empty() {
+            return sessions;
+        }
+
+        match self.store.load_by_ids(&missing).await {
+            Ok(loaded) => {
+                for s in loaded.into_values() {
+                    self.store.upsert_cache(s.clone());
+                    sessions.insert(s.session_id, s);
+                }
+            }
+            Err(e) => warn!(
+                component = "worker",
+                pair_id = %self.pair_id,
+                batch_id = %batch.batch_id,
+                error = %e,
+                "Failed to load batch sessions"
+            ),
+        }
+
+        sessions
+    }
+}
+
+/// Skips every chunk of an allocation without executing it.
+fn skip_user(u: &ReservedUser, reason: &str, cooldown_ms: Option<u64>) -> UserResult {
+    UserResult {
+        session_id: u.session_id,
+        chunk_results: u
+            .chunks
+            .iter()
+            .map(|c| ChunkResult {
+                chunk_id: c.chunk_id,
+                status: ChunkStatus::Skipped {
+                    reason: reason.into(),
+                },
+            })
+            .collect(),
+        cooldown_ms,
+    }
+}
+
+/// Outcomes after which a user's remaining chunks are not issued.
+fn stops_user(status: &ChunkStatus) -> bool {
+    matches!(
+        status,
+        ChunkStatus::Failed { .. } | ChunkStatus::Unknown { .. }
+    )
+}
+
+fn gate_b_skipped(ch: &ReservedChunk) -> ChunkResult {
+    ChunkResult {
+        chunk_id: ch.chunk_id,
+        status: ChunkStatus::Skipped {
+            reason: "GATE_B_CONSTRAINTS".into(),
+        },
+    }
+}
+
+/// Gate B: final constraint enforcement right before execution.
+/// Missing or stale (older than `max_snapshot_age_ms`) market data fails closed.
+fn gate_b_ok(
+    session: &Session,
+    market: Option<&MarketMetricsView>,
+    now_ms: u64,
+    max_snapshot_age_ms: u64,
+) -> bool {
+    let m = match market {
+        Some(m) if m.is_fresh(now_ms, max_snapshot_age_ms) => m,
+        _ => return false,
+    };
+
+    let c = &session.intent.constraints;
+    m.spread_bps <= c.max_spread_bps
+        && m.trend_drop_bps <= c.max_trend_drop_bps
+        && m.slippage_bps.is_none_or(|s| s <= c.max_slippage_bps)
+}
+
+/// Normalizes executor errors into stable bounded strings.
+/// Only `Other` carries free-form text, which is truncated.
+fn classify_error(e: &SwapError) -> String {
+    match e {
+        SwapError::MarketNotOpen => "MarketNotOpen".into(),
+        SwapError::Slippage => "Slippage".into(),
+        SwapError::InsufficientLiquidity => "InsufficientLiquidity".into(),
+        SwapError::Rejected { code } => format!("Rejected:{code}"),
+        SwapError::Timeout => "Timeout".into(),
+        SwapError::Other(msg) => truncate_reason(msg),
+    }
+}
+
+/// Bounds free-form failure reasons (char-boundary safe).
+fn truncate_reason(s: &str) -> String {
+    const MAX: usize = 160;
+    if s.len() > MAX {
+        let mut end = MAX;
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("ERR:{}", &s[..end])
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::sync::mpsc;
+    use tokio::time::{Duration, sleep};
+    use uuid::Uuid;
+
+    use async_trait::async_trait;
+    use parking_lot::Mutex as PlMutex;
+
+    use crate::error::{ReassignPairError, RepositoryError};
+    use crate::execution::types::{
+        ChunkEvent, PendingChunk, RecoveryReport, ReservedChunk, ReservedUser,
+    };
+    use crate::execution::types::{SwapCall, SwapReceipt};
+    use crate::market::market_view_store::MarketViewStore;
+    use crate::session::model::{Session, SessionIntent, SessionState, UserConstraints};
+    use crate::session::repository::SessionRepository;
+    use crate::session::store::SessionStore;
+
+    /// Create a cache-only SessionStore with a single session.
     ///
     /// - No DB
     /// - No paging
@@ -402,19 +1366,57 @@ mod tests {
     /// This is intentionally a free function (not `new`) to satisfy Rust
     /// conventions and Clippy rules.
     fn make_test_store(session: Session) -> Arc<SessionStore> {
-        struct DummyRepo;
+        make_recording_store(session).0
+    }
+
+    /// Like `make_test_store`, but also returns every `UserResult` passed to
+    /// `commit_batch` so tests can inspect per-chunk outcomes.
+    fn make_recording_store(
+        session: Session,
+    ) -> (Arc<SessionStore>, Arc<PlMutex<Vec<UserResult>>>) {
+        struct DummyRepo {
+            committed: Arc<PlMutex<Vec<UserResult>>>,
+        }
 
         #[async_trait]
         impl SessionRepository for DummyRepo {
-            async fn fetch_page(&self, _: usize, _: usize) -> anyhow::Result<Vec<Session>> {
+            async fn fetch_page(
+                &self,
+                _: usize,
+                _: usize,
+                _: Option<&str>,
+            ) -> Result<Vec<Session>, RepositoryError> {
+                Ok(vec![])
+            }
+            async fn fetch_page_after(
+                &self,
+                _: usize,
+                _: Option<Uuid>,
+                _: Option<&str>,
+            ) -> Result<Vec<Session>, RepositoryError> {
                 Ok(vec![])
             }
 
-            async fn fetch_by_id(&self, _: &Uuid) -> anyhow::Result<Option<Session>> {
+            async fn fetch_by_id(&self, _: &Uuid) -> Result<Option<Session>, RepositoryError> {
                 Ok(None)
             }
+            async fn fetch_by_ids(
+                &self,
+                _: &[Uuid],
+            ) -> Result<HashMap<Uuid, Session>, RepositoryError> {
+                Ok(HashMap::new())
+            }
+
+            async fn expire_due(&self, _: u64) -> Result<u64, RepositoryError> {
+                Ok(0)
+            }
 
-            async fn persist_fairness(&self, _: &Uuid, _: i128, _: u64) -> anyhow::Result<()> {
+            async fn persist_fairness(
+                &self,
+                _: &Uuid,
+                _: i128,
+                _: u64,
+            ) -> Result<(), RepositoryError> {
                 Ok(())
             }
 
@@ -423,26 +1425,80 @@ mod tests {
                 _: &str,
                 _: u64,
                 _: &[crate::planner::types::PlannedAllocation],
-            ) -> anyhow::Result<Option<ReservedBatch>> {
+            ) -> Result<Option<ReservedBatch>, RepositoryError> {
                 unreachable!("not used in executor unit tests")
             }
 
+            async fn claim_batch(&self, _: &Uuid) -> Result<bool, RepositoryError> {
+                Ok(true)
+            }
             async fn commit_batch(
                 &self,
                 _: &ReservedBatch,
-                _: &[UserResult],
-            ) -> anyhow::Result<()> {
+                results: &[UserResult],
+            ) -> Result<(), RepositoryError> {
+                self.committed.lock().extend_from_slice(results);
                 Ok(())
             }
 
-            async fn recover_uncommitted(&self) -> anyhow::Result<()> {
+            async fn pending_chunks(&self) -> Result<Vec<PendingChunk>, RepositoryError> {
+                Ok(Vec::new())
+            }
+
+            async fn settle_landed_chunk(
+                &self,
+                _: &Uuid,
+                _: &Uuid,
+                _: &str,
+            ) -> Result<bool, RepositoryError> {
+                Ok(false)
+            }
+
+            async fn recover_uncommitted(&self) -> Result<RecoveryReport, RepositoryError> {
+                Ok(RecoveryReport::default())
+            }
+            async fn recover_stale(&self, _: u64) -> Result<RecoveryReport, RepositoryError> {
+                Ok(RecoveryReport::default())
+            }
+            async fn record_commit_failure(&self, _: &Uuid) -> Result<(), RepositoryError> {
+                Ok(())
+            }
+            async fn dead_letter_batch(
+                &self,
+                _: &ReservedBatch,
+                _: &[UserResult],
+                _: &str,
+            ) -> Result<(), RepositoryError> {
+                Ok(())
+            }
+            async fn abort_batch(&self, _: &Uuid, _: &str) -> Result<(), RepositoryError> {
+                Ok(())
+            }
+            async fn fetch_chunk_history(
+                &self,
+                _: &Uuid,
+                _: usize,
+            ) -> Result<Vec<ChunkEvent>, RepositoryError> {
+                Ok(Vec::new())
+            }
+            async fn reassign_pair(&self, _: &Uuid, _: &str) -> Result<(), ReassignPairError> {
                 Ok(())
             }
         }
 
-        let store = SessionStore::new(Arc::new(DummyRepo));
+        let committed = Arc::new(PlMutex::new(Vec::new()));
+        let store = SessionStore::new(Arc::new(DummyRepo {
+            committed: committed.clone(),
+        }));
         store.upsert_cache(session);
-        Arc::new(store)
+        (Arc::new(store), committed)
+    }
+
+    fn test_cfg() -> WorkerConfig {
+        WorkerConfig {
+            default_failure_cooldown_ms: 5_000,
+            ..Default::default()
+        }
     }
 
     fn mk_session(id: Uuid) -> Session {
@@ -458,6 +1514,11 @@ mod tests {
                 },
                 preferred_chunk_bid: 100,
                 max_bid_per_tick: 1_000,
+                active_windows: Vec::new(),
+                quantum_weight: 1,
+                twap_interval_ms: 0,
+                expires_at_ms: 0,
+                failure_cooldown_ms: None,
             },
             state: SessionState {
                 remaining_bid: 1_000,
@@ -493,17 +1554,19 @@ mod tests {
     struct MockExecutor {
         calls: AtomicUsize,
         fail_on_call: Option<usize>,
+        fail_with: SwapError,
     }
 
     #[async_trait]
     impl SwapExecutor for MockExecutor {
-        async fn execute_swap(&self, _: SwapCall) -> anyhow::Result<SwapReceipt> {
+        async fn execute_swap(&self, _: SwapCall) -> Result<SwapReceipt, SwapError> {
             let n = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
             if self.fail_on_call == Some(n) {
-                Err(anyhow::anyhow!("MarketNotOpen"))
+                Err(self.fail_with.clone())
             } else {
                 Ok(SwapReceipt {
                     tx_id: format!("tx-{n}"),
+                    idempotency_key: None,
                 })
             }
         }
@@ -517,13 +1580,14 @@ mod tests {
         let exec = Arc::new(MockExecutor {
             calls: AtomicUsize::new(0),
             fail_on_call: None,
+            fail_with: SwapError::MarketNotOpen,
         });
 
         let worker = ExecutorWorker::new(
             store,
             MarketViewStore::new(), // no market snapshot
             exec.clone(),
-            5_000,
+            test_cfg(),
             "TON/USDT".into(),
         );
 
@@ -544,6 +1608,7 @@ mod tests {
         let exec = Arc::new(MockExecutor {
             calls: AtomicUsize::new(0),
             fail_on_call: Some(1),
+            fail_with: SwapError::MarketNotOpen,
         });
 
         let market_view = MarketViewStore::new();
@@ -551,16 +1616,22 @@ mod tests {
             .set(
                 "TON/USDT",
                 crate::market::types::MarketMetricsView {
-                    ts_ms: 0,
+                    ts_ms: now_ms(),
                     spread_bps: 5.0,
                     trend_drop_bps: 5.0,
                     max_depth: 1_000,
+                    slippage_bps: None,
                 },
             )
             .await;
 
-        let worker =
-            ExecutorWorker::new(store, market_view, exec.clone(), 5_000, "TON/USDT".into());
+        let worker = ExecutorWorker::new(
+            store,
+            market_view,
+            exec.clone(),
+            test_cfg(),
+            "TON/USDT".into(),
+        );
 
         worker.execute_batch(mk_batch(id, 2)).await.unwrap();
 
@@ -583,13 +1654,14 @@ mod tests {
         let exec = Arc::new(MockExecutor {
             calls: AtomicUsize::new(0),
             fail_on_call: None,
+            fail_with: SwapError::MarketNotOpen,
         });
 
         let worker = ExecutorWorker::new(
             store,
             MarketViewStore::new(),
             exec.clone(),
-            5_000,
+            test_cfg(),
             "TON/USDT".into(),
         );
 
@@ -610,13 +1682,14 @@ mod tests {
         let exec = Arc::new(MockExecutor {
             calls: AtomicUsize::new(0),
             fail_on_call: None,
+            fail_with: SwapError::MarketNotOpen,
         });
 
         let router = Arc::new(PairExecutorRouter::new(
             store,
             MarketViewStore::new(),
             exec,
-            5_000,
+            test_cfg(),
             8,
         ));
 
@@ -640,6 +1713,52 @@ mod tests {
         sleep(Duration::from_millis(50)).await;
     }
 
+    #[tokio::test]
+    async fn router_shutdown_drains_buffered_batches() {
+        let id = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(id));
+
+        let router = Arc::new(PairExecutorRouter::new(
+            store,
+            MarketViewStore::new(),
+            Arc::new(MockExecutor {
+                calls: AtomicUsize::new(0),
+                fail_on_call: None,
+                fail_with: SwapError::MarketNotOpen,
+            }),
+            test_cfg(),
+            8,
+        ));
+
+        let (tx, rx) = mpsc::channel(8);
+        for _ in 0..3 {
+            tx.send(ExecutionEvent::Reserved(mk_batch(id, 1)))
+                .await
+                .unwrap();
+        }
+
+        // Cancelled before the router starts: buffered batches still reach
+        // the worker and are committed before `run_with_shutdown` returns.
+        let shutdown = CancellationToken::new();
+        shutdown.cancel();
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            router.clone().run_with_shutdown(rx, shutdown),
+        )
+        .await
+        .expect("router did not drain");
+
+        assert_eq!(committed.lock().len(), 3);
+        assert!(router.active_pairs().await.is_empty());
+
+        // Input is closed; late batches are left for recovery.
+        assert!(
+            tx.send(ExecutionEvent::Reserved(mk_batch(id, 1)))
+                .await
+                .is_err()
+        );
+    }
+
     #[tokio::test(start_paused = true)]
     async fn router_recreates_worker_with_virtual_time() {
         use tokio::time::advance;
@@ -650,13 +1769,14 @@ mod tests {
         let exec = Arc::new(MockExecutor {
             calls: AtomicUsize::new(0),
             fail_on_call: None,
+            fail_with: SwapError::MarketNotOpen,
         });
 
         let router = Arc::new(PairExecutorRouter::new(
             store,
             MarketViewStore::new(),
             exec,
-            5_000,
+            test_cfg(),
             1, // small capacity to stress lifecycle
         ));
 
@@ -679,19 +1799,52 @@ mod tests {
         advance(Duration::from_secs(1)).await;
     }
 
-    #[tokio::test]
+    #[tokio::test(start_paused = true)]
     async fn commit_failure_does_not_double_execute() {
-        struct FailingCommitRepo;
+        #[derive(Default)]
+        struct FailingCommitRepo {
+            commits: AtomicUsize,
+            recorded: AtomicUsize,
+            dead_lettered: PlMutex<Vec<(Uuid, usize)>>,
+        }
 
         #[async_trait]
         impl SessionRepository for FailingCommitRepo {
-            async fn fetch_page(&self, _: usize, _: usize) -> anyhow::Result<Vec<Session>> {
+            async fn fetch_page(
+                &self,
+                _: usize,
+                _: usize,
+                _: Option<&str>,
+            ) -> Result<Vec<Session>, RepositoryError> {
                 Ok(vec![])
             }
-            async fn fetch_by_id(&self, _: &Uuid) -> anyhow::Result<Option<Session>> {
+            async fn fetch_page_after(
+                &self,
+                _: usize,
+                _: Option<Uuid>,
+                _: Option<&str>,
+            ) -> Result<Vec<Session>, RepositoryError> {
+                Ok(vec![])
+            }
+            async fn fetch_by_id(&self, _: &Uuid) -> Result<Option<Session>, RepositoryError> {
                 Ok(None)
             }
-            async fn persist_fairness(&self, _: &Uuid, _: i128, _: u64) -> anyhow::Result<()> {
+            async fn fetch_by_ids(
+                &self,
+                _: &[Uuid],
+            ) -> Result<HashMap<Uuid, Session>, RepositoryError> {
+                Ok(HashMap::new())
+            }
+            async fn expire_due(&self, _: u64) -> Result<u64, RepositoryError> {
+                Ok(0)
+            }
+
+            async fn persist_fairness(
+                &self,
+                _: &Uuid,
+                _: i128,
+                _: u64,
+            ) -> Result<(), RepositoryError> {
                 Ok(())
             }
             async fn reserve_execution(
@@ -699,28 +1852,78 @@ mod tests {
                 _: &str,
                 _: u64,
                 _: &[crate::planner::types::PlannedAllocation],
-            ) -> anyhow::Result<Option<ReservedBatch>> {
+            ) -> Result<Option<ReservedBatch>, RepositoryError> {
                 unreachable!()
             }
+            async fn claim_batch(&self, _: &Uuid) -> Result<bool, RepositoryError> {
+                Ok(true)
+            }
             async fn commit_batch(
                 &self,
                 _: &ReservedBatch,
                 _: &[UserResult],
-            ) -> anyhow::Result<()> {
-                Err(anyhow::anyhow!("DB down"))
+            ) -> Result<(), RepositoryError> {
+                self.commits.fetch_add(1, Ordering::SeqCst);
+                Err(RepositoryError::Db(sqlx::Error::Protocol("DB down".into())))
+            }
+            async fn pending_chunks(&self) -> Result<Vec<PendingChunk>, RepositoryError> {
+                Ok(Vec::new())
+            }
+
+            async fn settle_landed_chunk(
+                &self,
+                _: &Uuid,
+                _: &Uuid,
+                _: &str,
+            ) -> Result<bool, RepositoryError> {
+                Ok(false)
+            }
+
+            async fn recover_uncommitted(&self) -> Result<RecoveryReport, RepositoryError> {
+                Ok(RecoveryReport::default())
+            }
+            async fn recover_stale(&self, _: u64) -> Result<RecoveryReport, RepositoryError> {
+                Ok(RecoveryReport::default())
+            }
+            async fn record_commit_failure(&self, _: &Uuid) -> Result<(), RepositoryError> {
+                self.recorded.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+            async fn dead_letter_batch(
+                &self,
+                batch: &ReservedBatch,
+                results: &[UserResult],
+                _: &str,
+            ) -> Result<(), RepositoryError> {
+                self.dead_lettered
+                    .lock()
+                    .push((batch.batch_id, results.len()));
+                Ok(())
+            }
+            async fn abort_batch(&self, _: &Uuid, _: &str) -> Result<(), RepositoryError> {
+                Ok(())
+            }
+            async fn fetch_chunk_history(
+                &self,
+                _: &Uuid,
+                _: usize,
+            ) -> Result<Vec<ChunkEvent>, RepositoryError> {
+                Ok(Vec::new())
             }
-            async fn recover_uncommitted(&self) -> anyhow::Result<()> {
+            async fn reassign_pair(&self, _: &Uuid, _: &str) -> Result<(), ReassignPairError> {
                 Ok(())
             }
         }
 
         let id = Uuid::new_v4();
-        let store = Arc::new(SessionStore::new(Arc::new(FailingCommitRepo)));
+        let repo = Arc::new(FailingCommitRepo::default());
+        let store = Arc::new(SessionStore::new(repo.clone()));
         store.upsert_cache(mk_session(id));
 
         let exec = Arc::new(MockExecutor {
             calls: AtomicUsize::new(0),
             fail_on_call: None,
+            fail_with: SwapError::MarketNotOpen,
         });
 
         let market_view = MarketViewStore::new();
@@ -728,26 +1931,38 @@ mod tests {
             .set(
                 "TON/USDT",
                 crate::market::types::MarketMetricsView {
-                    ts_ms: 0,
+                    ts_ms: now_ms(),
                     spread_bps: 5.0,
                     trend_drop_bps: 5.0,
                     max_depth: 1_000,
+                    slippage_bps: None,
                 },
             )
             .await;
 
-        let worker =
-            ExecutorWorker::new(store, market_view, exec.clone(), 5_000, "TON/USDT".into());
+        let worker = ExecutorWorker::new(
+            store,
+            market_view,
+            exec.clone(),
+            test_cfg(),
+            "TON/USDT".into(),
+        );
 
         let batch = mk_batch(id, 1);
+        let batch_id = batch.batch_id;
 
-        let _ = worker.execute_batch(batch).await;
+        assert!(worker.execute_batch(batch).await.is_err());
 
         assert_eq!(
             exec.calls.load(Ordering::SeqCst),
             1,
             "batch must not be re-executed after commit failure"
         );
+
+        // Only the commit is retried; then the batch is parked with its results.
+        assert_eq!(repo.commits.load(Ordering::SeqCst), 3);
+        assert_eq!(repo.recorded.load(Ordering::SeqCst), 3);
+        assert_eq!(*repo.dead_lettered.lock(), vec![(batch_id, 1)]);
     }
 
     #[tokio::test]
@@ -758,13 +1973,14 @@ mod tests {
         let exec = Arc::new(MockExecutor {
             calls: AtomicUsize::new(0),
             fail_on_call: None,
+            fail_with: SwapError::MarketNotOpen,
         });
 
         let router = Arc::new(PairExecutorRouter::new(
             store,
             MarketViewStore::new(),
             exec,
-            5_000,
+            test_cfg(),
             1, // capacity = 1
         ));
 
@@ -792,4 +2008,1567 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn classify_error_maps_every_variant() {
+        let cases = [
+            (SwapError::MarketNotOpen, "MarketNotOpen"),
+            (SwapError::Slippage, "Slippage"),
+            (SwapError::InsufficientLiquidity, "InsufficientLiquidity"),
+            (SwapError::Rejected { code: 42 }, "Rejected:42"),
+            (SwapError::Timeout, "Timeout"),
+            (SwapError::Other("rpc down".into()), "rpc down"),
+        ];
+
+        for (err, reason) in cases {
+            assert_eq!(classify_error(&err), reason);
+        }
+    }
+
+    #[test]
+    fn classify_error_truncates_only_other() {
+        let long = "x".repeat(500);
+        assert_eq!(
+            classify_error(&SwapError::Other(long.clone())),
+            format!("ERR:{}", &long[..160])
+        );
+
+        // Multi-byte characters straddling the cut must not panic.
+        let wide = "é".repeat(200);
+        assert!(classify_error(&SwapError::Other(wide)).len() <= 4 + 160);
+    }
+
+    #[test]
+    fn swap_error_from_anyhow_is_backward_compatible() {
+        // Typed errors survive a round-trip through anyhow (with context).
+        let e = anyhow::Error::from(SwapError::Rejected { code: 7 }).context("Slippage guard");
+        assert_eq!(SwapError::from(e), SwapError::Rejected { code: 7 });
+
+        // Legacy string errors still map to their codes.
+        assert_eq!(
+            SwapError::from(anyhow::anyhow!("chain said: MarketNotOpen (code 7)")),
+            SwapError::MarketNotOpen
+        );
+        assert_eq!(
+            SwapError::from(anyhow::anyhow!("rpc down")),
+            SwapError::Other("rpc down".into())
+        );
+    }
+
+    /// Executor that replaces the market view right after its first swap,
+    /// simulating conditions moving mid-batch.
+    struct MarketShiftingExecutor {
+        calls: AtomicUsize,
+        market_view: MarketViewStore,
+        shifted: MarketMetricsView,
+    }
+
+    impl MarketShiftingExecutor {
+        /// Shifts to a fresh snapshot whose spread violates the session max of 10.
+        fn widening_spread(market_view: MarketViewStore) -> Self {
+            Self {
+                calls: AtomicUsize::new(0),
+                market_view,
+                shifted: MarketMetricsView {
+                    ts_ms: now_ms(),
+                    spread_bps: 50.0,
+                    trend_drop_bps: 5.0,
+                    max_depth: 1_000,
+                    slippage_bps: None,
+                },
+            }
+        }
+    }
+
+    #[async_trait]
+    impl SwapExecutor for MarketShiftingExecutor {
+        async fn execute_swap(&self, _: SwapCall) -> Result<SwapReceipt, SwapError> {
+            let n = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            if n == 1 {
+                self.market_view.set("TON/USDT", self.shifted.clone()).await;
+            }
+            Ok(SwapReceipt {
+                tx_id: format!("tx-{n}"),
+                idempotency_key: None,
+            })
+        }
+    }
+
+    async fn good_market_view() -> MarketViewStore {
+        let market_view = MarketViewStore::new();
+        market_view
+            .set(
+                "TON/USDT",
+                crate::market::types::MarketMetricsView {
+                    ts_ms: now_ms(),
+                    spread_bps: 5.0,
+                    trend_drop_bps: 5.0,
+                    max_depth: 1_000,
+                    slippage_bps: None,
+                },
+            )
+            .await;
+        market_view
+    }
+
+    #[tokio::test]
+    async fn gate_b_refreshes_market_between_chunks() {
+        let id = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(id));
+        let market_view = good_market_view().await;
+
+        let exec = Arc::new(MarketShiftingExecutor::widening_spread(market_view.clone()));
+
+        let worker = ExecutorWorker::new(
+            store,
+            market_view,
+            exec.clone(),
+            test_cfg(),
+            "TON/USDT".into(),
+        );
+
+        worker.execute_batch(mk_batch(id, 3)).await.unwrap();
+
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 1);
+
+        let committed = committed.lock();
+        let chunks = &committed[0].chunk_results;
+        assert_eq!(chunks.len(), 2);
+        assert!(matches!(chunks[0].status, ChunkStatus::Success { .. }));
+        assert!(matches!(
+            &chunks[1].status,
+            ChunkStatus::Skipped { reason } if reason == "GATE_B_CONSTRAINTS"
+        ));
+    }
+
+    #[tokio::test]
+    async fn gate_b_refresh_interval_is_configurable() {
+        let id = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(id));
+        let market_view = good_market_view().await;
+
+        let exec = Arc::new(MarketShiftingExecutor::widening_spread(market_view.clone()));
+
+        let cfg = WorkerConfig {
+            market_refresh_every_chunks: 2,
+            ..test_cfg()
+        };
+        let worker = ExecutorWorker::new(store, market_view, exec.clone(), cfg, "TON/USDT".into());
+
+        worker.execute_batch(mk_batch(id, 3)).await.unwrap();
+
+        // Chunk 2 still uses the batch-start snapshot; chunk 3 sees the shift.
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 2);
+
+        let committed = committed.lock();
+        let chunks = &committed[0].chunk_results;
+        assert!(matches!(
+            &chunks[2].status,
+            ChunkStatus::Skipped { reason } if reason == "GATE_B_CONSTRAINTS"
+        ));
+    }
+
+    #[test]
+    fn gate_b_rejects_stale_snapshot() {
+        let session = mk_session(Uuid::new_v4());
+        let m = MarketMetricsView {
+            ts_ms: 10_000,
+            spread_bps: 5.0,
+            trend_drop_bps: 5.0,
+            max_depth: 1_000,
+            slippage_bps: None,
+        };
+
+        // Exactly at the threshold is still fresh.
+        assert!(gate_b_ok(&session, Some(&m), 15_000, 5_000));
+        // One millisecond over fails closed.
+        assert!(!gate_b_ok(&session, Some(&m), 15_001, 5_000));
+        // Future-stamped snapshots (clock skew) are not rejected.
+        assert!(gate_b_ok(&session, Some(&m), 9_000, 5_000));
+    }
+
+    #[test]
+    fn gate_b_enforces_max_slippage_once_known() {
+        let session = mk_session(Uuid::new_v4());
+        let mut m = MarketMetricsView {
+            ts_ms: 10_000,
+            spread_bps: 5.0,
+            trend_drop_bps: 5.0,
+            max_depth: 1_000,
+            slippage_bps: None,
+        };
+
+        // Unknown slippage (pulse warming up) does not block.
+        assert!(gate_b_ok(&session, Some(&m), 10_000, 5_000));
+
+        // mk_session allows 10 bps.
+        m.slippage_bps = Some(10.0);
+        assert!(gate_b_ok(&session, Some(&m), 10_000, 5_000));
+        m.slippage_bps = Some(10.5);
+        assert!(!gate_b_ok(&session, Some(&m), 10_000, 5_000));
+    }
+
+    /// Executor that tracks peak concurrency; fails the configured call immediately.
+    struct ConcurrencyProbeExecutor {
+        calls: AtomicUsize,
+        current: AtomicUsize,
+        peak: AtomicUsize,
+        fail_on_call: Option<usize>,
+    }
+
+    impl ConcurrencyProbeExecutor {
+        fn new(fail_on_call: Option<usize>) -> Self {
+            Self {
+                calls: AtomicUsize::new(0),
+                current: AtomicUsize::new(0),
+                peak: AtomicUsize::new(0),
+                fail_on_call,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl SwapExecutor for ConcurrencyProbeExecutor {
+        async fn execute_swap(&self, call: SwapCall) -> Result<SwapReceipt, SwapError> {
+            let n = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            if self.fail_on_call == Some(n) {
+                return Err(SwapError::Slippage);
+            }
+
+            let cur = self.current.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(cur, Ordering::SeqCst);
+            sleep(Duration::from_millis(10)).await;
+            self.current.fetch_sub(1, Ordering::SeqCst);
+
+            Ok(SwapReceipt {
+                tx_id: format!("tx-{}", call.chunk_id),
+                idempotency_key: None,
+            })
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn concurrent_chunks_respect_limit_and_order() {
+        let id = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(id));
+        let exec = Arc::new(ConcurrencyProbeExecutor::new(None));
+
+        let cfg = WorkerConfig {
+            max_concurrent_chunks: 3,
+            ..test_cfg()
+        };
+        let worker = ExecutorWorker::new(
+            store,
+            good_market_view().await,
+            exec.clone(),
+            cfg,
+            "TON/USDT".into(),
+        );
+
+        let batch = mk_batch(id, 7);
+        worker.execute_batch(batch.clone()).await.unwrap();
+
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 7);
+        assert_eq!(exec.peak.load(Ordering::SeqCst), 3);
+
+        let committed = committed.lock();
+        let got: Vec<Uuid> = committed[0]
+            .chunk_results
+            .iter()
+            .map(|r| r.chunk_id)
+            .collect();
+        let mut want: Vec<Uuid> = batch.users[0].chunks.iter().map(|c| c.chunk_id).collect();
+        want.sort();
+        assert_eq!(got, want, "results must be ordered by chunk_id");
+        assert!(
+            committed[0]
+                .chunk_results
+                .iter()
+                .all(|r| matches!(r.status, ChunkStatus::Success { .. }))
+        );
+        assert_eq!(committed[0].cooldown_ms, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn concurrent_chunks_stop_issuing_after_failure() {
+        let id = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(id));
+        let exec = Arc::new(ConcurrencyProbeExecutor::new(Some(2)));
+
+        let cfg = WorkerConfig {
+            max_concurrent_chunks: 2,
+            ..test_cfg()
+        };
+        let worker = ExecutorWorker::new(
+            store,
+            good_market_view().await,
+            exec.clone(),
+            cfg,
+            "TON/USDT".into(),
+        );
+
+        worker.execute_batch(mk_batch(id, 6)).await.unwrap();
+
+        // Chunk 1 was already in flight when chunk 2 failed: it is awaited,
+        // but nothing new is issued.
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 2);
+
+        let committed = committed.lock();
+        let results = &committed[0].chunk_results;
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results
+                .iter()
+                .filter(|r| matches!(r.status, ChunkStatus::Failed { .. }))
+                .count(),
+            1
+        );
+        assert!(results.windows(2).all(|w| w[0].chunk_id < w[1].chunk_id));
+        assert_eq!(committed[0].cooldown_ms, Some(5_000));
+    }
+
+    /// Fails every swap of `failing` and holds the others briefly so overlap
+    /// between users is observable.
+    struct PerUserExecutor {
+        failing: Uuid,
+        calls: PlMutex<Vec<Uuid>>,
+        current: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl SwapExecutor for PerUserExecutor {
+        async fn execute_swap(&self, call: SwapCall) -> Result<SwapReceipt, SwapError> {
+            self.calls.lock().push(call.session_id);
+
+            let cur = self.current.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(cur, Ordering::SeqCst);
+            sleep(Duration::from_millis(10)).await;
+            self.current.fetch_sub(1, Ordering::SeqCst);
+
+            if call.session_id == self.failing {
+                return Err(SwapError::Slippage);
+            }
+            Ok(SwapReceipt {
+                tx_id: format!("tx-{}", call.chunk_id),
+                idempotency_key: None,
+            })
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn parallel_users_produce_independent_results() {
+        let failing = Uuid::new_v4();
+        let succeeding = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(failing));
+        store.upsert_cache(mk_session(succeeding));
+
+        let exec = Arc::new(PerUserExecutor {
+            failing,
+            calls: PlMutex::new(Vec::new()),
+            current: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+        });
+
+        let cfg = WorkerConfig {
+            max_parallel_users: 2,
+            ..test_cfg()
+        };
+        let worker = ExecutorWorker::new(
+            store,
+            good_market_view().await,
+            exec.clone(),
+            cfg,
+            "TON/USDT".into(),
+        );
+
+        let mut batch = mk_batch(failing, 3);
+        batch.users.push(mk_batch(succeeding, 3).users.remove(0));
+        worker.execute_batch(batch).await.unwrap();
+
+        // Both users were in flight at once.
+        assert_eq!(exec.peak.load(Ordering::SeqCst), 2);
+
+        // The failing user stopped after its first chunk; the other ran all three.
+        let calls = exec.calls.lock();
+        assert_eq!(calls.iter().filter(|id| **id == failing).count(), 1);
+        assert_eq!(calls.iter().filter(|id| **id == succeeding).count(), 3);
+
+        // One result per user, in batch order, committed once.
+        let committed = committed.lock();
+        assert_eq!(committed.len(), 2);
+
+        assert_eq!(committed[0].session_id, failing);
+        assert_eq!(committed[0].chunk_results.len(), 1);
+        assert!(matches!(
+            committed[0].chunk_results[0].status,
+            ChunkStatus::Failed { .. }
+        ));
+        assert_eq!(committed[0].cooldown_ms, Some(5_000));
+
+        assert_eq!(committed[1].session_id, succeeding);
+        assert_eq!(committed[1].chunk_results.len(), 3);
+        assert!(
+            committed[1]
+                .chunk_results
+                .iter()
+                .all(|r| matches!(r.status, ChunkStatus::Success { .. }))
+        );
+        assert_eq!(committed[1].cooldown_ms, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn session_failure_cooldown_overrides_the_default() {
+        let tuned = Uuid::new_v4();
+        let plain = Uuid::new_v4();
+        let mut session = mk_session(tuned);
+        session.intent.failure_cooldown_ms = Some(1_234);
+        let (store, committed) = make_recording_store(session);
+        store.upsert_cache(mk_session(plain));
+
+        struct AlwaysSlippage;
+        #[async_trait]
+        impl SwapExecutor for AlwaysSlippage {
+            async fn execute_swap(&self, _: SwapCall) -> Result<SwapReceipt, SwapError> {
+                Err(SwapError::Slippage)
+            }
+        }
+
+        let worker = ExecutorWorker::new(
+            store,
+            good_market_view().await,
+            Arc::new(AlwaysSlippage),
+            test_cfg(),
+            "TON/USDT".into(),
+        );
+
+        let mut batch = mk_batch(tuned, 1);
+        batch.users.push(mk_batch(plain, 1).users.remove(0));
+        worker.execute_batch(batch).await.unwrap();
+
+        let committed = committed.lock();
+        assert_eq!(committed[0].session_id, tuned);
+        assert_eq!(committed[0].cooldown_ms, Some(1_234));
+        assert_eq!(committed[1].session_id, plain);
+        assert_eq!(committed[1].cooldown_ms, Some(5_000));
+    }
+
+    #[tokio::test]
+    async fn concurrent_mode_checks_gate_b_before_issuing() {
+        let id = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(id));
+        let exec = Arc::new(ConcurrencyProbeExecutor::new(None));
+
+        let cfg = WorkerConfig {
+            max_concurrent_chunks: 4,
+            ..test_cfg()
+        };
+        let worker = ExecutorWorker::new(
+            store,
+            MarketViewStore::new(), // no snapshot
+            exec.clone(),
+            cfg,
+            "TON/USDT".into(),
+        );
+
+        worker.execute_batch(mk_batch(id, 3)).await.unwrap();
+
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 0);
+        assert!(matches!(
+            &committed.lock()[0].chunk_results[0].status,
+            ChunkStatus::Skipped { reason } if reason == "GATE_B_CONSTRAINTS"
+        ));
+    }
+
+    #[tokio::test]
+    async fn gate_b_skips_remaining_chunks_when_snapshot_goes_stale_mid_batch() {
+        let id = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(id));
+        let market_view = good_market_view().await;
+
+        // Constraints still pass, but the feed stalled: snapshot is 1 minute old.
+        let exec = Arc::new(MarketShiftingExecutor {
+            calls: AtomicUsize::new(0),
+            market_view: market_view.clone(),
+            shifted: MarketMetricsView {
+                ts_ms: now_ms().saturating_sub(60_000),
+                spread_bps: 5.0,
+                trend_drop_bps: 5.0,
+                max_depth: 1_000,
+                slippage_bps: None,
+            },
+        });
+
+        let cfg = WorkerConfig {
+            max_snapshot_age_ms: 10_000,
+            ..test_cfg()
+        };
+        let worker = ExecutorWorker::new(store, market_view, exec.clone(), cfg, "TON/USDT".into());
+
+        worker.execute_batch(mk_batch(id, 4)).await.unwrap();
+
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 1);
+
+        let committed = committed.lock();
+        let chunks = &committed[0].chunk_results;
+        assert_eq!(chunks.len(), 2);
+        assert!(matches!(chunks[0].status, ChunkStatus::Success { .. }));
+        assert!(matches!(
+            &chunks[1].status,
+            ChunkStatus::Skipped { reason } if reason == "GATE_B_CONSTRAINTS"
+        ));
+    }
+
+    #[tokio::test]
+    async fn safe_mode_simulates_chunks_without_calling_executor() {
+        let id = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(id));
+        let exec = Arc::new(MockExecutor {
+            calls: AtomicUsize::new(0),
+            fail_on_call: None,
+            fail_with: SwapError::MarketNotOpen,
+        });
+
+        let cfg = WorkerConfig {
+            safe_mode: true,
+            ..test_cfg()
+        };
+        let worker = ExecutorWorker::new(
+            store,
+            good_market_view().await,
+            exec.clone(),
+            cfg,
+            "TON/USDT".into(),
+        );
+
+        worker.execute_batch(mk_batch(id, 3)).await.unwrap();
+
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 0);
+
+        let committed = committed.lock();
+        assert_eq!(committed[0].chunk_results.len(), 3);
+        assert!(
+            committed[0]
+                .chunk_results
+                .iter()
+                .all(|r| matches!(r.status, ChunkStatus::Simulated))
+        );
+        assert_eq!(committed[0].cooldown_ms, None);
+    }
+
+    #[tokio::test]
+    async fn safe_mode_still_enforces_gate_b() {
+        let id = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(id));
+        let exec = Arc::new(MockExecutor {
+            calls: AtomicUsize::new(0),
+            fail_on_call: None,
+            fail_with: SwapError::MarketNotOpen,
+        });
+
+        let cfg = WorkerConfig {
+            safe_mode: true,
+            ..test_cfg()
+        };
+        let worker = ExecutorWorker::new(
+            store,
+            MarketViewStore::new(),
+            exec.clone(),
+            cfg,
+            "TON/USDT".into(),
+        );
+
+        worker.execute_batch(mk_batch(id, 2)).await.unwrap();
+
+        assert!(matches!(
+            &committed.lock()[0].chunk_results[0].status,
+            ChunkStatus::Skipped { reason } if reason == "GATE_B_CONSTRAINTS"
+        ));
+    }
+
+    fn retry_cfg(max_attempts: u32) -> WorkerConfig {
+        WorkerConfig {
+            retry: RetryPolicy {
+                max_attempts,
+                base_backoff_ms: 100,
+            },
+            ..test_cfg()
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retryable_failure_is_retried_until_success() {
+        let id = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(id));
+        let exec = Arc::new(MockExecutor {
+            calls: AtomicUsize::new(0),
+            fail_on_call: Some(1),
+            fail_with: SwapError::Timeout,
+        });
+
+        let counters = Counters::default();
+        let worker = ExecutorWorker::new(
+            store,
+            good_market_view().await,
+            exec.clone(),
+            retry_cfg(3),
+            "TON/USDT".into(),
+        )
+        .with_counters(counters.clone());
+
+        worker.execute_batch(mk_batch(id, 1)).await.unwrap();
+
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(counters.snapshot().exec_swap_retries, 1);
+
+        let committed = committed.lock();
+        assert!(matches!(
+            committed[0].chunk_results[0].status,
+            ChunkStatus::Success { .. }
+        ));
+        assert_eq!(committed[0].cooldown_ms, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn non_retryable_failure_fails_immediately() {
+        let id = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(id));
+        let exec = Arc::new(MockExecutor {
+            calls: AtomicUsize::new(0),
+            fail_on_call: Some(1),
+            fail_with: SwapError::Slippage,
+        });
+
+        let worker = ExecutorWorker::new(
+            store,
+            good_market_view().await,
+            exec.clone(),
+            retry_cfg(3),
+            "TON/USDT".into(),
+        );
+
+        worker.execute_batch(mk_batch(id, 2)).await.unwrap();
+
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 1);
+        assert!(matches!(
+            &committed.lock()[0].chunk_results[0].status,
+            ChunkStatus::Failed { reason } if reason == "Slippage"
+        ));
+    }
+
+    struct AlwaysTimeoutExecutor {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl SwapExecutor for AlwaysTimeoutExecutor {
+        async fn execute_swap(&self, _: SwapCall) -> Result<SwapReceipt, SwapError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Err(SwapError::Timeout)
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_are_bounded_with_exponential_backoff() {
+        let id = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(id));
+        let exec = Arc::new(AlwaysTimeoutExecutor {
+            calls: AtomicUsize::new(0),
+        });
+
+        // A zero timeout settles each `Timeout` at once, so only the backoff
+        // advances the clock.
+        let worker = ExecutorWorker::new(
+            store,
+            good_market_view().await,
+            exec.clone(),
+            WorkerConfig {
+                swap_timeout_ms: 0,
+                ..retry_cfg(3)
+            },
+            "TON/USDT".into(),
+        );
+
+        let started = tokio::time::Instant::now();
+        worker.execute_batch(mk_batch(id, 1)).await.unwrap();
+
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 3);
+        // 100ms + 200ms between the three attempts.
+        assert_eq!(started.elapsed(), Duration::from_millis(300));
+        assert!(matches!(
+            &committed.lock()[0].chunk_results[0].status,
+            ChunkStatus::Failed { reason } if reason == "Timeout"
+        ));
+    }
+
+    /// Fails the first `fail_first` calls with `MarketNotOpen`, then succeeds.
+    struct FailFirstExecutor {
+        calls: AtomicUsize,
+        fail_first: usize,
+    }
+
+    #[async_trait]
+    impl SwapExecutor for FailFirstExecutor {
+        async fn execute_swap(&self, call: SwapCall) -> Result<SwapReceipt, SwapError> {
+            let n = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            if n <= self.fail_first {
+                return Err(SwapError::MarketNotOpen);
+            }
+            Ok(SwapReceipt {
+                tx_id: format!("tx-{}", call.chunk_id),
+                idempotency_key: None,
+            })
+        }
+    }
+
+    fn breaker_cfg(open_ms: u64) -> WorkerConfig {
+        WorkerConfig {
+            default_failure_cooldown_ms: open_ms,
+            breaker: BreakerConfig {
+                window_ms: 60_000,
+                min_samples: 2,
+                failure_ratio: 0.5,
+                consecutive_failures: 0,
+            },
+            ..test_cfg()
+        }
+    }
+
+    async fn route_and_settle(tx: &mpsc::Sender<ExecutionEvent>, batch: ReservedBatch) {
+        tx.send(ExecutionEvent::Reserved(batch)).await.unwrap();
+        sleep(Duration::from_millis(30)).await;
+    }
+
+    #[tokio::test]
+    async fn breaker_trips_and_short_circuits_batches_while_open() {
+        let id = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(id));
+        let exec = Arc::new(FailFirstExecutor {
+            calls: AtomicUsize::new(0),
+            fail_first: usize::MAX,
+        });
+
+        let router = Arc::new(PairExecutorRouter::new(
+            store,
+            good_market_view().await,
+            exec.clone(),
+            breaker_cfg(60_000),
+            8,
+        ));
+
+        let (tx, rx) = mpsc::channel(8);
+        tokio::spawn(router.clone().run(rx));
+
+        route_and_settle(&tx, mk_batch(id, 1)).await;
+        assert_eq!(router.breaker_state("TON/USDT"), Some(BreakerState::Closed));
+
+        route_and_settle(&tx, mk_batch(id, 1)).await;
+        assert!(matches!(
+            router.breaker_state("TON/USDT"),
+            Some(BreakerState::Open { .. })
+        ));
+
+        // While open, the batch is committed as skipped rather than left
+        // RESERVED, and never reaches the executor.
+        route_and_settle(&tx, mk_batch(id, 1)).await;
+
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 2);
+
+        let committed = committed.lock();
+        assert_eq!(committed.len(), 3);
+        assert!(matches!(
+            &committed[2].chunk_results[0].status,
+            ChunkStatus::Skipped { reason } if reason == "CircuitOpen"
+        ));
+        assert_eq!(committed[2].cooldown_ms, None);
+    }
+
+    #[tokio::test]
+    async fn breaker_closes_after_successful_probe() {
+        let id = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(id));
+        let exec = Arc::new(FailFirstExecutor {
+            calls: AtomicUsize::new(0),
+            fail_first: 2,
+        });
+
+        let router = Arc::new(PairExecutorRouter::new(
+            store,
+            good_market_view().await,
+            exec.clone(),
+            breaker_cfg(50),
+            8,
+        ));
+
+        let (tx, rx) = mpsc::channel(8);
+        tokio::spawn(router.clone().run(rx));
+
+        route_and_settle(&tx, mk_batch(id, 1)).await;
+        route_and_settle(&tx, mk_batch(id, 1)).await;
+        assert!(matches!(
+            router.breaker_state("TON/USDT"),
+            Some(BreakerState::Open { .. })
+        ));
+
+        sleep(Duration::from_millis(60)).await;
+
+        route_and_settle(&tx, mk_batch(id, 1)).await;
+
+        assert_eq!(router.breaker_state("TON/USDT"), Some(BreakerState::Closed));
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 3);
+        assert!(matches!(
+            committed.lock()[2].chunk_results[0].status,
+            ChunkStatus::Success { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn batch_is_dead_lettered_once_after_repeated_enqueue_failures() {
+        let id = Uuid::new_v4();
+        let store = make_test_store(mk_session(id));
+
+        let exec = Arc::new(MockExecutor {
+            calls: AtomicUsize::new(0),
+            fail_on_call: None,
+            fail_with: SwapError::MarketNotOpen,
+        });
+
+        let (dl_tx, mut dl_rx) = mpsc::channel(8);
+        let router = Arc::new(
+            PairExecutorRouter::new(
+                store,
+                MarketViewStore::new(),
+                exec.clone(),
+                WorkerConfig {
+                    max_enqueue_attempts: 3,
+                    ..test_cfg()
+                },
+                8,
+            )
+            .with_dead_letter(dl_tx)
+            // Every worker exits at once, so no enqueue can succeed.
+            .with_worker_spawner(Arc::new(|_, rx| {
+                drop(rx);
+                tokio::spawn(async {})
+            })),
+        );
+
+        let (tx, rx) = mpsc::channel(8);
+        let handle = tokio::spawn(router.clone().run(rx));
+
+        let batch = mk_batch(id, 1);
+        let batch_id = batch.batch_id;
+        tx.send(ExecutionEvent::Reserved(batch)).await.unwrap();
+
+        drop(tx);
+        handle.await.unwrap();
+
+        let dead = dl_rx.try_recv().expect("batch must be dead-lettered");
+        assert_eq!(dead.batch_id, batch_id);
+        assert!(dl_rx.try_recv().is_err(), "dead-lettered exactly once");
+
+        assert!(router.enqueue_failures.lock().is_empty());
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 0);
+    }
+
+    struct HangingExecutor {
+        calls: AtomicUsize,
+        /// What `query_status` reports once the swap was abandoned.
+        lookup: Result<Option<&'static str>, SwapError>,
+    }
+
+    impl HangingExecutor {
+        fn new(lookup: Result<Option<&'static str>, SwapError>) -> Self {
+            Self {
+                calls: AtomicUsize::new(0),
+                lookup,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl SwapExecutor for HangingExecutor {
+        async fn execute_swap(&self, _: SwapCall) -> Result<SwapReceipt, SwapError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            sleep(Duration::from_secs(3_600)).await;
+            Ok(SwapReceipt {
+                tx_id: "too-late".into(),
+                idempotency_key: None,
+            })
+        }
+
+        async fn query_status(&self, _: Uuid) -> Result<Option<SwapReceipt>, SwapError> {
+            self.lookup.clone().map(|landed| {
+                landed.map(|tx_id| SwapReceipt {
+                    tx_id: tx_id.into(),
+                    idempotency_key: None,
+                })
+            })
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn hung_swap_times_out_and_stops_batch() {
+        use tokio::time::advance;
+
+        let id = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(id));
+        let exec = Arc::new(HangingExecutor::new(Ok(None)));
+
+        let worker = ExecutorWorker::new(
+            store,
+            good_market_view().await,
+            exec.clone(),
+            WorkerConfig {
+                swap_timeout_ms: 1_000,
+                ..test_cfg()
+            },
+            "TON/USDT".into(),
+        );
+
+        let handle = tokio::spawn(async move { worker.execute_batch(mk_batch(id, 2)).await });
+
+        advance(Duration::from_millis(1_001)).await;
+        handle.await.unwrap().unwrap();
+
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 1);
+
+        let committed = committed.lock();
+        let user = &committed[0];
+        assert_eq!(user.chunk_results.len(), 1, "chunk loop must stop");
+        assert!(matches!(
+            &user.chunk_results[0].status,
+            ChunkStatus::Failed { reason } if reason == "Timeout"
+        ));
+        assert_eq!(user.cooldown_ms, Some(5_000));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timed_out_swap_that_landed_before_the_deadline_succeeds() {
+        let id = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(id));
+        let exec = Arc::new(HangingExecutor::new(Ok(Some("landed-tx"))));
+
+        let worker = ExecutorWorker::new(
+            store,
+            good_market_view().await,
+            exec.clone(),
+            WorkerConfig {
+                swap_timeout_ms: 1_000,
+                ..test_cfg()
+            },
+            "TON/USDT".into(),
+        );
+        worker.execute_batch(mk_batch(id, 2)).await.unwrap();
+
+        // The landed swap is not failed (and its bid not reused), so the
+        // user's second chunk still runs.
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 2);
+        let committed = committed.lock();
+        assert!(
+            committed[0].chunk_results.iter().all(
+                |c| matches!(&c.status, ChunkStatus::Success { tx_id } if tx_id == "landed-tx")
+            )
+        );
+        assert_eq!(committed[0].cooldown_ms, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timed_out_swap_with_unknown_status_is_left_for_recovery() {
+        let id = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(id));
+        let exec = Arc::new(HangingExecutor::new(Err(SwapError::Timeout)));
+        let counters = Counters::default();
+
+        let worker = ExecutorWorker::new(
+            store,
+            good_market_view().await,
+            exec.clone(),
+            WorkerConfig {
+                swap_timeout_ms: 1_000,
+                ..test_cfg()
+            },
+            "TON/USDT".into(),
+        )
+        .with_counters(counters.clone());
+        worker.execute_batch(mk_batch(id, 2)).await.unwrap();
+
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 1);
+        let committed = committed.lock();
+        assert_eq!(committed[0].chunk_results.len(), 1, "chunk loop must stop");
+        assert!(matches!(
+            &committed[0].chunk_results[0].status,
+            ChunkStatus::Unknown { reason } if reason == "Timeout"
+        ));
+        assert_eq!(committed[0].cooldown_ms, Some(5_000));
+        // Not finalized, so not reported as committed.
+        assert_eq!(counters.snapshot().exec_batches_committed, 0);
+    }
+
+    /// Reports `Timeout` itself, as the TON executor does when confirmation
+    /// does not arrive in time.
+    struct TimeoutErrorExecutor {
+        calls: AtomicUsize,
+        lookup: Result<Option<&'static str>, SwapError>,
+    }
+
+    #[async_trait]
+    impl SwapExecutor for TimeoutErrorExecutor {
+        async fn execute_swap(&self, _: SwapCall) -> Result<SwapReceipt, SwapError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Err(SwapError::Timeout)
+        }
+
+        async fn query_status(&self, _: Uuid) -> Result<Option<SwapReceipt>, SwapError> {
+            self.lookup.clone().map(|landed| {
+                landed.map(|tx_id| SwapReceipt {
+                    tx_id: tx_id.into(),
+                    idempotency_key: None,
+                })
+            })
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn executor_timeout_that_landed_is_not_retried() {
+        let id = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(id));
+        let exec = Arc::new(TimeoutErrorExecutor {
+            calls: AtomicUsize::new(0),
+            lookup: Ok(Some("landed-tx")),
+        });
+        let counters = Counters::default();
+
+        let worker = ExecutorWorker::new(
+            store,
+            good_market_view().await,
+            exec.clone(),
+            WorkerConfig {
+                swap_timeout_ms: 1_000,
+                ..retry_cfg(3)
+            },
+            "TON/USDT".into(),
+        )
+        .with_counters(counters.clone());
+        worker.execute_batch(mk_batch(id, 1)).await.unwrap();
+
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(counters.snapshot().exec_swap_retries, 0);
+        assert!(matches!(
+            &committed.lock()[0].chunk_results[0].status,
+            ChunkStatus::Success { tx_id } if tx_id == "landed-tx"
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn executor_timeout_with_unknown_status_is_left_for_recovery() {
+        let id = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(id));
+        let exec = Arc::new(TimeoutErrorExecutor {
+            calls: AtomicUsize::new(0),
+            lookup: Err(SwapError::Other("rpc down".into())),
+        });
+        let counters = Counters::default();
+
+        let worker = ExecutorWorker::new(
+            store,
+            good_market_view().await,
+            exec.clone(),
+            WorkerConfig {
+                swap_timeout_ms: 1_000,
+                ..retry_cfg(3)
+            },
+            "TON/USDT".into(),
+        )
+        .with_counters(counters.clone());
+        worker.execute_batch(mk_batch(id, 2)).await.unwrap();
+
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 1, "must not resubmit");
+        assert_eq!(counters.snapshot().exec_swap_retries, 0);
+        let committed = committed.lock();
+        assert_eq!(committed[0].chunk_results.len(), 1, "chunk loop must stop");
+        assert!(matches!(
+            &committed[0].chunk_results[0].status,
+            ChunkStatus::Unknown { reason } if reason == "Timeout"
+        ));
+        assert_eq!(counters.snapshot().exec_batches_committed, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn breaker_opens_on_consecutive_failures_and_auto_closes() {
+        use tokio::time::advance;
+
+        let id = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(id));
+        let exec = Arc::new(FailFirstExecutor {
+            calls: AtomicUsize::new(0),
+            fail_first: 3,
+        });
+
+        let router = Arc::new(PairExecutorRouter::new(
+            store,
+            good_market_view().await,
+            exec.clone(),
+            WorkerConfig {
+                default_failure_cooldown_ms: 10_000,
+                breaker: BreakerConfig {
+                    window_ms: 60_000,
+                    min_samples: usize::MAX,
+                    failure_ratio: 1.0,
+                    consecutive_failures: 3,
+                },
+                ..test_cfg()
+            },
+            8,
+        ));
+
+        let (tx, rx) = mpsc::channel(8);
+        tokio::spawn(router.clone().run(rx));
+
+        for _ in 0..3 {
+            route_and_settle(&tx, mk_batch(id, 2)).await;
+        }
+        assert!(matches!(
+            router.breaker_state("TON/USDT"),
+            Some(BreakerState::Open { .. })
+        ));
+
+        // Open: short-circuited without touching the executor.
+        route_and_settle(&tx, mk_batch(id, 2)).await;
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 3);
+
+        advance(Duration::from_millis(10_000)).await;
+        assert_eq!(
+            router.breaker_state("TON/USDT"),
+            Some(BreakerState::HalfOpen)
+        );
+
+        route_and_settle(&tx, mk_batch(id, 2)).await;
+        assert_eq!(router.breaker_state("TON/USDT"), Some(BreakerState::Closed));
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 5);
+
+        let committed = committed.lock();
+        assert!(committed[3].chunk_results.iter().all(
+            |c| matches!(&c.status, ChunkStatus::Skipped { reason } if reason == "CircuitOpen")
+        ));
+        assert!(
+            committed[4]
+                .chunk_results
+                .iter()
+                .all(|c| matches!(c.status, ChunkStatus::Success { .. }))
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn per_pair_swap_timeout_overrides_default() {
+        use tokio::time::advance;
+
+        let id = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(id));
+        let exec = Arc::new(HangingExecutor::new(Ok(None)));
+
+        let worker = ExecutorWorker::new(
+            store,
+            good_market_view().await,
+            exec.clone(),
+            WorkerConfig {
+                swap_timeout_ms: 600_000,
+                swap_timeout_ms_by_pair: HashMap::from([("TON/USDT".to_string(), 2_000)]),
+                ..test_cfg()
+            },
+            "TON/USDT".into(),
+        );
+
+        let handle = tokio::spawn(async move { worker.execute_batch(mk_batch(id, 1)).await });
+
+        advance(Duration::from_millis(2_001)).await;
+        handle.await.unwrap().unwrap();
+
+        assert!(matches!(
+            &committed.lock()[0].chunk_results[0].status,
+            ChunkStatus::Failed { reason } if reason == "Timeout"
+        ));
+    }
+
+    #[tokio::test]
+    async fn committed_event_is_published_after_commit() {
+        let id = Uuid::new_v4();
+        let (store, committed) = make_recording_store(mk_session(id));
+        let exec = Arc::new(MockExecutor {
+            calls: AtomicUsize::new(0),
+            fail_on_call: Some(3),
+            fail_with: SwapError::Slippage,
+        });
+
+        let (ev_tx, mut ev_rx) = mpsc::channel(8);
+        let worker = ExecutorWorker::new(
+            store,
+            good_market_view().await,
+            exec,
+            test_cfg(),
+            "TON/USDT".into(),
+        )
+        .with_events(ev_tx);
+
+        let batch = mk_batch(id, 4);
+        let batch_id = batch.batch_id;
+        let chunk_bid = batch.users[0].chunks[0].bid;
+
+        worker.execute_batch(batch).await.unwrap();
+        assert_eq!(committed.lock().len(), 1, "event follows the commit");
+
+        let Ok(ExecutionEvent::Committed {
+            batch_id: ev_batch,
+            pair_id,
+            results_summary,
+        }) = ev_rx.try_recv()
+        else {
+            panic!("expected committed event");
+        };
+
+        assert_eq!(ev_batch, batch_id);
+        assert_eq!(pair_id, "TON/USDT");
+        assert_eq!(
+            results_summary,
+            CommitSummary {
+                succeeded: 2,
+                failed: 1,
+                skipped: 0,
+                simulated: 0,
+                committed_bid: 2 * chunk_bid,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn committed_event_counts_skipped_chunks() {
+        let id = Uuid::new_v4();
+        let store = make_test_store(mk_session(id));
+
+        let counters = Counters::default();
+        let (ev_tx, mut ev_rx) = mpsc::channel(8);
+        let worker = ExecutorWorker::new(
+            store,
+            MarketViewStore::new(),
+            Arc::new(MockExecutor {
+                calls: AtomicUsize::new(0),
+                fail_on_call: None,
+                fail_with: SwapError::MarketNotOpen,
+            }),
+            test_cfg(),
+            "TON/USDT".into(),
+        )
+        .with_events(ev_tx)
+        .with_counters(counters.clone());
+
+        // Missing session => SESSION_NOT_FOUND skip, still committed.
+        worker
+            .execute_batch(mk_batch(Uuid::new_v4(), 1))
+            .await
+            .unwrap();
+        assert!(matches!(
+            ev_rx.try_recv(),
+            Ok(ExecutionEvent::Committed { results_summary, .. }) if results_summary.skipped == 1
+        ));
+
+        let mut prom = String::new();
+        counters.encode_prometheus(&mut prom);
+        assert!(prom.contains(
+            r#"kaskade_exec_chunk_outcomes{pair_id="TON/USDT",outcome="skipped",reason="SESSION_NOT_FOUND"} 1"#
+        ));
+
+        assert_eq!(counters.commit_batch_latency.count(), 1);
+
+        let snap = counters.snapshot();
+        assert_eq!(snap.exec_batches_committed, 1);
+        assert_eq!(snap.exec_chunks_skipped, 1);
+        assert_eq!(snap.exec_chunks_executed, 0);
+    }
+
+    #[tokio::test]
+    async fn batch_sessions_are_loaded_with_a_single_query() {
+        #[derive(Default)]
+        struct CountingRepo {
+            sessions: HashMap<Uuid, Session>,
+            by_id_calls: AtomicUsize,
+            by_ids_calls: AtomicUsize,
+        }
+
+        #[async_trait]
+        impl SessionRepository for CountingRepo {
+            async fn fetch_page(
+                &self,
+                _: usize,
+                _: usize,
+                _: Option<&str>,
+            ) -> Result<Vec<Session>, RepositoryError> {
+                Ok(vec![])
+            }
+            async fn fetch_page_after(
+                &self,
+                _: usize,
+                _: Option<Uuid>,
+                _: Option<&str>,
+            ) -> Result<Vec<Session>, RepositoryError> {
+                Ok(vec![])
+            }
+            async fn fetch_by_id(&self, id: &Uuid) -> Result<Option<Session>, RepositoryError> {
+                self.by_id_calls.fetch_add(1, Ordering::SeqCst);
+                Ok(self.sessions.get(id).cloned())
+            }
+            async fn fetch_by_ids(
+                &self,
+                ids: &[Uuid],
+            ) -> Result<HashMap<Uuid, Session>, RepositoryError> {
+                self.by_ids_calls.fetch_add(1, Ordering::SeqCst);
+                Ok(ids
+                    .iter()
+                    .filter_map(|id| self.sessions.get(id).map(|s| (*id, s.clone())))
+                    .collect())
+            }
+            async fn expire_due(&self, _: u64) -> Result<u64, RepositoryError> {
+                Ok(0)
+            }
+
+            async fn persist_fairness(
+                &self,
+                _: &Uuid,
+                _: i128,
+                _: u64,
+            ) -> Result<(), RepositoryError> {
+                Ok(())
+            }
+            async fn reserve_execution(
+                &self,
+                _: &str,
+                _: u64,
+                _: &[crate::planner::types::PlannedAllocation],
+            ) -> Result<Option<ReservedBatch>, RepositoryError> {
+                unreachable!("not used in executor unit tests")
+            }
+            async fn claim_batch(&self, _: &Uuid) -> Result<bool, RepositoryError> {
+                Ok(true)
+            }
+            async fn commit_batch(
+                &self,
+                _: &ReservedBatch,
+                _: &[UserResult],
+            ) -> Result<(), RepositoryError> {
+                Ok(())
+            }
+            async fn pending_chunks(&self) -> Result<Vec<PendingChunk>, RepositoryError> {
+                Ok(Vec::new())
+            }
+
+            async fn settle_landed_chunk(
+                &self,
+                _: &Uuid,
+                _: &Uuid,
+                _: &str,
+            ) -> Result<bool, RepositoryError> {
+                Ok(false)
+            }
+
+            async fn recover_uncommitted(&self) -> Result<RecoveryReport, RepositoryError> {
+                Ok(RecoveryReport::default())
+            }
+            async fn recover_stale(&self, _: u64) -> Result<RecoveryReport, RepositoryError> {
+                Ok(RecoveryReport::default())
+            }
+            async fn record_commit_failure(&self, _: &Uuid) -> Result<(), RepositoryError> {
+                Ok(())
+            }
+            async fn dead_letter_batch(
+                &self,
+                _: &ReservedBatch,
+                _: &[UserResult],
+                _: &str,
+            ) -> Result<(), RepositoryError> {
+                Ok(())
+            }
+            async fn abort_batch(&self, _: &Uuid, _: &str) -> Result<(), RepositoryError> {
+                Ok(())
+            }
+            async fn fetch_chunk_history(
+                &self,
+                _: &Uuid,
+                _: usize,
+            ) -> Result<Vec<ChunkEvent>, RepositoryError> {
+                Ok(Vec::new())
+            }
+            async fn reassign_pair(&self, _: &Uuid, _: &str) -> Result<(), ReassignPairError> {
+                Ok(())
+            }
+        }
+
+        let ids: Vec<Uuid> = (0..50).map(|_| Uuid::new_v4()).collect();
+        let repo = Arc::new(CountingRepo {
+            sessions: ids.iter().map(|id| (*id, mk_session(*id))).collect(),
+            ..Default::default()
+        });
+        let store = Arc::new(SessionStore::new(repo.clone()));
+
+        let (ev_tx, mut ev_rx) = mpsc::channel(8);
+        let worker = ExecutorWorker::new(
+            store.clone(),
+            MarketViewStore::new(),
+            Arc::new(MockExecutor {
+                calls: AtomicUsize::new(0),
+                fail_on_call: None,
+                fail_with: SwapError::MarketNotOpen,
+            }),
+            test_cfg(),
+            "TON/USDT".into(),
+        )
+        .with_events(ev_tx);
+
+        let mut batch = mk_batch(ids[0], 1);
+        batch.users = ids
+            .iter()
+            .map(|id| ReservedUser {
+                session_id: *id,
+                chunks: vec![ReservedChunk {
+                    chunk_id: Uuid::new_v4(),
+                    bid: 100,
+                }],
+            })
+            .collect();
+
+        worker.execute_batch(batch).await.unwrap();
+
+        assert_eq!(repo.by_ids_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(repo.by_id_calls.load(Ordering::SeqCst), 0);
+        assert!(ids.iter().all(|id| store.get_cached(id).is_some()));
+        assert!(matches!(
+            ev_rx.try_recv(),
+            Ok(ExecutionEvent::Committed { results_summary, .. }) if results_summary.skipped == 50
+        ));
+    }
+
+    #[test]
+    fn idempotency_key_is_stable_per_chunk() {
+        let batch_id = Uuid::new_v4();
+        let chunk_id = Uuid::new_v4();
+
+        let call = |deadline_ms| SwapCall {
+            pair_id: "TON/USDT".into(),
+            session_id: Uuid::new_v4(),
+            bid: 100,
+            chunk_id,
+            deadline_ms,
+            idempotency_key: SwapCall::idempotency_key_for(batch_id, chunk_id),
+        };
+
+        assert_eq!(call(1).idempotency_key, call(2).idempotency_key);
+        assert_ne!(
+            call(1).idempotency_key,
+            SwapCall::idempotency_key_for(batch_id, Uuid::new_v4())
+        );
+        assert_ne!(
+            call(1).idempotency_key,
+            SwapCall::idempotency_key_for(Uuid::new_v4(), chunk_id)
+        );
+    }
+
+    /// Records the idempotency key of every call it receives.
+    struct KeyRecordingExecutor {
+        keys: PlMutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl SwapExecutor for KeyRecordingExecutor {
+        async fn execute_swap(&self, call: SwapCall) -> Result<SwapReceipt, SwapError> {
+            self.keys.lock().push(call.idempotency_key.clone());
+            if self.keys.lock().len() == 1 {
+                return Err(SwapError::Timeout);
+            }
+            Ok(SwapReceipt {
+                tx_id: "tx".into(),
+                idempotency_key: Some(call.idempotency_key),
+            })
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn worker_threads_same_key_through_retries() {
+        let id = Uuid::new_v4();
+        let exec = Arc::new(KeyRecordingExecutor {
+            keys: PlMutex::new(Vec::new()),
+        });
+
+        let worker = ExecutorWorker::new(
+            make_test_store(mk_session(id)),
+            good_market_view().await,
+            exec.clone(),
+            retry_cfg(2),
+            "TON/USDT".into(),
+        );
+
+        let batch = mk_batch(id, 1);
+        let expected =
+            SwapCall::idempotency_key_for(batch.batch_id, batch.users[0].chunks[0].chunk_id);
+
+        worker.execute_batch(batch).await.unwrap();
+
+        assert_eq!(*exec.keys.lock(), vec![expected.clone(), expected]);
+    }
+
+    /// Blocks every swap until a permit is released.
+    struct GatedExecutor {
+        calls: AtomicUsize,
+        permits: tokio::sync::Semaphore,
+    }
+
+    #[async_trait]
+    impl SwapExecutor for GatedExecutor {
+        async fn execute_swap(&self, _: SwapCall) -> Result<SwapReceipt, SwapError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.permits.acquire().await.unwrap().forget();
+            Ok(SwapReceipt {
+                tx_id: "tx".into(),
+                idempotency_key: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn router_reports_queue_depth_and_active_pairs() {
+        let id = Uuid::new_v4();
+        let exec = Arc::new(GatedExecutor {
+            calls: AtomicUsize::new(0),
+            permits: tokio::sync::Semaphore::new(0),
+        });
+
+        let router = Arc::new(PairExecutorRouter::new(
+            make_test_store(mk_session(id)),
+            good_market_view().await,
+            exec.clone(),
+            test_cfg(),
+            8,
+        ));
+
+        assert_eq!(router.queue_depth("TON/USDT").await, None);
+        assert!(router.active_pairs().await.is_empty());
+
+        let (tx, rx) = mpsc::channel(8);
+        tokio::spawn(router.clone().run(rx));
+
+        for _ in 0..3 {
+            tx.send(ExecutionEvent::Reserved(mk_batch(id, 1)))
+                .await
+                .unwrap();
+        }
+        sleep(Duration::from_millis(30)).await;
+
+        // First batch is executing (blocked); two are waiting.
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(router.queue_depth("TON/USDT").await, Some(2));
+        assert_eq!(router.active_pairs().await, vec!["TON/USDT".to_string()]);
+
+        exec.permits.add_permits(3);
+        sleep(Duration::from_millis(30)).await;
+
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 3);
+        assert_eq!(router.queue_depth("TON/USDT").await, Some(0));
+    }
 }
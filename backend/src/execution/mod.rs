@@ -1,22 +1,139 @@
+pub mod breaker;
+pub mod chain;
 pub mod executor;
+pub mod ton;
 pub mod types;
 
-use crate::execution::types::ReservedBatch;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::error::RepositoryError;
+use crate::execution::executor::SwapExecutor;
+use crate::execution::types::{RecoveryReport, ReservedBatch};
+use crate::metrics::counters::Counters;
 use crate::planner::types::PlannedAllocation;
 use crate::session::store::SessionStore;
+use crate::time::now_ms;
 use types::UserResult;
 
 /// Entry point for execution recovery at the execution layer.
 ///
-/// This function deliberately contains no recovery logic.
-/// It acts as a stable orchestration boundary and delegates
-/// recovery to the persistence layer.
+/// Before delegating recovery to the persistence layer, every chunk left
+/// PENDING is looked up with `SwapExecutor::query_status`: a chunk that
+/// landed on-chain is recorded as SUCCESS (and settled), not unwound and
+/// later executed a second time.
 ///
 /// Semantics:
 /// - must be safe to call on startup
 /// - must be idempotent
-pub async fn recover_uncommitted(store: &SessionStore) -> anyhow::Result<()> {
-    store.repo.recover_uncommitted().await
+/// - fails (leaving batches RESERVED) if a chunk's status cannot be
+///   determined, rather than risk a double execution
+///
+/// Returns what was settled and unwound.
+pub async fn recover_uncommitted<E: SwapExecutor + ?Sized>(
+    store: &SessionStore,
+    exec: &E,
+) -> anyhow::Result<RecoveryReport> {
+    settle_landed_chunks(store, exec, u64::MAX, true).await?;
+    Ok(store.repo.recover_uncommitted().await?)
+}
+
+/// Reconciles batches that have been RESERVED since before
+/// `reserved_before_ms`, the way `recover_uncommitted` does on startup:
+/// chunks the executor reports as landed are settled, the rest unwound.
+///
+/// Unlike startup recovery this runs while workers are live, so batches a
+/// worker has claimed (EXECUTING) are skipped: their swaps may still be in
+/// flight. A queued batch recovered before its worker gets to it fails the
+/// worker's claim and is never executed.
+pub async fn recover_stale<E: SwapExecutor + ?Sized>(
+    store: &SessionStore,
+    exec: &E,
+    reserved_before_ms: u64,
+) -> anyhow::Result<RecoveryReport> {
+    settle_landed_chunks(store, exec, reserved_before_ms, false).await?;
+    Ok(store.repo.recover_stale(reserved_before_ms).await?)
+}
+
+/// Runs `recover_stale` every `max_age / 2` for batches older than
+/// `max_age`, until `shutdown`.
+pub async fn run_stale_recovery(
+    store: Arc<SessionStore>,
+    exec: Arc<dyn SwapExecutor>,
+    max_age: Duration,
+    counters: Counters,
+    shutdown: CancellationToken,
+) {
+    let mut ticker = tokio::time::interval(max_age / 2);
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown.cancelled() => break,
+        }
+
+        let cutoff = now_ms().saturating_sub(max_age.as_millis() as u64);
+        match recover_stale(&store, &*exec, cutoff).await {
+            Ok(report) if report.is_empty() => {}
+            Ok(report) => {
+                counters.record_recovery(&report);
+                warn!(?report, "recovered stale RESERVED batches");
+            }
+            Err(e) => warn!(error = ?e, "stale batch recovery failed"),
+        }
+    }
+
+    info!("stale batch recovery stopped");
+}
+
+/// Marks PENDING chunks of batches reserved before `reserved_before_ms`
+/// that `query_status` reports as landed, so recovery settles them.
+/// Chunks of claimed batches are only considered if `include_claimed`.
+async fn settle_landed_chunks<E: SwapExecutor + ?Sized>(
+    store: &SessionStore,
+    exec: &E,
+    reserved_before_ms: u64,
+    include_claimed: bool,
+) -> anyhow::Result<()> {
+    for chunk in store.repo.pending_chunks().await? {
+        if chunk.created_ms >= reserved_before_ms || (chunk.claimed && !include_claimed) {
+            continue;
+        }
+
+        let landed = exec
+            .query_status(chunk.chunk_id)
+            .await
+            .with_context(|| format!("status of chunk {} unknown", chunk.chunk_id))?;
+
+        if let Some(receipt) = landed
+            && store
+                .repo
+                .settle_landed_chunk(&chunk.batch_id, &chunk.chunk_id, &receipt.tx_id)
+                .await?
+        {
+            info!(
+                batch_id = %chunk.batch_id,
+                chunk_id = %chunk.chunk_id,
+                tx_id = %receipt.tx_id,
+                "recovered chunk had already landed"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Claims a RESERVED batch for its worker; see
+/// `SessionRepository::claim_batch`.
+pub async fn claim_batch(
+    store: &SessionStore,
+    batch: &ReservedBatch,
+) -> Result<bool, RepositoryError> {
+    store.repo.claim_batch(&batch.batch_id).await
 }
 
 /// Commits the results of a previously reserved batch.
@@ -30,10 +147,43 @@ pub async fn commit_batch(
     store: &SessionStore,
     batch: &ReservedBatch,
     results: &[UserResult],
-) -> anyhow::Result<()> {
+) -> Result<(), RepositoryError> {
     store.repo.commit_batch(batch, results).await
 }
 
+/// Records a failed `commit_batch` attempt for a RESERVED batch.
+pub async fn record_commit_failure(
+    store: &SessionStore,
+    batch: &ReservedBatch,
+) -> Result<(), RepositoryError> {
+    store.repo.record_commit_failure(&batch.batch_id).await
+}
+
+/// Hands a batch that could not be committed over to manual reconciliation.
+///
+/// Delegates to the repository, which keeps `results` and takes the batch
+/// out of restart recovery.
+pub async fn dead_letter_batch(
+    store: &SessionStore,
+    batch: &ReservedBatch,
+    results: &[UserResult],
+    error: &str,
+) -> Result<(), RepositoryError> {
+    store.repo.dead_letter_batch(batch, results, error).await
+}
+
+/// Aborts a RESERVED batch that will never reach a worker.
+///
+/// Like `commit_batch`, this delegates entirely to the repository, which
+/// unwinds in-flight state and is a no-op for already finalized batches.
+pub async fn abort_batch(
+    store: &SessionStore,
+    batch: &ReservedBatch,
+    reason: &str,
+) -> Result<(), RepositoryError> {
+    store.repo.abort_batch(&batch.batch_id, reason).await
+}
+
 /// Attempts to reserve execution capacity for a set of planned allocations.
 ///
 /// This function performs **only input validation and delegation**.
@@ -59,10 +209,10 @@ pub async fn reserve_execution(
         }
     }
 
-    store
+    Ok(store
         .repo
         .reserve_execution(pair_id, now_ms, allocations)
-        .await
+        .await?)
 }
 
 /// Narrowing helper used at the execution–persistence boundary.
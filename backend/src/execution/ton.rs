@@ -0,0 +1,410 @@
+//! TON swap executor over HTTP JSON-RPC.
+//!
+//! A chunk is submitted with `swap.submit` (the endpoint builds, signs and
+//! sends the swap message from `wallet_address`) and then polled with
+//! `swap.status` until it is confirmed or fails. The endpoint dedupes on
+//! `idempotency_key`, so a resubmitted chunk returns the original hash, and
+//! `swap.lookup` answers restart recovery's `query_status`.
+//!
+//! Chain rejections are mapped onto `SwapError` (see `map_chain_error`) so
+//! the worker records a bounded failure reason.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tracing::{debug, instrument};
+use uuid::Uuid;
+
+use crate::error::SwapError;
+use crate::execution::executor::SwapExecutor;
+use crate::execution::types::{SwapCall, SwapReceipt};
+
+/// JSON-RPC method submitting a swap; returns `{ "hash": .. }`.
+pub const SUBMIT_METHOD: &str = "swap.submit";
+
+/// JSON-RPC method reporting a submitted swap's status.
+pub const STATUS_METHOD: &str = "swap.status";
+
+/// JSON-RPC method finding the swap submitted for a chunk; returns `null`
+/// or `{ "hash": .., "status": .. }`.
+pub const LOOKUP_METHOD: &str = "swap.lookup";
+
+/// Endpoint, wallet and timeouts of a `TonSwapExecutor`. Not `Debug`, so
+/// the API key cannot end up in logs.
+#[derive(Clone)]
+pub struct TonRpcConfig {
+    pub rpc_url: Url,
+    pub wallet_address: String,
+    /// Sent as `X-API-Key` when set.
+    pub api_key: Option<String>,
+    pub connect_timeout: Duration,
+    /// Bound on each HTTP request.
+    pub request_timeout: Duration,
+    /// Delay between `swap.status` polls.
+    pub poll_interval: Duration,
+    /// Give up (as `SwapError::Timeout`) if the swap is not confirmed
+    /// within this long after submission.
+    pub confirm_timeout: Duration,
+}
+
+impl TonRpcConfig {
+    pub fn new(rpc_url: Url, wallet_address: String) -> Self {
+        Self {
+            rpc_url,
+            wallet_address,
+            api_key: None,
+            connect_timeout: Duration::from_secs(5),
+            request_timeout: Duration::from_secs(10),
+            poll_interval: Duration::from_secs(1),
+            confirm_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+pub struct TonSwapExecutor {
+    http: Client,
+    cfg: TonRpcConfig,
+}
+
+impl TonSwapExecutor {
+    pub fn new(cfg: TonRpcConfig) -> Result<Self, reqwest::Error> {
+        let http = Client::builder()
+            .connect_timeout(cfg.connect_timeout)
+            .timeout(cfg.request_timeout)
+            .build()?;
+
+        Ok(Self { http, cfg })
+    }
+
+    /// Calls `method` and returns its `result`, or the mapped JSON-RPC error.
+    async fn call(&self, method: &str, params: Value) -> Result<Value, SwapError> {
+        let mut req = self.http.post(self.cfg.rpc_url.clone()).json(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        }));
+        if let Some(key) = &self.cfg.api_key {
+            req = req.header("X-API-Key", key);
+        }
+
+        let resp = req.send().await.map_err(transport_error)?;
+        let status = resp.status();
+
+        // Error envelopes may come with a non-2xx status; read the body first.
+        let body: RpcResponse = resp.json().await.map_err(|e| {
+            if e.is_timeout() {
+                SwapError::Timeout
+            } else {
+                SwapError::Other(format!("invalid JSON-RPC response (http {status}): {e}"))
+            }
+        })?;
+
+        match body.error {
+            Some(err) => Err(map_chain_error(Some(err.code), &err.message)),
+            None => Ok(body.result),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    #[serde(default)]
+    result: Value,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct Submitted {
+    hash: String,
+}
+
+#[derive(Deserialize)]
+struct LookedUp {
+    hash: String,
+    status: String,
+}
+
+#[derive(Deserialize)]
+struct SwapStatus {
+    status: String,
+    exit_code: Option<i64>,
+    reason: Option<String>,
+}
+
+#[async_trait]
+impl SwapExecutor for TonSwapExecutor {
+    #[instrument(skip(self, call), fields(chunk_id = %call.chunk_id), level = "debug")]
+    async fn execute_swap(&self, call: SwapCall) -> Result<SwapReceipt, SwapError> {
+        let submitted = self
+            .call(
+                SUBMIT_METHOD,
+                json!({
+                    "wallet": self.cfg.wallet_address,
+                    "pair_id": call.pair_id,
+                    "session_id": call.session_id,
+                    "chunk_id": call.chunk_id,
+                    // u128 does not fit a JSON number.
+                    "bid": call.bid.to_string(),
+                    "idempotency_key": call.idempotency_key,
+                    "valid_until": call.deadline_ms / 1_000,
+                }),
+            )
+            .await?;
+        let Submitted { hash } = serde_json::from_value(submitted)
+            .map_err(|e| SwapError::Other(format!("invalid submit result: {e}")))?;
+
+        debug!(%hash, "ton swap submitted");
+
+        let give_up = tokio::time::Instant::now() + self.cfg.confirm_timeout;
+        loop {
+            if tokio::time::Instant::now() + self.cfg.poll_interval > give_up {
+                return Err(SwapError::Timeout);
+            }
+            tokio::time::sleep(self.cfg.poll_interval).await;
+
+            let status = self.call(STATUS_METHOD, json!({ "hash": hash })).await?;
+            let status: SwapStatus = serde_json::from_value(status)
+                .map_err(|e| SwapError::Other(format!("invalid status result: {e}")))?;
+
+            match status.status.as_str() {
+                "confirmed" => {
+                    return Ok(SwapReceipt {
+                        tx_id: hash,
+                        idempotency_key: Some(call.idempotency_key),
+                    });
+                }
+                "failed" => {
+                    return Err(map_chain_error(
+                        status.exit_code,
+                        status.reason.as_deref().unwrap_or_default(),
+                    ));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Only a confirmed swap counts as landed: one still pending expired at
+    /// its `valid_until`, which has passed by the time recovery runs.
+    async fn query_status(&self, chunk_id: Uuid) -> Result<Option<SwapReceipt>, SwapError> {
+        let found = self
+            .call(LOOKUP_METHOD, json!({ "chunk_id": chunk_id }))
+            .await?;
+        let found: Option<LookedUp> = serde_json::from_value(found)
+            .map_err(|e| SwapError::Other(format!("invalid lookup result: {e}")))?;
+
+        Ok(found
+            .filter(|f| f.status == "confirmed")
+            .map(|f| SwapReceipt {
+                tx_id: f.hash,
+                idempotency_key: None,
+            }))
+    }
+}
+
+/// Maps a chain/endpoint rejection onto `SwapError`.
+///
+/// Known reasons are matched on the message; anything else keeps its
+/// (non-negative) code as `Rejected`, or its message as `Other`.
+pub fn map_chain_error(code: Option<i64>, message: &str) -> SwapError {
+    let m = message.to_ascii_lowercase();
+
+    if m.contains("slippage") {
+        SwapError::Slippage
+    } else if m.contains("liquidity") {
+        SwapError::InsufficientLiquidity
+    } else if m.contains("market closed") || m.contains("market not open") {
+        SwapError::MarketNotOpen
+    } else if let Some(code) = code.and_then(|c| u32::try_from(c).ok()) {
+        SwapError::Rejected { code }
+    } else {
+        SwapError::Other(message.to_string())
+    }
+}
+
+fn transport_error(e: reqwest::Error) -> SwapError {
+    if e.is_timeout() {
+        SwapError::Timeout
+    } else {
+        SwapError::Other(format!("ton rpc: {e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn executor(server: &MockServer) -> TonSwapExecutor {
+        TonSwapExecutor::new(TonRpcConfig {
+            api_key: Some("secret".into()),
+            request_timeout: Duration::from_millis(200),
+            poll_interval: Duration::from_millis(10),
+            confirm_timeout: Duration::from_secs(2),
+            ..TonRpcConfig::new(server.uri().parse().unwrap(), "EQwallet".into())
+        })
+        .unwrap()
+    }
+
+    fn call() -> SwapCall {
+        SwapCall {
+            pair_id: "TON/USDT".into(),
+            session_id: Uuid::new_v4(),
+            bid: 1_000,
+            chunk_id: Uuid::new_v4(),
+            deadline_ms: 60_000,
+            idempotency_key: "batch:chunk".into(),
+        }
+    }
+
+    fn result(v: Value) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(json!({ "jsonrpc": "2.0", "id": 1, "result": v }))
+    }
+
+    fn rpc_error(code: i64, message: &str) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "error": { "code": code, "message": message },
+        }))
+    }
+
+    #[tokio::test]
+    async fn confirmed_swap_returns_the_tx_hash() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(header("X-API-Key", "secret"))
+            .and(body_partial_json(json!({
+                "method": SUBMIT_METHOD,
+                "params": { "wallet": "EQwallet", "bid": "1000", "idempotency_key": "batch:chunk" },
+            })))
+            .respond_with(result(json!({ "hash": "0xabc" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        // One pending poll, then confirmed.
+        Mock::given(body_partial_json(json!({ "method": STATUS_METHOD })))
+            .respond_with(result(json!({ "status": "pending" })))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(body_partial_json(
+            json!({ "method": STATUS_METHOD, "params": { "hash": "0xabc" } }),
+        ))
+        .respond_with(result(json!({ "status": "confirmed" })))
+        .mount(&server)
+        .await;
+
+        let receipt = executor(&server).execute_swap(call()).await.unwrap();
+
+        assert_eq!(receipt.tx_id, "0xabc");
+        assert_eq!(receipt.idempotency_key.as_deref(), Some("batch:chunk"));
+    }
+
+    #[tokio::test]
+    async fn slippage_rejection_maps_to_slippage() {
+        let server = MockServer::start().await;
+        Mock::given(body_partial_json(json!({ "method": SUBMIT_METHOD })))
+            .respond_with(rpc_error(-32000, "Slippage exceeded: min_out not met"))
+            .mount(&server)
+            .await;
+
+        let err = executor(&server).execute_swap(call()).await.unwrap_err();
+        assert_eq!(err, SwapError::Slippage);
+    }
+
+    #[tokio::test]
+    async fn failed_transaction_maps_its_exit_code() {
+        let server = MockServer::start().await;
+        Mock::given(body_partial_json(json!({ "method": SUBMIT_METHOD })))
+            .respond_with(result(json!({ "hash": "0xdef" })))
+            .mount(&server)
+            .await;
+        Mock::given(body_partial_json(json!({ "method": STATUS_METHOD })))
+            .respond_with(result(json!({ "status": "failed", "exit_code": 65 })))
+            .mount(&server)
+            .await;
+
+        let err = executor(&server).execute_swap(call()).await.unwrap_err();
+        assert_eq!(err, SwapError::Rejected { code: 65 });
+    }
+
+    #[tokio::test]
+    async fn query_status_reports_only_confirmed_swaps() {
+        let server = MockServer::start().await;
+        let landed = Uuid::new_v4();
+        let pending = Uuid::new_v4();
+
+        Mock::given(body_partial_json(
+            json!({ "method": LOOKUP_METHOD, "params": { "chunk_id": landed } }),
+        ))
+        .respond_with(result(json!({ "hash": "0xfeed", "status": "confirmed" })))
+        .mount(&server)
+        .await;
+        Mock::given(body_partial_json(
+            json!({ "method": LOOKUP_METHOD, "params": { "chunk_id": pending } }),
+        ))
+        .respond_with(result(json!({ "hash": "0xbeef", "status": "pending" })))
+        .mount(&server)
+        .await;
+        Mock::given(body_partial_json(json!({ "method": LOOKUP_METHOD })))
+            .respond_with(result(Value::Null))
+            .with_priority(10)
+            .mount(&server)
+            .await;
+
+        let exec = executor(&server);
+        let receipt = exec.query_status(landed).await.unwrap().unwrap();
+        assert_eq!(receipt.tx_id, "0xfeed");
+        assert!(exec.query_status(pending).await.unwrap().is_none());
+        assert!(exec.query_status(Uuid::new_v4()).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn slow_endpoint_times_out() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(result(json!({ "hash": "0x1" })).set_delay(Duration::from_secs(1)))
+            .mount(&server)
+            .await;
+
+        let err = executor(&server).execute_swap(call()).await.unwrap_err();
+        assert_eq!(err, SwapError::Timeout);
+        assert!(err.is_retryable());
+    }
+
+    #[test]
+    fn chain_errors_map_onto_swap_errors() {
+        let cases = [
+            (
+                None,
+                "pool has insufficient liquidity",
+                SwapError::InsufficientLiquidity,
+            ),
+            (Some(3), "Market closed", SwapError::MarketNotOpen),
+            (Some(-1), "market not open yet", SwapError::MarketNotOpen),
+            (Some(42), "bad message", SwapError::Rejected { code: 42 }),
+            (
+                Some(-32603),
+                "internal",
+                SwapError::Other("internal".into()),
+            ),
+        ];
+        for (code, message, want) in cases {
+            assert_eq!(map_chain_error(code, message), want, "{message}");
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use serde::Serialize;
 use uuid::Uuid;
 
 #[derive(Clone, Debug)]
@@ -12,6 +13,48 @@ pub struct ReservedUser {
     pub chunks: Vec<ReservedChunk>,
 }
 
+/// A chunk of a RESERVED or EXECUTING batch with no recorded outcome yet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PendingChunk {
+    pub batch_id: Uuid,
+    pub session_id: Uuid,
+    pub chunk_id: Uuid,
+    /// When the batch was reserved.
+    pub created_ms: u64,
+    /// The batch is EXECUTING, i.e. claimed by a worker.
+    pub claimed: bool,
+}
+
+/// What a recovery pass changed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RecoveryReport {
+    /// Batches with at least one recorded outcome, settled as COMMITTED.
+    pub batches_committed: usize,
+    /// Batches with no recorded outcome, finalized as ABORTED.
+    pub batches_aborted: usize,
+    /// PENDING chunks marked SKIPPED.
+    pub chunks_unwound: usize,
+    /// Sum of the bids of unwound chunks, released from `in_flight_bid`.
+    pub bid_released: u128,
+}
+
+impl RecoveryReport {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// One row of the per-chunk outcome trail (`chunk_events`).
+#[derive(Clone, Debug, Serialize)]
+pub struct ChunkEvent {
+    pub chunk_id: Uuid,
+    pub batch_id: Uuid,
+    pub session_id: Uuid,
+    pub status: ChunkStatus,
+    /// When the outcome was settled.
+    pub ts_ms: u64,
+}
+
 #[derive(Clone, Debug)]
 pub struct ReservedBatch {
     pub batch_id: Uuid,
@@ -23,22 +66,79 @@ pub struct ReservedBatch {
 #[derive(Clone, Debug)]
 pub enum ExecutionEvent {
     Reserved(ReservedBatch),
+    /// Published by the worker after `commit_batch` succeeds.
+    Committed {
+        batch_id: Uuid,
+        pair_id: String,
+        results_summary: CommitSummary,
+    },
 }
 
-#[derive(Clone, Debug)]
+/// Per-batch chunk outcome counts, as committed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommitSummary {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub simulated: usize,
+    /// Sum of bids of successful chunks (consumed from `remaining_bid`).
+    pub committed_bid: u128,
+}
+
+impl CommitSummary {
+    pub fn from_results(batch: &ReservedBatch, results: &[UserResult]) -> Self {
+        let bids: std::collections::HashMap<Uuid, u128> = batch
+            .users
+            .iter()
+            .flat_map(|u| &u.chunks)
+            .map(|c| (c.chunk_id, c.bid))
+            .collect();
+
+        let mut summary = Self::default();
+        for cr in results.iter().flat_map(|u| &u.chunk_results) {
+            match cr.status {
+                ChunkStatus::Success { .. } => {
+                    summary.succeeded += 1;
+                    summary.committed_bid += bids.get(&cr.chunk_id).copied().unwrap_or(0);
+                }
+                ChunkStatus::Failed { .. } => summary.failed += 1,
+                ChunkStatus::Skipped { .. } => summary.skipped += 1,
+                ChunkStatus::Simulated => summary.simulated += 1,
+                // Still in flight until recovery settles it.
+                ChunkStatus::Unknown { .. } => {}
+            }
+        }
+        summary
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub enum ChunkStatus {
-    Success { tx_id: String },
-    Failed { reason: String },
-    Skipped { reason: String },
+    Success {
+        tx_id: String,
+    },
+    Failed {
+        reason: String,
+    },
+    Skipped {
+        reason: String,
+    },
+    /// Safe mode: the chunk passed Gate B but no swap was submitted.
+    Simulated,
+    /// The swap timed out and whether it landed could not be determined.
+    /// Never settled by `commit_batch`: the item stays PENDING for recovery.
+    Unknown {
+        reason: String,
+    },
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct ChunkResult {
     pub chunk_id: Uuid,
     pub status: ChunkStatus,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct UserResult {
     pub session_id: Uuid,
     pub chunk_results: Vec<ChunkResult>,
@@ -52,10 +152,27 @@ pub struct SwapCall {
     pub session_id: uuid::Uuid,
     pub bid: u128,
     pub chunk_id: uuid::Uuid,
+    /// Unix ms after which the swap must not land. The worker stops waiting
+    /// at this point and asks `query_status` whether it landed before it.
+    pub deadline_ms: u64,
+    /// Deterministic per-chunk key; see [`SwapCall::idempotency_key_for`].
+    pub idempotency_key: String,
+}
+
+impl SwapCall {
+    /// Stable dedupe key for a reserved chunk, derived from `(batch_id, chunk_id)`.
+    ///
+    /// Every attempt at the same chunk (retries, re-delivery after a crash)
+    /// carries the same key, so the chain executor can recognise a resubmit.
+    pub fn idempotency_key_for(batch_id: Uuid, chunk_id: Uuid) -> String {
+        format!("{batch_id}:{chunk_id}")
+    }
 }
 
 /// Swap receipt output (what you store as tx_id).
 #[derive(Clone, Debug)]
 pub struct SwapReceipt {
     pub tx_id: String,
+    /// Key of the call this receipt answers, if the executor echoes it.
+    pub idempotency_key: Option<String>,
 }
@@ -1,116 +1,186 @@
 use std::sync::Arc;
 use std::time::Duration;
 
+use anyhow::Context;
 use backend::{
     config::AppConfig,
     db::Db,
+    error::RfqError,
     execution::{
-        executor::{PairExecutorRouter, SwapExecutor},
-        recover_uncommitted,
-        types::{self, ExecutionEvent, SwapReceipt},
+        breaker::BreakerConfig,
+        chain::build_executor,
+        executor::{PairExecutorRouter, RetryPolicy, SwapExecutor, WorkerConfig},
+        recover_uncommitted, run_stale_recovery,
+        types::ExecutionEvent,
     },
     logger::init_tracing,
     market::manager::MarketManager,
+    market::omniston::client::OmnistonWsClient,
+    market::pulses::{PulseWarmup, TrendConfirmation},
+    market::stonfi::poller::QuoteFeed,
     market::{market_view_store::MarketViewStore, stonfi::StonfiClient, types::Pair},
-    metrics::counters::Counters,
-    scheduler::scheduler::Scheduler,
-    session::repository_sqlx::SqlxSessionRepository,
+    metrics::{counters::Counters, http as metrics_http},
+    scheduler::{control::PairControl, scheduler::Scheduler, trace::SchedulerTrace},
+    session::repository_sqlx::{SqlDialect, SqlxSessionRepository},
     session::store::SessionStore,
-    time::now_ms,
 };
 use tokio::sync::mpsc;
+use tokio::task::{JoinError, JoinHandle};
+use tokio_util::sync::CancellationToken;
 
-#[derive(Clone)]
-struct DummySwapExecutor;
-
-#[async_trait::async_trait]
-impl SwapExecutor for DummySwapExecutor {
-    async fn execute_swap(&self, call: types::SwapCall) -> anyhow::Result<SwapReceipt> {
-        // TODO: Replace with real TON / EMC execution.
-        // Map chain errors into stable strings for classify_error(), e.g:
-        // - market closed => Err(anyhow::anyhow!("MarketNotOpen"))
-        // - slippage => Err(anyhow::anyhow!("Slippage"))
-        let _ = call;
-        Ok(SwapReceipt {
-            tx_id: "dummy_tx".to_string(),
-        })
-    }
-}
+type ExecutorRouter = PairExecutorRouter<dyn SwapExecutor>;
+type QuoteTask = JoinHandle<Result<(), RfqError>>;
 
 /// Initializes DB, runs migrations, constructs repository/store, and performs
-/// restart recovery to unwind any RESERVED-but-uncommitted batches.
-async fn init_store(cfg: &AppConfig) -> anyhow::Result<Arc<SessionStore>> {
-    let db = Db::connect(&cfg.database_url).await?;
+/// restart recovery to reconcile any RESERVED-but-uncommitted batches.
+async fn init_store(
+    cfg: &AppConfig,
+    exec: &dyn SwapExecutor,
+    counters: &Counters,
+) -> anyhow::Result<Arc<SessionStore>> {
+    let db = Db::connect(&cfg.database_url, &cfg.db).await?;
     db.migrate().await?;
 
-    let repo = Arc::new(SqlxSessionRepository::new(db.pool.clone()));
-    let store = Arc::new(SessionStore::new(repo));
+    let replica = match &cfg.database_read_url {
+        Some(url) => Some(Db::connect(url, &cfg.db).await?.pool),
+        None => None,
+    };
 
-    // Safety: unwind in-flight leakage from RESERVED batches on restart.
-    recover_uncommitted(&store).await?;
+    let repo = Arc::new(
+        SqlxSessionRepository::with_read_pool(db.pool.clone(), replica)
+            .with_dialect(SqlDialect::from_url(&cfg.database_url)),
+    );
+    let store = Arc::new(
+        SessionStore::new(repo)
+            .with_cache_metrics(counters.session_cache.clone())
+            .with_cache_max_idle_ms(cfg.session_cache_max_idle_ms),
+    );
+
+    // Safety: settle or unwind RESERVED batches left behind on restart.
+    let report = recover_uncommitted(&store, exec).await?;
+    counters.record_recovery(&report);
+    if report.is_empty() {
+        tracing::info!("restart recovery: nothing to reconcile");
+    } else {
+        tracing::warn!(
+            batches_committed = report.batches_committed,
+            batches_aborted = report.batches_aborted,
+            chunks_unwound = report.chunks_unwound,
+            bid_released = %report.bid_released,
+            "restart recovery reconciled RESERVED batches"
+        );
+    }
 
     Ok(store)
 }
 
-/// Starts the per-pair executor router and returns the scheduler->router sender.
+/// Starts the per-pair executor router and returns the scheduler->router sender,
+/// the router (used by the scheduler for backpressure) and its task, which
+/// completes once the router has drained after `shutdown`.
 fn start_executor_router(
+    exec_impl: Arc<dyn SwapExecutor>,
     store: Arc<SessionStore>,
     market_view: MarketViewStore,
     cfg: &AppConfig,
-) -> mpsc::Sender<ExecutionEvent> {
+    counters: Counters,
+    shutdown: CancellationToken,
+) -> (
+    mpsc::Sender<ExecutionEvent>,
+    Arc<ExecutorRouter>,
+    JoinHandle<()>,
+) {
     let (exec_tx, exec_rx) = mpsc::channel::<ExecutionEvent>(cfg.exec_queue_capacity);
 
-    let exec_impl = Arc::new(DummySwapExecutor);
-
-    let router = Arc::new(PairExecutorRouter::new(
-        store,
-        market_view,
-        exec_impl,
-        cfg.default_failure_cooldown_ms,
-        128, // per-pair queue capacity
-    ));
+    let router = Arc::new(
+        PairExecutorRouter::new(
+            store,
+            market_view,
+            exec_impl,
+            WorkerConfig {
+                default_failure_cooldown_ms: cfg.default_failure_cooldown_ms,
+                market_refresh_every_chunks: cfg.exec_market_refresh_every_chunks,
+                max_snapshot_age_ms: cfg.max_snapshot_age_ms,
+                max_concurrent_chunks: cfg.exec_max_concurrent_chunks,
+                max_parallel_users: cfg.exec_max_parallel_users,
+                safe_mode: cfg.safe_mode,
+                retry: RetryPolicy {
+                    max_attempts: cfg.exec_retry_max_attempts,
+                    base_backoff_ms: cfg.exec_retry_base_backoff_ms,
+                },
+                breaker: BreakerConfig {
+                    window_ms: cfg.exec_breaker_window_ms,
+                    min_samples: cfg.exec_breaker_min_samples,
+                    failure_ratio: cfg.exec_breaker_failure_ratio,
+                    consecutive_failures: cfg.exec_breaker_consecutive_failures,
+                },
+                max_enqueue_attempts: cfg.exec_max_enqueue_attempts,
+                max_commit_attempts: cfg.exec_max_commit_attempts,
+                swap_timeout_ms: cfg.swap_timeout_ms,
+                swap_timeout_ms_by_pair: cfg.swap_timeout_ms_by_pair.clone(),
+            },
+            128, // per-pair queue capacity
+        )
+        .with_counters(counters),
+    );
 
-    tokio::spawn(router.run(exec_rx));
+    let task = tokio::spawn(router.clone().run_with_shutdown(exec_rx, shutdown));
 
-    exec_tx
+    (exec_tx, router, task)
 }
 
-/// Starts the scheduler loop (fixed cadence). Each tick reads the latest market snapshot
-/// and calls scheduler.on_tick().
-fn start_scheduler_loop(
-    scheduler: Scheduler,
+fn setup_market_manager(
     market_view: MarketViewStore,
-    exec_tx: mpsc::Sender<ExecutionEvent>,
-    pair_id: String,
-    interval: Duration,
-) {
-    tokio::spawn(async move {
-        let mut ticker = tokio::time::interval(interval);
-
-        loop {
-            ticker.tick().await;
-
-            let Some(market) = market_view.get(&pair_id).await else {
-                // No market snapshot yet -> skip scheduling.
-                continue;
-            };
-
-            if let Err(e) = scheduler
-                .on_tick(&pair_id, market, exec_tx.clone(), now_ms())
-                .await
-            {
-                tracing::error!(error=?e, pair_id=%pair_id, "scheduler tick failed");
-            }
-        }
-    });
+    cfg: &AppConfig,
+    shutdown: CancellationToken,
+) -> MarketManager {
+    let stonfi_client = StonfiClient::new(cfg.stonfi_http_endpoint.clone()).unwrap();
+
+    MarketManager::new(stonfi_client, market_view, Duration::from_secs(3))
+        .with_max_feed_gap_ms(cfg.max_snapshot_age_ms)
+        .with_shutdown(shutdown)
 }
 
-fn setup_market_manager(market_view: MarketViewStore, cfg: &AppConfig) -> MarketManager {
-    let stonfi_client = StonfiClient::new(cfg.stonfi_http_endpoint.clone()).unwrap();
+/// Starts the Omniston quote stream for `pair_id` when configured and
+/// returns the feed for the pair's poller and the stream task, which ends on `shutdown`.
+/// An invalid subscription (e.g. unset assets) is an error, not a feed that
+/// never delivers.
+fn start_quote_feed(
+    cfg: &AppConfig,
+    pair_id: &str,
+    counters: &Counters,
+    shutdown: CancellationToken,
+) -> anyhow::Result<Option<(QuoteFeed, QuoteTask)>> {
+    let Some(feed) = cfg.omniston.as_ref() else {
+        return Ok(None);
+    };
+    let rfq = feed
+        .rfq(pair_id)
+        .with_context(|| format!("invalid Omniston feed config for {pair_id}"))?;
+    let client = OmnistonWsClient::new(feed.ws_url.clone()).with_metrics(counters.omniston.clone());
+
+    let (tx, rx) = mpsc::channel(256);
+    let task =
+        tokio::spawn(async move { client.request_for_quote_stream(&rfq, tx, shutdown).await });
+
+    Ok(Some((QuoteFeed::new(rx), task)))
+}
 
-    let market = MarketManager::new(stonfi_client, market_view, Duration::from_secs(3));
+/// Completes once the quote stream task ends; pending forever without one.
+async fn quote_stream_ended(
+    task: &mut Option<QuoteTask>,
+) -> Result<Result<(), RfqError>, JoinError> {
+    match task {
+        Some(task) => task.await,
+        None => std::future::pending().await,
+    }
+}
 
-    market
+fn log_quote_stream_exit(res: Result<Result<(), RfqError>, JoinError>) {
+    match res {
+        Ok(Err(e)) => tracing::error!(error=?e, "omniston quote stream failed"),
+        Err(e) => tracing::error!(error=?e, "omniston quote stream task failed"),
+        Ok(Ok(())) => {}
+    }
 }
 
 #[tokio::main]
@@ -123,56 +193,199 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("Starting Kaskade backend...");
 
     let cfg = AppConfig::from_env();
+    if cfg.safe_mode {
+        tracing::warn!("Safe mode enabled: swaps will be simulated, not submitted");
+    }
 
     // Choose the pair you want to run (single-pair bootstrap).
     let pair = Pair::new("TON".into(), "STON".into());
     let pair_id = pair.id();
 
-    let market_view = MarketViewStore::new();
+    // Gates fail closed on a dead feed: stale snapshots read as missing.
+    let market_view = MarketViewStore::new()
+        .with_ttl(cfg.max_snapshot_age_ms)
+        .with_history(cfg.market_history_len);
+
+    // Misconfiguration fails here, before recovery or any swap.
+    let exec_impl = build_executor(&cfg)?;
+    tracing::info!(executor = cfg.executor.name(), "chain executor configured");
+
+    let counters = Counters::default();
+
+    let store = init_store(&cfg, &*exec_impl, &counters).await?;
+
+    // Scheduler and market feed stop on `shutdown`; the router is cancelled
+    // only after the scheduler has exited so no reserved batch is dropped.
+    let shutdown = CancellationToken::new();
+    let router_shutdown = CancellationToken::new();
+
+    let metrics_listener = tokio::net::TcpListener::bind(&cfg.metrics_addr).await?;
+    let pair_control = PairControl::new();
+    let scheduler_trace = SchedulerTrace::new();
+    tokio::spawn(metrics_http::serve(
+        metrics_listener,
+        counters.clone(),
+        pair_control.clone(),
+        scheduler_trace.clone(),
+        shutdown.clone(),
+    ));
 
-    let store = init_store(&cfg).await?;
+    let stale_recovery_task = (cfg.stale_batch_recovery_ms > 0).then(|| {
+        tokio::spawn(run_stale_recovery(
+            store.clone(),
+            exec_impl.clone(),
+            Duration::from_millis(cfg.stale_batch_recovery_ms),
+            counters.clone(),
+            shutdown.clone(),
+        ))
+    });
 
-    let exec_tx = start_executor_router(store.clone(), market_view.clone(), &cfg);
+    let (exec_tx, router, router_task) = start_executor_router(
+        exec_impl,
+        store.clone(),
+        market_view.clone(),
+        &cfg,
+        counters.clone(),
+        router_shutdown.clone(),
+    );
 
-    let scheduler = Scheduler::new(
+    let expiry_task = tokio::spawn(store.clone().run_expiry_sweeper(
+        Duration::from_millis(cfg.session_expiry_sweep_ms),
+        shutdown.clone(),
+    ));
+
+    let (quotes, mut quote_task) =
+        start_quote_feed(&cfg, &pair_id, &counters, shutdown.clone())?.unzip();
+
+    let mut scheduler = Scheduler::new(
         store,
         cfg.scheduler_candidate_min,
         cfg.scheduler_max_attempts,
         cfg.scheduler_max_users_per_batch,
-        Counters::default(),
-    );
+        counters,
+    )
+    .with_max_snapshot_age_ms(cfg.max_snapshot_age_ms)
+    .with_plan_rotation(cfg.scheduler_rotate_plan_start)
+    .with_plan_shuffle(cfg.scheduler_shuffle_plan_order)
+    .with_allocation_mode(cfg.planner_allocation_mode)
+    .with_starvation_ms(cfg.starvation_ms)
+    .with_backpressure(router, cfg.max_inflight_batches_per_pair)
+    .with_tick_jitter(Duration::from_millis(cfg.tick_jitter_ms))
+    .with_pair_control(pair_control);
+    if let Some(cap) = cfg.scheduler_max_total_bid_per_tick {
+        scheduler = scheduler.with_max_total_bid_per_tick(cap);
+    }
+    if let Some(cap) = cfg.scheduler_max_deficit {
+        scheduler = scheduler.with_max_deficit(cap);
+    }
+    if cfg.scheduler_trace {
+        scheduler = scheduler.with_trace(scheduler_trace);
+    }
+    if cfg.scheduler_dry_run {
+        tracing::warn!("scheduler dry run: batches are planned but never reserved");
+        scheduler = scheduler.with_dry_run(None);
+    }
 
-    start_scheduler_loop(
-        scheduler,
+    let scheduler_task = tokio::spawn(scheduler.run(
+        pair_id.clone(),
         market_view.clone(),
         exec_tx,
-        pair_id.clone(),
         Duration::from_millis(250),
-    );
+        shutdown.clone(),
+    ));
 
-    let market_manager = Arc::new(setup_market_manager(market_view, &cfg));
+    let market_manager = setup_market_manager(market_view, &cfg, shutdown.clone());
+    if let Some(quotes) = quotes {
+        market_manager.add_quote_feed(&pair_id, quotes).await;
+    }
 
-    let mm = Arc::clone(&market_manager);
-    let pair_id_clone = pair_id.clone();
     let pool_addr = "EQAdPJcaFwTk7CfJIeE9HElAyjBqx_tni6_m8cDCv9X0SOwn".to_string();
 
-    tokio::spawn(async move {
-        if let Err(e) = mm
-            .subscribe_stonfi_pair(
-                pair_id_clone,
-                pool_addr,
-                cfg.window_size,
-                cfg.min_warm_up,
-                cfg.max_slippage_bps,
-            )
-            .await
-        {
+    let feed_task = match market_manager
+        .subscribe_stonfi_pair(
+            pair_id.clone(),
+            pool_addr,
+            cfg.window_size,
+            PulseWarmup {
+                min_samples: cfg.min_warm_up_samples,
+                min_age_ms: cfg.min_warm_up,
+            },
+            cfg.max_slippage_bps,
+            TrendConfirmation {
+                min_drop_bps: cfg.trend_min_drop_bps,
+                min_slope_bps_per_min: cfg.trend_min_slope_bps_per_min,
+            },
+        )
+        .await
+    {
+        Ok(task) => Some(task),
+        Err(e) => {
             tracing::error!(error=?e, "failed to subscribe stonfi pair");
+            None
+        }
+    };
+
+    // A quote stream that ends early leaves the pair without slippage data
+    // (the poller stops publishing it), so the process shuts down instead.
+    let quote_stream_exit = tokio::select! {
+        res = tokio::signal::ctrl_c() => {
+            res?;
+            None
+        }
+        res = quote_stream_ended(&mut quote_task) => Some(res),
+    };
+    match quote_stream_exit {
+        None => tracing::info!("Shutdown signal received"),
+        Some(res) => {
+            quote_task = None;
+            log_quote_stream_exit(res);
+            tracing::error!("omniston quote stream ended; shutting down");
+        }
+    }
+
+    shutdown.cancel();
+
+    let drain = async {
+        if let Err(e) = scheduler_task.await {
+            tracing::error!(error=?e, "scheduler task failed");
+        }
+
+        if let Err(e) = expiry_task.await {
+            tracing::error!(error=?e, "session expiry task failed");
+        }
+
+        if let Some(task) = stale_recovery_task
+            && let Err(e) = task.await
+        {
+            tracing::error!(error=?e, "stale batch recovery task failed");
+        }
+
+        // The scheduler has dropped its sender; let the router drain.
+        router_shutdown.cancel();
+        if let Err(e) = router_task.await {
+            tracing::error!(error=?e, "router task failed");
+        }
+
+        if let Some(task) = feed_task {
+            match task.await {
+                Ok(Err(e)) => tracing::error!(error=?e, "market poller failed"),
+                Err(e) => tracing::error!(error=?e, "market poller task failed"),
+                Ok(Ok(())) => {}
+            }
         }
-    });
 
-    tokio::signal::ctrl_c().await?;
-    tracing::info!("Shutdown signal received");
+        if let Some(task) = quote_task {
+            log_quote_stream_exit(task.await);
+        }
+    };
+
+    match tokio::time::timeout(Duration::from_millis(cfg.shutdown_timeout_ms), drain).await {
+        Ok(()) => tracing::info!("Shutdown complete"),
+        Err(_) => tracing::warn!(
+            timeout_ms = cfg.shutdown_timeout_ms,
+            "Shutdown timed out; RESERVED batches will be recovered on restart"
+        ),
+    }
 
     Ok(())
 }
@@ -3,19 +3,21 @@
 //! Responsible for spawning and managing market pollers
 //! (one poller per trading pair).
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{Result, anyhow};
 use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 use tracing::info;
 
 use crate::market::market_view_store::MarketViewStore;
+use crate::market::pulses::{PulseWarmup, TrendConfirmation};
 use crate::market::stonfi::client::StonfiClient;
 use crate::market::stonfi::market_service::StonfiMarketService;
-use crate::market::stonfi::poller::run_stonfi_market_poller;
+use crate::market::stonfi::poller::{PoolSource, QuoteFeed, run_stonfi_market_poller};
 
 /// MarketManager controls lifecycle of market pollers.
 ///
@@ -28,8 +30,17 @@ pub struct MarketManager {
     store: MarketViewStore,
     poll_every: Duration,
 
+    // Feed gap after which a poller resets its rolling pulses
+    max_feed_gap_ms: Option<u64>,
+
     // Tracks active pollers to prevent duplicates
     active_pairs: Arc<Mutex<HashSet<String>>>,
+
+    // Quote feeds waiting for their pair's poller
+    quote_feeds: Arc<Mutex<HashMap<String, QuoteFeed>>>,
+
+    // Cancelling stops every poller spawned by this manager
+    shutdown: CancellationToken,
 }
 
 impl MarketManager {
@@ -39,26 +50,54 @@ impl MarketManager {
             client,
             store,
             poll_every,
+            max_feed_gap_ms: None,
             active_pairs: Arc::new(Mutex::new(HashSet::new())),
+            quote_feeds: Arc::new(Mutex::new(HashMap::new())),
+            shutdown: CancellationToken::new(),
         }
     }
 
+    /// Stops all pollers when `shutdown` is cancelled.
+    pub fn with_shutdown(mut self, shutdown: CancellationToken) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Pollers reset their rolling pulses after a gap of more than
+    /// `max_feed_gap_ms` between pool snapshots (e.g. API outage).
+    pub fn with_max_feed_gap_ms(mut self, max_feed_gap_ms: u64) -> Self {
+        self.max_feed_gap_ms = Some(max_feed_gap_ms);
+        self
+    }
+
+    /// Feed the slippage pulse of `pair_id` from Omniston `quotes`.
+    /// Takes effect at the pair's next `subscribe_stonfi_pair`.
+    pub async fn add_quote_feed(&self, pair_id: &str, quotes: QuoteFeed) {
+        self.quote_feeds
+            .lock()
+            .await
+            .insert(pair_id.to_string(), quotes);
+    }
+
     /// Subscribe to market data for a STON.fi pair.
     ///
-    /// Spawns a background poller task if not already active.
+    /// Spawns a background poller task if not already active. The pair is
+    /// unsubscribed once the poller exits (shutdown or error).
     ///
     /// # Arguments
     /// - `pair_id`
     /// - `pool_address` → STON.fi pool address
     /// - `window_size`  → rolling window length
-    /// - `min_warmup_ms`→ trend warm-up duration
+    /// - `warmup`       → trend/slippage warm-up (samples and duration)
+    /// - `trend_confirmation` → thresholds a drop must clear to be reported
     pub async fn subscribe_stonfi_pair(
         &self,
         pair_id: String,
         pool_address: String,
         window_size: usize,
-        min_warmup_ms: u64,
+        warmup: PulseWarmup,
         max_slippage_bps: f64,
+        trend_confirmation: TrendConfirmation,
     ) -> Result<JoinHandle<Result<()>>> {
         {
             let mut g = self.active_pairs.lock().await;
@@ -74,14 +113,30 @@ impl MarketManager {
             "starting stonfi market poller"
         );
 
-        let client = self.client.clone();
+        let pool = PoolSource {
+            client: self.client.clone(),
+            address: pool_address,
+            every: self.poll_every,
+        };
         let store = self.store.clone();
-        let poll_every = self.poll_every;
+        let quotes = self.quote_feeds.lock().await.remove(&pair_id);
+
+        let mut market = StonfiMarketService::new(window_size, warmup.min_age_ms, max_slippage_bps)
+            .with_warmup(warmup)
+            .with_trend_confirmation(trend_confirmation);
+        if let Some(gap) = self.max_feed_gap_ms {
+            market = market.with_max_gap_ms(gap);
+        }
 
-        let market = StonfiMarketService::new(window_size, min_warmup_ms, max_slippage_bps);
+        let active_pairs = self.active_pairs.clone();
+        let shutdown = self.shutdown.clone();
 
         let handle = tokio::spawn(async move {
-            run_stonfi_market_poller(pair_id, pool_address, poll_every, client, market, store).await
+            let res =
+                run_stonfi_market_poller(pair_id.clone(), pool, market, store, quotes, shutdown)
+                    .await;
+            active_pairs.lock().await.remove(&pair_id);
+            res
         });
 
         Ok(handle)
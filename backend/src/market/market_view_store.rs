@@ -1,14 +1,41 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, watch};
 
 use crate::market::types::MarketMetricsView;
+use crate::time::now_ms;
 
 /// In-memory store of the latest market snapshot per trading pair.
 /// Used by scheduler (Gate A) and executor (Gate B) for constraint checks.
+///
+/// With a TTL (`with_ttl`), snapshots older than `ttl_ms` are evicted on read,
+/// so a dead feed makes both gates fail closed.
+///
+/// Each pair's slot is a `watch` channel: consumers can `subscribe` and be
+/// woken on updates instead of polling.
+///
+/// With `with_history`, the last N snapshots per pair are also kept (for
+/// inspecting gate decisions after the fact); memory stays bounded per pair.
 #[derive(Clone, Default)]
 pub struct MarketViewStore {
-    inner: Arc<RwLock<HashMap<String, MarketMetricsView>>>,
+    inner: Arc<RwLock<HashMap<String, PairSlot>>>,
+    ttl_ms: Option<u64>,
+    history_len: usize,
+}
+
+struct PairSlot {
+    latest: watch::Sender<Option<MarketMetricsView>>,
+    /// Oldest first; at most `history_len` entries.
+    history: VecDeque<MarketMetricsView>,
+}
+
+impl PairSlot {
+    fn new() -> Self {
+        Self {
+            latest: watch::channel(None).0,
+            history: VecDeque::new(),
+        }
+    }
 }
 
 impl MarketViewStore {
@@ -17,16 +44,223 @@ impl MarketViewStore {
         Self::default()
     }
 
-    /// Update the latest snapshot for a trading pair.
+    /// Treat snapshots older than `ttl_ms` as missing.
+    pub fn with_ttl(mut self, ttl_ms: u64) -> Self {
+        self.ttl_ms = Some(ttl_ms);
+        self
+    }
+
+    /// Keep the last `len` snapshots of each pair for `history` (0 = off).
+    pub fn with_history(mut self, len: usize) -> Self {
+        self.history_len = len;
+        self
+    }
+
+    /// Update the latest snapshot for a trading pair and notify subscribers.
     /// Last write wins; snapshots are treated as advisory only.
     pub async fn set(&self, pair_id: &str, v: MarketMetricsView) {
         let mut g = self.inner.write().await;
-        g.insert(pair_id.to_string(), v);
+        let slot = g.entry(pair_id.to_string()).or_insert_with(PairSlot::new);
+
+        if self.history_len > 0 {
+            if slot.history.len() >= self.history_len {
+                slot.history.pop_front();
+            }
+            slot.history.push_back(v.clone());
+        }
+        slot.latest.send_replace(Some(v));
+    }
+
+    /// Drop a pair's latest snapshot (e.g. its feed disconnected) so both
+    /// gates fail closed until the next `set`. Subscribers see `None`;
+    /// history is kept.
+    pub async fn invalidate(&self, pair_id: &str) {
+        let g = self.inner.read().await;
+        if let Some(slot) = g.get(pair_id) {
+            slot.latest.send_if_modified(|v| v.take().is_some());
+        }
+    }
+
+    /// Up to `limit` most recent snapshots of a pair, newest first.
+    /// Empty unless the store was built `with_history`.
+    pub async fn history(&self, pair_id: &str, limit: usize) -> Vec<MarketMetricsView> {
+        let g = self.inner.read().await;
+        g.get(pair_id)
+            .map(|slot| slot.history.iter().rev().take(limit).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Watch a pair's snapshot. The receiver sees the current value
+    /// immediately and is marked changed on every `set` (and on TTL
+    /// eviction, as `None`); updates between two reads are coalesced into
+    /// the latest one. Pairs without a snapshot yet start at `None`.
+    pub async fn subscribe(&self, pair_id: &str) -> watch::Receiver<Option<MarketMetricsView>> {
+        let mut g = self.inner.write().await;
+        g.entry(pair_id.to_string())
+            .or_insert_with(PairSlot::new)
+            .latest
+            .subscribe()
     }
 
-    /// Fetch the latest snapshot for a trading pair, if available.
+    /// Fetch the latest snapshot for a trading pair, if available and not
+    /// expired.
     pub async fn get(&self, pair_id: &str) -> Option<MarketMetricsView> {
+        self.get_at(pair_id, now_ms()).await
+    }
+
+    /// Like `get`, with an explicit clock. An expired snapshot is evicted.
+    pub async fn get_at(&self, pair_id: &str, now_ms: u64) -> Option<MarketMetricsView> {
+        let expired =
+            |v: &MarketMetricsView| self.ttl_ms.is_some_and(|ttl| !v.is_fresh(now_ms, ttl));
+
         let g = self.inner.read().await;
-        g.get(pair_id).cloned()
+        let tx = &g.get(pair_id)?.latest;
+
+        // `send_if_modified` holds the slot's lock, so a concurrent `set`
+        // cannot be evicted by mistake.
+        let mut current = None;
+        tx.send_if_modified(|slot| match slot {
+            Some(v) if expired(v) => {
+                *slot = None;
+                true
+            }
+            _ => {
+                current = slot.clone();
+                false
+            }
+        });
+        current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(ts_ms: u64) -> MarketMetricsView {
+        MarketMetricsView {
+            ts_ms,
+            spread_bps: 5.0,
+            trend_drop_bps: 0.0,
+            max_depth: 1_000,
+            slippage_bps: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn fresh_snapshot_is_returned() {
+        let store = MarketViewStore::new().with_ttl(5_000);
+        store.set("TON/USDT", view(10_000)).await;
+
+        let v = store.get_at("TON/USDT", 15_000).await.unwrap();
+        assert_eq!(v.ts_ms, 10_000);
+    }
+
+    #[tokio::test]
+    async fn expired_snapshot_is_evicted() {
+        let store = MarketViewStore::new().with_ttl(5_000);
+        store.set("TON/USDT", view(10_000)).await;
+
+        assert!(store.get_at("TON/USDT", 15_001).await.is_none());
+        // Evicted: even an earlier clock no longer sees it.
+        assert!(store.get_at("TON/USDT", 10_000).await.is_none());
+
+        // A new snapshot brings the pair back.
+        store.set("TON/USDT", view(20_000)).await;
+        assert!(store.get_at("TON/USDT", 20_000).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn invalidate_hides_the_snapshot_until_the_next_set() {
+        let store = MarketViewStore::new();
+        store.set("TON/USDT", view(10_000)).await;
+        let mut rx = store.subscribe("TON/USDT").await;
+        rx.borrow_and_update();
+
+        store.invalidate("TON/USDT").await;
+        assert!(rx.has_changed().unwrap());
+        assert!(store.get_at("TON/USDT", 10_000).await.is_none());
+
+        store.set("TON/USDT", view(11_000)).await;
+        assert!(store.get_at("TON/USDT", 11_000).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn subscriber_sees_latest_value_and_coalesces_updates() {
+        let store = MarketViewStore::new();
+        let mut rx = store.subscribe("TON/USDT").await;
+        assert!(rx.borrow_and_update().is_none());
+
+        // Three rapid updates wake the subscriber once, with the last value.
+        for ts in [1_000, 2_000, 3_000] {
+            store.set("TON/USDT", view(ts)).await;
+        }
+        rx.changed().await.unwrap();
+        assert_eq!(rx.borrow_and_update().as_ref().unwrap().ts_ms, 3_000);
+        assert!(!rx.has_changed().unwrap());
+
+        // Other pairs do not wake it.
+        store.set("BTC/USDT", view(4_000)).await;
+        assert!(!rx.has_changed().unwrap());
+
+        // A late subscriber starts from the current snapshot.
+        let late = store.subscribe("TON/USDT").await;
+        assert_eq!(late.borrow().as_ref().unwrap().ts_ms, 3_000);
+    }
+
+    #[tokio::test]
+    async fn ttl_eviction_notifies_subscribers() {
+        let store = MarketViewStore::new().with_ttl(5_000);
+        store.set("TON/USDT", view(10_000)).await;
+        let mut rx = store.subscribe("TON/USDT").await;
+        rx.borrow_and_update();
+
+        assert!(store.get_at("TON/USDT", 15_001).await.is_none());
+        assert!(rx.has_changed().unwrap());
+        assert!(rx.borrow_and_update().is_none());
+    }
+
+    #[tokio::test]
+    async fn history_keeps_only_the_most_recent_snapshots() {
+        let store = MarketViewStore::new().with_history(10);
+        for ts in 0..100u64 {
+            store.set("TON/USDT", view(ts)).await;
+        }
+
+        let ts: Vec<u64> = store
+            .history("TON/USDT", usize::MAX)
+            .await
+            .iter()
+            .map(|v| v.ts_ms)
+            .collect();
+        assert_eq!(ts, (90..100).rev().collect::<Vec<_>>());
+
+        let newest: Vec<u64> = store
+            .history("TON/USDT", 3)
+            .await
+            .iter()
+            .map(|v| v.ts_ms)
+            .collect();
+        assert_eq!(newest, vec![99, 98, 97]);
+
+        // The latest pointer used by the gates is unaffected.
+        assert_eq!(store.get_at("TON/USDT", 99).await.unwrap().ts_ms, 99);
+        assert!(store.history("BTC/USDT", 10).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn history_is_off_by_default() {
+        let store = MarketViewStore::new();
+        store.set("TON/USDT", view(1)).await;
+        assert!(store.history("TON/USDT", 10).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn without_ttl_last_snapshot_is_kept() {
+        let store = MarketViewStore::new();
+        store.set("TON/USDT", view(0)).await;
+
+        assert!(store.get_at("TON/USDT", u64::MAX).await.is_some());
+        assert!(store.get("TON/USDT").await.is_some());
     }
 }
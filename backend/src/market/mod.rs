@@ -1,5 +1,6 @@
 pub mod manager;
 pub mod market_view_store;
+pub mod omniston;
 pub mod pulses;
 pub mod stonfi;
 pub mod types;
@@ -0,0 +1,1155 @@
+//! Omniston RFQ WebSocket client.
+//!
+//! Subscribes to a quote stream and forwards parsed `OmnistonEvent`s to a
+//! channel, reconnecting on socket errors, server closes and heartbeat
+//! timeouts. Omniston sends `keep_alive` events on idle streams, so a socket
+//! that stays silent for `heartbeat_timeout` is treated as dead.
+//!
+//! `subscribe_many` multiplexes several pairs over one connection.
+//!
+//! Requests are validated (`RfqRequest::validate`) before connecting: the
+//! server never answers a malformed subscription, so it would otherwise look
+//! like a healthy but quiet feed.
+//!
+//! The client pings the server every `ping_interval`; the pong (like any
+//! other frame) resets the heartbeat timer, so a half-open connection that
+//! stops answering is dropped after `heartbeat_timeout` even when the
+//! server's own `keep_alive` events cannot be relied on.
+//!
+//! Reconnects back off exponentially with jitter (`ReconnectBackoff`), so
+//! clients do not reconnect in lockstep after a server outage.
+//!
+//! Events the parser does not recognize are still forwarded as
+//! `OmnistonEvent::Unknown`, but are also logged at `warn` with their
+//! top-level key and passed to the `with_on_unknown` hook, so new Omniston
+//! event types do not vanish silently.
+//!
+//! With `with_metrics`, every parsed event and reconnect attempt is counted
+//! in `OmnistonMetrics`.
+//!
+//! With `with_recorder`, every parsed quote is also written to a
+//! `QuoteRecorder` for later replay.
+//!
+//! Connection changes are reported in-band as `OmnistonEvent::ConnectionState`
+//! (once per pair), so consumers can `MarketViewStore::invalidate` a pair
+//! while its feed is down.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use futures::{SinkExt, Stream, StreamExt};
+use rand::Rng;
+use serde_json::{Value, json};
+use tokio::sync::mpsc;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::error::RfqError;
+use crate::market::omniston::metrics::OmnistonMetrics;
+use crate::market::omniston::parser::parse_omniston_event;
+use crate::market::omniston::recorder::{QuoteRecorder, record_event};
+use crate::market::types::{OmnistonEvent, RfqAmount, RfqRequest};
+use crate::time::now_ms;
+
+/// JSON-RPC method of the quote subscription.
+pub const QUOTE_SUBSCRIBE_METHOD: &str = "v1beta7.quote";
+
+/// JSON-RPC method cancelling a quote subscription.
+pub const QUOTE_UNSUBSCRIBE_METHOD: &str = "v1beta7.quote.unsubscribe";
+
+/// Upper bound on the unsubscribe/close handshake at shutdown.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(1);
+
+/// TON blockchain id in Omniston asset addresses.
+const TON_BLOCKCHAIN: u32 = 607;
+
+/// Why a read loop over one connection ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamEnd {
+    /// No message within the heartbeat timeout.
+    HeartbeatTimeout,
+    /// Server closed the socket.
+    Closed,
+    /// Socket error.
+    Error,
+    /// Shutdown was requested.
+    Shutdown,
+    /// The event receiver was dropped.
+    ReceiverDropped,
+}
+
+impl StreamEnd {
+    /// True if the client should open a new connection.
+    pub fn should_reconnect(self) -> bool {
+        matches!(self, Self::HeartbeatTimeout | Self::Closed | Self::Error)
+    }
+}
+
+/// Exponential reconnect backoff with jitter.
+///
+/// The n-th consecutive failed connection waits
+/// `min(base * multiplier^n, max)`, shortened by up to `jitter` of itself.
+/// A connection that stayed up for `stable_after` resets the sequence.
+#[derive(Clone, Debug)]
+pub struct ReconnectBackoff {
+    pub base: Duration,
+    pub max: Duration,
+    /// Growth per consecutive failure (values below 1 are treated as 1).
+    pub multiplier: f64,
+    /// Fraction of the delay that is randomized, in `[0, 1]`.
+    pub jitter: f64,
+    /// Uptime after which a connection counts as healthy.
+    pub stable_after: Duration,
+}
+
+impl Default for ReconnectBackoff {
+    fn default() -> Self {
+        Self {
+            base: Duration::from_millis(500),
+            max: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.5,
+            stable_after: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ReconnectBackoff {
+    /// Delay after `failures` prior consecutive failures, with
+    /// `jitter_sample` drawn from `[0, 1)`.
+    pub fn delay(&self, failures: u32, jitter_sample: f64) -> Duration {
+        let growth = self.multiplier.max(1.0).powi(failures.min(64) as i32);
+        let raw = (self.base.as_secs_f64() * growth).min(self.max.as_secs_f64());
+        let cut = self.jitter.clamp(0.0, 1.0) * jitter_sample.clamp(0.0, 1.0);
+        Duration::from_secs_f64(raw * (1.0 - cut))
+    }
+
+    /// Consecutive failure count to continue from after a connection that
+    /// was up for `uptime` (`None` = the connection never came up).
+    pub fn failures_after(&self, failures: u32, uptime: Option<Duration>) -> u32 {
+        match uptime {
+            Some(up) if up >= self.stable_after => 0,
+            _ => failures,
+        }
+    }
+}
+
+/// Called with the raw payload of every unrecognized event.
+pub type UnknownEventHook = Arc<dyn Fn(&Value) + Send + Sync>;
+
+pub struct OmnistonWsClient {
+    url: String,
+    heartbeat_timeout: Duration,
+    ping_interval: Duration,
+    backoff: ReconnectBackoff,
+    /// Consecutive connections that failed or dropped before `stable_after`.
+    failures: AtomicU32,
+    recorder: Option<Arc<dyn QuoteRecorder>>,
+    metrics: OmnistonMetrics,
+    on_unknown: Option<UnknownEventHook>,
+}
+
+impl OmnistonWsClient {
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            heartbeat_timeout: Duration::from_secs(30),
+            ping_interval: Duration::from_secs(10),
+            backoff: ReconnectBackoff::default(),
+            failures: AtomicU32::new(0),
+            recorder: None,
+            metrics: OmnistonMetrics::default(),
+            on_unknown: None,
+        }
+    }
+
+    /// Reconnect when no message (quote or keep-alive) arrives for this long.
+    pub fn with_heartbeat_timeout(mut self, heartbeat_timeout: Duration) -> Self {
+        self.heartbeat_timeout = heartbeat_timeout;
+        self
+    }
+
+    /// Send a WebSocket ping this often while connected. Keep it well below
+    /// the heartbeat timeout so the pong arrives in time.
+    pub fn with_ping_interval(mut self, ping_interval: Duration) -> Self {
+        self.ping_interval = ping_interval;
+        self
+    }
+
+    /// Replace the reconnect backoff (see `ReconnectBackoff::default`).
+    pub fn with_backoff(mut self, backoff: ReconnectBackoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Record every received quote (see `replay_from_file`). A failing
+    /// recorder is logged and never interrupts the stream.
+    pub fn with_recorder(mut self, recorder: Arc<dyn QuoteRecorder>) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Count events and reconnects into `metrics` (e.g. `Counters::omniston`).
+    pub fn with_metrics(mut self, metrics: OmnistonMetrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Call `hook` with the raw payload of every event the parser does not
+    /// recognize. The event is still forwarded as `OmnistonEvent::Unknown`.
+    pub fn with_on_unknown(mut self, hook: UnknownEventHook) -> Self {
+        self.on_unknown = Some(hook);
+        self
+    }
+
+    /// Streams quotes for `rfq` into `tx` until `shutdown` is cancelled or
+    /// the receiver is dropped, resubscribing on every new connection.
+    ///
+    /// On shutdown the subscription is cancelled and the socket closed before
+    /// returning. An invalid `rfq` is returned as an error without connecting.
+    pub async fn request_for_quote_stream(
+        &self,
+        rfq: &RfqRequest,
+        tx: mpsc::Sender<OmnistonEvent>,
+        shutdown: CancellationToken,
+    ) -> Result<(), RfqError> {
+        let requests = [(String::new(), rfq.clone())];
+        validate_all(&requests)?;
+        self.run(&requests, &tx, &shutdown, |_, event| event).await;
+        Ok(())
+    }
+
+    /// Streams quotes for several pairs over one connection, tagging every
+    /// event with the pair it belongs to (see `SubscriptionMux`). Nothing is
+    /// subscribed if any request is invalid.
+    pub async fn subscribe_many(
+        &self,
+        requests: Vec<(String, RfqRequest)>,
+        tx: mpsc::Sender<(String, OmnistonEvent)>,
+        shutdown: CancellationToken,
+    ) -> Result<(), RfqError> {
+        validate_all(&requests)?;
+        self.run(&requests, &tx, &shutdown, |pair, event| (pair, event))
+            .await;
+        Ok(())
+    }
+
+    /// Connect/subscribe/read loop shared by the single- and multi-pair
+    /// streams; `tag` shapes what is sent to `tx`.
+    async fn run<T>(
+        &self,
+        requests: &[(String, RfqRequest)],
+        tx: &mpsc::Sender<T>,
+        shutdown: &CancellationToken,
+        mut tag: impl FnMut(String, OmnistonEvent) -> T,
+    ) {
+        let pairs: Vec<String> = requests.iter().map(|(pair, _)| pair.clone()).collect();
+        let subscribes: Vec<String> = requests
+            .iter()
+            .enumerate()
+            .map(|(i, (_, rfq))| subscribe_message(rfq, i as u64 + 1).to_string())
+            .collect();
+
+        // Reconnect attempts since the last disconnect (0 = initial connect).
+        let mut attempt = 0u32;
+        let mut last_connected_ms = None;
+        let state = |connected, attempt, last_connected_ms| OmnistonEvent::ConnectionState {
+            connected,
+            attempt,
+            last_connected_ms,
+        };
+
+        while !shutdown.is_cancelled() {
+            let mut uptime = None;
+
+            if attempt > 0 {
+                self.metrics.reconnects.fetch_add(1, Ordering::Relaxed);
+                if !broadcast(
+                    tx,
+                    &pairs,
+                    &mut tag,
+                    state(false, attempt, last_connected_ms),
+                )
+                .await
+                {
+                    return;
+                }
+            }
+
+            match connect_async(self.url.as_str()).await {
+                Ok((ws, _)) => {
+                    let connected_at = tokio::time::Instant::now();
+                    let (mut write, read) = ws.split();
+
+                    let mut sent = Ok(());
+                    for subscribe in &subscribes {
+                        sent = write.send(Message::text(subscribe.clone())).await;
+                        if sent.is_err() {
+                            break;
+                        }
+                    }
+
+                    if let Err(e) = sent {
+                        warn!(error = %e, "omniston subscribe failed");
+                    } else {
+                        info!(
+                            url = %self.url,
+                            subscriptions = subscribes.len(),
+                            attempt,
+                            "omniston quote stream subscribed"
+                        );
+
+                        last_connected_ms = Some(now_ms());
+                        if !broadcast(
+                            tx,
+                            &pairs,
+                            &mut tag,
+                            state(true, attempt, last_connected_ms),
+                        )
+                        .await
+                        {
+                            return;
+                        }
+
+                        // Subscription ids are per connection, so each one
+                        // gets a fresh mux.
+                        let mut mux = SubscriptionMux::new(&pairs);
+                        let pump = pump_frames(read, self.heartbeat_timeout, tx, shutdown, |v| {
+                            let (pair, event) = mux.route(&v)?;
+                            self.metrics.record(&event);
+                            if let OmnistonEvent::Unknown(raw) = &event {
+                                self.report_unknown(&pair, raw);
+                            }
+                            if let Some(recorder) = &self.recorder
+                                && let Err(e) = record_event(&**recorder, now_ms(), &event)
+                            {
+                                warn!(error = %e, "quote recording failed");
+                            }
+                            Some(tag(pair, event))
+                        });
+                        let end = tokio::select! {
+                            end = pump => end,
+                            e = send_pings(&mut write, self.ping_interval) => {
+                                warn!(error = %e, "omniston ping failed");
+                                StreamEnd::Error
+                            }
+                        };
+
+                        if end == StreamEnd::Shutdown {
+                            close_gracefully(&mut write, &mux).await;
+                        }
+                        if !end.should_reconnect() {
+                            return;
+                        }
+                        uptime = Some(connected_at.elapsed());
+                        warn!(reason = ?end, "omniston stream ended — reconnecting");
+
+                        attempt = 0;
+                        if !broadcast(tx, &pairs, &mut tag, state(false, 0, last_connected_ms))
+                            .await
+                        {
+                            return;
+                        }
+                    }
+                }
+                Err(e) => warn!(error = %e, "omniston connect failed"),
+            }
+
+            let failures = self
+                .backoff
+                .failures_after(self.failures.load(Ordering::Relaxed), uptime);
+            let delay = self.backoff.delay(failures, rand::thread_rng().r#gen());
+            self.failures
+                .store(failures.saturating_add(1), Ordering::Relaxed);
+
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = shutdown.cancelled() => {}
+            }
+            attempt = attempt.saturating_add(1);
+        }
+    }
+
+    /// Logs an unrecognized event and hands it to the `on_unknown` hook.
+    fn report_unknown(&self, pair: &str, raw: &Value) {
+        warn!(
+            pair,
+            event_key = event_key(raw),
+            "unrecognized omniston event"
+        );
+        if let Some(hook) = &self.on_unknown {
+            hook(raw);
+        }
+    }
+}
+
+/// Top-level key of an event payload (`quote_updated`, `keep_alive`, ...),
+/// or a placeholder when the payload is not a single-key object.
+fn event_key(event: &Value) -> &str {
+    match event.as_object() {
+        Some(obj) if obj.len() == 1 => obj.keys().next().map_or("", String::as_str),
+        Some(obj) if obj.is_empty() => "<empty>",
+        Some(_) => "<multiple>",
+        None => "<non-object>",
+    }
+}
+
+/// First invalid request, logged with its pair.
+fn validate_all(requests: &[(String, RfqRequest)]) -> Result<(), RfqError> {
+    for (pair, rfq) in requests {
+        if let Err(e) = rfq.validate() {
+            warn!(pair = %pair, error = %e, "invalid rfq; not subscribing");
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Pings every `interval` until a send fails, returning that error.
+async fn send_pings<W>(write: &mut W, interval: Duration) -> WsError
+where
+    W: futures::Sink<Message, Error = WsError> + Unpin,
+{
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        if let Err(e) = write.send(Message::Ping(Default::default())).await {
+            return e;
+        }
+    }
+}
+
+/// Sends `event` once per pair; false if the receiver was dropped.
+async fn broadcast<T>(
+    tx: &mpsc::Sender<T>,
+    pairs: &[String],
+    tag: &mut impl FnMut(String, OmnistonEvent) -> T,
+    event: OmnistonEvent,
+) -> bool {
+    for pair in pairs {
+        if tx.send(tag(pair.clone(), event.clone())).await.is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+/// Unsubscribes every acknowledged subscription and closes the socket,
+/// giving up after `SHUTDOWN_GRACE` so shutdown stays prompt.
+async fn close_gracefully<W>(write: &mut W, mux: &SubscriptionMux)
+where
+    W: futures::Sink<Message, Error = WsError> + Unpin,
+{
+    let frames: Vec<Message> = mux
+        .subscription_ids()
+        .map(|sub| {
+            Message::text(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "method": QUOTE_UNSUBSCRIBE_METHOD,
+                    "params": [sub],
+                })
+                .to_string(),
+            )
+        })
+        .chain(std::iter::once(Message::Close(None)))
+        .collect();
+
+    let close = async {
+        for frame in frames {
+            write.send(frame).await?;
+        }
+        Ok::<_, WsError>(())
+    };
+
+    match tokio::time::timeout(SHUTDOWN_GRACE, close).await {
+        Ok(Ok(())) => info!("omniston quote stream unsubscribed"),
+        Ok(Err(e)) => warn!(error = %e, "omniston unsubscribe failed"),
+        Err(_) => warn!("omniston unsubscribe timed out"),
+    }
+}
+
+/// Routes the events of several subscriptions sharing one connection to
+/// their pair.
+///
+/// Subscription `i` (0-based) is sent with JSON-RPC id `i + 1`. The server
+/// answers each with `{"id": .., "result": <subscription id>}`, and every
+/// later event carries that id in `params.subscription`.
+#[derive(Debug, Default)]
+pub struct SubscriptionMux {
+    /// Request id → pair, until the subscription is acknowledged.
+    pending: HashMap<u64, String>,
+    /// Subscription id → pair.
+    subscriptions: HashMap<u64, String>,
+}
+
+impl SubscriptionMux {
+    pub fn new(pairs: &[String]) -> Self {
+        Self {
+            pending: pairs
+                .iter()
+                .enumerate()
+                .map(|(i, pair)| (i as u64 + 1, pair.clone()))
+                .collect(),
+            subscriptions: HashMap::new(),
+        }
+    }
+
+    /// Records subscription acks and tags events of known subscriptions.
+    /// Anything else yields `None`.
+    pub fn route(&mut self, frame: &Value) -> Option<(String, OmnistonEvent)> {
+        if let (Some(id), Some(sub)) = (
+            frame.get("id").and_then(Value::as_u64),
+            frame.get("result").and_then(Value::as_u64),
+        ) {
+            if let Some(pair) = self.pending.remove(&id) {
+                self.subscriptions.insert(sub, pair);
+            }
+            return None;
+        }
+
+        let params = frame.get("params")?;
+        let sub = params.get("subscription")?.as_u64()?;
+        let pair = self.subscriptions.get(&sub)?;
+        let event = params.pointer("/result/event")?;
+        Some((pair.clone(), parse_omniston_event(event)))
+    }
+
+    /// Ids of the acknowledged subscriptions.
+    pub fn subscription_ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.subscriptions.keys().copied()
+    }
+}
+
+/// Forwards events from one connection to `tx`.
+///
+/// Every received frame (including pings and keep-alives) resets the
+/// heartbeat timer; frames that are not subscription events are skipped.
+pub async fn pump_events<S>(
+    read: S,
+    heartbeat_timeout: Duration,
+    tx: &mpsc::Sender<OmnistonEvent>,
+    shutdown: &CancellationToken,
+) -> StreamEnd
+where
+    S: Stream<Item = Result<Message, WsError>> + Unpin,
+{
+    pump_frames(read, heartbeat_timeout, tx, shutdown, |frame: Value| {
+        frame
+            .pointer("/params/result/event")
+            .map(parse_omniston_event)
+    })
+    .await
+}
+
+/// Like `pump_events`, forwarding whatever `route` makes of each JSON frame.
+pub async fn pump_frames<S, T>(
+    mut read: S,
+    heartbeat_timeout: Duration,
+    tx: &mpsc::Sender<T>,
+    shutdown: &CancellationToken,
+    mut route: impl FnMut(Value) -> Option<T>,
+) -> StreamEnd
+where
+    S: Stream<Item = Result<Message, WsError>> + Unpin,
+{
+    loop {
+        let next = tokio::select! {
+            _ = shutdown.cancelled() => return StreamEnd::Shutdown,
+            next = tokio::time::timeout(heartbeat_timeout, read.next()) => next,
+        };
+
+        let msg = match next {
+            Err(_) => return StreamEnd::HeartbeatTimeout,
+            Ok(None) => return StreamEnd::Closed,
+            Ok(Some(Err(e))) => {
+                warn!(error = %e, "omniston socket error");
+                return StreamEnd::Error;
+            }
+            Ok(Some(Ok(msg))) => msg,
+        };
+
+        let text = match msg {
+            Message::Text(text) => text,
+            Message::Close(_) => return StreamEnd::Closed,
+            _ => continue,
+        };
+
+        let Some(item) = serde_json::from_str::<Value>(text.as_str())
+            .ok()
+            .and_then(&mut route)
+        else {
+            continue;
+        };
+
+        if tx.send(item).await.is_err() {
+            return StreamEnd::ReceiverDropped;
+        }
+    }
+}
+
+fn subscribe_message(rfq: &RfqRequest, id: u64) -> Value {
+    let asset = |address: &str| json!({ "blockchain": TON_BLOCKCHAIN, "address": address });
+    let amount = match &rfq.amount {
+        RfqAmount::BidUnits(units) => json!({ "bid_units": units }),
+        RfqAmount::AskUnits(units) => json!({ "ask_units": units }),
+    };
+
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": QUOTE_SUBSCRIBE_METHOD,
+        "params": {
+            "bid_asset_address": asset(&rfq.bid_asset),
+            "ask_asset_address": asset(&rfq.ask_asset),
+            "amount": amount,
+            "referrer_fee_bps": 0,
+            "settlement_methods": [0],
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::net::TcpListener;
+
+    fn event(ev: Value) -> Result<Message, WsError> {
+        let msg =
+            json!({ "jsonrpc": "2.0", "method": "event", "params": { "result": { "event": ev } } });
+        Ok(Message::text(msg.to_string()))
+    }
+
+    #[test]
+    fn backoff_grows_caps_and_resets() {
+        let b = ReconnectBackoff {
+            base: Duration::from_millis(100),
+            max: Duration::from_secs(1),
+            multiplier: 2.0,
+            jitter: 0.5,
+            stable_after: Duration::from_secs(10),
+        };
+
+        let delays: Vec<u128> = (0..6).map(|n| b.delay(n, 0.0).as_millis()).collect();
+        assert_eq!(delays, vec![100, 200, 400, 800, 1_000, 1_000]);
+        assert_eq!(b.delay(u32::MAX, 0.0), Duration::from_secs(1));
+
+        // Jitter only shortens the delay, by at most `jitter` of it.
+        assert_eq!(b.delay(2, 1.0), Duration::from_millis(200));
+        assert_eq!(b.delay(2, 0.5), Duration::from_millis(300));
+
+        // Only a connection that stayed up long enough resets the growth.
+        assert_eq!(b.failures_after(4, None), 4);
+        assert_eq!(b.failures_after(4, Some(Duration::from_secs(9))), 4);
+        assert_eq!(b.failures_after(4, Some(Duration::from_secs(10))), 0);
+    }
+
+    #[test]
+    fn backoff_multiplier_below_one_is_constant() {
+        let b = ReconnectBackoff {
+            multiplier: 0.5,
+            jitter: 0.0,
+            ..Default::default()
+        };
+        assert_eq!(b.delay(0, 0.9), b.base);
+        assert_eq!(b.delay(5, 0.9), b.base);
+    }
+
+    const TON: &str = "EQAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAM9c";
+    const USDT: &str = "EQCxE6mUtQJKFnGfaROTKOt1lZbDiiX1kCixRv7Nw2Id_sDs";
+
+    fn rfq() -> RfqRequest {
+        RfqRequest {
+            bid_asset: TON.into(),
+            ask_asset: USDT.into(),
+            amount: RfqAmount::BidUnits("1000".into()),
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_requests_fail_before_connecting() {
+        // Nothing listens here: an attempt to connect would only retry.
+        let client = OmnistonWsClient::new("ws://127.0.0.1:9".into());
+        let (tx, _rx) = mpsc::channel(8);
+
+        let empty = RfqRequest {
+            bid_asset: " ".into(),
+            ..rfq()
+        };
+        let err = client
+            .request_for_quote_stream(&empty, tx.clone(), CancellationToken::new())
+            .await
+            .unwrap_err();
+        assert_eq!(err, RfqError::EmptyAddress { field: "bid_asset" });
+
+        let (tx_many, _rx_many) = mpsc::channel(8);
+        let zero = RfqRequest {
+            amount: RfqAmount::AskUnits("0".into()),
+            ..rfq()
+        };
+        let err = client
+            .subscribe_many(
+                vec![("TON/USDT".into(), rfq()), ("STON/TON".into(), zero)],
+                tx_many,
+                CancellationToken::new(),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err, RfqError::InvalidAmount("0".into()));
+    }
+
+    fn frame(v: Value) -> Result<Message, WsError> {
+        Ok(Message::text(v.to_string()))
+    }
+
+    fn sub_event(sub: u64, ev: Value) -> Result<Message, WsError> {
+        frame(json!({
+            "jsonrpc": "2.0",
+            "method": "event",
+            "params": { "subscription": sub, "result": { "event": ev } }
+        }))
+    }
+
+    #[tokio::test]
+    async fn multiplexed_events_are_routed_to_their_pair() {
+        let pairs = vec!["TON/USDT".to_string(), "STON/TON".to_string()];
+        let read = stream::iter(vec![
+            // Events before the ack cannot be attributed yet.
+            sub_event(7, json!({ "keep_alive": {} })),
+            frame(json!({ "jsonrpc": "2.0", "id": 2, "result": 9 })),
+            frame(json!({ "jsonrpc": "2.0", "id": 1, "result": 7 })),
+            sub_event(9, json!({ "no_quote": {} })),
+            sub_event(7, json!({ "keep_alive": {} })),
+            sub_event(42, json!({ "keep_alive": {} })),
+            // Unknown request ids do not create routes.
+            frame(json!({ "jsonrpc": "2.0", "id": 5, "result": 42 })),
+            sub_event(42, json!({ "no_quote": {} })),
+        ]);
+
+        let (tx, mut rx) = mpsc::channel(8);
+        let mut mux = SubscriptionMux::new(&pairs);
+        let end = pump_frames(
+            read,
+            Duration::from_secs(5),
+            &tx,
+            &CancellationToken::new(),
+            |v: Value| mux.route(&v),
+        )
+        .await;
+        assert_eq!(end, StreamEnd::Closed);
+
+        let (pair, ev) = rx.try_recv().unwrap();
+        assert_eq!(pair, "STON/TON");
+        assert!(matches!(ev, OmnistonEvent::NoQuote));
+
+        let (pair, ev) = rx.try_recv().unwrap();
+        assert_eq!(pair, "TON/USDT");
+        assert!(matches!(ev, OmnistonEvent::KeepAlive));
+
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn subscriptions_get_distinct_request_ids() {
+        let rfq = RfqRequest {
+            amount: RfqAmount::AskUnits("5".into()),
+            ..rfq()
+        };
+        let a = subscribe_message(&rfq, 1);
+        let b = subscribe_message(&rfq, 2);
+        assert_eq!(a["id"], 1);
+        assert_eq!(b["id"], 2);
+        assert_eq!(a["params"]["amount"]["ask_units"], "5");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stalled_stream_times_out() {
+        let (tx, mut rx) = mpsc::channel(8);
+        let read = stream::iter(vec![event(json!({ "keep_alive": {} }))]).chain(stream::pending());
+
+        let started = tokio::time::Instant::now();
+        let end = pump_events(read, Duration::from_secs(5), &tx, &CancellationToken::new()).await;
+
+        assert_eq!(end, StreamEnd::HeartbeatTimeout);
+        assert!(end.should_reconnect());
+        assert_eq!(started.elapsed(), Duration::from_secs(5));
+        assert!(matches!(rx.try_recv(), Ok(OmnistonEvent::KeepAlive)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn every_message_resets_the_heartbeat() {
+        let (tx, mut rx) = mpsc::channel(8);
+        // Four keep-alives 4s apart outlive a 5s timeout in total, but never
+        // leave a 5s silence; the non-event frame counts too.
+        let read = stream::unfold(0, |i| async move {
+            if i == 4 {
+                return None;
+            }
+            tokio::time::sleep(Duration::from_secs(4)).await;
+            let msg = match i {
+                2 => Ok(Message::Ping(Vec::new().into())),
+                _ => event(json!({ "keep_alive": {} })),
+            };
+            Some((msg, i + 1))
+        })
+        .boxed();
+
+        let end = pump_events(read, Duration::from_secs(5), &tx, &CancellationToken::new()).await;
+
+        assert_eq!(end, StreamEnd::Closed);
+        let mut received = 0;
+        while rx.try_recv().is_ok() {
+            received += 1;
+        }
+        assert_eq!(received, 3);
+    }
+
+    #[tokio::test]
+    async fn shutdown_and_dropped_receiver_stop_without_reconnect() {
+        let (tx, rx) = mpsc::channel(8);
+        let shutdown = CancellationToken::new();
+        shutdown.cancel();
+        let end = pump_events(stream::pending(), Duration::from_secs(5), &tx, &shutdown).await;
+        assert_eq!(end, StreamEnd::Shutdown);
+
+        drop(rx);
+        let read = stream::iter(vec![event(json!({ "no_quote": {} }))]);
+        let end = pump_events(read, Duration::from_secs(5), &tx, &CancellationToken::new()).await;
+        assert_eq!(end, StreamEnd::ReceiverDropped);
+        assert!(!end.should_reconnect());
+    }
+
+    #[tokio::test]
+    async fn silent_server_triggers_reconnect_and_resubscribe() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let subscribes = Arc::new(AtomicUsize::new(0));
+
+        // Accepts connections, reads the subscription and then goes silent.
+        let seen = subscribes.clone();
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((tcp, _)) = listener.accept().await {
+                let mut ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+                if let Some(Ok(Message::Text(t))) = ws.next().await {
+                    let v: Value = serde_json::from_str(t.as_str()).unwrap();
+                    assert_eq!(v["method"], QUOTE_SUBSCRIBE_METHOD);
+                    seen.fetch_add(1, Ordering::SeqCst);
+                }
+                held.push(ws);
+            }
+        });
+
+        let metrics = OmnistonMetrics::default();
+        let client = OmnistonWsClient::new(url)
+            .with_metrics(metrics.clone())
+            .with_heartbeat_timeout(Duration::from_millis(50))
+            .with_backoff(ReconnectBackoff {
+                base: Duration::from_millis(10),
+                max: Duration::from_millis(10),
+                ..Default::default()
+            });
+        let rfq = rfq();
+        let (tx, _rx) = mpsc::channel(8);
+        let shutdown = CancellationToken::new();
+
+        let task = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move {
+                client
+                    .request_for_quote_stream(&rfq, tx, shutdown)
+                    .await
+                    .unwrap()
+            }
+        });
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while subscribes.load(Ordering::SeqCst) < 2 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("client should reconnect after the heartbeat timeout");
+
+        shutdown.cancel();
+        task.await.unwrap();
+        assert!(metrics.reconnects.load(Ordering::Relaxed) >= 1);
+    }
+
+    #[tokio::test]
+    async fn pings_keep_a_quiet_connection_alive() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let subscribes = Arc::new(AtomicUsize::new(0));
+        let pings = Arc::new(AtomicUsize::new(0));
+
+        // Never sends events, but keeps reading so pings get their pong.
+        let (seen, pinged) = (subscribes.clone(), pings.clone());
+        tokio::spawn(async move {
+            while let Ok((tcp, _)) = listener.accept().await {
+                let mut ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+                let (seen, pinged) = (seen.clone(), pinged.clone());
+                tokio::spawn(async move {
+                    while let Some(Ok(msg)) = ws.next().await {
+                        match msg {
+                            Message::Text(_) => seen.fetch_add(1, Ordering::SeqCst),
+                            Message::Ping(_) => pinged.fetch_add(1, Ordering::SeqCst),
+                            _ => 0,
+                        };
+                    }
+                });
+            }
+        });
+
+        let client = OmnistonWsClient::new(url)
+            .with_heartbeat_timeout(Duration::from_millis(150))
+            .with_ping_interval(Duration::from_millis(30));
+        let rfq = rfq();
+        let (tx, _rx) = mpsc::channel(8);
+        let shutdown = CancellationToken::new();
+
+        let task = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move {
+                client
+                    .request_for_quote_stream(&rfq, tx, shutdown)
+                    .await
+                    .unwrap()
+            }
+        });
+
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        shutdown.cancel();
+        task.await.unwrap();
+
+        assert!(pings.load(Ordering::SeqCst) >= 3);
+        // Pongs reset the heartbeat: no reconnect, so no second subscribe.
+        assert_eq!(subscribes.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn connection_changes_are_reported_as_state_events() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        // Drops the first connection right after the subscription and keeps
+        // the second one open.
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            for n in 0..2 {
+                let (tcp, _) = listener.accept().await.unwrap();
+                let mut ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+                ws.next().await.unwrap().unwrap();
+                if n == 0 {
+                    ws.close(None).await.unwrap();
+                }
+                held.push(ws);
+            }
+            std::future::pending::<()>().await;
+        });
+
+        let client = OmnistonWsClient::new(url).with_backoff(ReconnectBackoff {
+            base: Duration::from_millis(10),
+            max: Duration::from_millis(10),
+            ..Default::default()
+        });
+        let rfq = rfq();
+        let (tx, mut rx) = mpsc::channel(8);
+        let shutdown = CancellationToken::new();
+
+        let task = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move {
+                client
+                    .request_for_quote_stream(&rfq, tx, shutdown)
+                    .await
+                    .unwrap()
+            }
+        });
+
+        let mut states = Vec::new();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while states.len() < 4 {
+                if let Some(OmnistonEvent::ConnectionState {
+                    connected,
+                    attempt,
+                    last_connected_ms,
+                }) = rx.recv().await
+                {
+                    states.push((connected, attempt, last_connected_ms));
+                }
+            }
+        })
+        .await
+        .expect("client should report connect, disconnect and reconnect");
+
+        shutdown.cancel();
+        task.await.unwrap();
+
+        let transitions: Vec<(bool, u32)> = states.iter().map(|&(c, a, _)| (c, a)).collect();
+        assert_eq!(
+            transitions,
+            vec![(true, 0), (false, 0), (false, 1), (true, 1)],
+            "connect, disconnect, reconnect attempt, reconnect"
+        );
+
+        let first_up = states[0].2.expect("connected at");
+        assert_eq!(states[1].2, Some(first_up));
+        assert_eq!(states[2].2, Some(first_up));
+        assert!(states[3].2.unwrap() >= first_up);
+    }
+
+    #[test]
+    fn event_key_names_the_top_level_field() {
+        assert_eq!(event_key(&json!({ "brand_new": { "x": 1 } })), "brand_new");
+        assert_eq!(event_key(&json!({})), "<empty>");
+        assert_eq!(event_key(&json!({ "a": 1, "b": 2 })), "<multiple>");
+        assert_eq!(event_key(&json!([1])), "<non-object>");
+    }
+
+    #[tokio::test]
+    async fn unknown_events_reach_the_hook_and_the_stream() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        // Acks the subscription, then sends an event type the parser does
+        // not know and a known one.
+        tokio::spawn(async move {
+            let (tcp, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+            ws.next().await.unwrap().unwrap();
+            for msg in [
+                frame(json!({ "jsonrpc": "2.0", "id": 1, "result": 7 })),
+                sub_event(7, json!({ "resolver_paused": { "resolver_id": "r1" } })),
+                sub_event(7, json!({ "no_quote": {} })),
+            ] {
+                ws.send(msg.unwrap()).await.unwrap();
+            }
+            std::future::pending::<()>().await;
+        });
+
+        let seen = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let client = OmnistonWsClient::new(url).with_on_unknown({
+            let seen = seen.clone();
+            Arc::new(move |raw: &Value| seen.lock().push(raw.clone()))
+        });
+        let rfq = rfq();
+        let (tx, mut rx) = mpsc::channel(8);
+        let shutdown = CancellationToken::new();
+
+        let task = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move {
+                client
+                    .request_for_quote_stream(&rfq, tx, shutdown)
+                    .await
+                    .unwrap()
+            }
+        });
+
+        let mut forwarded = Vec::new();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                match rx.recv().await.unwrap() {
+                    OmnistonEvent::ConnectionState { .. } => {}
+                    OmnistonEvent::NoQuote => break,
+                    other => forwarded.push(other),
+                }
+            }
+        })
+        .await
+        .expect("events should arrive");
+
+        shutdown.cancel();
+        task.await.unwrap();
+
+        let unknown = json!({ "resolver_paused": { "resolver_id": "r1" } });
+        assert_eq!(*seen.lock(), vec![unknown.clone()]);
+        assert!(matches!(
+            forwarded.as_slice(),
+            [OmnistonEvent::Unknown(raw)] if *raw == unknown
+        ));
+    }
+
+    #[tokio::test]
+    async fn cancellation_unsubscribes_and_returns_promptly() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        // Acks the subscription as id 77, sends one event, then records
+        // every frame the client sends until it closes.
+        let server = tokio::spawn(async move {
+            let (tcp, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+            ws.next().await.unwrap().unwrap();
+            ws.send(Message::text(
+                json!({ "jsonrpc": "2.0", "id": 1, "result": 77 }).to_string(),
+            ))
+            .await
+            .unwrap();
+            ws.send(sub_event(77, json!({ "keep_alive": {} })).unwrap())
+                .await
+                .unwrap();
+
+            let mut received = Vec::new();
+            while let Some(Ok(msg)) = ws.next().await {
+                let closed = msg.is_close();
+                received.push(msg);
+                if closed {
+                    break;
+                }
+            }
+            received
+        });
+
+        let client = OmnistonWsClient::new(url);
+        let rfq = rfq();
+        let (tx, mut rx) = mpsc::channel(8);
+        let shutdown = CancellationToken::new();
+
+        let task = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move {
+                client
+                    .request_for_quote_stream(&rfq, tx, shutdown)
+                    .await
+                    .unwrap()
+            }
+        });
+
+        let connected = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await;
+        assert!(matches!(
+            connected,
+            Ok(Some(OmnistonEvent::ConnectionState {
+                connected: true,
+                ..
+            }))
+        ));
+        let first = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await;
+        assert!(matches!(first, Ok(Some(OmnistonEvent::KeepAlive))));
+
+        shutdown.cancel();
+        tokio::time::timeout(Duration::from_secs(2), task)
+            .await
+            .expect("cancelled stream should return promptly")
+            .unwrap();
+
+        let received = server.await.unwrap();
+        let Message::Text(unsub) = &received[0] else {
+            panic!("expected an unsubscribe frame, got {received:?}");
+        };
+        let unsub: Value = serde_json::from_str(unsub.as_str()).unwrap();
+        assert_eq!(unsub["method"], QUOTE_UNSUBSCRIBE_METHOD);
+        assert_eq!(unsub["params"], json!([77]));
+        assert!(received[1].is_close());
+    }
+}
@@ -0,0 +1,121 @@
+//! Quote feed health counters.
+//!
+//! `OmnistonMetrics` tallies every event the WS client parses, by variant,
+//! plus reconnect attempts. Clones share the same atomics, so one instance
+//! can be handed to the client and exported through `Counters`. A rising
+//! share of `no_quote` or `unknown` events is the early sign of resolver
+//! trouble.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::market::types::OmnistonEvent;
+
+#[derive(Clone, Debug, Default)]
+pub struct OmnistonMetrics {
+    pub quote_updated: Arc<AtomicU64>,
+    /// Multi-resolver payloads (counted once per payload).
+    pub quotes_batch: Arc<AtomicU64>,
+    pub no_quote: Arc<AtomicU64>,
+    pub keep_alive: Arc<AtomicU64>,
+    pub ack: Arc<AtomicU64>,
+    pub unsubscribed: Arc<AtomicU64>,
+    /// Events the parser did not recognize (including malformed quotes).
+    pub unknown: Arc<AtomicU64>,
+    /// Reconnect attempts after a failed connect or a dropped stream.
+    pub reconnects: Arc<AtomicU64>,
+}
+
+impl OmnistonMetrics {
+    /// Counts one parsed server event. Client-generated `ConnectionState`
+    /// events are not counted; see `reconnects`.
+    pub fn record(&self, event: &OmnistonEvent) {
+        let counter = match event {
+            OmnistonEvent::QuoteUpdated(_) => &self.quote_updated,
+            OmnistonEvent::QuotesBatch(_) => &self.quotes_batch,
+            OmnistonEvent::NoQuote => &self.no_quote,
+            OmnistonEvent::KeepAlive => &self.keep_alive,
+            OmnistonEvent::Ack { .. } => &self.ack,
+            OmnistonEvent::Unsubscribed { .. } => &self.unsubscribed,
+            OmnistonEvent::Unknown(_) => &self.unknown,
+            OmnistonEvent::ConnectionState { .. } => return,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::omniston::client::SubscriptionMux;
+    use serde_json::{Value, json};
+
+    fn quote(resolver: &str) -> Value {
+        let asset = json!({ "blockchain": 607, "address": "EQ" });
+        json!({
+            "quote_id": format!("q-{resolver}"),
+            "resolver_id": resolver,
+            "resolver_name": resolver,
+            "bid_asset_address": asset,
+            "ask_asset_address": asset,
+            "bid_units": "1000",
+            "ask_units": "990",
+            "referrer_address": null,
+            "referrer_fee_asset": asset,
+            "referrer_fee_units": "0",
+            "protocol_fee_asset": asset,
+            "protocol_fee_units": "0",
+            "quote_timestamp": 0,
+            "trade_start_deadline": 0,
+            "gas_budget": "0",
+            "estimated_gas_consumption": "0",
+            "params": { "swap": null }
+        })
+    }
+
+    fn sub_event(ev: Value) -> Value {
+        json!({ "method": "event", "params": { "subscription": 9, "result": { "event": ev } } })
+    }
+
+    #[test]
+    fn parsed_frames_are_tallied_by_variant() {
+        let metrics = OmnistonMetrics::default();
+        let mut mux = SubscriptionMux::new(&["TON/USDT".to_string()]);
+
+        let frames = [
+            json!({ "id": 1, "result": 9 }),
+            sub_event(json!({ "ack": { "rfq_id": "rfq-1" } })),
+            sub_event(json!({ "quote_updated": quote("r1") })),
+            sub_event(json!({ "quote_updated": quote("r2") })),
+            sub_event(json!({ "quote_updated": [quote("r1"), quote("r2")] })),
+            sub_event(json!({ "no_quote": {} })),
+            sub_event(json!({ "no_quote": {} })),
+            sub_event(json!({ "keep_alive": {} })),
+            sub_event(json!({ "quote_updated": { "resolver_id": "r1" } })),
+            sub_event(json!({ "surprise": 1 })),
+            sub_event(json!({ "unsubscribed": { "rfq_id": "rfq-1" } })),
+            // Not a subscription event: never parsed, never counted.
+            json!({ "method": "event", "params": { "subscription": 5 } }),
+        ];
+        for frame in &frames {
+            if let Some((_, event)) = mux.route(frame) {
+                metrics.record(&event);
+            }
+        }
+        metrics.record(&OmnistonEvent::ConnectionState {
+            connected: true,
+            attempt: 0,
+            last_connected_ms: None,
+        });
+
+        let load = |c: &AtomicU64| c.load(Ordering::Relaxed);
+        assert_eq!(load(&metrics.ack), 1);
+        assert_eq!(load(&metrics.quote_updated), 2);
+        assert_eq!(load(&metrics.quotes_batch), 1);
+        assert_eq!(load(&metrics.no_quote), 2);
+        assert_eq!(load(&metrics.keep_alive), 1);
+        assert_eq!(load(&metrics.unknown), 2);
+        assert_eq!(load(&metrics.unsubscribed), 1);
+        assert_eq!(load(&metrics.reconnects), 0);
+    }
+}
@@ -0,0 +1,9 @@
+pub mod client;
+pub mod metrics;
+pub mod parser;
+pub mod recorder;
+
+pub use client::OmnistonWsClient;
+pub use metrics::OmnistonMetrics;
+pub use parser::{QuoteBook, parse_omniston_event};
+pub use recorder::{FileQuoteRecorder, QuoteRecorder, ReplayCadence, replay_from_file};
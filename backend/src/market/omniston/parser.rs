@@ -0,0 +1,311 @@
+//! Omniston RFQ event parsing.
+//!
+//! Maps the `event` object of an Omniston subscription message onto
+//! `OmnistonEvent`. Several resolvers may answer the same RFQ, either one
+//! `quote_updated` at a time or as an array in a single payload; `QuoteBook`
+//! keeps the latest quote of each resolver and picks the best-priced one.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::market::types::{OmnistonEvent, Quote};
+
+/// Parses one Omniston event object.
+///
+/// - `quote_updated: {..}`   → `QuoteUpdated`
+/// - `quote_updated: [..]`   → `QuotesBatch`, one quote per resolver (the
+///   last one in the payload wins)
+/// - `ack`, `no_quote`, `keep_alive`, `unsubscribed` → matching variants
+///
+/// Anything else, including malformed quotes, is returned as `Unknown`.
+pub fn parse_omniston_event(event: &Value) -> OmnistonEvent {
+    let unknown = || OmnistonEvent::Unknown(event.clone());
+    let Some(obj) = event.as_object() else {
+        return unknown();
+    };
+
+    if let Some(payload) = obj.get("quote_updated") {
+        return match payload {
+            Value::Array(items) => {
+                let Ok(quotes) = items
+                    .iter()
+                    .map(|q| serde_json::from_value::<Quote>(q.clone()))
+                    .collect::<Result<Vec<_>, _>>()
+                else {
+                    return unknown();
+                };
+                OmnistonEvent::QuotesBatch(dedupe_by_resolver(quotes))
+            }
+            _ => match serde_json::from_value::<Quote>(payload.clone()) {
+                Ok(q) => OmnistonEvent::QuoteUpdated(Box::new(q)),
+                Err(_) => unknown(),
+            },
+        };
+    }
+
+    let rfq_id = |v: &Value| v.get("rfq_id").and_then(Value::as_str).map(String::from);
+
+    if let Some(ack) = obj.get("ack") {
+        return match rfq_id(ack) {
+            Some(rfq_id) => OmnistonEvent::Ack { rfq_id },
+            None => unknown(),
+        };
+    }
+    if obj.contains_key("no_quote") {
+        return OmnistonEvent::NoQuote;
+    }
+    if obj.contains_key("keep_alive") {
+        return OmnistonEvent::KeepAlive;
+    }
+    if let Some(unsub) = obj.get("unsubscribed") {
+        return OmnistonEvent::Unsubscribed {
+            rfq_id: rfq_id(unsub),
+        };
+    }
+
+    unknown()
+}
+
+/// Keeps the last quote of each resolver, in first-seen order.
+fn dedupe_by_resolver(quotes: Vec<Quote>) -> Vec<Quote> {
+    let mut out: Vec<Quote> = Vec::with_capacity(quotes.len());
+    for q in quotes {
+        match out.iter_mut().find(|o| o.resolver_id == q.resolver_id) {
+            Some(slot) => *slot = q,
+            None => out.push(q),
+        }
+    }
+    out
+}
+
+/// Latest quote per resolver for one RFQ.
+#[derive(Debug, Clone, Default)]
+pub struct QuoteBook {
+    by_resolver: HashMap<String, Quote>,
+}
+
+impl QuoteBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores `quote`, replacing the resolver's previous quote (returned).
+    pub fn upsert(&mut self, quote: Quote) -> Option<Quote> {
+        self.by_resolver.insert(quote.resolver_id.clone(), quote)
+    }
+
+    /// Folds a parsed event into the book and returns how many quotes it
+    /// stored. `Unsubscribed` and a disconnect clear the book.
+    pub fn apply(&mut self, event: &OmnistonEvent) -> usize {
+        match event {
+            OmnistonEvent::QuoteUpdated(q) => {
+                self.upsert((**q).clone());
+                1
+            }
+            OmnistonEvent::QuotesBatch(quotes) => {
+                for q in quotes {
+                    self.upsert(q.clone());
+                }
+                quotes.len()
+            }
+            OmnistonEvent::Unsubscribed { .. }
+            | OmnistonEvent::ConnectionState {
+                connected: false, ..
+            } => {
+                self.by_resolver.clear();
+                0
+            }
+            _ => 0,
+        }
+    }
+
+    pub fn get(&self, resolver_id: &str) -> Option<&Quote> {
+        self.by_resolver.get(resolver_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_resolver.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_resolver.is_empty()
+    }
+
+    pub fn quotes(&self) -> impl Iterator<Item = &Quote> {
+        self.by_resolver.values()
+    }
+
+    /// Quote with the highest `Quote::ask_per_bid`; ties go to the lowest
+    /// resolver id so the pick does not depend on map order. Quotes without
+    /// a price are ignored.
+    pub fn best_by_price(&self) -> Option<&Quote> {
+        self.by_resolver
+            .values()
+            .filter_map(|q| Some((q.ask_per_bid()?, q)))
+            .max_by(|(pa, a), (pb, b)| {
+                pa.total_cmp(pb)
+                    .then_with(|| b.resolver_id.cmp(&a.resolver_id))
+            })
+            .map(|(_, q)| q)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn quote(resolver: &str, ask: &str) -> Value {
+        let asset = json!({ "blockchain": 607, "address": "EQ" });
+        json!({
+            "quote_id": format!("{resolver}-{ask}"),
+            "resolver_id": resolver,
+            "resolver_name": resolver,
+            "bid_asset_address": asset,
+            "ask_asset_address": asset,
+            "bid_units": "1000",
+            "ask_units": ask,
+            "referrer_address": null,
+            "referrer_fee_asset": asset,
+            "referrer_fee_units": "0",
+            "protocol_fee_asset": asset,
+            "protocol_fee_units": "0",
+            "quote_timestamp": 0,
+            "trade_start_deadline": 0,
+            "gas_budget": "0",
+            "estimated_gas_consumption": "0",
+            "params": { "swap": null }
+        })
+    }
+
+    #[test]
+    fn single_quote_is_parsed() {
+        let ev = parse_omniston_event(&json!({ "quote_updated": quote("r1", "990") }));
+        let OmnistonEvent::QuoteUpdated(q) = ev else {
+            panic!("expected QuoteUpdated, got {ev:?}");
+        };
+        assert_eq!(q.resolver_id, "r1");
+        assert_eq!(q.ask_units, "990");
+    }
+
+    #[test]
+    fn two_resolver_payload_is_a_batch() {
+        let ev = parse_omniston_event(&json!({
+            "quote_updated": [quote("r1", "990"), quote("r2", "995")]
+        }));
+        let OmnistonEvent::QuotesBatch(quotes) = ev else {
+            panic!("expected QuotesBatch, got {ev:?}");
+        };
+        let ids: Vec<_> = quotes.iter().map(|q| q.resolver_id.as_str()).collect();
+        assert_eq!(ids, vec!["r1", "r2"]);
+    }
+
+    #[test]
+    fn batch_keeps_last_quote_of_a_repeated_resolver() {
+        let ev = parse_omniston_event(&json!({
+            "quote_updated": [quote("r1", "990"), quote("r2", "995"), quote("r1", "998")]
+        }));
+        let OmnistonEvent::QuotesBatch(quotes) = ev else {
+            panic!("expected QuotesBatch, got {ev:?}");
+        };
+        assert_eq!(quotes.len(), 2);
+        assert_eq!(quotes[0].resolver_id, "r1");
+        assert_eq!(quotes[0].ask_units, "998");
+    }
+
+    #[test]
+    fn quote_book_replaces_by_resolver() {
+        let mut book = QuoteBook::new();
+
+        let first = parse_omniston_event(&json!({
+            "quote_updated": [quote("r1", "990"), quote("r2", "995")]
+        }));
+        assert_eq!(book.apply(&first), 2);
+
+        let update = parse_omniston_event(&json!({ "quote_updated": quote("r2", "999") }));
+        assert_eq!(book.apply(&update), 1);
+
+        assert_eq!(book.len(), 2);
+        assert_eq!(book.get("r1").unwrap().ask_units, "990");
+        assert_eq!(book.get("r2").unwrap().ask_units, "999");
+
+        book.apply(&parse_omniston_event(&json!({ "unsubscribed": {} })));
+        assert!(book.is_empty());
+    }
+
+    #[test]
+    fn best_by_price_picks_the_highest_ask_per_bid() {
+        let mut book = QuoteBook::new();
+        assert!(book.best_by_price().is_none());
+
+        book.apply(&parse_omniston_event(&json!({
+            "quote_updated": [quote("r1", "990"), quote("r2", "1500"), quote("r3", "995")]
+        })));
+        // r2 quotes more ask in total, but for twice the bid.
+        let mut r2 = book.get("r2").unwrap().clone();
+        r2.bid_units = "2000".into();
+        book.upsert(r2);
+        let mut broken = book.get("r1").unwrap().clone();
+        broken.resolver_id = "r4".into();
+        broken.bid_units = "0".into();
+        book.upsert(broken);
+
+        assert_eq!(book.best_by_price().unwrap().resolver_id, "r3");
+
+        // Equal prices resolve to the lowest resolver id.
+        book.upsert(serde_json::from_value(quote("r0", "995")).unwrap());
+        assert_eq!(book.best_by_price().unwrap().resolver_id, "r0");
+    }
+
+    #[test]
+    fn quote_book_is_cleared_on_disconnect() {
+        let mut book = QuoteBook::new();
+        book.apply(&parse_omniston_event(
+            &json!({ "quote_updated": quote("r1", "990") }),
+        ));
+
+        let state = |connected| OmnistonEvent::ConnectionState {
+            connected,
+            attempt: 0,
+            last_connected_ms: Some(1_000),
+        };
+        book.apply(&state(true));
+        assert_eq!(book.len(), 1);
+
+        book.apply(&state(false));
+        assert!(book.is_empty());
+    }
+
+    #[test]
+    fn control_events_and_unknown_fallthrough() {
+        assert!(matches!(
+            parse_omniston_event(&json!({ "ack": { "rfq_id": "rfq-1" } })),
+            OmnistonEvent::Ack { rfq_id } if rfq_id == "rfq-1"
+        ));
+        assert!(matches!(
+            parse_omniston_event(&json!({ "no_quote": {} })),
+            OmnistonEvent::NoQuote
+        ));
+        assert!(matches!(
+            parse_omniston_event(&json!({ "keep_alive": {} })),
+            OmnistonEvent::KeepAlive
+        ));
+        assert!(matches!(
+            parse_omniston_event(&json!({ "unsubscribed": { "rfq_id": "rfq-1" } })),
+            OmnistonEvent::Unsubscribed { rfq_id: Some(_) }
+        ));
+
+        for v in [
+            json!({ "something_else": 1 }),
+            json!({ "quote_updated": { "resolver_id": "r1" } }),
+            json!({ "quote_updated": [quote("r1", "990"), 5] }),
+            json!("keep_alive"),
+        ] {
+            assert!(matches!(
+                parse_omniston_event(&v),
+                OmnistonEvent::Unknown(_)
+            ));
+        }
+    }
+}
@@ -0,0 +1,268 @@
+//! Quote audit log and replay.
+//!
+//! A `QuoteRecorder` receives every parsed quote with its receive time.
+//! `FileQuoteRecorder` appends them as JSON lines, and `replay_from_file`
+//! streams such a file back as `OmnistonEvent::QuoteUpdated`, so the pulse
+//! pipeline can be re-run on the exact quotes seen in production.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use futures::{Stream, StreamExt, stream};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::market::types::{OmnistonEvent, Quote};
+
+/// Sink for received quotes.
+pub trait QuoteRecorder: Send + Sync {
+    fn record(&self, received_ms: u64, quote: &Quote) -> io::Result<()>;
+}
+
+/// One line of a quote log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedQuote {
+    pub received_ms: u64,
+    pub quote: Quote,
+}
+
+/// Appends quotes to a file, one JSON object per line. Every line is
+/// flushed, so a crash loses at most the quote being written.
+pub struct FileQuoteRecorder {
+    out: Mutex<BufWriter<File>>,
+}
+
+impl FileQuoteRecorder {
+    /// Opens `path` for appending, creating it if needed.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            out: Mutex::new(BufWriter::new(file)),
+        })
+    }
+}
+
+impl QuoteRecorder for FileQuoteRecorder {
+    fn record(&self, received_ms: u64, quote: &Quote) -> io::Result<()> {
+        let line = serde_json::to_string(&RecordedQuote {
+            received_ms,
+            quote: quote.clone(),
+        })?;
+
+        let mut out = self.out.lock();
+        writeln!(out, "{line}")?;
+        out.flush()
+    }
+}
+
+/// Records the quotes carried by `event`, if any.
+pub fn record_event(
+    recorder: &dyn QuoteRecorder,
+    received_ms: u64,
+    event: &OmnistonEvent,
+) -> io::Result<()> {
+    match event {
+        OmnistonEvent::QuoteUpdated(q) => recorder.record(received_ms, q),
+        OmnistonEvent::QuotesBatch(quotes) => quotes
+            .iter()
+            .try_for_each(|q| recorder.record(received_ms, q)),
+        _ => Ok(()),
+    }
+}
+
+/// Pacing of `replay_from_file`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayCadence {
+    /// Keep the recorded gaps between quotes.
+    Original,
+    /// Divide the recorded gaps by this factor (> 1 is faster).
+    Accelerated(f64),
+    /// No delay between quotes.
+    Unpaced,
+}
+
+impl ReplayCadence {
+    fn delay(self, gap_ms: u64) -> Duration {
+        let gap = Duration::from_millis(gap_ms);
+        match self {
+            Self::Original => gap,
+            Self::Accelerated(f) if f > 0.0 => gap.div_f64(f),
+            Self::Accelerated(_) | Self::Unpaced => Duration::ZERO,
+        }
+    }
+}
+
+/// Streams a quote log back as `OmnistonEvent::QuoteUpdated`, spacing
+/// events by their recorded receive times according to `cadence`.
+///
+/// Malformed lines are skipped with a warning.
+pub async fn replay_from_file(
+    path: impl AsRef<Path>,
+    cadence: ReplayCadence,
+) -> io::Result<impl Stream<Item = OmnistonEvent>> {
+    let text = tokio::fs::read_to_string(path).await?;
+
+    let recorded: Vec<RecordedQuote> = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(|(i, line)| match serde_json::from_str(line) {
+            Ok(r) => Some(r),
+            Err(e) => {
+                warn!(line = i + 1, error = %e, "skipping malformed quote log line");
+                None
+            }
+        })
+        .collect();
+
+    let mut prev_ms = recorded.first().map(|r| r.received_ms);
+    Ok(stream::iter(recorded).then(move |r| {
+        let gap_ms = r
+            .received_ms
+            .saturating_sub(prev_ms.unwrap_or(r.received_ms));
+        prev_ms = Some(r.received_ms);
+        async move {
+            let delay = cadence.delay(gap_ms);
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+            OmnistonEvent::QuoteUpdated(Box::new(r.quote))
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::omniston::parse_omniston_event;
+    use crate::market::pulses::slippage::SlippageSample;
+    use serde_json::json;
+
+    fn quote(resolver: &str, ask_units: &str, slippage_bps: u32) -> Quote {
+        let event = parse_omniston_event(&json!({ "quote_updated": {
+            "quote_id": format!("q-{resolver}"),
+            "resolver_id": resolver,
+            "resolver_name": resolver,
+            "bid_asset_address": { "blockchain": 607, "address": "EQ-bid" },
+            "ask_asset_address": { "blockchain": 607, "address": "EQ-ask" },
+            "bid_units": "1000",
+            "ask_units": ask_units,
+            "referrer_address": null,
+            "referrer_fee_asset": { "blockchain": 607, "address": "EQ-bid" },
+            "referrer_fee_units": "0",
+            "protocol_fee_asset": { "blockchain": 607, "address": "EQ-bid" },
+            "protocol_fee_units": "1",
+            "quote_timestamp": 1_700_000_000,
+            "trade_start_deadline": 1_700_000_060,
+            "gas_budget": "300000000",
+            "estimated_gas_consumption": "100000000",
+            "params": { "swap": {
+                "routes": [{ "steps": [{
+                    "bid_asset_address": { "blockchain": 607, "address": "EQ-bid" },
+                    "ask_asset_address": { "blockchain": 607, "address": "EQ-ask" },
+                    "chunks": [{
+                        "protocol": "StonFiV2",
+                        "bid_amount": "1000",
+                        "ask_amount": ask_units,
+                        "extra_version": 1,
+                        "extra": [1, 2, 3],
+                    }],
+                }] }],
+                "min_ask_amount": "900",
+                "recommended_min_ask_amount": "950",
+                "recommended_slippage_bps": slippage_bps,
+            } },
+        } }));
+        let OmnistonEvent::QuoteUpdated(q) = event else {
+            panic!("fixture should parse as a quote, got {event:?}");
+        };
+        *q
+    }
+
+    fn sample(q: &Quote) -> (String, u32, u128, u128) {
+        let s = SlippageSample::from_quote(q, 0).unwrap();
+        (
+            q.quote_id.clone(),
+            s.recommended_slippage_bps,
+            s.ask_units,
+            s.min_ask_units,
+        )
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("{name}-{}.jsonl", uuid::Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn recorded_quotes_replay_identically() {
+        let path = temp_path("quotes");
+        let quotes = [quote("r1", "990", 30), quote("r2", "995", 45)];
+
+        let recorder = FileQuoteRecorder::create(&path).unwrap();
+        record_event(
+            &recorder,
+            1_000,
+            &OmnistonEvent::QuotesBatch(quotes.to_vec()),
+        )
+        .unwrap();
+        record_event(&recorder, 1_500, &OmnistonEvent::KeepAlive).unwrap();
+        drop(recorder);
+
+        let replayed: Vec<Quote> = replay_from_file(&path, ReplayCadence::Unpaced)
+            .await
+            .unwrap()
+            .filter_map(|ev| async move {
+                match ev {
+                    OmnistonEvent::QuoteUpdated(q) => Some(*q),
+                    _ => None,
+                }
+            })
+            .collect()
+            .await;
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            replayed.iter().map(sample).collect::<Vec<_>>(),
+            quotes.iter().map(sample).collect::<Vec<_>>()
+        );
+        let chunk = &replayed[1].params.swap.as_ref().unwrap().routes[0].steps[0].chunks[0];
+        assert_eq!(chunk.extra, vec![1, 2, 3]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn replay_keeps_or_compresses_the_recorded_cadence() {
+        let path = temp_path("cadence");
+        let recorder = FileQuoteRecorder::create(&path).unwrap();
+        for (ms, resolver) in [(10_000, "a"), (12_000, "b"), (16_000, "c")] {
+            recorder.record(ms, &quote(resolver, "990", 30)).unwrap();
+        }
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"not json\n")
+            .unwrap();
+
+        for (cadence, expected) in [
+            (ReplayCadence::Original, Duration::from_secs(6)),
+            (
+                ReplayCadence::Accelerated(4.0),
+                Duration::from_millis(1_500),
+            ),
+            (ReplayCadence::Unpaced, Duration::ZERO),
+        ] {
+            let started = tokio::time::Instant::now();
+            let n = replay_from_file(&path, cadence)
+                .await
+                .unwrap()
+                .count()
+                .await;
+            assert_eq!(n, 3, "{cadence:?}");
+            assert_eq!(started.elapsed(), expected, "{cadence:?}");
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
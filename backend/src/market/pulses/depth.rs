@@ -2,8 +2,14 @@
 //!
 //! Computes *maximum executable trade size* for a given slippage tolerance.
 //! This pulse answers: “How much can I trade *right now* safely?”
+//!
+//! `quote_depth` offers a quote-implied alternative: the bid volume an RFQ
+//! resolver routed, aggregated per `RouteDepth`.
 
-use crate::market::{pulses::MarketPulse, types::PoolSnapshot};
+use crate::market::{
+    pulses::MarketPulse,
+    types::{ExecutionScope, PoolSnapshot, Quote, Route, RouteStep},
+};
 
 #[derive(Debug, Clone, Default)]
 pub struct DepthState {
@@ -31,6 +37,7 @@ impl DepthPulse {
 }
 
 impl MarketPulse for DepthPulse {
+    type Input = PoolSnapshot;
     type Output = DepthState;
 
     fn update(&mut self, _snapshot: PoolSnapshot) {
@@ -95,9 +102,57 @@ impl DepthPulse {
     }
 }
 
+/// How the depth of a quoted route is aggregated across its steps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RouteDepth {
+    /// Sum of every chunk's `bid_amount` over all steps.
+    #[default]
+    SumChunks,
+    /// Smallest per-step `bid_amount` total: a multi-hop route is only as
+    /// deep as its tightest hop.
+    MinStep,
+}
+
+/// Quote-implied depth: the deepest route of a swap quote, counting only the
+/// chunks within `scope`. 0 for non-swap quotes.
+pub fn quote_depth(quote: &Quote, scope: &ExecutionScope, mode: RouteDepth) -> u128 {
+    quote
+        .params
+        .swap
+        .as_ref()
+        .and_then(|swap| {
+            swap.routes
+                .iter()
+                .map(|r| route_depth(r, scope, mode))
+                .max()
+        })
+        .unwrap_or(0)
+}
+
+/// Depth of one route. Unparsable amounts count as 0.
+pub fn route_depth(route: &Route, scope: &ExecutionScope, mode: RouteDepth) -> u128 {
+    let steps = route.steps.iter().map(|step| step_bid(step, scope));
+    match mode {
+        RouteDepth::SumChunks => steps.fold(0u128, u128::saturating_add),
+        RouteDepth::MinStep => steps.min().unwrap_or(0),
+    }
+}
+
+fn step_bid(step: &RouteStep, scope: &ExecutionScope) -> u128 {
+    step.chunks
+        .iter()
+        .filter(|c| match scope {
+            ExecutionScope::MarketWide => true,
+            ExecutionScope::ProtocolOnly { protocol } => &c.protocol == protocol,
+        })
+        .map(|c| c.bid_amount.parse::<u128>().unwrap_or(0))
+        .fold(0, u128::saturating_add)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::market::types::{AssetAddress, RouteChunk};
 
     fn snapshot(r0: u128, r1: u128) -> PoolSnapshot {
         PoolSnapshot {
@@ -109,6 +164,68 @@ mod tests {
         }
     }
 
+    fn step(chunks: &[(&str, &str)]) -> RouteStep {
+        let asset = || AssetAddress {
+            blockchain: 607,
+            address: "EQ".to_string(),
+        };
+        RouteStep {
+            bid_asset_address: asset(),
+            ask_asset_address: asset(),
+            chunks: chunks
+                .iter()
+                .map(|&(protocol, bid)| RouteChunk {
+                    protocol: protocol.to_string(),
+                    bid_amount: bid.to_string(),
+                    ask_amount: "0".to_string(),
+                    extra_version: 0,
+                    extra: Vec::new(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn min_step_depth_is_bounded_by_the_shallow_hop() {
+        let route = Route {
+            steps: vec![
+                step(&[("StonFiV2", "600000"), ("DeDust", "400000")]),
+                step(&[("StonFiV2", "50000")]),
+            ],
+        };
+        let all = ExecutionScope::MarketWide;
+
+        assert_eq!(route_depth(&route, &all, RouteDepth::SumChunks), 1_050_000);
+        assert_eq!(route_depth(&route, &all, RouteDepth::MinStep), 50_000);
+
+        let ston = ExecutionScope::ProtocolOnly {
+            protocol: "StonFiV2".to_string(),
+        };
+        assert_eq!(route_depth(&route, &ston, RouteDepth::MinStep), 50_000);
+        assert_eq!(route_depth(&route, &ston, RouteDepth::SumChunks), 650_000);
+    }
+
+    #[test]
+    fn quote_depth_picks_the_deepest_route() {
+        let mut q = crate::market::pulses::slippage::tests::quote(0, "1000", "1000");
+        q.params.swap.as_mut().unwrap().routes = vec![
+            Route {
+                steps: vec![step(&[("a", "900")]), step(&[("b", "10")])],
+            },
+            Route {
+                steps: vec![step(&[("a", "200")])],
+            },
+            Route { steps: vec![] },
+        ];
+        let all = ExecutionScope::MarketWide;
+
+        assert_eq!(quote_depth(&q, &all, RouteDepth::SumChunks), 910);
+        assert_eq!(quote_depth(&q, &all, RouteDepth::MinStep), 200);
+
+        q.params.swap = None;
+        assert_eq!(quote_depth(&q, &all, RouteDepth::MinStep), 0);
+    }
+
     #[test]
     fn liquidity_gate_blocks_ghost_pools() {
         let d = DepthPulse::new(50.0);
@@ -1,28 +1,51 @@
 //! Market Pulse Abstraction
 //!
 //! A Pulse is a side-effect-free observer that derives a single market signal
-//! (e.g., Spread, Trend, Depth) from raw pool snapshots.
+//! (e.g., Spread, Trend, Depth, Slippage) from raw market inputs
+//! (pool snapshots or RFQ quotes).
 
 pub mod depth;
+pub mod slippage;
 pub mod spread;
 pub mod trend;
+pub mod window;
 
-pub use self::depth::DepthPulse;
+pub use self::depth::{DepthPulse, RouteDepth};
+pub use self::slippage::{SlippagePulse, SlippagePulseResult, SlippageSample};
 pub use self::spread::SpreadMonitor;
-pub use self::trend::TrendMonitor;
+pub use self::trend::{TrendConfirmation, TrendMonitor};
+pub use self::window::RollingWindow;
 
-use crate::market::types::PoolSnapshot;
+/// Warm-up a rolling pulse needs before its output is valid: at least
+/// `min_samples` inputs spanning at least `min_age_ms`.
+///
+/// Fast pairs can afford a short warm-up; illiquid pairs may want a longer one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PulseWarmup {
+    pub min_samples: usize,
+    pub min_age_ms: u64,
+}
 
-/// Trait for deriving market signals from pool state.
+impl PulseWarmup {
+    /// Whether a window of `samples` inputs spanning `age_ms` is warm.
+    pub fn is_warm(&self, samples: usize, age_ms: u64) -> bool {
+        samples >= self.min_samples && age_ms >= self.min_age_ms
+    }
+}
+
+/// Trait for deriving market signals from market inputs.
 ///
 /// Implementors are responsible for maintaining their own internal state
 /// (like rolling windows) while remaining deterministic.
 pub trait MarketPulse {
+    /// The raw observation this pulse consumes (e.g. a pool snapshot).
+    type Input;
+
     /// The specific signal produced by this pulse.
     type Output;
 
-    /// Ingests a new snapshot to update internal metrics.
-    fn update(&mut self, snapshot: PoolSnapshot);
+    /// Ingests a new input to update internal metrics.
+    fn update(&mut self, input: Self::Input);
 
     /// Computes and returns the current signal.
     ///
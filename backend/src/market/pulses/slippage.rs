@@ -0,0 +1,284 @@
+//! Omniston Slippage Pulse.
+//!
+//! Estimates expected execution slippage (bps) from RFQ quotes.
+//! Each quote contributes the larger of:
+//! - the resolver's `recommended_slippage_bps`
+//! - the gap between the quoted `ask_units` and `params.swap.min_ask_amount`
+//!
+//! The window reports its worst (largest) estimate, so a single bad quote
+//! keeps the gate closed until it rolls out.
+
+use std::collections::VecDeque;
+
+use crate::market::{
+    pulses::{MarketPulse, PulseWarmup},
+    types::Quote,
+};
+
+/// Slippage inputs extracted from one quote.
+#[derive(Debug, Clone)]
+pub struct SlippageSample {
+    pub ts_ms: u64,
+    pub recommended_slippage_bps: u32,
+    pub ask_units: u128,
+    pub min_ask_units: u128,
+}
+
+impl SlippageSample {
+    /// Extracts a sample from a swap quote observed at `ts_ms`.
+    ///
+    /// Returns `None` for non-swap quotes or unparsable amounts.
+    pub fn from_quote(quote: &Quote, ts_ms: u64) -> Option<Self> {
+        let swap = quote.params.swap.as_ref()?;
+
+        Some(Self {
+            ts_ms,
+            recommended_slippage_bps: swap.recommended_slippage_bps,
+            ask_units: quote.ask_units.parse().ok()?,
+            min_ask_units: swap.min_ask_amount.parse().ok()?,
+        })
+    }
+
+    /// Slippage estimate (bps) for this quote.
+    fn estimate_bps(&self) -> f64 {
+        let gap_bps = if self.ask_units == 0 {
+            0.0
+        } else {
+            let gap = self.ask_units.saturating_sub(self.min_ask_units) as f64;
+            gap / self.ask_units as f64 * 10_000.0
+        };
+
+        gap_bps.max(self.recommended_slippage_bps as f64)
+    }
+}
+
+/// Derived slippage signal.
+#[derive(Debug, Clone, Default)]
+pub struct SlippagePulseResult {
+    pub slippage_bps: f64,
+    pub ts_ms: u64,
+    pub validity: bool,
+}
+
+/// Rolling slippage pulse fed by quotes.
+pub struct SlippagePulse {
+    window: VecDeque<(u64, f64)>,
+    max_size: usize,
+    warmup: PulseWarmup,
+}
+
+impl SlippagePulse {
+    pub fn new(max_size: usize, min_warmup_ms: u64) -> Self {
+        Self {
+            window: VecDeque::with_capacity(max_size),
+            max_size,
+            warmup: PulseWarmup {
+                min_samples: 1,
+                min_age_ms: min_warmup_ms,
+            },
+        }
+    }
+
+    /// Replace the warm-up (`min_samples` of at least one quote).
+    pub fn with_warmup(mut self, warmup: PulseWarmup) -> Self {
+        self.warmup = PulseWarmup {
+            min_samples: warmup.min_samples.max(1),
+            ..warmup
+        };
+        self
+    }
+}
+
+impl MarketPulse for SlippagePulse {
+    type Input = SlippageSample;
+    type Output = SlippagePulseResult;
+
+    fn update(&mut self, sample: SlippageSample) {
+        if self.window.len() >= self.max_size {
+            self.window.pop_front();
+        }
+        self.window.push_back((sample.ts_ms, sample.estimate_bps()));
+    }
+
+    fn compute(&self) -> SlippagePulseResult {
+        let (Some(&(oldest_ts, _)), Some(&(newest_ts, _))) =
+            (self.window.front(), self.window.back())
+        else {
+            return SlippagePulseResult::default();
+        };
+
+        let worst = self
+            .window
+            .iter()
+            .map(|&(_, bps)| bps)
+            .fold(0.0_f64, f64::max);
+
+        let duration = newest_ts.saturating_sub(oldest_ts);
+
+        SlippagePulseResult {
+            slippage_bps: worst,
+            ts_ms: newest_ts,
+            validity: worst.is_finite() && self.warmup.is_warm(self.window.len(), duration),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn sample(ts_ms: u64, recommended: u32, ask: u128, min_ask: u128) -> SlippageSample {
+        SlippageSample {
+            ts_ms,
+            recommended_slippage_bps: recommended,
+            ask_units: ask,
+            min_ask_units: min_ask,
+        }
+    }
+
+    pub(crate) fn quote(recommended: u32, ask: &str, min_ask: &str) -> Quote {
+        let asset = serde_json::json!({ "blockchain": 607, "address": "EQ" });
+        serde_json::from_value(serde_json::json!({
+            "quote_id": "q",
+            "resolver_id": "r",
+            "resolver_name": "r",
+            "bid_asset_address": asset,
+            "ask_asset_address": asset,
+            "bid_units": "1000",
+            "ask_units": ask,
+            "referrer_address": null,
+            "referrer_fee_asset": asset,
+            "referrer_fee_units": "0",
+            "protocol_fee_asset": asset,
+            "protocol_fee_units": "0",
+            "quote_timestamp": 0,
+            "trade_start_deadline": 0,
+            "gas_budget": "0",
+            "estimated_gas_consumption": "0",
+            "params": {
+                "swap": {
+                    "routes": [],
+                    "min_ask_amount": min_ask,
+                    "recommended_min_ask_amount": min_ask,
+                    "recommended_slippage_bps": recommended
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn from_quote_reads_swap_params() {
+        let s = SlippageSample::from_quote(&quote(30, "10000", "9900"), 7).unwrap();
+        assert_eq!(s.ts_ms, 7);
+        assert_eq!(s.recommended_slippage_bps, 30);
+        assert_eq!(s.ask_units, 10_000);
+        assert_eq!(s.min_ask_units, 9_900);
+        assert!((s.estimate_bps() - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ask_denominated_quote_measures_slippage_on_the_ask_side() {
+        // An `AskUnits` RFQ fixes `ask_units` and lets the resolver pick
+        // `bid_units`; the estimate must not depend on the bid side.
+        let mut exact_out = quote(30, "10000", "9900");
+        exact_out.bid_units = "123456".into();
+
+        let s = SlippageSample::from_quote(&exact_out, 0).unwrap();
+        assert_eq!(s.ask_units, 10_000);
+        assert!((s.estimate_bps() - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn from_quote_ignores_non_swap_and_malformed_quotes() {
+        let mut q = quote(30, "10000", "9900");
+        q.params.swap = None;
+        assert!(SlippageSample::from_quote(&q, 0).is_none());
+
+        assert!(SlippageSample::from_quote(&quote(30, "lots", "9900"), 0).is_none());
+    }
+
+    #[test]
+    fn empty_window_is_invalid() {
+        let p = SlippagePulse::new(5, 0);
+        assert!(!p.compute().validity);
+    }
+
+    #[test]
+    fn recommended_slippage_dominates_small_gap() {
+        let mut p = SlippagePulse::new(5, 0);
+        // gap = 10 bps, recommended = 50 bps
+        p.update(sample(1_000, 50, 10_000, 9_990));
+
+        let r = p.compute();
+        assert!(r.validity);
+        assert!((r.slippage_bps - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn min_ask_gap_dominates_low_recommendation() {
+        let mut p = SlippagePulse::new(5, 0);
+        // gap = 200 bps, recommended = 10 bps
+        p.update(sample(1_000, 10, 10_000, 9_800));
+
+        assert!((p.compute().slippage_bps - 200.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn reports_worst_estimate_in_window() {
+        let mut p = SlippagePulse::new(3, 0);
+        p.update(sample(1_000, 20, 10_000, 10_000));
+        p.update(sample(2_000, 80, 10_000, 10_000));
+        p.update(sample(3_000, 30, 10_000, 10_000));
+        assert!((p.compute().slippage_bps - 80.0).abs() < 1e-9);
+
+        // The 80 bps quote rolls out of the window.
+        p.update(sample(4_000, 30, 10_000, 10_000));
+        p.update(sample(5_000, 25, 10_000, 10_000));
+        assert!((p.compute().slippage_bps - 30.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn warmup_gate_blocks_short_windows() {
+        let mut p = SlippagePulse::new(5, 5_000);
+        p.update(sample(1_000, 20, 10_000, 10_000));
+        p.update(sample(2_000, 20, 10_000, 10_000));
+        assert!(!p.compute().validity);
+
+        p.update(sample(6_000, 20, 10_000, 10_000));
+        assert!(p.compute().validity);
+    }
+
+    #[test]
+    fn warmup_min_samples_blocks_sparse_quotes() {
+        let mut p = SlippagePulse::new(5, 0).with_warmup(PulseWarmup {
+            min_samples: 3,
+            min_age_ms: 0,
+        });
+        p.update(sample(1_000, 20, 10_000, 10_000));
+        p.update(sample(2_000, 20, 10_000, 10_000));
+        assert!(!p.compute().validity);
+
+        p.update(sample(3_000, 20, 10_000, 10_000));
+        assert!(p.compute().validity);
+    }
+
+    #[test]
+    fn reset_clears_state() {
+        let mut p = SlippagePulse::new(5, 0);
+        p.update(sample(1_000, 20, 10_000, 10_000));
+        p.reset();
+        assert!(!p.compute().validity);
+    }
+
+    #[test]
+    fn zero_ask_units_uses_recommendation_only() {
+        let mut p = SlippagePulse::new(5, 0);
+        p.update(sample(1_000, 15, 0, 0));
+        assert!((p.compute().slippage_bps - 15.0).abs() < 1e-9);
+    }
+}
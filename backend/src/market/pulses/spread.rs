@@ -7,10 +7,17 @@
 //! - AMM curvature
 //!
 //! It intentionally excludes size-dependent slippage.
+//!
+//! Optionally (`with_volatility_band`), a spread that jumps more than `k`
+//! standard deviations above the window's recent mean is reported invalid,
+//! so a sudden blow-out blocks execution even below users' absolute limits.
 
 use std::collections::VecDeque;
 
-use crate::market::{pulses::MarketPulse, types::PoolSnapshot};
+use crate::market::{
+    pulses::{MarketPulse, RollingWindow},
+    types::PoolSnapshot,
+};
 
 /// Derived infinitesimal spread state (ε → 0).
 #[derive(Debug, Clone, Default)]
@@ -19,6 +26,10 @@ pub struct SpreadState {
     pub buy_price: f64,
     pub sell_price: f64,
     pub spread_bps: f64,
+    /// Mean and standard deviation of the valid spreads seen before this one
+    /// (0 while the window is empty).
+    pub mean_spread_bps: f64,
+    pub stddev_spread_bps: f64,
     pub ts_ms: u64,
     pub validity: bool,
 }
@@ -28,6 +39,8 @@ pub struct SpreadMonitor {
     window: VecDeque<SpreadState>,
     max_size: usize,
     min_liquidity: u128,
+    spreads: RollingWindow,
+    volatility_band: Option<f64>,
 }
 
 impl SpreadMonitor {
@@ -36,6 +49,24 @@ impl SpreadMonitor {
             window: VecDeque::with_capacity(max_size),
             max_size,
             min_liquidity: 100,
+            spreads: RollingWindow::new(max_size),
+            volatility_band: None,
+        }
+    }
+
+    /// Report a spread above `mean + k_stddev * stddev` of the preceding
+    /// window as invalid. Needs at least two prior samples to apply.
+    pub fn with_volatility_band(mut self, k_stddev: f64) -> Self {
+        self.volatility_band = Some(k_stddev);
+        self
+    }
+
+    fn within_band(&self, s: &SpreadState) -> bool {
+        match self.volatility_band {
+            Some(k) if self.spreads.len() >= 2 => {
+                s.spread_bps <= s.mean_spread_bps + k * s.stddev_spread_bps
+            }
+            _ => true,
         }
     }
 
@@ -76,15 +107,26 @@ impl SpreadMonitor {
             spread_bps,
             ts_ms: snapshot.ts_ms,
             validity: spread_bps.is_finite(),
+            ..Default::default()
         }
     }
 }
 
 impl MarketPulse for SpreadMonitor {
+    type Input = PoolSnapshot;
     type Output = SpreadState;
 
     fn update(&mut self, snapshot: PoolSnapshot) {
-        let state = Self::derive(&snapshot, self.min_liquidity);
+        let mut state = Self::derive(&snapshot, self.min_liquidity);
+        state.mean_spread_bps = self.spreads.mean().unwrap_or(0.0);
+        state.stddev_spread_bps = self.spreads.stddev().unwrap_or(0.0);
+        state.validity = state.validity && self.within_band(&state);
+
+        // Only healthy spreads form the baseline; an outlier must not widen it.
+        if state.validity {
+            self.spreads.push(state.spread_bps);
+        }
+
         if self.window.len() >= self.max_size {
             self.window.pop_front();
         }
@@ -97,6 +139,7 @@ impl MarketPulse for SpreadMonitor {
 
     fn reset(&mut self) {
         self.window.clear();
+        self.spreads.clear();
     }
 }
 
@@ -179,6 +222,50 @@ mod tests {
         assert!(state.mid_price < 1.0); // y/x < 1 due to higher reserve0
     }
 
+    #[test]
+    fn reports_baseline_of_preceding_spreads() {
+        let mut monitor = SpreadMonitor::new(5);
+
+        monitor.update(snapshot(1_000_000, 1_000_000, 20, 10));
+        let first = monitor.compute();
+        assert_eq!(first.mean_spread_bps, 0.0);
+
+        monitor.update(snapshot(1_000_000, 1_000_000, 20, 10));
+        let second = monitor.compute();
+        assert!((second.mean_spread_bps - first.spread_bps).abs() < 1e-9);
+        assert!(second.stddev_spread_bps.abs() < 1e-9);
+    }
+
+    #[test]
+    fn volatility_band_rejects_spread_spike() {
+        let mut monitor = SpreadMonitor::new(10).with_volatility_band(4.0);
+
+        // Fees alternate 29/30/31 bps: a ~2 bps spread stddev around 60 bps.
+        for fee in [29, 30, 31, 30, 29, 31] {
+            monitor.update(snapshot(1_000_000, 1_000_000, fee, 0));
+            assert!(monitor.compute().validity);
+        }
+
+        // A 45 bps fee (~90 bps spread) is far outside the band.
+        monitor.update(snapshot(1_000_000, 1_000_000, 45, 0));
+        let spike = monitor.compute();
+        assert!(!spike.validity);
+        assert!(spike.spread_bps > spike.mean_spread_bps + 4.0 * spike.stddev_spread_bps);
+
+        // The spike did not widen the baseline; normal spreads pass again.
+        monitor.update(snapshot(1_000_000, 1_000_000, 30, 0));
+        assert!(monitor.compute().validity);
+    }
+
+    #[test]
+    fn without_band_spikes_stay_valid() {
+        let mut monitor = SpreadMonitor::new(10);
+        for fee in [30, 30, 30, 90] {
+            monitor.update(snapshot(1_000_000, 1_000_000, fee, 0));
+        }
+        assert!(monitor.compute().validity);
+    }
+
     #[test]
     fn numeric_stability_extreme_reserves() {
         let mut monitor = SpreadMonitor::new(1);
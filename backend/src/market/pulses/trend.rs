@@ -2,16 +2,51 @@
 //!
 //! Detects *downward price pressure over time* using mid-price evolution.
 //! This pulse protects users from trading into rapid sell-offs.
+//!
+//! The drop is the slope of a least-squares line fitted to the window's mid
+//! prices (normalized to the oldest sample), projected over the window span.
+//! Fitting every sample instead of comparing the two endpoints keeps a single
+//! outlier at either end from dominating; R² of the fit is reported as
+//! `confidence`.
+//!
+//! A drop is only reported once it is *confirmed*: both the cumulative move and
+//! its rate must clear `TrendConfirmation` thresholds, so small jittery downticks
+//! do not block execution.
 
 use std::collections::VecDeque;
 
-use crate::market::{pulses::MarketPulse, types::PoolSnapshot};
+use crate::market::{
+    pulses::{MarketPulse, PulseWarmup},
+    types::PoolSnapshot,
+};
+
+/// Samples required before a trend can be fitted. Also the floor for any
+/// configured `PulseWarmup::min_samples`.
+const MIN_SAMPLES: usize = 2;
+
+/// Thresholds a downward move must clear before it is reported as a trend.
+///
+/// The default disables confirmation (every drop is reported as-is).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TrendConfirmation {
+    /// Minimum cumulative drop across the window (bps).
+    pub min_drop_bps: f64,
+    /// Minimum average rate of the drop (bps per minute).
+    pub min_slope_bps_per_min: f64,
+}
 
 #[derive(Debug, Clone, Default)]
 pub struct TrendState {
     pub current_mid_price: f64,
     pub reference_mid_price: f64,
+    /// Confirmed drop (0 when an unconfirmed downtick was filtered out).
     pub trend_drop_bps: f64,
+    /// Unfiltered fitted move across the window (positive = drop).
+    pub raw_drop_bps: f64,
+    /// Fitted rate of the move (bps per minute, positive = falling).
+    pub slope_bps_per_min: f64,
+    /// R² of the fit in `[0, 1]`; 0 when prices did not move at all.
+    pub confidence: f64,
     pub window_duration_ms: u64,
     pub ts_ms: u64,
     pub validity: bool,
@@ -22,7 +57,8 @@ pub struct TrendMonitor {
     window: VecDeque<PoolSnapshot>,
     max_size: usize,
     min_liquidity: u128,
-    min_warmup_ms: u64,
+    warmup: PulseWarmup,
+    confirmation: TrendConfirmation,
 }
 
 impl TrendMonitor {
@@ -31,12 +67,37 @@ impl TrendMonitor {
             window: VecDeque::with_capacity(max_size),
             max_size,
             min_liquidity: 100,
-            min_warmup_ms,
+            warmup: PulseWarmup {
+                min_samples: MIN_SAMPLES,
+                min_age_ms: min_warmup_ms,
+            },
+            confirmation: TrendConfirmation::default(),
         }
     }
+
+    /// Replace the warm-up (`min_samples` is raised to the fitting minimum).
+    pub fn with_warmup(mut self, warmup: PulseWarmup) -> Self {
+        self.warmup = PulseWarmup {
+            min_samples: warmup.min_samples.max(MIN_SAMPLES),
+            ..warmup
+        };
+        self
+    }
+
+    /// Require drops to clear `confirmation` before they are reported.
+    pub fn with_confirmation(mut self, confirmation: TrendConfirmation) -> Self {
+        self.confirmation = confirmation;
+        self
+    }
+
+    fn is_confirmed(&self, drop_bps: f64, slope_bps_per_min: f64) -> bool {
+        drop_bps >= self.confirmation.min_drop_bps
+            && slope_bps_per_min >= self.confirmation.min_slope_bps_per_min
+    }
 }
 
 impl MarketPulse for TrendMonitor {
+    type Input = PoolSnapshot;
     type Output = TrendState;
 
     fn update(&mut self, snapshot: PoolSnapshot) {
@@ -47,7 +108,7 @@ impl MarketPulse for TrendMonitor {
     }
 
     fn compute(&self) -> TrendState {
-        if self.window.len() < 2 {
+        if self.window.len() < MIN_SAMPLES {
             return TrendState::default();
         }
 
@@ -55,10 +116,10 @@ impl MarketPulse for TrendMonitor {
         let newest = self.window.back().unwrap();
         let duration = newest.ts_ms.saturating_sub(oldest.ts_ms);
 
-        if oldest.reserve0 < self.min_liquidity
-            || oldest.reserve1 < self.min_liquidity
-            || newest.reserve0 < self.min_liquidity
-            || newest.reserve1 < self.min_liquidity
+        if self
+            .window
+            .iter()
+            .any(|s| s.reserve0 < self.min_liquidity || s.reserve1 < self.min_liquidity)
         {
             return TrendState {
                 ts_ms: newest.ts_ms,
@@ -67,17 +128,40 @@ impl MarketPulse for TrendMonitor {
             };
         }
 
-        let old_mid = oldest.reserve1 as f64 / oldest.reserve0 as f64;
-        let new_mid = newest.reserve1 as f64 / newest.reserve0 as f64;
-        let drop_bps = ((old_mid - new_mid) / old_mid) * 10_000.0;
+        let old_mid = mid_price(oldest);
+        let new_mid = mid_price(newest);
+        let fit = fit_line(self.window.iter().map(|s| {
+            (
+                s.ts_ms.saturating_sub(oldest.ts_ms) as f64,
+                mid_price(s) / old_mid,
+            )
+        }));
+
+        // Fitted move over the window, relative to the fitted starting price.
+        let drop_bps = -fit.slope * duration as f64 / fit.intercept * 10_000.0;
+        let slope_bps_per_min = if duration > 0 {
+            drop_bps * 60_000.0 / duration as f64
+        } else {
+            0.0
+        };
+
+        // Rises pass through untouched; drops must be confirmed to count.
+        let trend_drop_bps = if drop_bps > 0.0 && !self.is_confirmed(drop_bps, slope_bps_per_min) {
+            0.0
+        } else {
+            drop_bps
+        };
 
         TrendState {
             current_mid_price: new_mid,
             reference_mid_price: old_mid,
-            trend_drop_bps: drop_bps,
+            trend_drop_bps,
+            raw_drop_bps: drop_bps,
+            slope_bps_per_min,
+            confidence: fit.r_squared,
             window_duration_ms: duration,
             ts_ms: newest.ts_ms,
-            validity: drop_bps.is_finite() && duration >= self.min_warmup_ms,
+            validity: drop_bps.is_finite() && self.warmup.is_warm(self.window.len(), duration),
         }
     }
 
@@ -86,6 +170,51 @@ impl MarketPulse for TrendMonitor {
     }
 }
 
+fn mid_price(s: &PoolSnapshot) -> f64 {
+    s.reserve1 as f64 / s.reserve0 as f64
+}
+
+/// Least-squares fit `y = intercept + slope * x`.
+struct LineFit {
+    intercept: f64,
+    slope: f64,
+    r_squared: f64,
+}
+
+fn fit_line(points: impl Iterator<Item = (f64, f64)> + Clone) -> LineFit {
+    let n = points.clone().count() as f64;
+    let (sum_x, sum_y) = points
+        .clone()
+        .fold((0.0, 0.0), |(sx, sy), (x, y)| (sx + x, sy + y));
+    let (mean_x, mean_y) = (sum_x / n, sum_y / n);
+
+    let (sxx, sxy, syy) = points
+        .clone()
+        .fold((0.0, 0.0, 0.0), |(sxx, sxy, syy), (x, y)| {
+            let (dx, dy) = (x - mean_x, y - mean_y);
+            (sxx + dx * dx, sxy + dx * dy, syy + dy * dy)
+        });
+
+    // All samples share a timestamp: no slope can be fitted.
+    let slope = if sxx > 0.0 { sxy / sxx } else { 0.0 };
+    let intercept = mean_y - slope * mean_x;
+
+    let r_squared = if syy > 0.0 {
+        let ss_res: f64 = points
+            .map(|(x, y)| (y - (intercept + slope * x)).powi(2))
+            .sum();
+        (1.0 - ss_res / syy).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    LineFit {
+        intercept,
+        slope,
+        r_squared,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -147,6 +276,45 @@ mod tests {
         assert!(!t.validity);
     }
 
+    /// Feeds a rising series one second apart until the monitor is valid.
+    fn ticks_until_valid(mut m: TrendMonitor) -> Option<u64> {
+        (0..20u64)
+            .find(|&i| {
+                m.update(snap(1_000, 1_000 + i as u128, i * 1_000));
+                m.compute().validity
+            })
+            .map(|i| i + 1)
+    }
+
+    #[test]
+    fn shorter_warmup_is_valid_in_fewer_ticks() {
+        let default = ticks_until_valid(TrendMonitor::new(10, 5_000)).unwrap();
+        let short = ticks_until_valid(TrendMonitor::new(10, 5_000).with_warmup(PulseWarmup {
+            min_samples: 2,
+            min_age_ms: 1_000,
+        }))
+        .unwrap();
+
+        assert_eq!(default, 6);
+        assert_eq!(short, 2);
+    }
+
+    #[test]
+    fn warmup_min_samples_is_enforced_and_floored() {
+        let by_samples = TrendMonitor::new(10, 0).with_warmup(PulseWarmup {
+            min_samples: 4,
+            min_age_ms: 0,
+        });
+        assert_eq!(ticks_until_valid(by_samples), Some(4));
+
+        // A line cannot be fitted through fewer than two samples.
+        let floored = TrendMonitor::new(10, 0).with_warmup(PulseWarmup {
+            min_samples: 0,
+            min_age_ms: 0,
+        });
+        assert_eq!(ticks_until_valid(floored), Some(2));
+    }
+
     #[test]
     fn insufficient_data_is_invalid() {
         let mut m = TrendMonitor::new(5, 1000);
@@ -194,4 +362,120 @@ mod tests {
         m.reset();
         assert!(!m.compute().validity);
     }
+
+    #[test]
+    fn strictly_decreasing_series_has_confident_drop() {
+        let mut m = TrendMonitor::new(10, 1_000);
+
+        // -1% every 10s over 50s => ~500 bps.
+        for i in 0..6u64 {
+            m.update(snap(100_000, 100_000 - i as u128 * 1_000, i * 10_000));
+        }
+
+        let t = m.compute();
+        assert!(t.validity);
+        assert!((t.trend_drop_bps - 500.0).abs() < 1.0);
+        assert!((t.slope_bps_per_min - 600.0).abs() < 1.0);
+        assert!(t.confidence > 0.99);
+    }
+
+    #[test]
+    fn confirmation_leaves_a_rise_untouched() {
+        let mut m = confirmed_monitor();
+
+        // +1% every 10s over 50s: far from a confirmed drop, yet the negative
+        // drop is reported as measured rather than zeroed.
+        for i in 0..6u64 {
+            m.update(snap(100_000, 100_000 + i as u128 * 1_000, i * 10_000));
+        }
+
+        let t = m.compute();
+        assert_eq!(t.trend_drop_bps, t.raw_drop_bps);
+        assert!((t.raw_drop_bps + 500.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn flat_series_has_no_drop() {
+        let mut m = TrendMonitor::new(10, 1_000);
+
+        for i in 0..6u64 {
+            m.update(snap(100_000, 100_000, i * 10_000));
+        }
+
+        let t = m.compute();
+        assert!(t.validity);
+        assert!(t.trend_drop_bps.abs() < 1e-9);
+        assert!(t.slope_bps_per_min.abs() < 1e-9);
+        assert_eq!(t.confidence, 0.0);
+    }
+
+    #[test]
+    fn fit_discounts_single_endpoint_outlier() {
+        let mut m = TrendMonitor::new(10, 1_000);
+
+        // Flat, then one sharp tick down on the newest sample.
+        for i in 0..5u64 {
+            m.update(snap(100_000, 100_000, i * 10_000));
+        }
+        m.update(snap(100_000, 97_000, 50_000));
+
+        let t = m.compute();
+        // Endpoint comparison would report 300 bps.
+        assert!(t.raw_drop_bps > 0.0);
+        assert!(t.raw_drop_bps < 300.0);
+        assert!(t.confidence < 0.9);
+    }
+
+    fn confirmed_monitor() -> TrendMonitor {
+        TrendMonitor::new(10, 1_000).with_confirmation(TrendConfirmation {
+            min_drop_bps: 20.0,
+            min_slope_bps_per_min: 5.0,
+        })
+    }
+
+    #[test]
+    fn noisy_downticks_are_not_confirmed() {
+        let mut m = confirmed_monitor();
+
+        // Jitter around 1.0 ending ~5 bps lower over 60s.
+        m.update(snap(100_000, 100_000, 0));
+        m.update(snap(100_000, 99_980, 15_000));
+        m.update(snap(100_000, 100_010, 30_000));
+        m.update(snap(100_000, 99_970, 45_000));
+        m.update(snap(100_000, 99_950, 60_000));
+
+        let t = m.compute();
+        assert!(t.validity);
+        assert!(t.raw_drop_bps > 0.0);
+        assert_eq!(t.trend_drop_bps, 0.0);
+    }
+
+    #[test]
+    fn sustained_drop_is_confirmed() {
+        let mut m = confirmed_monitor();
+
+        // ~3% over 60s => 300 bps, 300 bps/min.
+        for (i, r1) in [100_000u128, 99_000, 98_000, 97_000].iter().enumerate() {
+            m.update(snap(100_000, *r1, i as u64 * 20_000));
+        }
+
+        let t = m.compute();
+        assert!(t.validity);
+        assert!((t.trend_drop_bps - 300.0).abs() < 1.0);
+        assert_eq!(t.trend_drop_bps, t.raw_drop_bps);
+    }
+
+    #[test]
+    fn slow_drop_below_slope_threshold_is_not_confirmed() {
+        let mut m = confirmed_monitor();
+
+        // 50 bps but spread over 1 hour => < 1 bps/min.
+        m.update(snap(100_000, 100_000, 0));
+        m.update(snap(100_000, 99_500, 3_600_000));
+
+        let t = m.compute();
+        assert!(t.raw_drop_bps > 49.0);
+        assert!(t.slope_bps_per_min < 5.0);
+        assert_eq!(t.trend_drop_bps, 0.0);
+    }
 }
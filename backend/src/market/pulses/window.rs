@@ -0,0 +1,163 @@
+//! Fixed-capacity rolling window of `f64` samples with running aggregates.
+//!
+//! `mean` and `stddev` are O(1) from running sums updated on push/evict;
+//! `min` and `max` scan the (small) window. Sums are kept relative to a
+//! shift (the first sample since the window was last empty), which avoids
+//! catastrophic cancellation when samples vary little around a large value.
+
+use std::collections::VecDeque;
+
+pub struct RollingWindow {
+    values: VecDeque<f64>,
+    capacity: usize,
+    shift: f64,
+    sum: f64,
+    sum_sq: f64,
+}
+
+impl RollingWindow {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            values: VecDeque::with_capacity(capacity),
+            capacity,
+            shift: 0.0,
+            sum: 0.0,
+            sum_sq: 0.0,
+        }
+    }
+
+    /// Appends `v`, evicting and returning the oldest sample when full.
+    pub fn push(&mut self, v: f64) -> Option<f64> {
+        let evicted = if self.values.len() >= self.capacity {
+            self.values.pop_front()
+        } else {
+            None
+        };
+
+        if self.values.is_empty() {
+            self.shift = v;
+        }
+
+        if let Some(old) = evicted {
+            let d = old - self.shift;
+            self.sum -= d;
+            self.sum_sq -= d * d;
+        }
+        let d = v - self.shift;
+        self.values.push_back(v);
+        self.sum += d;
+        self.sum_sq += d * d;
+
+        evicted
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn last(&self) -> Option<f64> {
+        self.values.back().copied()
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        self.values.iter().copied().reduce(f64::min)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.values.iter().copied().reduce(f64::max)
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (!self.is_empty()).then(|| self.shift + self.sum / self.len() as f64)
+    }
+
+    /// Population standard deviation of the live window.
+    pub fn stddev(&self) -> Option<f64> {
+        if self.is_empty() {
+            return None;
+        }
+        let n = self.len() as f64;
+        let mean_d = self.sum / n;
+        // Running sums can still drift slightly below zero.
+        let var = (self.sum_sq / n - mean_d * mean_d).max(0.0);
+        Some(var.sqrt())
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.shift = 0.0;
+        self.sum = 0.0;
+        self.sum_sq = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Option<f64>, b: f64) -> bool {
+        a.is_some_and(|a| (a - b).abs() < 1e-9)
+    }
+
+    #[test]
+    fn empty_window_has_no_aggregates() {
+        let w = RollingWindow::new(3);
+        assert!(w.min().is_none());
+        assert!(w.max().is_none());
+        assert!(w.mean().is_none());
+        assert!(w.stddev().is_none());
+    }
+
+    #[test]
+    fn aggregates_track_known_sequence_with_eviction() {
+        let mut w = RollingWindow::new(4);
+        for v in [2.0, 4.0, 4.0, 4.0] {
+            assert!(w.push(v).is_none());
+        }
+        assert!(close(w.mean(), 3.5));
+        assert!(close(w.stddev(), 0.75_f64.sqrt()));
+        assert!(close(w.min(), 2.0));
+        assert!(close(w.max(), 4.0));
+
+        // Evicts 2.0 => [4, 4, 4, 5]
+        assert_eq!(w.push(5.0), Some(2.0));
+        assert!(close(w.mean(), 4.25));
+        assert!(close(w.stddev(), 0.1875_f64.sqrt()));
+        assert!(close(w.min(), 4.0));
+        assert!(close(w.max(), 5.0));
+
+        // Evicts all the 4s => [5, 7, 9, 11]
+        for v in [7.0, 9.0, 11.0] {
+            assert_eq!(w.push(v), Some(4.0));
+        }
+        assert_eq!(w.len(), 4);
+        assert!(close(w.mean(), 8.0));
+        assert!(close(w.stddev(), 5.0_f64.sqrt()));
+        assert!(close(w.min(), 5.0));
+        assert!(close(w.last(), 11.0));
+    }
+
+    #[test]
+    fn constant_window_has_zero_stddev() {
+        let mut w = RollingWindow::new(8);
+        for _ in 0..20 {
+            w.push(60.123456789);
+        }
+        assert!(close(w.stddev(), 0.0));
+    }
+
+    #[test]
+    fn clear_resets_sums() {
+        let mut w = RollingWindow::new(2);
+        w.push(10.0);
+        w.clear();
+        w.push(1.0);
+        assert!(close(w.mean(), 1.0));
+        assert!(close(w.stddev(), 0.0));
+    }
+}
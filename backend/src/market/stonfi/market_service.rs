@@ -1,27 +1,66 @@
 use crate::market::{
     pulses::{
-        MarketPulse,
+        MarketPulse, PulseWarmup,
         depth::{DepthPulse, DepthState},
+        slippage::{SlippagePulse, SlippageSample},
         spread::SpreadMonitor,
-        trend::TrendMonitor,
+        trend::{TrendConfirmation, TrendMonitor},
     },
-    types::{MarketMetrics, PoolSnapshot},
+    types::{MarketMetrics, MarketMetricsView, PoolSnapshot, Quote},
 };
 
+/// Validity (warm-up and sanity) of every pulse of a pair at one snapshot.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PulseHealth {
+    pub spread: bool,
+    pub trend: bool,
+    pub depth: bool,
+    /// Quote-driven; not required for publishing (see `evaluate_all`).
+    pub slippage: bool,
+}
+
+impl PulseHealth {
+    /// Worst case over the pulses a published view depends on.
+    pub fn is_ready(&self) -> bool {
+        self.spread && self.trend && self.depth
+    }
+
+    /// Required pulses that are not valid yet, for diagnostics.
+    pub fn not_ready(&self) -> Vec<&'static str> {
+        [
+            ("spread", self.spread),
+            ("trend", self.trend),
+            ("depth", self.depth),
+        ]
+        .into_iter()
+        .filter_map(|(name, ok)| (!ok).then_some(name))
+        .collect()
+    }
+}
+
 /// Orchestrates all *market-level* pulses for a single STON.fi pool.
 ///
 /// Responsibilities:
 /// - Spread → structural friction (rolling)
 /// - Trend  → temporal risk (rolling)
 /// - Depth  → instantaneous capacity (on-demand)
+/// - Slippage → expected execution slippage (rolling, fed by quotes)
 ///
 /// Note:
 /// - Market validity is determined ONLY by spread + trend
-/// - Depth is advisory capacity, not a health signal
+/// - Depth is advisory capacity, not a health signal; a published view
+///   (`evaluate_all`) additionally requires some executable depth
+/// - Slippage is enforced per user by the gates, not here
 pub struct StonfiMarketService {
     spread: SpreadMonitor,
     trend: TrendMonitor,
     depth: DepthPulse,
+    slippage: SlippagePulse,
+
+    /// Pool snapshots further apart than this reset the rolling pulses
+    /// (`None` = never).
+    max_gap_ms: Option<u64>,
+    last_tick_ms: Option<u64>,
 }
 
 impl StonfiMarketService {
@@ -35,6 +74,46 @@ impl StonfiMarketService {
             spread: SpreadMonitor::new(window_size),
             trend: TrendMonitor::new(window_size, min_warmup_ms),
             depth: DepthPulse::new(max_slippage_bps),
+            slippage: SlippagePulse::new(window_size, min_warmup_ms),
+            max_gap_ms: None,
+            last_tick_ms: None,
+        }
+    }
+
+    /// Reset the rolling pulses when consecutive pool snapshots are more
+    /// than `max_gap_ms` apart (e.g. after a feed outage), so pre-gap
+    /// samples do not count towards the warm-up.
+    pub fn with_max_gap_ms(mut self, max_gap_ms: u64) -> Self {
+        self.max_gap_ms = Some(max_gap_ms);
+        self
+    }
+
+    /// Apply `warmup` to the rolling trend and slippage pulses.
+    pub fn with_warmup(mut self, warmup: PulseWarmup) -> Self {
+        self.trend = self.trend.with_warmup(warmup);
+        self.slippage = self.slippage.with_warmup(warmup);
+        self
+    }
+
+    /// Invalidate the market when the spread jumps more than `k_stddev`
+    /// standard deviations above its recent mean.
+    pub fn with_spread_volatility_band(mut self, k_stddev: f64) -> Self {
+        self.spread = self.spread.with_volatility_band(k_stddev);
+        self
+    }
+
+    /// Only report trend drops that clear `confirmation` (see `TrendMonitor`).
+    pub fn with_trend_confirmation(mut self, confirmation: TrendConfirmation) -> Self {
+        self.trend = self.trend.with_confirmation(confirmation);
+        self
+    }
+
+    /// Ingest an RFQ quote observed at `ts_ms` into the slippage pulse.
+    ///
+    /// Non-swap or malformed quotes are ignored.
+    pub fn observe_quote(&mut self, quote: &Quote, ts_ms: u64) {
+        if let Some(sample) = SlippageSample::from_quote(quote, ts_ms) {
+            self.slippage.update(sample);
         }
     }
 
@@ -42,24 +121,69 @@ impl StonfiMarketService {
     ///
     /// Called on every poll.
     pub fn tick(&mut self, snapshot: PoolSnapshot) -> MarketMetrics {
+        if let (Some(max_gap), Some(last)) = (self.max_gap_ms, self.last_tick_ms)
+            && snapshot.ts_ms.saturating_sub(last) > max_gap
+        {
+            self.reset();
+        }
+        self.last_tick_ms = Some(snapshot.ts_ms);
+
         self.spread.update(snapshot.clone());
         self.trend.update(snapshot.clone());
 
         let spread_state = self.spread.compute();
         let trend_state = self.trend.compute();
         let depth = self.depth.compute_with_snapshot(&snapshot);
+        let slippage = self.slippage.compute();
 
         MarketMetrics {
             ts_ms: snapshot.ts_ms,
             spread_bps: spread_state.spread_bps,
             trend_drop_bps: trend_state.trend_drop_bps,
             max_depth: depth.max_dx,
+            slippage_bps: slippage.validity.then_some(slippage.slippage_bps),
 
             // Market is valid ONLY if spread + trend are healthy
             validity: spread_state.validity && trend_state.validity,
         }
     }
 
+    /// Runs every pulse against `snapshot` and assembles the view published
+    /// to the scheduler and executor.
+    ///
+    /// Returns `None` ("not ready") unless spread, trend and depth are all
+    /// valid for this tick. Slippage is quote-driven and may lag behind the
+    /// pool feed, so while it warms up the view carries `slippage_bps: None`
+    /// instead of being withheld.
+    ///
+    /// All fields are derived from the same tick and share its `ts_ms`.
+    pub fn evaluate_all(&mut self, snapshot: PoolSnapshot) -> Option<MarketMetricsView> {
+        let metrics = self.tick(snapshot.clone());
+
+        if !self.health(&snapshot).is_ready() {
+            return None;
+        }
+
+        Some(MarketMetricsView {
+            ts_ms: metrics.ts_ms,
+            spread_bps: metrics.spread_bps,
+            trend_drop_bps: metrics.trend_drop_bps,
+            max_depth: metrics.max_depth,
+            slippage_bps: metrics.slippage_bps,
+        })
+    }
+
+    /// Current validity of every pulse, with depth evaluated at `snapshot`.
+    /// Does not ingest `snapshot`.
+    pub fn health(&self, snapshot: &PoolSnapshot) -> PulseHealth {
+        PulseHealth {
+            spread: self.spread.compute().validity,
+            trend: self.trend.compute().validity,
+            depth: self.depth.compute_with_snapshot(snapshot).validity,
+            slippage: self.slippage.compute().validity,
+        }
+    }
+
     /// Compute instantaneous market depth at a specific snapshot.
     ///
     /// This is used by:
@@ -71,11 +195,170 @@ impl StonfiMarketService {
         self.depth.compute_with_snapshot(snapshot)
     }
 
+    /// Drops every observed quote, e.g. once their feed has closed.
+    pub fn reset_slippage(&mut self) {
+        self.slippage.reset();
+    }
+
     /// Reset all internal rolling state.
     ///
     /// Used when switching pools or recovering from data gaps.
     pub fn reset(&mut self) {
         self.spread.reset();
         self.trend.reset();
+        self.slippage.reset();
+        self.last_tick_ms = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snap(r0: u128, r1: u128, ts_ms: u64) -> PoolSnapshot {
+        PoolSnapshot {
+            reserve0: r0,
+            reserve1: r1,
+            lp_fee: 20,
+            protocol_fee: 10,
+            ts_ms,
+        }
+    }
+
+    #[test]
+    fn evaluate_all_publishes_view_once_warm() {
+        let mut svc = StonfiMarketService::new(5, 1_000, 75.0);
+
+        // Trend still warming up.
+        assert!(svc.evaluate_all(snap(1_000_000, 1_000_000, 0)).is_none());
+
+        let view = svc
+            .evaluate_all(snap(1_000_000, 1_000_000, 2_000))
+            .expect("all pulses valid");
+        assert_eq!(view.ts_ms, 2_000);
+        assert!(view.max_depth > 0);
+        assert!(view.slippage_bps.is_none());
+    }
+
+    #[test]
+    fn observed_quotes_reach_view_after_warmup() {
+        use crate::market::pulses::slippage::tests::quote;
+
+        let mut svc = StonfiMarketService::new(5, 1_000, 75.0);
+        svc.evaluate_all(snap(1_000_000, 1_000_000, 0));
+
+        // One quote: slippage still warming up, the view leaves it unset.
+        svc.observe_quote(&quote(20, "10000", "9950"), 0);
+        let view = svc.evaluate_all(snap(1_000_000, 1_000_000, 2_000)).unwrap();
+        assert!(view.slippage_bps.is_none());
+
+        // Quotes spanning the warm-up: the worst estimate (50 bps gap) is published.
+        svc.observe_quote(&quote(20, "10000", "9990"), 1_500);
+        let view = svc.evaluate_all(snap(1_000_000, 1_000_000, 3_000)).unwrap();
+        assert_eq!(view.slippage_bps, Some(50.0));
+
+        // Non-swap quotes are ignored; reset drops the estimate.
+        svc.reset();
+        let mut q = quote(20, "10000", "9990");
+        q.params.swap = None;
+        svc.observe_quote(&q, 4_000);
+        svc.evaluate_all(snap(1_000_000, 1_000_000, 4_000));
+        let view = svc.evaluate_all(snap(1_000_000, 1_000_000, 6_000)).unwrap();
+        assert!(view.slippage_bps.is_none());
+    }
+
+    #[test]
+    fn health_reports_each_pulse_and_worst_case() {
+        let mut svc = StonfiMarketService::new(5, 1_000, 75.0);
+        let s0 = snap(1_000_000, 1_000_000, 0);
+
+        // Spread and depth are valid on the first snapshot; trend is warming
+        // up and no quote has been seen.
+        assert!(svc.evaluate_all(s0.clone()).is_none());
+        let h = svc.health(&s0);
+        assert_eq!(
+            h,
+            PulseHealth {
+                spread: true,
+                trend: false,
+                depth: true,
+                slippage: false,
+            }
+        );
+        assert!(!h.is_ready());
+        assert_eq!(h.not_ready(), vec!["trend"]);
+
+        // Trend warms up: ready although slippage is still unknown.
+        let s1 = snap(1_000_000, 1_000_000, 2_000);
+        assert!(svc.evaluate_all(s1.clone()).is_some());
+        let h = svc.health(&s1);
+        assert!(h.is_ready());
+        assert!(!h.slippage);
+        assert!(h.not_ready().is_empty());
+
+        // A drained pool invalidates depth even though spread and trend are fine.
+        let drained = snap(50, 1_000_000, 3_000);
+        assert!(!svc.health(&drained).depth);
+        assert!(!svc.health(&drained).is_ready());
+    }
+
+    #[test]
+    fn feed_gap_resets_pulses_into_warmup() {
+        use crate::market::pulses::slippage::tests::quote;
+
+        let mut svc = StonfiMarketService::new(5, 1_000, 75.0).with_max_gap_ms(5_000);
+        svc.observe_quote(&quote(20, "10000", "9990"), 0);
+        svc.observe_quote(&quote(20, "10000", "9990"), 1_000);
+        svc.evaluate_all(snap(1_000_000, 1_000_000, 0));
+        let view = svc.evaluate_all(snap(1_000_000, 1_000_000, 2_000)).unwrap();
+        assert!(view.slippage_bps.is_some());
+
+        // A gap within the limit keeps the windows.
+        assert!(
+            svc.evaluate_all(snap(1_000_000, 1_000_000, 7_000))
+                .is_some()
+        );
+
+        // A longer gap drops every pre-gap sample: trend warms up again and
+        // the slippage estimate is gone.
+        assert!(
+            svc.evaluate_all(snap(1_000_000, 1_000_000, 12_001))
+                .is_none()
+        );
+        assert!(!svc.health(&snap(1_000_000, 1_000_000, 12_001)).slippage);
+
+        let view = svc
+            .evaluate_all(snap(1_000_000, 1_000_000, 13_001))
+            .unwrap();
+        assert!(view.slippage_bps.is_none());
+    }
+
+    #[test]
+    fn without_max_gap_windows_survive_outages() {
+        let mut svc = StonfiMarketService::new(5, 1_000, 75.0);
+        svc.evaluate_all(snap(1_000_000, 1_000_000, 0));
+        assert!(
+            svc.evaluate_all(snap(1_000_000, 1_000_000, 2_000))
+                .is_some()
+        );
+        assert!(
+            svc.evaluate_all(snap(1_000_000, 1_000_000, 600_000))
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn evaluate_all_withholds_view_when_depth_invalid() {
+        // Fees alone exceed a zero slippage budget, so no depth is executable.
+        let mut svc = StonfiMarketService::new(5, 1_000, 0.0);
+
+        svc.tick(snap(1_000_000, 1_000_000, 0));
+        assert!(svc.tick(snap(1_000_000, 1_000_000, 2_000)).validity);
+        assert!(!svc.depth_at(&snap(1_000_000, 1_000_000, 3_000)).validity);
+
+        assert!(
+            svc.evaluate_all(snap(1_000_000, 1_000_000, 3_000))
+                .is_none()
+        );
     }
 }
@@ -4,48 +4,153 @@
 //! converts raw API data into a `PoolSnapshot`, feeds it
 //! into the `StonfiMarketService`, and publishes an
 //! immutable `MarketMetricsView` into the MarketViewStore.
+//!
+//! With an Omniston quote feed, the best quote of every update feeds the
+//! slippage pulse. While that feed is disconnected the pair's view is
+//! invalidated and nothing is published, so the gates fail closed instead
+//! of trusting a slippage estimate that is no longer refreshed. A feed that
+//! closes for good also clears the slippage window and is never published
+//! past again.
 
 use std::time::Duration;
 
 use anyhow::{Context, Result};
+use tokio::sync::mpsc;
 use tokio::time::{MissedTickBehavior, interval};
+use tokio_util::sync::CancellationToken;
 use tracing::{info, warn};
 
 use crate::market::market_view_store::MarketViewStore;
+use crate::market::omniston::parser::QuoteBook;
 use crate::market::stonfi::client::StonfiClient;
 use crate::market::stonfi::market_service::StonfiMarketService;
-use crate::market::types::MarketMetricsView;
-use crate::market::types::PoolSnapshot;
+use crate::market::types::{OmnistonEvent, PoolSnapshot};
+
+/// Where and how often a poller fetches its pool.
+pub struct PoolSource {
+    pub client: StonfiClient,
+    /// STON.fi pool address.
+    pub address: String,
+    pub every: Duration,
+}
+
+/// Omniston quote stream of the polled pair.
+pub struct QuoteFeed {
+    rx: mpsc::Receiver<OmnistonEvent>,
+    book: QuoteBook,
+    connected: bool,
+    closed: bool,
+}
+
+impl QuoteFeed {
+    /// Counts as connected until the client reports otherwise.
+    pub fn new(rx: mpsc::Receiver<OmnistonEvent>) -> Self {
+        Self {
+            rx,
+            book: QuoteBook::new(),
+            connected: true,
+            closed: false,
+        }
+    }
+
+    /// Marks the stream as ended: it stays disconnected and the quotes it
+    /// delivered are dropped from `market`'s slippage window.
+    fn close(&mut self, market: &mut StonfiMarketService) {
+        self.closed = true;
+        self.connected = false;
+        market.reset_slippage();
+    }
+
+    /// Folds `event` into the book and hands the best quote to `market`.
+    /// Returns true if the event reports a disconnect.
+    fn apply(
+        &mut self,
+        event: &OmnistonEvent,
+        market: &mut StonfiMarketService,
+        ts_ms: u64,
+    ) -> bool {
+        if self.book.apply(event) > 0
+            && let Some(best) = self.book.best_by_price()
+        {
+            market.observe_quote(best, ts_ms);
+        }
+
+        match event {
+            OmnistonEvent::ConnectionState { connected, .. } => {
+                let dropped = self.connected && !connected;
+                self.connected = *connected;
+                dropped
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Next event of `feed`, pending forever without an open one.
+async fn next_quote(feed: &mut Option<QuoteFeed>) -> Option<OmnistonEvent> {
+    match feed {
+        Some(feed) if !feed.closed => feed.rx.recv().await,
+        _ => std::future::pending().await,
+    }
+}
 
 /// Runs a market poller loop for a single STON.fi pool.
 ///
 /// Data flow:
 /// Pool → Poller → MarketService → MarketViewStore
+/// Omniston `quotes` (optional) → MarketService (slippage)
+///
+/// Returns `Ok(())` once `shutdown` is cancelled; cancellation is observed
+/// between polls, so a fetched snapshot is always published.
 pub async fn run_stonfi_market_poller(
     pair_id: String,
-    pool_address: String, // STON.fi pool address
-    poll_every: Duration,
-    client: StonfiClient,
+    pool: PoolSource,
     mut market: StonfiMarketService,
     store: MarketViewStore,
+    mut quotes: Option<QuoteFeed>,
+    shutdown: CancellationToken,
 ) -> Result<()> {
-    let mut ticker = interval(poll_every);
+    let mut ticker = interval(pool.every);
     ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
 
     info!(
         pair = %pair_id,
-        pool = %pool_address,
-        every_ms = poll_every.as_millis(),
+        pool = %pool.address,
+        every_ms = pool.every.as_millis(),
         "stonfi market poller started"
     );
 
     loop {
-        ticker.tick().await;
+        tokio::select! {
+            _ = ticker.tick() => {}
+            event = next_quote(&mut quotes) => {
+                match (event, quotes.as_mut()) {
+                    (Some(event), Some(feed)) => {
+                        if feed.apply(&event, &mut market, crate::time::now_ms()) {
+                            warn!(pair = %pair_id, "omniston feed disconnected — invalidating market view");
+                            store.invalidate(&pair_id).await;
+                        }
+                    }
+                    (None, Some(feed)) => {
+                        warn!(pair = %pair_id, "omniston feed closed — invalidating market view");
+                        feed.close(&mut market);
+                        store.invalidate(&pair_id).await;
+                    }
+                    (_, None) => {}
+                }
+                continue;
+            }
+            _ = shutdown.cancelled() => {
+                info!(pair = %pair_id, "stonfi market poller stopped");
+                return Ok(());
+            }
+        }
 
-        let resp = client
-            .fetch_pool(&pool_address)
+        let resp = pool
+            .client
+            .fetch_pool(&pool.address)
             .await
-            .with_context(|| format!("failed to fetch pool {}", pool_address))?;
+            .with_context(|| format!("failed to fetch pool {}", pool.address))?;
 
         let reserve0: u128 = resp.reserve0.parse().context("parse reserve0")?;
         let reserve1: u128 = resp.reserve1.parse().context("parse reserve1")?;
@@ -60,45 +165,107 @@ pub async fn run_stonfi_market_poller(
             ts_ms: crate::time::now_ms(),
         };
 
-        let metrics = market.tick(snapshot);
-
-        if !metrics.validity {
-            tracing::warn!(
+        let ts_ms = snapshot.ts_ms;
+        // Publish only when every required pulse is valid, so the scheduler
+        // never sees a partially warm snapshot.
+        let Some(view) = market.evaluate_all(snapshot.clone()) else {
+            warn!(
                 pool = %pair_id,
-                ts_ms = metrics.ts_ms,
-                spread_bps = metrics.spread_bps,
-                trend_drop_bps = metrics.trend_drop_bps,
-                valid = metrics.validity,
-                "market metrics invalid — skipping scheduling tick"
+                ts_ms,
+                not_ready = ?market.health(&snapshot).not_ready(),
+                "market pulses not ready — skipping publish"
             );
             continue;
+        };
+
+        if quotes.as_ref().is_some_and(|feed| !feed.connected) {
+            warn!(pair = %pair_id, ts_ms, "omniston feed down — skipping publish");
+            continue;
         }
 
-        let view = MarketMetricsView {
-            ts_ms: metrics.ts_ms,
-            spread_bps: metrics.spread_bps,
-            trend_drop_bps: metrics.trend_drop_bps,
-            max_depth: metrics.max_depth,
-        };
+        info!(
+            pair = %pair_id,
+            ts_ms = view.ts_ms,
+            spread_bps = view.spread_bps,
+            trend_drop_bps = view.trend_drop_bps,
+            "market metrics published"
+        );
 
-        store.set(&pair_id, view.clone()).await;
+        store.set(&pair_id, view).await;
+    }
+}
 
-        if metrics.validity {
-            info!(
-                pair = %pair_id,
-                ts_ms = view.ts_ms,
-                spread_bps = view.spread_bps,
-                trend_drop_bps = view.trend_drop_bps,
-                "market metrics published"
-            );
-        } else {
-            warn!(
-                pair = %pair_id,
-                ts_ms = view.ts_ms,
-                spread_bps = view.spread_bps,
-                trend_drop_bps = view.trend_drop_bps,
-                "market metrics invalid (gated)"
-            );
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::pulses::slippage::tests::quote;
+
+    fn snap(ts_ms: u64) -> PoolSnapshot {
+        PoolSnapshot {
+            reserve0: 1_000_000,
+            reserve1: 1_000_000,
+            lp_fee: 20,
+            protocol_fee: 10,
+            ts_ms,
+        }
+    }
+
+    fn state(connected: bool) -> OmnistonEvent {
+        OmnistonEvent::ConnectionState {
+            connected,
+            attempt: 0,
+            last_connected_ms: None,
         }
     }
+
+    #[test]
+    fn quote_feed_observes_best_quote_and_reports_disconnects() {
+        let (_tx, rx) = mpsc::channel(1);
+        let mut feed = QuoteFeed::new(rx);
+        let mut market = StonfiMarketService::new(5, 1_000, 75.0);
+        market.evaluate_all(snap(0));
+
+        // The best-priced quote (50 bps gap) is observed, not the other one.
+        let mut worse = quote(20, "9000", "8000");
+        worse.resolver_id = "r2".into();
+        let batch = OmnistonEvent::QuotesBatch(vec![quote(20, "10000", "9950"), worse]);
+        assert!(!feed.apply(&batch, &mut market, 0));
+        let best = OmnistonEvent::QuoteUpdated(Box::new(quote(20, "10000", "9950")));
+        assert!(!feed.apply(&best, &mut market, 1_500));
+        let view = market.evaluate_all(snap(3_000)).unwrap();
+        assert_eq!(view.slippage_bps, Some(50.0));
+
+        // A disconnect is reported once; reconnecting clears it.
+        assert!(feed.apply(&state(false), &mut market, 4_000));
+        assert!(!feed.connected);
+        assert!(!feed.apply(&state(false), &mut market, 4_500));
+        assert!(!feed.apply(&state(true), &mut market, 5_000));
+        assert!(feed.connected);
+    }
+
+    #[test]
+    fn closed_quote_feed_drops_slippage_and_stays_down() {
+        let (tx, rx) = mpsc::channel(1);
+        let mut feed = QuoteFeed::new(rx);
+        let mut market = StonfiMarketService::new(5, 1_000, 75.0);
+        market.evaluate_all(snap(0));
+
+        let best = OmnistonEvent::QuoteUpdated(Box::new(quote(20, "10000", "9950")));
+        feed.apply(&best, &mut market, 0);
+        feed.apply(&best, &mut market, 1_500);
+        assert!(
+            market
+                .evaluate_all(snap(3_000))
+                .unwrap()
+                .slippage_bps
+                .is_some()
+        );
+
+        drop(tx);
+        feed.close(&mut market);
+
+        assert!(!feed.connected);
+        let view = market.evaluate_all(snap(4_000)).unwrap();
+        assert_eq!(view.slippage_bps, None);
+    }
 }
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::error::RfqError;
+
 /// Snapshot of raw STON.fi pool state
 #[derive(Debug, Clone)]
 pub struct PoolSnapshot {
@@ -25,16 +27,34 @@ pub struct MarketMetrics {
 
     pub max_depth: u128,
 
+    /// Expected execution slippage from recent quotes.
+    /// `None` until the slippage pulse has warmed up.
+    pub slippage_bps: Option<f64>,
+
     /// Market is healthy enough to trade.
     pub validity: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RfqAmount {
     BidUnits(String),
     AskUnits(String),
 }
 
+/// Parses `bid:<units>` or `ask:<units>`. The units themselves are checked
+/// by `RfqRequest::validate`.
+impl std::str::FromStr for RfqAmount {
+    type Err = RfqError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().split_once(':') {
+            Some(("bid", units)) => Ok(RfqAmount::BidUnits(units.trim().to_string())),
+            Some(("ask", units)) => Ok(RfqAmount::AskUnits(units.trim().to_string())),
+            _ => Err(RfqError::InvalidAmount(s.to_string())),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct RfqRequest {
     pub bid_asset: String,
@@ -42,15 +62,57 @@ pub struct RfqRequest {
     pub amount: RfqAmount,
 }
 
+impl RfqRequest {
+    /// Checks that both assets look like TON addresses and that the amount
+    /// is a positive integer. `RfqAmount` already guarantees exactly one of
+    /// bid/ask units is set.
+    pub fn validate(&self) -> Result<(), RfqError> {
+        for (field, address) in [
+            ("bid_asset", &self.bid_asset),
+            ("ask_asset", &self.ask_asset),
+        ] {
+            let address = address.trim();
+            if address.is_empty() {
+                return Err(RfqError::EmptyAddress { field });
+            }
+            if !is_ton_address(address) {
+                return Err(RfqError::InvalidAddress {
+                    field,
+                    address: address.to_string(),
+                });
+            }
+        }
+
+        let (RfqAmount::BidUnits(units) | RfqAmount::AskUnits(units)) = &self.amount;
+        match units.trim().parse::<u128>() {
+            Ok(n) if n > 0 => Ok(()),
+            _ => Err(RfqError::InvalidAmount(units.clone())),
+        }
+    }
+}
+
+/// Raw (`0:<64 hex>`) or user-friendly (48 base64/base64url chars) form.
+/// The checksum of the friendly form is not verified.
+fn is_ton_address(s: &str) -> bool {
+    if let Some((workchain, hash)) = s.split_once(':') {
+        return workchain.parse::<i32>().is_ok()
+            && hash.len() == 64
+            && hash.bytes().all(|b| b.is_ascii_hexdigit());
+    }
+    s.len() == 48
+        && s.bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'+' | b'/'))
+}
+
 /// TON Jetton or native asset address used by Omniston.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AssetAddress {
     pub blockchain: u32,
     pub address: String,
 }
 
 /// Single protocol execution inside a swap step.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RouteChunk {
     pub protocol: String,
     pub bid_amount: String,
@@ -60,7 +122,7 @@ pub struct RouteChunk {
 }
 
 /// Swap route step (bid_asset → ask_asset).
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RouteStep {
     pub bid_asset_address: AssetAddress,
     pub ask_asset_address: AssetAddress,
@@ -68,13 +130,13 @@ pub struct RouteStep {
 }
 
 /// Full route (possibly multi-hop)
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Route {
     pub steps: Vec<RouteStep>,
 }
 
 /// Swap-specific parameters inside `"params.swap"`
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SwapParams {
     pub routes: Vec<Route>,
     pub min_ask_amount: String,
@@ -83,13 +145,13 @@ pub struct SwapParams {
 }
 
 /// Container for `params.swap`, `.escrow`, etc.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QuoteParams {
     pub swap: Option<SwapParams>,
 }
 
 /// Typed representation of `"quote_updated"`
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Quote {
     pub quote_id: String,
     pub resolver_id: String,
@@ -118,6 +180,16 @@ pub struct Quote {
     pub params: QuoteParams,
 }
 
+impl Quote {
+    /// Ask units received per bid unit. Higher is better for the taker
+    /// whichever side the RFQ fixed. `None` for unparsable or zero amounts.
+    pub fn ask_per_bid(&self) -> Option<f64> {
+        let bid: u128 = self.bid_units.parse().ok()?;
+        let ask: u128 = self.ask_units.parse().ok()?;
+        (bid > 0).then(|| ask as f64 / bid as f64)
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct QuoteUpdatedEvent {
     pub quote_updated: Quote,
@@ -126,11 +198,27 @@ pub struct QuoteUpdatedEvent {
 /// Unified Omniston event enum for your engine.
 #[derive(Debug, Clone)]
 pub enum OmnistonEvent {
-    Ack { rfq_id: String },
+    Ack {
+        rfq_id: String,
+    },
     QuoteUpdated(Box<Quote>),
+    /// Quotes from several resolvers in one payload, one per resolver.
+    QuotesBatch(Vec<Quote>),
     NoQuote,
     KeepAlive,
-    Unsubscribed { rfq_id: Option<String> },
+    Unsubscribed {
+        rfq_id: Option<String>,
+    },
+    /// Emitted by the WS client itself, not the server: on connect, on
+    /// disconnect and before each reconnect attempt. `attempt` counts
+    /// reconnect attempts since the last disconnect (0 = initial connection
+    /// or the disconnect itself). `last_connected_ms` is when the most
+    /// recent connection came up (`None` = never).
+    ConnectionState {
+        connected: bool,
+        attempt: u32,
+        last_connected_ms: Option<u64>,
+    },
     Unknown(serde_json::Value),
 }
 
@@ -178,6 +266,22 @@ pub struct MarketMetricsView {
     pub trend_drop_bps: f64,
 
     pub max_depth: u128,
+
+    /// Expected execution slippage (bps) from recent quotes, if known.
+    /// Gates skip the slippage check while this is `None`.
+    #[serde(default)]
+    pub slippage_bps: Option<f64>,
+}
+
+/// Default upper bound on snapshot age before gates treat it as missing.
+pub const DEFAULT_MAX_SNAPSHOT_AGE_MS: u64 = 15_000;
+
+impl MarketMetricsView {
+    /// True if the snapshot is at most `max_age_ms` old at `now_ms`.
+    /// Snapshots stamped in the future (clock skew) count as fresh.
+    pub fn is_fresh(&self, now_ms: u64, max_age_ms: u64) -> bool {
+        now_ms.saturating_sub(self.ts_ms) <= max_age_ms
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -185,3 +289,64 @@ pub enum ExecutionScope {
     MarketWide,
     ProtocolOnly { protocol: String },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rfq(bid_asset: &str, ask_asset: &str, amount: RfqAmount) -> RfqRequest {
+        RfqRequest {
+            bid_asset: bid_asset.into(),
+            ask_asset: ask_asset.into(),
+            amount,
+        }
+    }
+
+    const TON: &str = "EQAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAM9c";
+    const RAW: &str = "0:b113a994b5024a16719f69139328eb759596c38a25f59028b146fecdc3621dfe";
+
+    #[test]
+    fn rfq_validation_rejects_empty_and_malformed_addresses() {
+        let units = || RfqAmount::BidUnits("1000".into());
+
+        assert_eq!(rfq(TON, RAW, units()).validate(), Ok(()));
+        assert_eq!(
+            rfq("", RAW, units()).validate(),
+            Err(RfqError::EmptyAddress { field: "bid_asset" })
+        );
+        assert_eq!(
+            rfq(TON, "  ", units()).validate(),
+            Err(RfqError::EmptyAddress { field: "ask_asset" })
+        );
+        let bad_workchain = RAW.replace("0:", "x:");
+        for bad in ["EQ-ask", "0:abc", &bad_workchain, &format!("{TON}=")] {
+            assert!(
+                matches!(
+                    rfq(TON, bad, units()).validate(),
+                    Err(RfqError::InvalidAddress {
+                        field: "ask_asset",
+                        ..
+                    })
+                ),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn rfq_validation_requires_a_positive_integer_amount() {
+        for ok in [
+            RfqAmount::BidUnits("1".into()),
+            RfqAmount::AskUnits(" 500 ".into()),
+        ] {
+            assert_eq!(rfq(TON, RAW, ok).validate(), Ok(()));
+        }
+        for bad in ["0", "-5", "1.5", "", "abc"] {
+            assert_eq!(
+                rfq(TON, RAW, RfqAmount::AskUnits(bad.into())).validate(),
+                Err(RfqError::InvalidAmount(bad.into())),
+                "{bad}"
+            );
+        }
+    }
+}
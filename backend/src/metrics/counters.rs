@@ -1,5 +1,14 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
 use std::sync::Arc;
-use std::sync::atomic::AtomicU64;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::Serialize;
+
+use crate::execution::types::RecoveryReport;
+use crate::market::omniston::metrics::OmnistonMetrics;
+use crate::metrics::histogram::LatencyHistogram;
+use crate::session::cache::SessionCacheMetrics;
 
 /// Minimal counters for operational visibility.
 #[derive(Clone, Default)]
@@ -10,11 +19,419 @@ pub struct Counters {
     pub sched_empty: Arc<AtomicU64>,
     pub sched_skip_pending: Arc<AtomicU64>,
     pub sched_no_alloc: Arc<AtomicU64>,
+    /// Ticks skipped because the market snapshot was older than the max age.
+    pub sched_stale_market: Arc<AtomicU64>,
+    /// Ticks skipped because the pair's executor queue was full.
+    pub sched_backpressure: Arc<AtomicU64>,
+    /// Ticks skipped because the pair was paused by an operator.
+    pub sched_paused: Arc<AtomicU64>,
+    /// Dry-run ticks that produced a plan (nothing reserved).
+    pub sched_dry_run_plans: Arc<AtomicU64>,
 
     // skip reasons
     pub sched_skip_inactive: Arc<AtomicU64>,
     pub sched_skip_cooldown: Arc<AtomicU64>,
+    pub sched_skip_window: Arc<AtomicU64>,
+    /// Sessions skipped because their TWAP spacing had not elapsed.
+    pub sched_skip_twap: Arc<AtomicU64>,
     pub sched_skip_empty: Arc<AtomicU64>,
     pub sched_skip_constraints: Arc<AtomicU64>,
     pub sched_skip_deficit: Arc<AtomicU64>,
+
+    /// Sessions whose credit was boosted by the starvation watchdog.
+    pub sched_starvation_boosts: Arc<AtomicU64>,
+
+    // executor
+    /// Batches committed by executor workers (including skipped batches).
+    pub exec_batches_committed: Arc<AtomicU64>,
+    /// Chunks that executed successfully (or were simulated in safe mode).
+    pub exec_chunks_executed: Arc<AtomicU64>,
+    pub exec_chunks_failed: Arc<AtomicU64>,
+    pub exec_chunks_skipped: Arc<AtomicU64>,
+    /// Swap calls retried after a transient failure.
+    pub exec_swap_retries: Arc<AtomicU64>,
+    /// `commit_batch` attempts retried after a failure.
+    pub exec_commit_retries: Arc<AtomicU64>,
+
+    // recovery
+    /// RESERVED batches settled as COMMITTED by recovery.
+    pub recovery_batches_committed: Arc<AtomicU64>,
+    /// RESERVED batches aborted by recovery.
+    pub recovery_batches_aborted: Arc<AtomicU64>,
+    /// PENDING chunks unwound by recovery.
+    pub recovery_chunks_unwound: Arc<AtomicU64>,
+    /// Bid released from `in_flight_bid` by recovery.
+    pub recovery_bid_released: Arc<AtomicU64>,
+
+    /// Quote feed events by variant and reconnect attempts; hand a clone
+    /// to `OmnistonWsClient::with_metrics`.
+    pub omniston: OmnistonMetrics,
+
+    /// Session cache hits, misses, inserts, updates, evictions and idle
+    /// expirations; hand a
+    /// clone to `SessionStore::with_cache_metrics`.
+    pub session_cache: SessionCacheMetrics,
+
+    /// Committed chunk counts by pair, outcome and reason.
+    pub exec_chunk_outcomes: Arc<parking_lot::Mutex<BTreeMap<ChunkOutcomeKey, u64>>>,
+
+    // latency
+    /// Duration of `reserve_execution` calls made by the scheduler.
+    pub reserve_execution_latency: Arc<LatencyHistogram>,
+    /// Duration of each `commit_batch` attempt made by executor workers.
+    pub commit_batch_latency: Arc<LatencyHistogram>,
+    /// Duration of whole scheduler ticks.
+    pub sched_tick_latency: Arc<LatencyHistogram>,
+}
+
+/// Label set of `exec_chunk_outcomes`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ChunkOutcomeKey {
+    pub pair_id: String,
+    /// `success`, `simulated`, `failed` or `skipped`.
+    pub outcome: &'static str,
+    /// Normalized reason code; empty for successful chunks.
+    pub reason: String,
+}
+
+/// Point-in-time copy of every counter, for export.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct CountersSnapshot {
+    pub sched_batches: u64,
+    pub sched_selected: u64,
+    pub sched_empty: u64,
+    pub sched_skip_pending: u64,
+    pub sched_no_alloc: u64,
+    pub sched_stale_market: u64,
+    pub sched_backpressure: u64,
+    pub sched_paused: u64,
+    pub sched_dry_run_plans: u64,
+    pub sched_skip_inactive: u64,
+    pub sched_skip_cooldown: u64,
+    pub sched_skip_window: u64,
+    pub sched_skip_twap: u64,
+    pub sched_skip_empty: u64,
+    pub sched_skip_constraints: u64,
+    pub sched_skip_deficit: u64,
+    pub sched_starvation_boosts: u64,
+    pub exec_batches_committed: u64,
+    pub exec_chunks_executed: u64,
+    pub exec_chunks_failed: u64,
+    pub exec_chunks_skipped: u64,
+    pub exec_swap_retries: u64,
+    pub exec_commit_retries: u64,
+    pub recovery_batches_committed: u64,
+    pub recovery_batches_aborted: u64,
+    pub recovery_chunks_unwound: u64,
+    pub recovery_bid_released: u64,
+    pub omniston_quote_updated: u64,
+    pub omniston_quotes_batch: u64,
+    pub omniston_no_quote: u64,
+    pub omniston_keep_alive: u64,
+    pub omniston_ack: u64,
+    pub omniston_unsubscribed: u64,
+    pub omniston_unknown: u64,
+    pub omniston_reconnects: u64,
+    pub session_cache_hits: u64,
+    pub session_cache_misses: u64,
+    pub session_cache_inserts: u64,
+    pub session_cache_updates: u64,
+    pub session_cache_evictions: u64,
+    pub session_cache_expired: u64,
+}
+
+impl Counters {
+    /// Counts one committed chunk. `reason` is reduced to its code (the part
+    /// before `:`); free-form reasons collapse to `Other` to bound label
+    /// cardinality.
+    pub fn record_chunk_outcome(&self, pair_id: &str, outcome: &'static str, reason: &str) {
+        let key = ChunkOutcomeKey {
+            pair_id: pair_id.to_string(),
+            outcome,
+            reason: reason_code(reason),
+        };
+        *self.exec_chunk_outcomes.lock().entry(key).or_insert(0) += 1;
+    }
+
+    /// Adds a recovery pass to the `recovery_*` counters.
+    pub fn record_recovery(&self, report: &RecoveryReport) {
+        let add = |c: &AtomicU64, n: u64| c.fetch_add(n, Ordering::Relaxed);
+        add(
+            &self.recovery_batches_committed,
+            report.batches_committed as u64,
+        );
+        add(
+            &self.recovery_batches_aborted,
+            report.batches_aborted as u64,
+        );
+        add(&self.recovery_chunks_unwound, report.chunks_unwound as u64);
+        add(
+            &self.recovery_bid_released,
+            u64::try_from(report.bid_released).unwrap_or(u64::MAX),
+        );
+    }
+
+    fn scalars(&self) -> [(&'static str, &AtomicU64); 41] {
+        [
+            ("sched_batches", &self.sched_batches),
+            ("sched_selected", &self.sched_selected),
+            ("sched_empty", &self.sched_empty),
+            ("sched_skip_pending", &self.sched_skip_pending),
+            ("sched_no_alloc", &self.sched_no_alloc),
+            ("sched_stale_market", &self.sched_stale_market),
+            ("sched_backpressure", &self.sched_backpressure),
+            ("sched_paused", &self.sched_paused),
+            ("sched_dry_run_plans", &self.sched_dry_run_plans),
+            ("sched_skip_inactive", &self.sched_skip_inactive),
+            ("sched_skip_cooldown", &self.sched_skip_cooldown),
+            ("sched_skip_window", &self.sched_skip_window),
+            ("sched_skip_twap", &self.sched_skip_twap),
+            ("sched_skip_empty", &self.sched_skip_empty),
+            ("sched_skip_constraints", &self.sched_skip_constraints),
+            ("sched_skip_deficit", &self.sched_skip_deficit),
+            ("sched_starvation_boosts", &self.sched_starvation_boosts),
+            ("exec_batches_committed", &self.exec_batches_committed),
+            ("exec_chunks_executed", &self.exec_chunks_executed),
+            ("exec_chunks_failed", &self.exec_chunks_failed),
+            ("exec_chunks_skipped", &self.exec_chunks_skipped),
+            ("exec_swap_retries", &self.exec_swap_retries),
+            ("exec_commit_retries", &self.exec_commit_retries),
+            (
+                "recovery_batches_committed",
+                &self.recovery_batches_committed,
+            ),
+            ("recovery_batches_aborted", &self.recovery_batches_aborted),
+            ("recovery_chunks_unwound", &self.recovery_chunks_unwound),
+            ("recovery_bid_released", &self.recovery_bid_released),
+            ("omniston_quote_updated", &self.omniston.quote_updated),
+            ("omniston_quotes_batch", &self.omniston.quotes_batch),
+            ("omniston_no_quote", &self.omniston.no_quote),
+            ("omniston_keep_alive", &self.omniston.keep_alive),
+            ("omniston_ack", &self.omniston.ack),
+            ("omniston_unsubscribed", &self.omniston.unsubscribed),
+            ("omniston_unknown", &self.omniston.unknown),
+            ("omniston_reconnects", &self.omniston.reconnects),
+            ("session_cache_hits", &self.session_cache.hits),
+            ("session_cache_misses", &self.session_cache.misses),
+            ("session_cache_inserts", &self.session_cache.inserts),
+            ("session_cache_updates", &self.session_cache.updates),
+            ("session_cache_evictions", &self.session_cache.evictions),
+            ("session_cache_expired", &self.session_cache.expired),
+        ]
+    }
+
+    /// Appends every counter to `buf` in Prometheus text exposition format
+    /// (`kaskade_` prefix). Chunk outcomes are labelled by `pair_id`,
+    /// `outcome` and `reason`; latencies follow as histograms in ms.
+    pub fn encode_prometheus(&self, buf: &mut String) {
+        for (name, value) in self.scalars() {
+            let _ = writeln!(buf, "# TYPE kaskade_{name} counter");
+            let _ = writeln!(buf, "kaskade_{name} {}", value.load(Ordering::Relaxed));
+        }
+
+        let _ = writeln!(buf, "# TYPE kaskade_exec_chunk_outcomes counter");
+        for (k, n) in self.exec_chunk_outcomes.lock().iter() {
+            let _ = writeln!(
+                buf,
+                "kaskade_exec_chunk_outcomes{{pair_id=\"{}\",outcome=\"{}\",reason=\"{}\"}} {n}",
+                escape_label(&k.pair_id),
+                k.outcome,
+                escape_label(&k.reason),
+            );
+        }
+
+        self.reserve_execution_latency
+            .encode_prometheus("reserve_execution", buf);
+        self.commit_batch_latency
+            .encode_prometheus("commit_batch", buf);
+        self.sched_tick_latency.encode_prometheus("sched_tick", buf);
+    }
+
+    /// Loads every counter. Individual loads are relaxed, so the snapshot is
+    /// not atomic across counters.
+    pub fn snapshot(&self) -> CountersSnapshot {
+        let load = |c: &AtomicU64| c.load(Ordering::Relaxed);
+
+        CountersSnapshot {
+            sched_batches: load(&self.sched_batches),
+            sched_selected: load(&self.sched_selected),
+            sched_empty: load(&self.sched_empty),
+            sched_skip_pending: load(&self.sched_skip_pending),
+            sched_no_alloc: load(&self.sched_no_alloc),
+            sched_stale_market: load(&self.sched_stale_market),
+            sched_backpressure: load(&self.sched_backpressure),
+            sched_paused: load(&self.sched_paused),
+            sched_dry_run_plans: load(&self.sched_dry_run_plans),
+            sched_skip_inactive: load(&self.sched_skip_inactive),
+            sched_skip_cooldown: load(&self.sched_skip_cooldown),
+            sched_skip_window: load(&self.sched_skip_window),
+            sched_skip_twap: load(&self.sched_skip_twap),
+            sched_skip_empty: load(&self.sched_skip_empty),
+            sched_skip_constraints: load(&self.sched_skip_constraints),
+            sched_skip_deficit: load(&self.sched_skip_deficit),
+            sched_starvation_boosts: load(&self.sched_starvation_boosts),
+            exec_batches_committed: load(&self.exec_batches_committed),
+            exec_chunks_executed: load(&self.exec_chunks_executed),
+            exec_chunks_failed: load(&self.exec_chunks_failed),
+            exec_chunks_skipped: load(&self.exec_chunks_skipped),
+            exec_swap_retries: load(&self.exec_swap_retries),
+            exec_commit_retries: load(&self.exec_commit_retries),
+            recovery_batches_committed: load(&self.recovery_batches_committed),
+            recovery_batches_aborted: load(&self.recovery_batches_aborted),
+            recovery_chunks_unwound: load(&self.recovery_chunks_unwound),
+            recovery_bid_released: load(&self.recovery_bid_released),
+            omniston_quote_updated: load(&self.omniston.quote_updated),
+            omniston_quotes_batch: load(&self.omniston.quotes_batch),
+            omniston_no_quote: load(&self.omniston.no_quote),
+            omniston_keep_alive: load(&self.omniston.keep_alive),
+            omniston_ack: load(&self.omniston.ack),
+            omniston_unsubscribed: load(&self.omniston.unsubscribed),
+            omniston_unknown: load(&self.omniston.unknown),
+            omniston_reconnects: load(&self.omniston.reconnects),
+            session_cache_hits: load(&self.session_cache.hits),
+            session_cache_misses: load(&self.session_cache.misses),
+            session_cache_inserts: load(&self.session_cache.inserts),
+            session_cache_updates: load(&self.session_cache.updates),
+            session_cache_evictions: load(&self.session_cache.evictions),
+            session_cache_expired: load(&self.session_cache.expired),
+        }
+    }
+}
+
+/// `Rejected:42` -> `Rejected`; anything that is not a short identifier
+/// (free-form error text) -> `Other`.
+fn reason_code(reason: &str) -> String {
+    let code = reason.split(':').next().unwrap_or_default();
+    let is_code = code.len() <= 32
+        && code != "ERR"
+        && code.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    if is_code { code } else { "Other" }.to_string()
+}
+
+/// Escapes a Prometheus label value.
+fn escape_label(v: &str) -> String {
+    v.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_reflects_shared_counters() {
+        let counters = Counters::default();
+        let clone = counters.clone();
+
+        clone.sched_batches.fetch_add(2, Ordering::Relaxed);
+        clone.exec_chunks_failed.fetch_add(1, Ordering::Relaxed);
+
+        let snap = counters.snapshot();
+        assert_eq!(snap.sched_batches, 2);
+        assert_eq!(snap.exec_chunks_failed, 1);
+        assert_eq!(snap.exec_batches_committed, 0);
+    }
+
+    #[test]
+    fn prometheus_output_has_a_sample_for_every_counter() {
+        let counters = Counters::default();
+        counters.exec_swap_retries.fetch_add(3, Ordering::Relaxed);
+
+        let mut out = String::new();
+        counters.encode_prometheus(&mut out);
+
+        let snapshot = serde_json::to_value(counters.snapshot()).unwrap();
+        let fields = snapshot.as_object().unwrap();
+        assert_eq!(fields.len(), counters.scalars().len());
+        for (name, value) in fields {
+            let line = format!("kaskade_{name} {value}\n");
+            assert!(out.contains(&line), "missing {line:?}");
+        }
+        assert!(out.contains("kaskade_exec_swap_retries 3\n"));
+    }
+
+    #[test]
+    fn reason_codes_are_bounded() {
+        assert_eq!(reason_code("Timeout"), "Timeout");
+        assert_eq!(reason_code("Rejected:42"), "Rejected");
+        assert_eq!(reason_code("GATE_B_CONSTRAINTS"), "GATE_B_CONSTRAINTS");
+        assert_eq!(reason_code(""), "");
+        assert_eq!(reason_code("rpc down"), "Other");
+        assert_eq!(reason_code("ERR:xxxx"), "Other");
+    }
+
+    fn is_metric_name(s: &str) -> bool {
+        let mut chars = s.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == ':')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+    }
+
+    #[test]
+    fn prometheus_output_is_well_formed() {
+        let counters = Counters::default();
+        counters.sched_batches.fetch_add(4, Ordering::Relaxed);
+        counters.record_chunk_outcome("TON/STON", "success", "");
+        counters.record_chunk_outcome("TON/STON", "failed", "Timeout");
+        counters.record_chunk_outcome("TON/STON", "failed", "Timeout");
+        counters.record_chunk_outcome("TON/\"X\"", "failed", "rpc down");
+        counters
+            .sched_tick_latency
+            .record(std::time::Duration::from_millis(7));
+
+        let mut out = String::new();
+        counters.encode_prometheus(&mut out);
+
+        let mut typed = std::collections::HashMap::new();
+        for line in out.lines() {
+            if let Some(rest) = line.strip_prefix("# TYPE ") {
+                let (name, kind) = rest.split_once(' ').unwrap();
+                assert!(is_metric_name(name), "bad name in {line}");
+                assert!(["counter", "histogram", "gauge"].contains(&kind), "{line}");
+                assert!(
+                    typed.insert(name.to_string(), kind).is_none(),
+                    "duplicate TYPE: {line}"
+                );
+                continue;
+            }
+
+            let (series, value) = line.rsplit_once(' ').unwrap();
+            value.parse::<f64>().expect("numeric sample value");
+
+            let (name, labels) = match series.split_once('{') {
+                Some((name, labels)) => (name, labels.strip_suffix('}').expect("closed label set")),
+                None => (series, ""),
+            };
+            assert!(is_metric_name(name), "bad name in {line}");
+
+            let family = ["_bucket", "_sum", "_count"]
+                .iter()
+                .find_map(|s| {
+                    let base = name.strip_suffix(s)?;
+                    (typed.get(base) == Some(&"histogram")).then_some(base)
+                })
+                .unwrap_or(name);
+            let kind = typed
+                .get(family)
+                .unwrap_or_else(|| panic!("sample before TYPE: {line}"));
+            match (*kind, name.ends_with("_bucket")) {
+                ("histogram", true) => assert!(labels.starts_with("le=\""), "{line}"),
+                ("gauge", _) => assert!(labels.starts_with("quantile=\""), "{line}"),
+                _ if !labels.is_empty() => {
+                    assert!(labels.contains("pair_id=\""), "missing pair_id: {line}")
+                }
+                _ => {}
+            }
+        }
+
+        assert!(out.contains("kaskade_sched_batches 4\n"));
+        assert!(out.contains(
+            "kaskade_exec_chunk_outcomes{pair_id=\"TON/STON\",outcome=\"failed\",reason=\"Timeout\"} 2\n"
+        ));
+        assert!(out.contains(r#"pair_id="TON/\"X\"",outcome="failed",reason="Other""#));
+        assert!(out.contains("kaskade_commit_batch_ms_count 0\n"));
+    }
 }
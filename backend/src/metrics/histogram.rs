@@ -0,0 +1,183 @@
+//! Fixed-bucket latency histogram backed by atomics.
+//!
+//! Cheap enough to record on every DB call and tick. Quantiles are read back
+//! at bucket resolution: `quantile` reports the upper bound of the bucket
+//! holding the requested rank, as Prometheus' `histogram_quantile` would
+//! without interpolation.
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Upper bucket bounds in ms; a final `+Inf` bucket catches the rest.
+pub const LATENCY_BUCKETS_MS: [u64; 12] = [1, 2, 5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000];
+
+/// Quantiles exported next to the buckets.
+const EXPORTED_QUANTILES: [(f64, &str); 3] = [(0.5, "0.5"), (0.95, "0.95"), (0.99, "0.99")];
+
+#[derive(Debug, Default)]
+pub struct LatencyHistogram {
+    /// Non-cumulative counts; index `LATENCY_BUCKETS_MS.len()` is `+Inf`.
+    buckets: [AtomicU64; LATENCY_BUCKETS_MS.len() + 1],
+    sum_us: AtomicU64,
+}
+
+impl LatencyHistogram {
+    pub fn record(&self, elapsed: Duration) {
+        let ms = elapsed.as_secs_f64() * 1_000.0;
+        let idx = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|&le| ms <= le as f64)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+
+        self.buckets[idx].fetch_add(1, Ordering::Relaxed);
+        self.sum_us.fetch_add(
+            u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX),
+            Ordering::Relaxed,
+        );
+    }
+
+    /// Per-bucket (non-cumulative) counts; the last entry is `+Inf`.
+    pub fn bucket_counts(&self) -> Vec<u64> {
+        self.buckets
+            .iter()
+            .map(|b| b.load(Ordering::Relaxed))
+            .collect()
+    }
+
+    pub fn count(&self) -> u64 {
+        self.bucket_counts().iter().sum()
+    }
+
+    /// Upper bound of the bucket containing the `q`-quantile (`q` in
+    /// `[0, 1]`). `None` if nothing was recorded or the rank falls in the
+    /// `+Inf` bucket.
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        let counts = self.bucket_counts();
+        let total: u64 = counts.iter().sum();
+        if total == 0 {
+            return None;
+        }
+
+        let rank = ((q.clamp(0.0, 1.0) * total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, n) in counts.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                return LATENCY_BUCKETS_MS
+                    .get(i)
+                    .map(|&le| Duration::from_millis(le));
+            }
+        }
+        None
+    }
+
+    /// Appends `kaskade_{name}_ms` as a Prometheus histogram, followed by its
+    /// p50/p95/p99 as the `kaskade_{name}_ms_quantile` gauge.
+    pub fn encode_prometheus(&self, name: &str, buf: &mut String) {
+        let counts = self.bucket_counts();
+
+        let _ = writeln!(buf, "# TYPE kaskade_{name}_ms histogram");
+        let mut cumulative = 0;
+        for (i, n) in counts.iter().enumerate() {
+            cumulative += n;
+            match LATENCY_BUCKETS_MS.get(i) {
+                Some(le) => {
+                    let _ = writeln!(buf, "kaskade_{name}_ms_bucket{{le=\"{le}\"}} {cumulative}");
+                }
+                None => {
+                    let _ = writeln!(buf, "kaskade_{name}_ms_bucket{{le=\"+Inf\"}} {cumulative}");
+                }
+            }
+        }
+        let sum_ms = self.sum_us.load(Ordering::Relaxed) as f64 / 1_000.0;
+        let _ = writeln!(buf, "kaskade_{name}_ms_sum {sum_ms}");
+        let _ = writeln!(buf, "kaskade_{name}_ms_count {cumulative}");
+
+        let _ = writeln!(buf, "# TYPE kaskade_{name}_ms_quantile gauge");
+        for (q, label) in EXPORTED_QUANTILES {
+            if let Some(v) = self.quantile(q) {
+                let _ = writeln!(
+                    buf,
+                    "kaskade_{name}_ms_quantile{{quantile=\"{label}\"}} {}",
+                    v.as_millis()
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn known_durations_land_in_expected_buckets() {
+        let h = LatencyHistogram::default();
+        for d in [
+            Duration::from_micros(300),
+            ms(1),
+            ms(3),
+            ms(7),
+            ms(7),
+            ms(80),
+            ms(100),
+            ms(101),
+            ms(60_000),
+        ] {
+            h.record(d);
+        }
+
+        // le: 1 2 5 10 25 50 100 250 500 1000 2500 5000 +Inf
+        assert_eq!(
+            h.bucket_counts(),
+            vec![2, 0, 1, 2, 0, 0, 2, 1, 0, 0, 0, 0, 1]
+        );
+        assert_eq!(h.count(), 9);
+    }
+
+    #[test]
+    fn quantiles_report_bucket_upper_bounds() {
+        let h = LatencyHistogram::default();
+        assert_eq!(h.quantile(0.5), None);
+
+        for _ in 0..90 {
+            h.record(ms(4));
+        }
+        for _ in 0..9 {
+            h.record(ms(40));
+        }
+        h.record(ms(400));
+
+        assert_eq!(h.quantile(0.5), Some(ms(5)));
+        assert_eq!(h.quantile(0.95), Some(ms(50)));
+        assert_eq!(h.quantile(0.99), Some(ms(50)));
+        assert_eq!(h.quantile(1.0), Some(ms(500)));
+
+        h.record(ms(10_000));
+        assert_eq!(h.quantile(1.0), None);
+    }
+
+    #[test]
+    fn prometheus_buckets_are_cumulative() {
+        let h = LatencyHistogram::default();
+        h.record(ms(3));
+        h.record(ms(30));
+
+        let mut out = String::new();
+        h.encode_prometheus("commit_batch", &mut out);
+
+        assert!(out.contains("# TYPE kaskade_commit_batch_ms histogram\n"));
+        assert!(out.contains("kaskade_commit_batch_ms_bucket{le=\"5\"} 1\n"));
+        assert!(out.contains("kaskade_commit_batch_ms_bucket{le=\"50\"} 2\n"));
+        assert!(out.contains("kaskade_commit_batch_ms_bucket{le=\"+Inf\"} 2\n"));
+        assert!(out.contains("kaskade_commit_batch_ms_sum 33\n"));
+        assert!(out.contains("kaskade_commit_batch_ms_count 2\n"));
+        assert!(out.contains("kaskade_commit_batch_ms_quantile{quantile=\"0.5\"} 5\n"));
+        assert!(out.contains("kaskade_commit_batch_ms_quantile{quantile=\"0.99\"} 50\n"));
+    }
+}
@@ -0,0 +1,283 @@
+//! Minimal HTTP endpoint for runtime counters and per-pair controls.
+//!
+//! - `GET /metrics` returns a JSON `CountersSnapshot`
+//!   (`curl localhost:PORT/metrics`).
+//! - `GET /metrics/prometheus` returns Prometheus text exposition format.
+//! - `GET /scheduler/trace` returns each pair's latest per-candidate
+//!   decision trace (empty unless `SCHEDULER_TRACE` is enabled).
+//! - `GET /control/pairs` lists paused pairs.
+//! - `POST /control/pause?pair=TON/USDT` and `POST /control/resume?pair=...`
+//!   stop and restart scheduling of one pair (`curl -X POST ...`).
+//!
+//! The control routes are unauthenticated; keep `METRICS_ADDR` on a private
+//! interface. Every other request is answered with 404.
+
+use std::convert::Infallible;
+
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode, header};
+use hyper_util::rt::TokioIo;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::metrics::counters::Counters;
+use crate::scheduler::control::PairControl;
+use crate::scheduler::trace::SchedulerTrace;
+
+/// Serves `/metrics`, `/scheduler` and `/control` on `listener` until
+/// `shutdown` is cancelled.
+///
+/// Accept errors are logged and do not stop the server.
+pub async fn serve(
+    listener: TcpListener,
+    counters: Counters,
+    control: PairControl,
+    trace: SchedulerTrace,
+    shutdown: CancellationToken,
+) {
+    if let Ok(addr) = listener.local_addr() {
+        info!(%addr, "metrics endpoint listening");
+    }
+
+    loop {
+        let stream = tokio::select! {
+            res = listener.accept() => match res {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!(error = %e, "metrics accept failed");
+                    continue;
+                }
+            },
+            _ = shutdown.cancelled() => break,
+        };
+
+        let counters = counters.clone();
+        let control = control.clone();
+        let trace = trace.clone();
+        tokio::spawn(async move {
+            let svc = service_fn(move |req| {
+                let res = respond(&req, &counters, &control, &trace);
+                async move { Ok::<_, Infallible>(res) }
+            });
+
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), svc)
+                .await
+            {
+                debug!(error = %e, "metrics connection closed with error");
+            }
+        });
+    }
+
+    info!("metrics endpoint stopped");
+}
+
+fn respond(
+    req: &Request<Incoming>,
+    counters: &Counters,
+    control: &PairControl,
+    trace: &SchedulerTrace,
+) -> Response<Full<Bytes>> {
+    let (content_type, body) = match (req.method(), req.uri().path()) {
+        (&Method::GET, "/metrics") => (
+            "application/json",
+            serde_json::to_vec(&counters.snapshot()).expect("snapshot serializes"),
+        ),
+        (&Method::GET, "/metrics/prometheus") => {
+            let mut buf = String::new();
+            counters.encode_prometheus(&mut buf);
+            ("text/plain; version=0.0.4", buf.into_bytes())
+        }
+        (&Method::GET, "/scheduler/trace") => (
+            "application/json",
+            serde_json::to_vec(&trace.snapshot()).expect("trace serializes"),
+        ),
+        (&Method::GET, "/control/pairs") => (
+            "application/json",
+            serde_json::to_vec(&serde_json::json!({ "paused": control.paused() }))
+                .expect("pair list serializes"),
+        ),
+        (&Method::POST, path @ ("/control/pause" | "/control/resume")) => {
+            let Some(pair_id) = pair_param(req) else {
+                return status(StatusCode::BAD_REQUEST);
+            };
+            let pause = path == "/control/pause";
+            let changed = if pause {
+                control.pause(&pair_id)
+            } else {
+                control.resume(&pair_id)
+            };
+            if changed {
+                warn!(pair_id = %pair_id, paused = pause, "pair scheduling switched by operator");
+            }
+            (
+                "application/json",
+                serde_json::to_vec(&serde_json::json!({
+                    "pair": pair_id,
+                    "paused": pause,
+                    "changed": changed,
+                }))
+                .expect("control response serializes"),
+            )
+        }
+        _ => return status(StatusCode::NOT_FOUND),
+    };
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .body(Full::new(Bytes::from(body)))
+        .expect("static response is valid")
+}
+
+fn status(code: StatusCode) -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(code)
+        .body(Full::new(Bytes::new()))
+        .expect("static response is valid")
+}
+
+/// Non-empty, percent-decoded `pair` query parameter.
+fn pair_param(req: &Request<Incoming>) -> Option<String> {
+    let url = reqwest::Url::parse(&format!("http://localhost{}", req.uri())).ok()?;
+    url.query_pairs()
+        .find(|(k, _)| k == "pair")
+        .map(|(_, v)| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::Ordering;
+
+    #[tokio::test]
+    async fn metrics_endpoint_serves_live_counters() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let counters = Counters::default();
+        let shutdown = CancellationToken::new();
+        let server = tokio::spawn(serve(
+            listener,
+            counters.clone(),
+            PairControl::new(),
+            SchedulerTrace::new(),
+            shutdown.clone(),
+        ));
+
+        counters.sched_batches.fetch_add(3, Ordering::Relaxed);
+        counters
+            .exec_chunks_executed
+            .fetch_add(7, Ordering::Relaxed);
+
+        let client = reqwest::Client::new();
+        let body: serde_json::Value = client
+            .get(format!("http://{addr}/metrics"))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(body["sched_batches"], 3);
+        assert_eq!(body["exec_chunks_executed"], 7);
+
+        let prom = client
+            .get(format!("http://{addr}/metrics/prometheus"))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert!(prom.contains("kaskade_sched_batches 3\n"));
+
+        let trace: serde_json::Value = client
+            .get(format!("http://{addr}/scheduler/trace"))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(trace, serde_json::json!({}));
+
+        let missing = client
+            .get(format!("http://{addr}/nope"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), reqwest::StatusCode::NOT_FOUND);
+
+        shutdown.cancel();
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn control_endpoint_pauses_and_resumes_pairs() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let control = PairControl::new();
+        let shutdown = CancellationToken::new();
+        let server = tokio::spawn(serve(
+            listener,
+            Counters::default(),
+            control.clone(),
+            SchedulerTrace::new(),
+            shutdown.clone(),
+        ));
+
+        let client = reqwest::Client::new();
+        let post = |action: &str, query: &str| {
+            client
+                .post(format!("http://{addr}/control/{action}{query}"))
+                .send()
+        };
+
+        let body: serde_json::Value = post("pause", "?pair=TON%2FUSDT")
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(body["changed"], true);
+        assert!(control.is_paused("TON/USDT"));
+
+        let listed: serde_json::Value = client
+            .get(format!("http://{addr}/control/pairs"))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(listed["paused"], serde_json::json!(["TON/USDT"]));
+
+        let body: serde_json::Value = post("resume", "?pair=TON/USDT")
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(body["paused"], false);
+        assert!(!control.is_paused("TON/USDT"));
+
+        let missing = post("pause", "").await.unwrap();
+        assert_eq!(missing.status(), reqwest::StatusCode::BAD_REQUEST);
+        let wrong_method = client
+            .get(format!("http://{addr}/control/pause?pair=TON/USDT"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(wrong_method.status(), reqwest::StatusCode::NOT_FOUND);
+        assert!(control.paused().is_empty());
+
+        shutdown.cancel();
+        server.await.unwrap();
+    }
+}
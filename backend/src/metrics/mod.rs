@@ -1 +1,3 @@
 pub mod counters;
+pub mod histogram;
+pub mod http;
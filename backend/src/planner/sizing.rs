@@ -1,7 +1,10 @@
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
 use tracing::{Span, debug, field, instrument};
 
 use crate::market::types::MarketMetricsView;
-use crate::planner::types::{PlannedAllocation, SizingPolicy, UserIntent};
+use crate::planner::types::{AllocationMode, PlannedAllocation, SizingPolicy, UserIntent};
 
 /// Convert scheduler intents into concrete, bounded per-user allocations for the current tick.
 ///
@@ -12,8 +15,10 @@ use crate::planner::types::{PlannedAllocation, SizingPolicy, UserIntent};
 /// - chunk splitting within [min_chunk_bid, max_chunk_bid]
 ///
 /// Notes:
-/// - `desired_chunks` is treated as a hint and is currently ignored.
-/// - Order of `intents` matters (first-fit into the remaining global budget).
+/// - `desired_chunks > 0` requests that many roughly equal chunks; the hint is
+///   dropped (greedy max-size split) when no such split fits the bounds.
+/// - Under `AllocationMode::FirstFit` the order of `intents` matters (first-fit
+///   into the remaining global budget); `Proportional` shares it by demand.
 #[instrument(
     target = "planner",
     skip(intents),
@@ -56,11 +61,36 @@ pub fn derive_execution_plan(
         return vec![];
     }
 
+    let out = match policy.allocation_mode {
+        AllocationMode::FirstFit => plan_first_fit(intents, policy, &mut remaining_budget),
+        AllocationMode::Proportional => plan_proportional(intents, policy, &mut remaining_budget),
+    };
+
+    let total_allocated = total_cap.saturating_sub(remaining_budget);
+
+    // Update span metadata for structured logging
+    Span::current().record("total_bid_allocated", total_allocated);
+
+    debug!(
+        allocations_count = out.len(),
+        total_allocated, "execution plan derived"
+    );
+
+    out
+}
+
+/// First-fit: serve intents in order, each taking as much of the remaining
+/// budget as its caps allow.
+fn plan_first_fit(
+    intents: &[UserIntent],
+    policy: &SizingPolicy,
+    remaining_budget: &mut u128,
+) -> Vec<PlannedAllocation> {
     let mut out = Vec::new();
 
     for u in intents {
         // Stop early once remaining budget cannot produce a valid chunk.
-        if remaining_budget < policy.min_chunk_bid {
+        if *remaining_budget < policy.min_chunk_bid {
             debug!("global budget exhausted for this tick");
             break;
         }
@@ -77,48 +107,117 @@ pub fn derive_execution_plan(
         // Cap by per-user limit and remaining global budget.
         let allow = want
             .min(policy.max_bid_per_user_per_tick)
-            .min(remaining_budget);
+            .min(*remaining_budget);
 
-        // Skip if allocation can't produce at least one valid chunk.
-        if allow < policy.min_chunk_bid {
-            debug!(
-                session_id = %u.session_id,
-                allow,
-                "skipping intent: allowance below min_chunk_bid after capping"
-            );
-            continue;
+        if let Some(a) = allocate(u, allow, policy) {
+            // Consume global budget by the actual allocated amount.
+            *remaining_budget = remaining_budget.saturating_sub(a.total_bid);
+            out.push(a);
         }
+    }
+
+    out
+}
+
+/// Proportional: when capped demand exceeds the budget, every intent gets
+/// `budget * demand_i / total_demand` (floored), so no single user can take
+/// the whole tick. Budget freed by shares below `min_chunk_bid` is not
+/// redistributed.
+fn plan_proportional(
+    intents: &[UserIntent],
+    policy: &SizingPolicy,
+    remaining_budget: &mut u128,
+) -> Vec<PlannedAllocation> {
+    let demands: Vec<u128> = intents
+        .iter()
+        .map(|u| {
+            if u.desired_bid < policy.min_chunk_bid {
+                0
+            } else {
+                u.desired_bid.min(policy.max_bid_per_user_per_tick)
+            }
+        })
+        .collect();
+
+    let total_demand = demands.iter().fold(0u128, |acc, d| acc.saturating_add(*d));
+    let budget = *remaining_budget;
 
-        // Split into safe atomic chunks; any remainder < min_chunk is dropped.
-        let chunks = split_into_chunks(allow, policy.max_chunk_bid, policy.min_chunk_bid);
+    let mut out = Vec::new();
 
-        if chunks.is_empty() {
+    for (u, &demand) in intents.iter().zip(&demands) {
+        if demand == 0 {
+            debug!(session_id = %u.session_id, "skipping intent: below min_chunk_bid");
             continue;
         }
 
-        let sum: u128 = chunks.iter().copied().sum();
+        let share = if total_demand <= budget {
+            demand
+        } else {
+            mul_div_floor(demand, budget, total_demand)
+        }
+        .min(*remaining_budget);
+
+        if let Some(a) = allocate(u, share, policy) {
+            *remaining_budget = remaining_budget.saturating_sub(a.total_bid);
+            out.push(a);
+        }
+    }
 
-        out.push(PlannedAllocation {
-            session_id: u.session_id,
-            total_bid: sum,
-            chunks,
-        });
+    out
+}
 
-        // Consume global budget by the actual allocated amount.
-        remaining_budget = remaining_budget.saturating_sub(sum);
+/// Turns one user's allowance into chunks, honouring the `desired_chunks` hint.
+fn allocate(u: &UserIntent, allow: u128, policy: &SizingPolicy) -> Option<PlannedAllocation> {
+    // Skip if allocation can't produce at least one valid chunk.
+    if allow < policy.min_chunk_bid {
+        debug!(
+            session_id = %u.session_id,
+            allow,
+            "skipping intent: allowance below min_chunk_bid after capping"
+        );
+        return None;
     }
 
-    let total_allocated = total_cap.saturating_sub(remaining_budget);
+    // Split into safe atomic chunks; any remainder < min_chunk is dropped.
+    let chunks = (u.desired_chunks > 0)
+        .then(|| {
+            even_split(
+                allow,
+                u.desired_chunks,
+                policy.min_chunk_bid,
+                policy.max_chunk_bid,
+            )
+        })
+        .flatten()
+        .unwrap_or_else(|| split_into_chunks(allow, policy.max_chunk_bid, policy.min_chunk_bid));
+
+    if chunks.is_empty() {
+        return None;
+    }
 
-    // Update span metadata for structured logging
-    Span::current().record("total_bid_allocated", total_allocated);
+    Some(PlannedAllocation {
+        session_id: u.session_id,
+        total_bid: chunks.iter().copied().sum(),
+        chunks,
+    })
+}
 
-    debug!(
-        allocations_count = out.len(),
-        total_allocated, "execution plan derived"
-    );
+/// Deterministically shuffles `intents` from `seed`.
+///
+/// First-fit gives the head of the slice the full budget; shuffling before
+/// planning spreads that advantage across users. The same seed always yields
+/// the same order, so callers can seed from the tick time and stay reproducible.
+pub fn shuffle_intents(intents: &mut [UserIntent], seed: u64) {
+    intents.shuffle(&mut StdRng::seed_from_u64(seed));
+}
 
-    out
+/// `floor(a * b / d)` without overflowing for the ranges used here.
+/// Assumes `a <= d`, so the result never exceeds `b`.
+fn mul_div_floor(a: u128, b: u128, d: u128) -> u128 {
+    match a.checked_mul(b) {
+        Some(p) => p / d,
+        None => ((a as f64 / d as f64) * b as f64).floor().min(b as f64) as u128,
+    }
 }
 
 /// Split `total` into chunks within [min_chunk, max_chunk].
@@ -140,6 +239,31 @@ fn split_into_chunks(total: u128, max_chunk: u128, min_chunk: u128) -> Vec<u128>
     out
 }
 
+/// Split `total` into exactly `target_chunks` near-equal chunks within [min, max].
+///
+/// Chunk sizes differ by at most 1 and sum to `total`. Returns `None` when no
+/// such split exists, i.e. `target_chunks * min > total` or
+/// `target_chunks * max < total`.
+fn even_split(total: u128, target_chunks: u32, min: u128, max: u128) -> Option<Vec<u128>> {
+    if target_chunks == 0 || min > max {
+        return None;
+    }
+
+    let n = target_chunks as u128;
+    if n.checked_mul(min)? > total || n.saturating_mul(max) < total {
+        return None;
+    }
+
+    let base = total / n;
+    let extra = total % n;
+
+    Some(
+        (0..n)
+            .map(|i| if i < extra { base + 1 } else { base })
+            .collect(),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -151,6 +275,7 @@ mod tests {
             spread_bps: 0.0,
             trend_drop_bps: 0.0,
             max_depth,
+            slippage_bps: None,
         }
     }
 
@@ -167,6 +292,7 @@ mod tests {
             max_bid_per_user_per_tick: per_user_max,
             max_chunk_bid: max_chunk,
             min_chunk_bid: min_chunk,
+            allocation_mode: AllocationMode::FirstFit,
         }
     }
 
@@ -309,6 +435,172 @@ mod tests {
         );
     }
 
+    fn intent_with_chunks(bid: u128, chunks: u32) -> UserIntent {
+        UserIntent {
+            desired_chunks: chunks,
+            ..intent(bid)
+        }
+    }
+
+    #[test]
+    fn desired_chunks_splits_evenly() {
+        let market = market_with_depth(1_000_000);
+        let p = policy(1_000_000, 1.0, 1_000_000, 100_000, 10_000);
+
+        let out = derive_execution_plan(&market, &[intent_with_chunks(100_001, 4)], &p);
+        assert_eq!(out[0].chunks, vec![25_001, 25_000, 25_000, 25_000]);
+        assert_eq!(out[0].total_bid, 100_001);
+    }
+
+    #[test]
+    fn infeasible_desired_chunks_falls_back_to_greedy() {
+        let market = market_with_depth(1_000_000);
+        let p = policy(1_000_000, 1.0, 1_000_000, 100_000, 10_000);
+
+        // 20 chunks of >= 10k do not fit in 150k.
+        let out = derive_execution_plan(&market, &[intent_with_chunks(150_000, 20)], &p);
+        assert_eq!(out[0].chunks, vec![100_000, 50_000]);
+
+        // 1 chunk of <= 100k cannot hold 150k.
+        let out = derive_execution_plan(&market, &[intent_with_chunks(150_000, 1)], &p);
+        assert_eq!(out[0].chunks, vec![100_000, 50_000]);
+    }
+
+    #[test]
+    fn even_split_bounds() {
+        assert_eq!(even_split(10, 3, 1, 10), Some(vec![4, 3, 3]));
+        assert_eq!(even_split(30, 3, 10, 10), Some(vec![10, 10, 10]));
+        assert_eq!(even_split(29, 3, 10, 10), None);
+        assert_eq!(even_split(31, 3, 10, 10), None);
+        assert_eq!(even_split(10, 0, 1, 10), None);
+    }
+
+    fn proportional(mut p: SizingPolicy) -> SizingPolicy {
+        p.allocation_mode = AllocationMode::Proportional;
+        p
+    }
+
+    #[test]
+    fn first_fit_vs_proportional_same_inputs() {
+        // total_cap = 300k; demand = 400k + 200k = 600k.
+        let market = market_with_depth(300_000);
+        let p = policy(1_000_000, 1.0, 1_000_000, 100_000, 10_000);
+        let intents = [intent(400_000), intent(200_000)];
+
+        let ff = derive_execution_plan(&market, &intents, &p);
+        assert_eq!(ff.len(), 1, "first user takes the whole budget");
+        assert_eq!(ff[0].total_bid, 300_000);
+
+        let pr = derive_execution_plan(&market, &intents, &proportional(p));
+        assert_eq!(pr.len(), 2);
+        assert_eq!(pr[0].total_bid, 200_000);
+        assert_eq!(pr[1].total_bid, 100_000);
+    }
+
+    #[test]
+    fn proportional_splits_equal_demand_evenly() {
+        // Budget 150k, two users wanting 150k each: 75k/75k, not 150k/0.
+        let market = market_with_depth(150_000);
+        let p = policy(1_000_000, 1.0, 1_000_000, 100_000, 10_000);
+        let intents = [intent(150_000), intent(150_000)];
+
+        let ff = derive_execution_plan(&market, &intents, &p);
+        assert_eq!(ff.len(), 1);
+
+        let out = derive_execution_plan(&market, &intents, &proportional(p));
+        assert_eq!(out.len(), 2);
+        assert_eq!(out[0].total_bid, 75_000);
+        assert_eq!(out[1].total_bid, 75_000);
+    }
+
+    #[test]
+    fn proportional_respects_per_user_cap_in_demand() {
+        // Capped demand: 100k + 100k, budget 100k -> 50k each.
+        let market = market_with_depth(100_000);
+        let p = proportional(policy(1_000_000, 1.0, 100_000, 100_000, 10_000));
+
+        let out = derive_execution_plan(&market, &[intent(900_000), intent(100_000)], &p);
+        assert_eq!(out.len(), 2);
+        assert_eq!(out[0].total_bid, 50_000);
+        assert_eq!(out[1].total_bid, 50_000);
+    }
+
+    #[test]
+    fn proportional_matches_first_fit_when_budget_suffices() {
+        let market = market_with_depth(10_000_000);
+        let p = policy(10_000_000, 1.0, 1_000_000, 100_000, 10_000);
+        let intents = [intent(150_000), intent(5_000), intent(70_000)];
+
+        let ff = derive_execution_plan(&market, &intents, &p);
+        let pr = derive_execution_plan(&market, &intents, &proportional(p));
+
+        let totals = |v: &[PlannedAllocation]| v.iter().map(|a| a.total_bid).collect::<Vec<_>>();
+        assert_eq!(totals(&ff), totals(&pr));
+    }
+
+    #[test]
+    fn proportional_drops_shares_below_min_chunk() {
+        // Budget 100k over demand 990k + 10k: second share is 1k < min.
+        let market = market_with_depth(100_000);
+        let p = proportional(policy(1_000_000, 1.0, 1_000_000, 100_000, 10_000));
+
+        let out = derive_execution_plan(&market, &[intent(990_000), intent(10_000)], &p);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].total_bid, 99_000);
+    }
+
+    #[test]
+    fn shuffle_is_deterministic_per_seed() {
+        let intents: Vec<UserIntent> = (0..8).map(|_| intent(100_000)).collect();
+        let ids = |v: &[UserIntent]| v.iter().map(|u| u.session_id).collect::<Vec<_>>();
+
+        let mut a = intents.clone();
+        let mut b = intents.clone();
+        shuffle_intents(&mut a, 42);
+        shuffle_intents(&mut b, 42);
+        assert_eq!(ids(&a), ids(&b));
+
+        let mut sorted_a = ids(&a);
+        let mut sorted_orig = ids(&intents);
+        sorted_a.sort();
+        sorted_orig.sort();
+        assert_eq!(sorted_a, sorted_orig, "shuffle must be a permutation");
+    }
+
+    #[test]
+    fn shuffled_order_spreads_wins_across_users() {
+        // Budget fits exactly one user per tick.
+        let market = market_with_depth(100_000);
+        let p = policy(1_000_000, 1.0, 1_000_000, 100_000, 10_000);
+        let intents: Vec<UserIntent> = (0..4).map(|_| intent(100_000)).collect();
+
+        let winners = |shuffle: bool| {
+            let mut wins = vec![0usize; intents.len()];
+            for seed in 0..1_000u64 {
+                let mut ordered = intents.clone();
+                if shuffle {
+                    shuffle_intents(&mut ordered, seed);
+                }
+                let out = derive_execution_plan(&market, &ordered, &p);
+                assert_eq!(out.len(), 1);
+                let idx = intents
+                    .iter()
+                    .position(|u| u.session_id == out[0].session_id)
+                    .unwrap();
+                wins[idx] += 1;
+            }
+            wins
+        };
+
+        // Fixed order: the head always wins.
+        assert_eq!(winners(false), vec![1_000, 0, 0, 0]);
+
+        // Shuffled: every user wins roughly a quarter of the ticks.
+        for w in winners(true) {
+            assert!((150..=350).contains(&w), "uneven wins: {w}");
+        }
+    }
+
     #[test]
     fn hard_limit_overrides_market_depth() {
         let market = market_with_depth(1_000_000); // 1M depth
@@ -337,12 +629,15 @@ mod proptests {
             hard_limit in 1..=1_000_000_000u128,
             utilization in 0.0..=1.0f64,
 
-            // Random user intents
-            intents in prop::collection::vec(0..=2_000_000u128, 1..20)
+            // Random user intents with an optional chunk-count hint
+            intents in prop::collection::vec((0..=2_000_000u128, 0..=12u32), 1..20),
+            proportional in any::<bool>(),
+            shuffle_seed in prop::option::of(any::<u64>())
         ) {
             let market = MarketMetricsView {
                 ts_ms: 0, spread_bps: 0.0, trend_drop_bps: 0.0,
                 max_depth: market_depth,
+                slippage_bps: None,
             };
 
             let p = SizingPolicy {
@@ -351,12 +646,22 @@ mod proptests {
                 max_bid_per_user_per_tick: per_user,
                 max_chunk_bid: max_chunk,
                 min_chunk_bid: min_chunk,
+                allocation_mode: if proportional {
+                    AllocationMode::Proportional
+                } else {
+                    AllocationMode::FirstFit
+                },
             };
 
-            let user_intents: Vec<UserIntent> = intents.into_iter()
-                .map(|bid| UserIntent { session_id: uuid::Uuid::new_v4(), desired_bid: bid, desired_chunks: 0 })
+            let mut user_intents: Vec<UserIntent> = intents.into_iter()
+                .map(|(bid, chunks)| UserIntent { session_id: uuid::Uuid::new_v4(), desired_bid: bid, desired_chunks: chunks })
                 .collect();
 
+            // Invariants must hold for any ordering of the intents.
+            if let Some(seed) = shuffle_seed {
+                shuffle_intents(&mut user_intents, seed);
+            }
+
             let plan = derive_execution_plan(&market, &user_intents, &p);
 
             // --- INVARIANT 1: Total allocated never exceeds global budget ---
@@ -379,6 +684,18 @@ mod proptests {
 
                 // --- INVARIANT 4: No user exceeds the per-user cap ---
                 assert!(alloc.total_bid <= per_user);
+
+                // --- INVARIANT 5: honouring a chunk hint yields near-equal chunks ---
+                let hint = user_intents
+                    .iter()
+                    .find(|u| u.session_id == alloc.session_id)
+                    .map(|u| u.desired_chunks as usize)
+                    .unwrap();
+                if hint > 0 && alloc.chunks.len() == hint {
+                    let lo = alloc.chunks.iter().min().unwrap();
+                    let hi = alloc.chunks.iter().max().unwrap();
+                    assert!(hi - lo <= 1, "hinted split {:?} is not even", alloc.chunks);
+                }
             }
         }
     }
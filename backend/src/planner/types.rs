@@ -22,6 +22,20 @@ pub struct SizingPolicy {
     /// `min_chunk_bid` are not produced.
     pub max_chunk_bid: u128,
     pub min_chunk_bid: u128,
+
+    /// How the global tick budget is divided across intents.
+    pub allocation_mode: AllocationMode,
+}
+
+/// Strategy for dividing the global tick budget across user intents.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AllocationMode {
+    /// Intents are served in order; each takes as much as it can.
+    #[default]
+    FirstFit,
+    /// Each intent gets a share of the budget proportional to its capped
+    /// `desired_bid`. Shares too small to form a chunk are dropped.
+    Proportional,
 }
 
 impl Default for SizingPolicy {
@@ -32,6 +46,7 @@ impl Default for SizingPolicy {
             max_bid_per_user_per_tick: 10_000_000,
             max_chunk_bid: 2_000_000,
             min_chunk_bid: 100_000,
+            allocation_mode: AllocationMode::FirstFit,
         }
     }
 }
@@ -66,3 +81,14 @@ pub struct PlannedAllocation {
     /// Chunks are executed sequentially and may partially succeed.
     pub chunks: Vec<u128>,
 }
+
+/// Plan of a dry-run tick: what the scheduler would have reserved.
+#[derive(Clone, Debug)]
+pub struct PlannedBatch {
+    pub pair_id: String,
+
+    /// Tick time the plan was derived at.
+    pub planned_ms: u64,
+
+    pub allocations: Vec<PlannedAllocation>,
+}
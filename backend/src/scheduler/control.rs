@@ -0,0 +1,62 @@
+//! Operator switches for per-pair scheduling.
+
+use parking_lot::RwLock;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Shared set of paused pairs.
+///
+/// Cloned into every pair's `Scheduler` and the control endpoint. A paused
+/// pair's ticks reserve nothing; batches already reserved still execute.
+#[derive(Clone, Default)]
+pub struct PairControl {
+    paused: Arc<RwLock<HashSet<String>>>,
+}
+
+impl PairControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stops scheduling `pair_id`. Returns `false` if it was already paused.
+    pub fn pause(&self, pair_id: &str) -> bool {
+        self.paused.write().insert(pair_id.to_string())
+    }
+
+    /// Resumes scheduling `pair_id`. Returns `false` if it was not paused.
+    pub fn resume(&self, pair_id: &str) -> bool {
+        self.paused.write().remove(pair_id)
+    }
+
+    pub fn is_paused(&self, pair_id: &str) -> bool {
+        self.paused.read().contains(pair_id)
+    }
+
+    /// Paused pairs, sorted.
+    pub fn paused(&self) -> Vec<String> {
+        let mut pairs: Vec<String> = self.paused.read().iter().cloned().collect();
+        pairs.sort();
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pause_and_resume_are_per_pair() {
+        let control = PairControl::new();
+        let view = control.clone();
+
+        assert!(control.pause("TON/USDT"));
+        assert!(!control.pause("TON/USDT"));
+        assert!(view.is_paused("TON/USDT"));
+        assert!(!view.is_paused("STON/TON"));
+        assert_eq!(view.paused(), vec!["TON/USDT".to_string()]);
+
+        assert!(control.resume("TON/USDT"));
+        assert!(!control.resume("TON/USDT"));
+        assert!(!view.is_paused("TON/USDT"));
+    }
+}
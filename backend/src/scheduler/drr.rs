@@ -5,15 +5,29 @@ use uuid::Uuid;
 use crate::execution::types::ReservedBatch;
 use crate::session::model::Session;
 
-/// Accumulates credit for the session based on its quantum.
+/// Bound on the magnitude of a DRR deficit.
+///
+/// Deficits are persisted in a BIGINT column, so all DRR math here stays
+/// within `[-DEFICIT_BOUND, DEFICIT_BOUND]` and `persist_fairness` never hits
+/// an i64 overflow.
+pub const DEFICIT_BOUND: i128 = i64::MAX as i128;
+
+/// Accumulates credit for the session based on its quantum, scaled by the
+/// session's `quantum_weight` tier.
 ///
 /// A domain-specific cap is enforced to prevent "burstiness" where a long-inactive
 /// session could otherwise accumulate massive credit and monopolize the scheduler.
 #[instrument(skip(s), target = "session_logic")]
 pub fn accumulate_credit(s: &mut Session) {
-    let max_credit = (s.intent.preferred_chunk_bid as i128).saturating_mul(2);
+    let max_credit = credit_cap(s);
 
-    let next_credit = s.state.deficit.saturating_add(s.state.quantum as i128);
+    let weight = s.intent.quantum_weight.max(1) as u128;
+    let added = s
+        .state
+        .quantum
+        .saturating_mul(weight)
+        .min(i128::MAX as u128) as i128;
+    let next_credit = s.state.deficit.saturating_add(added);
 
     s.state.deficit = next_credit.min(max_credit);
 
@@ -24,6 +38,56 @@ pub fn accumulate_credit(s: &mut Session) {
     );
 }
 
+/// Starvation watchdog: boosts credit for sessions unserved for longer than
+/// `starvation_ms`.
+///
+/// The bonus is `preferred_chunk_bid * wait / starvation_ms`, so once the
+/// threshold is crossed a single pass affords a full chunk. The same
+/// 2x `preferred_chunk_bid` cap as `accumulate_credit` applies.
+///
+/// Returns `true` if a boost was applied.
+#[instrument(skip(s), target = "session_logic")]
+pub fn apply_aging(s: &mut Session, now_ms: u64, starvation_ms: u64) -> bool {
+    let wait = now_ms.saturating_sub(s.state.last_served_ms);
+    if starvation_ms == 0 || wait <= starvation_ms {
+        return false;
+    }
+
+    let max_credit = credit_cap(s);
+    let bonus = s.intent.preferred_chunk_bid.saturating_mul(wait as u128) / starvation_ms as u128;
+
+    s.state.deficit = s
+        .state
+        .deficit
+        .saturating_add(bonus.min(i128::MAX as u128) as i128)
+        .min(max_credit);
+
+    debug!(
+        session_id = %s.session_id,
+        wait_ms = wait,
+        new_deficit = s.state.deficit,
+        "starvation boost applied"
+    );
+    true
+}
+
+/// Clamps the deficit to an absolute `max_deficit`, on top of the
+/// per-session 2x `preferred_chunk_bid` cap. Bounds the burst a session with
+/// a large preferred chunk can claim after a long wait.
+pub fn clamp_deficit(s: &mut Session, max_deficit: u128) {
+    let cap = max_deficit.min(i128::MAX as u128) as i128;
+    s.state.deficit = s.state.deficit.min(cap);
+}
+
+/// Per-session credit cap: 2x `preferred_chunk_bid`, within `DEFICIT_BOUND`.
+fn credit_cap(s: &Session) -> i128 {
+    s.intent
+        .preferred_chunk_bid
+        .min(DEFICIT_BOUND as u128)
+        .saturating_mul(2)
+        .min(DEFICIT_BOUND as u128) as i128
+}
+
 pub fn sum_reserved(batch: &ReservedBatch) -> HashMap<Uuid, (u128, u32)> {
     let mut m: HashMap<Uuid, (u128, u32)> = HashMap::new();
     for u in &batch.users {
@@ -41,7 +105,12 @@ pub fn can_serve(s: &Session, cost_bid: u128) -> bool {
 
 /// Charge deficit by the served cost.
 pub fn charge(s: &mut Session, cost_bid: u128) {
-    s.state.deficit = s.state.deficit.saturating_sub(cost_bid as i128);
+    let cost = cost_bid.min(DEFICIT_BOUND as u128) as i128;
+    s.state.deficit = s
+        .state
+        .deficit
+        .saturating_sub(cost)
+        .clamp(-DEFICIT_BOUND, DEFICIT_BOUND);
 
     debug!(
         session_id = %s.session_id,
@@ -69,6 +138,11 @@ mod tests {
                 },
                 preferred_chunk_bid: preferred_bid,
                 max_bid_per_tick: 1_000_000,
+                active_windows: Vec::new(),
+                quantum_weight: 1,
+                twap_interval_ms: 0,
+                expires_at_ms: 0,
+                failure_cooldown_ms: None,
             },
             state: SessionState {
                 remaining_bid: 1_000_000,
@@ -91,6 +165,64 @@ mod tests {
         assert_eq!(s.state.deficit, 150, "Deficit should increase by quantum");
     }
 
+    #[test]
+    fn test_accumulate_credit_scales_by_weight() {
+        let mut s = mk_test_session(0, 50, 1000);
+        s.intent.quantum_weight = 3;
+        accumulate_credit(&mut s);
+        assert_eq!(
+            s.state.deficit, 150,
+            "Deficit should increase by quantum * weight"
+        );
+
+        // Weight 0 is treated as baseline rather than starving the session.
+        s.intent.quantum_weight = 0;
+        accumulate_credit(&mut s);
+        assert_eq!(s.state.deficit, 200);
+    }
+
+    #[test]
+    fn test_weighted_session_reaches_credit_in_fewer_ticks() {
+        fn ticks_to_credit(weight: u32) -> usize {
+            let mut s = mk_test_session(0, 25_000, 100_000);
+            s.intent.quantum_weight = weight;
+            (1..)
+                .find(|_| {
+                    accumulate_credit(&mut s);
+                    s.has_sufficient_credit()
+                })
+                .unwrap()
+        }
+
+        assert_eq!(ticks_to_credit(1), 4);
+        assert_eq!(ticks_to_credit(2), 2);
+        assert_eq!(ticks_to_credit(4), 1);
+    }
+
+    #[test]
+    fn test_apply_aging_boosts_only_past_threshold() {
+        let mut s = mk_test_session(0, 1, 1000);
+        s.state.last_served_ms = 10_000;
+
+        assert!(!apply_aging(&mut s, 15_000, 5_000), "wait == threshold");
+        assert_eq!(s.state.deficit, 0);
+
+        // Waited 1.5x the threshold: bonus = 1.5 * preferred_chunk_bid.
+        assert!(apply_aging(&mut s, 17_500, 5_000));
+        assert_eq!(s.state.deficit, 1500);
+
+        // Capped at 2x preferred_chunk_bid.
+        assert!(apply_aging(&mut s, 100_000, 5_000));
+        assert_eq!(s.state.deficit, 2000);
+    }
+
+    #[test]
+    fn test_apply_aging_disabled_when_threshold_zero() {
+        let mut s = mk_test_session(0, 1, 1000);
+        assert!(!apply_aging(&mut s, u64::MAX, 0));
+        assert_eq!(s.state.deficit, 0);
+    }
+
     #[test]
     fn test_accumulate_credit_respects_cap() {
         // preferred_chunk_bid = 1000, so max_credit = 2000
@@ -103,6 +235,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_long_starved_session_bursts_a_bounded_number_of_chunks() {
+        // Count consecutive chunks served from banked credit alone.
+        fn burst(s: &mut Session) -> usize {
+            let cost = s.intent.preferred_chunk_bid;
+            let mut served = 0;
+            while can_serve(s, cost) {
+                charge(s, cost);
+                served += 1;
+            }
+            served
+        }
+
+        let mut s = mk_test_session(0, 10_000, 1_000);
+        for _ in 0..1_000 {
+            accumulate_credit(&mut s);
+        }
+        assert_eq!(burst(&mut s), 2, "per-session cap is 2 chunks");
+
+        let mut s = mk_test_session(0, 10_000, 1_000);
+        for _ in 0..1_000 {
+            accumulate_credit(&mut s);
+            clamp_deficit(&mut s, 1_000);
+        }
+        assert_eq!(s.state.deficit, 1_000);
+        assert_eq!(burst(&mut s), 1);
+    }
+
+    #[test]
+    fn test_clamp_deficit_leaves_smaller_and_negative_deficits() {
+        let mut s = mk_test_session(500, 0, 1_000);
+        clamp_deficit(&mut s, 1_000);
+        assert_eq!(s.state.deficit, 500);
+
+        s.state.deficit = -20;
+        clamp_deficit(&mut s, 0);
+        assert_eq!(s.state.deficit, -20);
+    }
+
     #[test]
     fn test_accumulate_credit_saturating_overflow() {
         // Testing that i128 doesn't panic even if quantum + deficit is massive
@@ -139,4 +310,21 @@ mod tests {
         // 100 - 200 = -100. (Note: standard i128 sub allows negative)
         assert_eq!(s.state.deficit, -100);
     }
+
+    #[test]
+    fn test_deficit_stays_within_bound_for_extreme_inputs() {
+        let mut s = mk_test_session(DEFICIT_BOUND - 10, u128::MAX, u128::MAX);
+        s.intent.quantum_weight = u32::MAX;
+        accumulate_credit(&mut s);
+        assert_eq!(s.state.deficit, DEFICIT_BOUND);
+
+        s.state.last_served_ms = 0;
+        apply_aging(&mut s, u64::MAX, 1);
+        assert_eq!(s.state.deficit, DEFICIT_BOUND);
+
+        s.state.deficit = -DEFICIT_BOUND + 10;
+        charge(&mut s, u128::MAX);
+        assert_eq!(s.state.deficit, -DEFICIT_BOUND);
+        assert!(i64::try_from(s.state.deficit).is_ok());
+    }
 }
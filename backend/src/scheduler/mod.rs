@@ -1,4 +1,7 @@
+pub mod control;
 pub mod drr;
 
 #[allow(clippy::module_inception)]
 pub mod scheduler;
+pub mod tick;
+pub mod trace;
@@ -12,26 +12,41 @@
 //! - Final market validation (Gate B happens in the executor per-chunk).
 //!
 //! Safety/liveness properties:
-//! - Work per tick is bounded by `max_attempts` and `max_users_per_batch`.
+//! - Work per tick is bounded by `max_attempts` and `max_users_per_batch`,
+//!   and optionally by `max_total_bid_per_tick`.
+//! - With a backlog probe attached, no batch is reserved while the pair's
+//!   executor queue holds `max_inflight_batches_per_pair` or more batches.
+//! - A pair paused through `PairControl` reserves nothing until resumed.
+//! - In dry-run mode nothing is reserved and no fairness state is written.
 //! - DRR prevents starvation over time (provided sessions are revisited).
 //! - Reservations are restart-safe: if enqueue fails, recovery unwinds RESERVED batches.
 
 use std::collections::HashSet;
 use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::Relaxed;
 use std::time::Duration;
 
 use tokio::sync::mpsc::Sender;
-use tracing::{debug, field, info, instrument, warn};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, field, info, instrument, warn};
 use uuid::Uuid;
 
+use crate::execution::executor::ExecutorBacklog;
 use crate::execution::reserve_execution;
 use crate::execution::types::{ExecutionEvent, ReservedBatch};
 use crate::logger::warn_if_slow;
-use crate::market::types::MarketMetricsView;
+use crate::market::market_view_store::MarketViewStore;
+use crate::market::types::{DEFAULT_MAX_SNAPSHOT_AGE_MS, MarketMetricsView};
 use crate::metrics::counters::Counters;
-use crate::planner::sizing::derive_execution_plan;
-use crate::planner::types::{PlannedAllocation, SizingPolicy, UserIntent as PlannerUserIntent};
+use crate::planner::sizing::{derive_execution_plan, shuffle_intents};
+use crate::planner::types::{
+    AllocationMode, PlannedAllocation, PlannedBatch, SizingPolicy, UserIntent as PlannerUserIntent,
+};
+use crate::scheduler::control::PairControl;
 use crate::scheduler::drr;
+use crate::scheduler::tick::TickJitter;
+use crate::scheduler::trace::{CandidateOutcome, SchedulerTrace, TickRecorder};
 use crate::session::model::Session;
 use crate::session::store::SessionStore;
 
@@ -56,6 +71,53 @@ pub struct Scheduler {
     /// Upper bound on selected users per batch (executor bound).
     max_users_per_batch: usize,
 
+    /// Upper bound on the summed `desired_bid` of one tick's intents.
+    /// Throttles a pair independently of market depth (`None` = unbounded).
+    max_total_bid_per_tick: Option<u128>,
+
+    /// Market snapshots older than this skip the whole tick and fail Gate A
+    /// (treated as missing).
+    max_snapshot_age_ms: u64,
+
+    /// Rotate the planner's first-fit starting index each tick.
+    rotate_plan_start: bool,
+
+    /// Shuffle intents (seeded from the tick time) before planning.
+    /// Takes precedence over `rotate_plan_start`.
+    shuffle_plan_order: bool,
+
+    /// Round-robin starting index applied to the intents handed to the planner.
+    plan_offset: AtomicUsize,
+
+    /// Sessions unserved for longer than this get a DRR credit boost
+    /// (0 disables the watchdog).
+    starvation_ms: u64,
+
+    /// Absolute cap on a session's DRR deficit, applied after accumulation
+    /// and aging (`None` = only the per-session 2x chunk cap).
+    max_deficit: Option<u128>,
+
+    /// Executor queue depth probe; `None` disables backpressure.
+    backlog: Option<Arc<dyn ExecutorBacklog>>,
+
+    /// Queue depth at or above which a tick skips reservation.
+    max_inflight_batches_per_pair: usize,
+
+    /// Tick jitter for `run`; zero ticks at a fixed phase.
+    tick_jitter: Duration,
+
+    /// Operator pause switches; `None` schedules every pair.
+    pair_control: Option<PairControl>,
+
+    /// Dry run: plan but never reserve or write fairness state.
+    dry_run: bool,
+
+    /// Receives each tick's per-candidate decisions; `None` disables tracing.
+    trace: Option<SchedulerTrace>,
+
+    /// Receives each dry-run plan; `None` only logs it.
+    plan_previews: Option<Sender<PlannedBatch>>,
+
     /// Observability counters (does not affect behavior).
     counters: Counters,
 }
@@ -74,15 +136,163 @@ impl Scheduler {
             candidate_min,
             max_attempts,
             max_users_per_batch: max_users_per_batch.max(1),
+            max_total_bid_per_tick: None,
+            max_snapshot_age_ms: DEFAULT_MAX_SNAPSHOT_AGE_MS,
+            rotate_plan_start: true,
+            shuffle_plan_order: false,
+            plan_offset: AtomicUsize::new(0),
+            starvation_ms: 0,
+            max_deficit: None,
+            backlog: None,
+            max_inflight_batches_per_pair: usize::MAX,
+            tick_jitter: Duration::ZERO,
+            pair_control: None,
+            dry_run: false,
+            trace: None,
+            plan_previews: None,
             counters,
         }
     }
 
+    /// Overrides the maximum market snapshot age accepted by Gate A.
+    pub fn with_max_snapshot_age_ms(mut self, max_snapshot_age_ms: u64) -> Self {
+        self.max_snapshot_age_ms = max_snapshot_age_ms;
+        self
+    }
+
+    /// Caps the total bid selected per tick (see `max_total_bid_per_tick`).
+    pub fn with_max_total_bid_per_tick(mut self, cap: u128) -> Self {
+        self.max_total_bid_per_tick = Some(cap);
+        self
+    }
+
+    /// Enables a deterministic shuffle of intents before planning, seeded
+    /// from `now_ms` (disabled by default). Replaces rotation when enabled.
+    pub fn with_plan_shuffle(mut self, enabled: bool) -> Self {
+        self.shuffle_plan_order = enabled;
+        self
+    }
+
+    /// Selects how the planner divides the tick budget (first-fit by default).
+    pub fn with_allocation_mode(mut self, mode: AllocationMode) -> Self {
+        self.policy.allocation_mode = mode;
+        self
+    }
+
+    /// Enables/disables rotating the planner's first-fit start (enabled by default).
+    pub fn with_plan_rotation(mut self, enabled: bool) -> Self {
+        self.rotate_plan_start = enabled;
+        self
+    }
+
+    /// Boosts credit for sessions unserved for longer than `starvation_ms`
+    /// (see `drr::apply_aging`). Disabled by default.
+    pub fn with_starvation_ms(mut self, starvation_ms: u64) -> Self {
+        self.starvation_ms = starvation_ms;
+        self
+    }
+
+    /// Caps every session's DRR deficit at `max_deficit` (see
+    /// `drr::clamp_deficit`).
+    pub fn with_max_deficit(mut self, max_deficit: u128) -> Self {
+        self.max_deficit = Some(max_deficit);
+        self
+    }
+
+    /// Skips reservation while `backlog` reports `max_inflight_batches_per_pair`
+    /// or more batches queued for the pair.
+    pub fn with_backpressure(
+        mut self,
+        backlog: Arc<dyn ExecutorBacklog>,
+        max_inflight_batches_per_pair: usize,
+    ) -> Self {
+        self.backlog = Some(backlog);
+        self.max_inflight_batches_per_pair = max_inflight_batches_per_pair.max(1);
+        self
+    }
+
+    /// Skips every tick while `control` has the pair paused.
+    pub fn with_pair_control(mut self, control: PairControl) -> Self {
+        self.pair_control = Some(control);
+        self
+    }
+
+    /// Dry-run mode: ticks select, charge and plan as usual, then log the
+    /// resulting `PlannedBatch` (and send it to `previews`, if given) instead
+    /// of reserving it. Neither the session cache nor the DB is written, so
+    /// every tick plans from the same fairness state.
+    pub fn with_dry_run(mut self, previews: Option<Sender<PlannedBatch>>) -> Self {
+        self.dry_run = true;
+        self.plan_previews = previews;
+        self
+    }
+
+    /// Records why each candidate was or was not served in every tick that
+    /// scans candidates; read it back with `SchedulerTrace::last_tick`.
+    pub fn with_trace(mut self, trace: SchedulerTrace) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+
+    /// Starts `run` at a random phase within its interval and moves every
+    /// tick by up to `±jitter`, so pair loops started together do not hit
+    /// the database in lockstep. See `TickJitter`.
+    pub fn with_tick_jitter(mut self, jitter: Duration) -> Self {
+        self.tick_jitter = jitter;
+        self
+    }
+
+    /// Drives `on_tick` for `pair_id` every `interval` (plus any configured
+    /// jitter) until `shutdown` is cancelled.
+    ///
+    /// Cancellation is only observed between ticks, so a batch reserved by a
+    /// running tick is always handed to `exec_tx` before the loop exits.
+    /// Dropping `exec_tx` on return lets the router finish draining.
+    pub async fn run(
+        self,
+        pair_id: String,
+        market_view: MarketViewStore,
+        exec_tx: Sender<ExecutionEvent>,
+        interval: Duration,
+        shutdown: CancellationToken,
+    ) {
+        let mut cadence = TickJitter::new(interval, self.tick_jitter, rand::random());
+        let start = tokio::time::Instant::now();
+        let mut nominal = cadence.phase();
+
+        loop {
+            let due = start + cadence.jittered(nominal);
+            tokio::select! {
+                _ = tokio::time::sleep_until(due) => {}
+                _ = shutdown.cancelled() => break,
+            }
+            nominal += interval;
+
+            let Some(market) = market_view.get(&pair_id).await else {
+                // No market snapshot yet -> skip scheduling.
+                continue;
+            };
+
+            let started = std::time::Instant::now();
+            let res = self
+                .on_tick(&pair_id, market, exec_tx.clone(), crate::time::now_ms())
+                .await;
+            self.counters.sched_tick_latency.record(started.elapsed());
+            if let Err(e) = res {
+                error!(error = ?e, pair_id = %pair_id, "scheduler tick failed");
+            }
+        }
+
+        info!(pair_id = %pair_id, "scheduler stopped");
+    }
+
     /// Executes one scheduling tick for `pair_id`.
     ///
     /// Flow:
+    /// 0) Skip the tick if the pair is paused, the market snapshot is stale or
+    ///    the pair's executor queue is saturated.
     /// 1) Ensure enough candidates are cached.
-    /// 2) Select intents (RR scan + DRR + Gate A).
+    /// 2) Select intents (RR scan + DRR + Gate A), rotating the first-fit start.
     /// 3) Planner derives chunked allocations bounded by market depth & caps.
     /// 4) Atomically reserve the batch in the DB.
     /// 5) Enqueue the reserved batch to the executor.
@@ -103,11 +313,53 @@ impl Scheduler {
     ) -> anyhow::Result<()> {
         debug!("starting scheduling tick");
 
-        // Load more sessions into the cache if we are below the minimum candidate set.
-        self.store.ensure_candidates(self.candidate_min).await?;
+        if self
+            .pair_control
+            .as_ref()
+            .is_some_and(|c| c.is_paused(pair_id))
+        {
+            self.counters.sched_paused.fetch_add(1, Relaxed);
+            debug!("pair paused; skipping tick");
+            return Ok(());
+        }
+
+        // A stalled feed keeps its last snapshot in the view store; refuse to
+        // schedule on it at all rather than failing Gate A per session.
+        if !market.is_fresh(now_ms, self.max_snapshot_age_ms) {
+            self.counters.sched_stale_market.fetch_add(1, Relaxed);
+            warn!(
+                market_ts_ms = market.ts_ms,
+                max_age_ms = self.max_snapshot_age_ms,
+                "market snapshot is stale; skipping tick"
+            );
+            return Ok(());
+        }
+
+        // Backpressure: do not pile up RESERVED batches behind a slow executor.
+        if let Some(backlog) = &self.backlog {
+            let depth = backlog.queue_depth(pair_id).await.unwrap_or(0);
+            if depth >= self.max_inflight_batches_per_pair {
+                self.counters.sched_backpressure.fetch_add(1, Relaxed);
+                debug!(
+                    queue_depth = depth,
+                    limit = self.max_inflight_batches_per_pair,
+                    "executor queue saturated; skipping reservation"
+                );
+                return Ok(());
+            }
+        }
+
+        // Load more of this pair's sessions into the cache if we are below the
+        // minimum candidate set.
+        self.store
+            .ensure_candidates(self.candidate_min, Some(pair_id))
+            .await?;
 
         // Gate A + fairness selection.
-        let intents = self.pick_intents(pair_id, &market, now_ms).await?;
+        let mut trace = TickRecorder::new(self.trace.as_ref(), pair_id, now_ms);
+        let mut intents = self
+            .pick_intents(pair_id, &market, now_ms, &mut trace)
+            .await?;
         if intents.is_empty() {
             self.counters
                 .sched_empty
@@ -116,10 +368,23 @@ impl Scheduler {
             return Ok(());
         }
 
+        // The planner is first-fit; shuffle or rotate the starting index each
+        // tick so the advantage of landing first (full budget) moves across
+        // selected users.
+        if self.shuffle_plan_order {
+            shuffle_intents(&mut intents, now_ms);
+        } else if self.rotate_plan_start {
+            let offset = self.plan_offset.fetch_add(1, Relaxed) % intents.len();
+            intents.rotate_left(offset);
+        }
+
         // Convert intents into concrete allocations (including chunk splitting).
         // Depth is applied here as a capacity limiter (not as a binary gate).
         let allocations: Vec<PlannedAllocation> =
             derive_execution_plan(&market, &intents, &self.policy);
+        trace.demote(CandidateOutcome::NotAllocated, |sid| {
+            !allocations.iter().any(|a| a.session_id == *sid)
+        });
 
         if allocations.is_empty() {
             self.counters
@@ -132,15 +397,29 @@ impl Scheduler {
             return Ok(());
         }
 
-        let batch_opt: Option<ReservedBatch> =
-            warn_if_slow("reserve_execution", Duration::from_millis(100), async {
-                reserve_execution(self.store.as_ref(), pair_id, now_ms, &allocations).await
-            })
-            .await?;
+        if self.dry_run {
+            self.preview(PlannedBatch {
+                pair_id: pair_id.to_string(),
+                planned_ms: now_ms,
+                allocations,
+            });
+            return Ok(());
+        }
+
+        let started = std::time::Instant::now();
+        let reserved = warn_if_slow("reserve_execution", Duration::from_millis(100), async {
+            reserve_execution(self.store.as_ref(), pair_id, now_ms, &allocations).await
+        })
+        .await;
+        self.counters
+            .reserve_execution_latency
+            .record(started.elapsed());
+        let batch_opt: Option<ReservedBatch> = reserved?;
 
         let batch = match batch_opt {
             Some(b) => b,
             None => {
+                trace.demote(CandidateOutcome::PendingBatch, |_| true);
                 self.counters
                     .sched_empty
                     .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
@@ -149,6 +428,10 @@ impl Scheduler {
             }
         };
 
+        trace.demote(CandidateOutcome::PendingBatch, |sid| {
+            !batch.users.iter().any(|u| u.session_id == *sid)
+        });
+
         let reserved = drr::sum_reserved(&batch);
 
         for (sid, (total_bid, total_chunks)) in reserved {
@@ -199,22 +482,26 @@ impl Scheduler {
     ///
     /// Durability:
     /// - DRR deficit is persisted so restarts/cache evictions do not reset fairness.
-    #[instrument(skip(self, _market), target = "scheduler")]
+    #[instrument(skip(self, market, trace), target = "scheduler")]
     async fn pick_intents(
         &self,
         pair_id: &str,
-        _market: &MarketMetricsView,
+        market: &MarketMetricsView,
         now_ms: u64,
+        trace: &mut TickRecorder<'_>,
     ) -> anyhow::Result<Vec<PlannerUserIntent>> {
         let mut out = Vec::new();
-        let mut served_this_tick = HashSet::<Uuid>::new();
+        // Each session is considered at most once per tick, so DRR credit is
+        // accumulated once per tick regardless of how often the RR scan wraps.
+        let mut visited_this_tick = HashSet::<Uuid>::new();
 
         let mut attempts = 0usize;
+        let mut total_bid: u128 = 0;
 
         while attempts < self.max_attempts && out.len() < self.max_users_per_batch {
             attempts += 1;
 
-            let sid = match self.store.rotate_candidate() {
+            let sid = match self.store.rotate_candidate(now_ms) {
                 Some(x) => x,
                 None => break,
             };
@@ -224,15 +511,69 @@ impl Scheduler {
                 None => continue,
             };
 
-            if served_this_tick.contains(&s.session_id) {
+            // The cache may hold other pairs' sessions (multi-pair refills);
+            // they are neither selected nor charged by this pair's tick.
+            if s.pair_id != pair_id {
                 continue;
             }
 
-            // --- filters (active, cooldown, pending, constraints, availability) ---
+            if !visited_this_tick.insert(s.session_id) {
+                continue;
+            }
+
+            // --- filters (active, cooldown, windows, TWAP, availability, constraints) ---
+            // Pending batches are not filtered here: the cached flag is only cleared
+            // on reload, so the reservation CAS in the DB is the authority for it.
+            if !s.active {
+                self.counters.sched_skip_inactive.fetch_add(1, Relaxed);
+                trace.record(sid, CandidateOutcome::Inactive);
+                continue;
+            }
+
+            if s.state.cooldown_until_ms > now_ms {
+                self.counters.sched_skip_cooldown.fetch_add(1, Relaxed);
+                trace.record(sid, CandidateOutcome::Cooldown);
+                continue;
+            }
+
+            if !s.in_active_window(now_ms) {
+                self.counters.sched_skip_window.fetch_add(1, Relaxed);
+                trace.record(sid, CandidateOutcome::OutsideWindow);
+                continue;
+            }
+
+            // TWAP spacing is a hard floor, not a fairness signal: no credit
+            // is accumulated while the session waits it out.
+            if !s.twap_ready(now_ms) {
+                self.counters.sched_skip_twap.fetch_add(1, Relaxed);
+                trace.record(sid, CandidateOutcome::TwapSpacing);
+                continue;
+            }
+
+            if s.available_bid() == 0 || s.available_chunks() == 0 {
+                self.counters.sched_skip_empty.fetch_add(1, Relaxed);
+                trace.record(sid, CandidateOutcome::Exhausted);
+                continue;
+            }
+
+            if !constraints_ok(&s, market, now_ms, self.max_snapshot_age_ms) {
+                self.counters.sched_skip_constraints.fetch_add(1, Relaxed);
+                trace.record(sid, CandidateOutcome::ConstraintsFailed);
+                continue;
+            }
 
             // DRR step 1: accumulate credit ONCE
             drr::accumulate_credit(&mut s);
 
+            // Starvation watchdog: long-unserved sessions are aged up.
+            if drr::apply_aging(&mut s, now_ms, self.starvation_ms) {
+                self.counters.sched_starvation_boosts.fetch_add(1, Relaxed);
+            }
+
+            if let Some(cap) = self.max_deficit {
+                drr::clamp_deficit(&mut s, cap);
+            }
+
             let want = s
                 .intent
                 .preferred_chunk_bid
@@ -240,28 +581,38 @@ impl Scheduler {
                 .min(s.available_bid());
 
             if want == 0 {
-                self.store.upsert_cache(s);
+                self.record_fairness(&s, false).await?;
+                trace.record(sid, CandidateOutcome::NoWant);
                 continue;
             }
 
             if !drr::can_serve(&s, want) {
-                self.store.upsert_cache(s);
+                // Persist the accumulated credit: a cache refill reloads the
+                // session from the DB and would otherwise discard it.
+                self.record_fairness(&s, true).await?;
+                trace.record(sid, CandidateOutcome::BelowCredit);
                 continue;
             }
 
+            // Tick budget: stop selecting once the next intent would exceed it.
+            if self
+                .max_total_bid_per_tick
+                .is_some_and(|cap| total_bid.saturating_add(want) > cap)
+            {
+                self.record_fairness(&s, true).await?;
+                trace.record(sid, CandidateOutcome::OverTickBudget);
+                break;
+            }
+            total_bid += want;
+
             // DRR step 2: charge EXACTLY ONCE
             drr::charge(&mut s, want);
             s.state.last_served_ms = now_ms;
 
-            served_this_tick.insert(s.session_id);
-
-            self.store.upsert_cache(s.clone());
-
             // Persist fairness (correct place)
-            self.store
-                .persist_fairness(&s.session_id, s.state.deficit, s.state.last_served_ms)
-                .await?;
+            self.record_fairness(&s, true).await?;
 
+            trace.record(sid, CandidateOutcome::Selected);
             out.push(PlannerUserIntent {
                 session_id: s.session_id,
                 desired_bid: want,
@@ -271,13 +622,56 @@ impl Scheduler {
 
         Ok(out)
     }
+
+    /// Writes a session's DRR progress to the cache and, with `persist`, the
+    /// DB. A no-op in dry-run mode.
+    async fn record_fairness(&self, s: &Session, persist: bool) -> anyhow::Result<()> {
+        if self.dry_run {
+            return Ok(());
+        }
+
+        self.store.upsert_cache(s.clone());
+        if persist {
+            self.store
+                .persist_fairness(&s.session_id, s.state.deficit, s.state.last_served_ms)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Reports a dry-run plan. Previews are best-effort: a full or closed
+    /// channel drops the plan rather than stalling the tick.
+    fn preview(&self, plan: PlannedBatch) {
+        self.counters.sched_dry_run_plans.fetch_add(1, Relaxed);
+        info!(
+            users = plan.allocations.len(),
+            total_bid = %plan.allocations.iter().map(|a| a.total_bid).sum::<u128>(),
+            chunks = plan.allocations.iter().map(|a| a.chunks.len()).sum::<usize>(),
+            "dry run: batch planned, not reserved"
+        );
+
+        if let Some(tx) = &self.plan_previews
+            && let Err(e) = tx.try_send(plan)
+        {
+            debug!(error = %e, "dry-run plan preview dropped");
+        }
+    }
 }
 
 /// Gate A: checks whether the current market state satisfies the session's constraints.
 ///
 /// This gate is intentionally conservative: the executor re-checks constraints
 /// (Gate B) immediately before each chunk is executed.
-pub fn constraints_ok(s: &Session, m: &MarketMetricsView) -> bool {
-    m.spread_bps <= s.intent.constraints.max_spread_bps
+/// A snapshot older than `max_snapshot_age_ms` is treated as missing and fails closed.
+pub fn constraints_ok(
+    s: &Session,
+    m: &MarketMetricsView,
+    now_ms: u64,
+    max_snapshot_age_ms: u64,
+) -> bool {
+    m.is_fresh(now_ms, max_snapshot_age_ms)
+        && m.spread_bps <= s.intent.constraints.max_spread_bps
         && m.trend_drop_bps <= s.intent.constraints.max_trend_drop_bps
+        && m.slippage_bps
+            .is_none_or(|bps| bps <= s.intent.constraints.max_slippage_bps)
 }
@@ -0,0 +1,131 @@
+//! Tick cadence for `Scheduler::run`.
+//!
+//! Pair loops started together would otherwise tick in lockstep and hit the
+//! database at the same instant every interval. With jitter enabled, each
+//! loop starts at a random phase within one interval and every tick is
+//! nudged by up to `±jitter` around its nominal time.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::Duration;
+
+/// Per-loop tick schedule. Ticks stay anchored to `phase + k * interval`, so
+/// jitter spreads ticks without drifting the cadence.
+pub struct TickJitter {
+    interval: Duration,
+    jitter: Duration,
+    rng: StdRng,
+}
+
+impl TickJitter {
+    /// `jitter` of zero disables both the random phase and per-tick jitter.
+    /// Per-tick jitter is capped at half the interval so ticks keep their order.
+    pub fn new(interval: Duration, jitter: Duration, seed: u64) -> Self {
+        Self {
+            interval,
+            jitter: jitter.min(interval / 2),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Offset of the loop's first tick, uniform in `[0, interval)`.
+    pub fn phase(&mut self) -> Duration {
+        if self.jitter.is_zero() || self.interval.is_zero() {
+            return Duration::ZERO;
+        }
+        self.interval.mul_f64(self.rng.r#gen::<f64>())
+    }
+
+    /// Time of the tick nominally due `nominal` after loop start, moved by a
+    /// uniform offset in `[-jitter, jitter]` and never before the start.
+    pub fn jittered(&mut self, nominal: Duration) -> Duration {
+        if self.jitter.is_zero() {
+            return nominal;
+        }
+        let j = self.jitter.as_secs_f64();
+        let offset = self.rng.gen_range(-j..=j);
+        if offset >= 0.0 {
+            nominal + Duration::from_secs_f64(offset)
+        } else {
+            nominal.saturating_sub(Duration::from_secs_f64(-offset))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_millis(250);
+    const JITTER: Duration = Duration::from_millis(25);
+
+    #[test]
+    fn phase_and_jitter_stay_within_bounds() {
+        for seed in 0..1_000 {
+            let mut t = TickJitter::new(INTERVAL, JITTER, seed);
+            let phase = t.phase();
+            assert!(phase < INTERVAL, "seed {seed}: phase {phase:?}");
+
+            for k in 1..20u32 {
+                let nominal = phase + INTERVAL * k;
+                let at = t.jittered(nominal);
+                assert!(
+                    at >= nominal - JITTER && at <= nominal + JITTER,
+                    "seed {seed}: tick {k} at {at:?}, nominal {nominal:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_schedule() {
+        let schedule = |seed| {
+            let mut t = TickJitter::new(INTERVAL, JITTER, seed);
+            let phase = t.phase();
+            (
+                phase,
+                (1..5u32)
+                    .map(|k| t.jittered(phase + INTERVAL * k))
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        assert_eq!(schedule(7), schedule(7));
+        assert_ne!(schedule(7), schedule(8));
+    }
+
+    #[test]
+    fn loops_start_at_staggered_phases() {
+        // Eight pairs: phases spread over the interval instead of aligning.
+        let mut phases: Vec<Duration> = (0..8)
+            .map(|seed| TickJitter::new(INTERVAL, JITTER, seed).phase())
+            .collect();
+        phases.sort();
+        phases.dedup();
+
+        assert_eq!(phases.len(), 8);
+        assert!(phases[7] - phases[0] > INTERVAL / 2, "{phases:?}");
+    }
+
+    #[test]
+    fn zero_jitter_keeps_the_fixed_cadence() {
+        let mut t = TickJitter::new(INTERVAL, Duration::ZERO, 42);
+
+        assert_eq!(t.phase(), Duration::ZERO);
+        assert_eq!(t.jittered(INTERVAL * 3), INTERVAL * 3);
+    }
+
+    #[test]
+    fn jitter_is_capped_at_half_the_interval() {
+        let mut t = TickJitter::new(INTERVAL, Duration::from_secs(10), 3);
+
+        for k in 1..100u32 {
+            let nominal = INTERVAL * k;
+            let at = t.jittered(nominal);
+            assert!(
+                at.abs_diff(nominal) <= INTERVAL / 2,
+                "{at:?} vs {nominal:?}"
+            );
+        }
+    }
+}
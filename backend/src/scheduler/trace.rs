@@ -0,0 +1,167 @@
+//! Per-candidate decision trace of the latest scheduling tick.
+//!
+//! Answers "why was session X not served": every candidate `pick_intents`
+//! looked at is recorded with the first check that rejected it, and selected
+//! sessions are updated if planning or the reservation CAS later dropped them.
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// What happened to one candidate in a tick.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum CandidateOutcome {
+    /// Picked and, if the tick reserved, part of the batch.
+    Selected,
+    Inactive,
+    Cooldown,
+    OutsideWindow,
+    /// Waiting out its TWAP spacing.
+    TwapSpacing,
+    /// No remaining bid or chunks beyond what is in flight.
+    Exhausted,
+    /// Gate A: the market is outside the session's constraints.
+    ConstraintsFailed,
+    /// Preferred chunk, tick cap and availability leave nothing to request.
+    NoWant,
+    /// DRR credit does not cover the requested bid yet.
+    BelowCredit,
+    /// The tick's bid budget was already spent.
+    OverTickBudget,
+    /// Selected, but the planner gave it no allocation.
+    NotAllocated,
+    /// Planned, but the reservation CAS skipped it (usually a batch is
+    /// already pending for the session).
+    PendingBatch,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CandidateTrace {
+    pub session_id: Uuid,
+    pub outcome: CandidateOutcome,
+}
+
+/// Decisions of one tick, in scan order.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct TickTrace {
+    pub now_ms: u64,
+    pub candidates: Vec<CandidateTrace>,
+}
+
+impl TickTrace {
+    pub fn outcome(&self, session_id: &Uuid) -> Option<CandidateOutcome> {
+        self.candidates
+            .iter()
+            .find(|c| c.session_id == *session_id)
+            .map(|c| c.outcome)
+    }
+}
+
+/// Shared sink holding the latest `TickTrace` per pair. Cloned into the
+/// scheduler (`Scheduler::with_trace`) and the metrics endpoint.
+#[derive(Clone, Default)]
+pub struct SchedulerTrace {
+    latest: Arc<Mutex<HashMap<String, TickTrace>>>,
+}
+
+impl SchedulerTrace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Latest traced tick of `pair_id`.
+    pub fn last_tick(&self, pair_id: &str) -> Option<TickTrace> {
+        self.latest.lock().get(pair_id).cloned()
+    }
+
+    /// Latest traced tick of every pair.
+    pub fn snapshot(&self) -> HashMap<String, TickTrace> {
+        self.latest.lock().clone()
+    }
+
+    fn publish(&self, pair_id: &str, trace: TickTrace) {
+        self.latest.lock().insert(pair_id.to_string(), trace);
+    }
+}
+
+/// Collects one tick's decisions and publishes them when dropped, so every
+/// early return of `on_tick` still leaves a trace. Inert without a sink.
+pub(crate) struct TickRecorder<'a> {
+    sink: Option<&'a SchedulerTrace>,
+    pair_id: &'a str,
+    trace: TickTrace,
+}
+
+impl<'a> TickRecorder<'a> {
+    pub(crate) fn new(sink: Option<&'a SchedulerTrace>, pair_id: &'a str, now_ms: u64) -> Self {
+        Self {
+            sink,
+            pair_id,
+            trace: TickTrace {
+                now_ms,
+                candidates: Vec::new(),
+            },
+        }
+    }
+
+    pub(crate) fn record(&mut self, session_id: Uuid, outcome: CandidateOutcome) {
+        if self.sink.is_some() {
+            self.trace.candidates.push(CandidateTrace {
+                session_id,
+                outcome,
+            });
+        }
+    }
+
+    /// Moves `Selected` candidates for which `dropped` holds to `outcome`.
+    pub(crate) fn demote(&mut self, outcome: CandidateOutcome, dropped: impl Fn(&Uuid) -> bool) {
+        for c in &mut self.trace.candidates {
+            if c.outcome == CandidateOutcome::Selected && dropped(&c.session_id) {
+                c.outcome = outcome;
+            }
+        }
+    }
+}
+
+impl Drop for TickRecorder<'_> {
+    fn drop(&mut self) {
+        if let Some(sink) = self.sink {
+            sink.publish(self.pair_id, std::mem::take(&mut self.trace));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recorder_without_sink_records_nothing() {
+        let mut rec = TickRecorder::new(None, "TON/USDT", 1);
+        rec.record(Uuid::new_v4(), CandidateOutcome::Selected);
+        assert!(rec.trace.candidates.is_empty());
+    }
+
+    #[test]
+    fn drop_publishes_demoted_outcomes() {
+        let sink = SchedulerTrace::new();
+        let (kept, dropped, cooled) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        let mut rec = TickRecorder::new(Some(&sink), "TON/USDT", 42);
+        rec.record(kept, CandidateOutcome::Selected);
+        rec.record(dropped, CandidateOutcome::Selected);
+        rec.record(cooled, CandidateOutcome::Cooldown);
+        rec.demote(CandidateOutcome::NotAllocated, |id| *id != kept);
+        assert!(sink.last_tick("TON/USDT").is_none());
+        drop(rec);
+
+        let tick = sink.last_tick("TON/USDT").unwrap();
+        assert_eq!(tick.now_ms, 42);
+        assert_eq!(tick.outcome(&kept), Some(CandidateOutcome::Selected));
+        assert_eq!(tick.outcome(&dropped), Some(CandidateOutcome::NotAllocated));
+        assert_eq!(tick.outcome(&cooled), Some(CandidateOutcome::Cooldown));
+        assert!(sink.snapshot().contains_key("TON/USDT"));
+    }
+}
@@ -1,27 +1,78 @@
 use parking_lot::Mutex;
 use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
 use tracing::{debug, info, instrument, warn};
 use uuid::Uuid;
 
 use crate::session::model::Session;
+use crate::time::now_ms;
+
+/// Point-in-time cache counters (cumulative since creation).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// `get` calls that found the session.
+    pub hits: u64,
+    /// `get` calls that did not.
+    pub misses: u64,
+    /// `upsert` calls that added a session.
+    pub inserts: u64,
+    /// `upsert` calls that replaced a cached session.
+    pub updates: u64,
+    /// Sessions evicted to make room for new ones.
+    pub evictions: u64,
+    /// Sessions dropped after sitting idle for `max_idle_ms`.
+    pub expired: u64,
+    /// Sessions currently cached.
+    pub size: usize,
+}
+
+/// Cumulative cache counters. Clones share the same atomics, so the
+/// instance held by `Counters` can be handed to `SessionCache::with_metrics`
+/// for export.
+#[derive(Clone, Debug, Default)]
+pub struct SessionCacheMetrics {
+    pub hits: Arc<AtomicU64>,
+    pub misses: Arc<AtomicU64>,
+    pub inserts: Arc<AtomicU64>,
+    pub updates: Arc<AtomicU64>,
+    pub evictions: Arc<AtomicU64>,
+    pub expired: Arc<AtomicU64>,
+}
+
+/// A cached session and when it was last upserted.
+struct Entry {
+    session: Session,
+    upserted_ms: u64,
+}
 
 /// Bounded in-memory session cache used by the scheduler.
 ///
 /// Guarantees:
 /// - Memory usage is bounded by `max_cached`.
 /// - Sessions are rotated fairly using a round-robin ring.
-/// - On overflow, evicts a "cold" session from a bounded sample:
-///   lowest DRR deficit first, then oldest `last_served_ms`.
+/// - On overflow, evicts a session chosen by the `EvictionPolicy` from a
+///   bounded sample of the ring (`ColdDrrPolicy` by default).
+/// - With `max_idle_ms`, sessions neither served nor upserted for that long
+///   are dropped lazily by `rotate` and `upsert`, unless they hold a pending
+///   batch or in-flight chunks.
 pub struct SessionCache {
     /// Max sessions held in memory.
     max_cached: usize,
     /// Number of RR entries sampled when selecting an eviction victim.
     eviction_scan: usize,
+    /// Chooses the victim among the sampled entries.
+    eviction_policy: Box<dyn EvictionPolicy>,
+    /// Idle time (ms) after which a session is dropped; `None` keeps
+    /// sessions until capacity eviction.
+    max_idle_ms: Option<u64>,
 
     /// Session storage by id.
-    map: Mutex<HashMap<Uuid, Session>>,
+    map: Mutex<HashMap<Uuid, Entry>>,
     /// Candidate rotation ring (ids only).
     rr: Mutex<VecDeque<Uuid>>,
+
+    metrics: SessionCacheMetrics,
 }
 
 impl SessionCache {
@@ -29,11 +80,60 @@ impl SessionCache {
         Self {
             max_cached,
             eviction_scan: 64,
+            eviction_policy: Box::new(ColdDrrPolicy),
+            max_idle_ms: None,
             map: Mutex::new(HashMap::new()),
             rr: Mutex::new(VecDeque::new()),
+            metrics: SessionCacheMetrics::default(),
         }
     }
 
+    /// Current hit/miss/insert/update/eviction counters and size.
+    pub fn stats(&self) -> CacheStats {
+        let load = |c: &AtomicU64| c.load(Relaxed);
+        CacheStats {
+            hits: load(&self.metrics.hits),
+            misses: load(&self.metrics.misses),
+            inserts: load(&self.metrics.inserts),
+            updates: load(&self.metrics.updates),
+            evictions: load(&self.metrics.evictions),
+            expired: load(&self.metrics.expired),
+            size: self.map.lock().len(),
+        }
+    }
+
+    /// Count into `metrics` (e.g. `Counters::session_cache`) instead of
+    /// private counters.
+    pub fn with_metrics(mut self, metrics: SessionCacheMetrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Replace the eviction policy (`ColdDrrPolicy` by default).
+    pub fn with_eviction_policy(mut self, policy: impl EvictionPolicy + 'static) -> Self {
+        self.eviction_policy = Box::new(policy);
+        self
+    }
+
+    /// Drop sessions idle for longer than `max_idle_ms` (0 disables).
+    pub fn with_max_idle_ms(mut self, max_idle_ms: u64) -> Self {
+        self.max_idle_ms = (max_idle_ms > 0).then_some(max_idle_ms);
+        self
+    }
+
+    /// True if `e` has been neither served nor upserted within
+    /// `max_idle_ms` of `now_ms` and holds no reservation.
+    fn is_idle(&self, e: &Entry, now_ms: u64) -> bool {
+        let Some(max_idle) = self.max_idle_ms else {
+            return false;
+        };
+        let st = &e.session.state;
+        let last_seen = st.last_served_ms.max(e.upserted_ms);
+        !st.has_pending_batch
+            && st.in_flight_chunks == 0
+            && now_ms.saturating_sub(last_seen) > max_idle
+    }
+
     /// Configure how many RR entries are sampled when choosing an eviction victim.
     /// A minimum of 8 is enforced to avoid pathological eviction.
     pub fn set_eviction_scan(&mut self, n: usize) {
@@ -52,6 +152,15 @@ impl SessionCache {
         self.rr.lock().len()
     }
 
+    /// Number of cached sessions of `pair_id`.
+    pub fn len_pair(&self, pair_id: &str) -> usize {
+        self.map
+            .lock()
+            .values()
+            .filter(|e| e.session.pair_id == pair_id)
+            .count()
+    }
+
     /// Clears both the backing map and the RR ring.
     /// Use when rebuilding cache from persistent storage.
     #[instrument(skip(self), target = "cache")]
@@ -64,35 +173,96 @@ impl SessionCache {
         info!(count, "session cache cleared");
     }
 
+    /// Drops `id` from the map and the RR ring; the next refill reloads it.
+    /// Returns whether it was cached.
+    pub fn remove(&self, id: &Uuid) -> bool {
+        let removed = self.map.lock().remove(id).is_some();
+        self.rr.lock().retain(|x| x != id);
+        removed
+    }
+
     /// Returns a cloned session if it is cached.
     pub fn get(&self, id: &Uuid) -> Option<Session> {
-        self.map.lock().get(id).cloned()
+        let found = self.map.lock().get(id).map(|e| e.session.clone());
+        match found {
+            Some(_) => self.metrics.hits.fetch_add(1, Relaxed),
+            None => self.metrics.misses.fetch_add(1, Relaxed),
+        };
+        found
     }
 
     /// Rotates the RR ring and returns the next candidate id.
     pub fn rotate(&self) -> Option<Uuid> {
+        self.rotate_at(now_ms())
+    }
+
+    /// Like `rotate`, with an explicit clock. Idle sessions reached on the
+    /// way are dropped instead of returned.
+    pub fn rotate_at(&self, now_ms: u64) -> Option<Uuid> {
+        let mut map = self.map.lock();
         let mut rr = self.rr.lock();
-        let id = rr.pop_front()?;
-        rr.push_back(id);
-        Some(id)
+
+        for _ in 0..rr.len() {
+            let id = rr.pop_front()?;
+            match map.get(&id) {
+                Some(e) if self.is_idle(e, now_ms) => {
+                    map.remove(&id);
+                    self.metrics.expired.fetch_add(1, Relaxed);
+                    debug!(session_id = %id, "idle session dropped from cache");
+                }
+                _ => {
+                    rr.push_back(id);
+                    return Some(id);
+                }
+            }
+        }
+        None
     }
 
     /// Insert or update a session and ensure it appears exactly once in the RR ring.
     /// If inserting a new session would exceed capacity, evicts a cold entry first.
+    pub fn upsert(&self, s: Session) {
+        self.upsert_at(s, now_ms())
+    }
+
+    /// Like `upsert`, with an explicit clock. When the cache is full, an idle
+    /// session in the eviction window is dropped before the policy is asked.
     #[instrument(
         skip(self, s),
         target = "cache", 
         fields(session_id = %s.session_id, pair_id = %s.pair_id)
     )]
-    pub fn upsert(&self, s: Session) {
+    pub fn upsert_at(&self, s: Session, now_ms: u64) {
         let mut map = self.map.lock();
         let mut rr = self.rr.lock();
 
         let session_id = s.session_id;
         let is_new = !map.contains_key(&session_id);
 
+        let idle = (is_new && map.len() >= self.max_cached)
+            .then(|| {
+                rr.iter()
+                    .take(self.eviction_scan)
+                    .find(|id| map.get(id).is_some_and(|e| self.is_idle(e, now_ms)))
+                    .copied()
+            })
+            .flatten();
+        if let Some(id) = idle {
+            map.remove(&id);
+            rr.retain(|x| *x != id);
+            self.metrics.expired.fetch_add(1, Relaxed);
+            debug!(session_id = %id, "idle session dropped from cache");
+        }
+
         if is_new && map.len() >= self.max_cached {
-            let victim = if let Some(v) = pick_victim(&map, &rr, self.eviction_scan) {
+            let window: Vec<(Uuid, &Session)> = rr
+                .iter()
+                .take(self.eviction_scan)
+                .filter_map(|id| map.get(id).map(|e| (*id, &e.session)))
+                .collect();
+            let picked = self.eviction_policy.pick_victim(&window);
+
+            let victim = if let Some(v) = picked {
                 v
             } else if let Some(evict) = rr.pop_front() {
                 evict
@@ -103,6 +273,7 @@ impl SessionCache {
 
             map.remove(&victim);
             rr.retain(|x| *x != victim);
+            self.metrics.evictions.fetch_add(1, Relaxed);
 
             info!(
                 evicted_id = %victim,
@@ -111,7 +282,18 @@ impl SessionCache {
             );
         }
 
-        map.insert(session_id, s);
+        map.insert(
+            session_id,
+            Entry {
+                session: s,
+                upserted_ms: now_ms,
+            },
+        );
+        if is_new {
+            self.metrics.inserts.fetch_add(1, Relaxed);
+        } else {
+            self.metrics.updates.fetch_add(1, Relaxed);
+        }
 
         if !rr.contains(&session_id) {
             rr.push_back(session_id);
@@ -122,33 +304,28 @@ impl SessionCache {
     }
 }
 
-/// Select an eviction victim from a bounded prefix of the RR ring.
+/// Chooses which cached session to evict when the cache is full.
+pub trait EvictionPolicy: Send + Sync {
+    /// Pick a victim from `window`, a bounded prefix of the RR ring in ring
+    /// order. `None` falls back to evicting the ring head.
+    fn pick_victim(&self, window: &[(Uuid, &Session)]) -> Option<Uuid>;
+}
+
+/// Default policy: evict the coldest session.
 /// Criteria:
 /// 1) lowest DRR deficit (colder)
 /// 2) if tie, oldest `last_served_ms`
-fn pick_victim(map: &HashMap<Uuid, Session>, rr: &VecDeque<Uuid>, scan: usize) -> Option<Uuid> {
-    let mut best: Option<(Uuid, i128, u64)> = None;
-
-    let n = rr.len().min(scan);
-    for i in 0..n {
-        let id = *rr.get(i)?;
-        let s = map.get(&id)?;
-
-        let cand = (id, s.state.deficit, s.state.last_served_ms);
-
-        best = match best {
-            None => Some(cand),
-            Some((bid, bdef, blast)) => {
-                if cand.1 < bdef || (cand.1 == bdef && cand.2 < blast) {
-                    Some(cand)
-                } else {
-                    Some((bid, bdef, blast))
-                }
-            }
-        };
+/// 3) if still tied, earliest in the ring
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ColdDrrPolicy;
+
+impl EvictionPolicy for ColdDrrPolicy {
+    fn pick_victim(&self, window: &[(Uuid, &Session)]) -> Option<Uuid> {
+        window
+            .iter()
+            .min_by_key(|(_, s)| (s.state.deficit, s.state.last_served_ms))
+            .map(|(id, _)| *id)
     }
-
-    best.map(|x| x.0)
 }
 
 /* =========================
@@ -185,6 +362,11 @@ mod tests {
                 },
                 preferred_chunk_bid: 100_000,
                 max_bid_per_tick: 1_000_000,
+                active_windows: Vec::new(),
+                quantum_weight: 1,
+                twap_interval_ms: 0,
+                expires_at_ms: 0,
+                failure_cooldown_ms: None,
             },
             state: SessionState {
                 remaining_bid: 1_000_000,
@@ -221,6 +403,139 @@ mod tests {
         assert!(cache.rotate().is_none());
     }
 
+    #[test]
+    fn remove_drops_one_session_from_map_and_rr() {
+        let cache = SessionCache::new(10);
+        let a = Uuid::new_v4();
+        let b = Uuid::new_v4();
+
+        cache.upsert(mk_session(a, 0, 0));
+        cache.upsert(mk_session(b, 0, 0));
+
+        assert!(cache.remove(&a));
+        assert!(!cache.remove(&a), "already gone");
+
+        assert_eq!(map_keys(&cache), vec![b]);
+        assert_eq!(cache.len_rr(), 1);
+        assert_eq!(cache.rotate(), Some(b));
+        assert_eq!(cache.rotate(), Some(b));
+    }
+
+    #[test]
+    fn stats_track_hits_misses_upserts_and_evictions() {
+        let cache = SessionCache::new(2);
+        let a = Uuid::new_v4();
+
+        assert!(cache.get(&a).is_none());
+        cache.upsert(mk_session(a, 0, 0));
+        assert!(cache.get(&a).is_some());
+        cache.upsert(mk_session(a, 5, 0));
+
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                inserts: 1,
+                updates: 1,
+                evictions: 0,
+                expired: 0,
+                size: 1,
+            }
+        );
+
+        cache.upsert(mk_session(Uuid::new_v4(), 0, 0));
+        cache.upsert(mk_session(Uuid::new_v4(), 0, 0));
+        let stats = cache.stats();
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.inserts, 3);
+        assert_eq!(stats.updates, 1);
+        assert_eq!(stats.size, 2);
+    }
+
+    #[test]
+    fn shared_metrics_see_every_cache_event() {
+        let metrics = SessionCacheMetrics::default();
+        let cache = SessionCache::new(2).with_metrics(metrics.clone());
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        cache.upsert(mk_session(a, 0, 0));
+        cache.upsert(mk_session(b, 5, 0));
+        cache.upsert(mk_session(b, 6, 0));
+        cache.get(&a);
+        cache.get(&b);
+        // Full: `a` (coldest) makes room for `c`.
+        cache.upsert(mk_session(c, 9, 0));
+        cache.get(&a);
+
+        let load = |c: &AtomicU64| c.load(Relaxed);
+        assert_eq!(load(&metrics.hits), 2);
+        assert_eq!(load(&metrics.misses), 1);
+        assert_eq!(load(&metrics.inserts), 3);
+        assert_eq!(load(&metrics.updates), 1);
+        assert_eq!(load(&metrics.evictions), 1);
+        assert_eq!(cache.stats().size, 2);
+    }
+
+    #[test]
+    fn idle_sessions_are_dropped_lazily_unless_reserved() {
+        let cache = SessionCache::new(10).with_max_idle_ms(1_000);
+        let idle = Uuid::new_v4();
+        let served = Uuid::new_v4();
+        let pending = Uuid::new_v4();
+        let in_flight = Uuid::new_v4();
+
+        cache.upsert_at(mk_session(idle, 0, 0), 0);
+        cache.upsert_at(mk_session(served, 0, 9_500), 0);
+        let mut s = mk_session(pending, 0, 0);
+        s.state.has_pending_batch = true;
+        cache.upsert_at(s, 0);
+        let mut s = mk_session(in_flight, 0, 0);
+        s.state.in_flight_chunks = 1;
+        cache.upsert_at(s, 0);
+
+        // Within the TTL nothing is dropped.
+        assert_eq!(cache.rotate_at(1_000), Some(idle));
+        assert_eq!(cache.len_rr(), 4);
+
+        let mut seen: Vec<Uuid> = (0..6).filter_map(|_| cache.rotate_at(10_000)).collect();
+        seen.sort();
+        seen.dedup();
+        let mut expected = vec![served, pending, in_flight];
+        expected.sort();
+        assert_eq!(seen, expected);
+        assert!(cache.get(&idle).is_none());
+        assert_eq!(cache.stats().expired, 1);
+
+        // A fresh upsert counts as activity.
+        cache.upsert_at(mk_session(idle, 0, 0), 10_000);
+        assert_eq!(cache.len_rr(), 4);
+    }
+
+    #[test]
+    fn full_cache_drops_an_idle_session_before_evicting() {
+        let cache = SessionCache::new(2).with_max_idle_ms(1_000);
+        let (idle, hot, new) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        // `hot` is the coldest by DRR, so the policy alone would pick it.
+        cache.upsert_at(mk_session(hot, -5, 4_500), 0);
+        cache.upsert_at(mk_session(idle, 10, 0), 0);
+        cache.upsert_at(mk_session(new, 0, 0), 5_000);
+
+        let keys = map_keys(&cache);
+        assert!(keys.contains(&hot) && keys.contains(&new), "{keys:?}");
+        let stats = cache.stats();
+        assert_eq!((stats.expired, stats.evictions), (1, 0));
+    }
+
+    #[test]
+    fn ttl_is_disabled_by_default() {
+        let cache = SessionCache::new(2);
+        let id = Uuid::new_v4();
+        cache.upsert_at(mk_session(id, 0, 0), 0);
+        assert_eq!(cache.rotate_at(u64::MAX), Some(id));
+    }
+
     #[test]
     fn bounded_scan_limits_eviction_candidate_pool() {
         let mut cache = SessionCache::new(12);
@@ -257,6 +572,55 @@ mod tests {
         assert!(keys.contains(&incoming));
     }
 
+    /// Evicts the session with the longest remaining cooldown, if any.
+    struct PreferCooldownPolicy;
+
+    impl EvictionPolicy for PreferCooldownPolicy {
+        fn pick_victim(&self, window: &[(Uuid, &Session)]) -> Option<Uuid> {
+            window
+                .iter()
+                .filter(|(_, s)| s.state.cooldown_until_ms > 0)
+                .max_by_key(|(_, s)| s.state.cooldown_until_ms)
+                .map(|(id, _)| *id)
+                .or_else(|| ColdDrrPolicy.pick_victim(window))
+        }
+    }
+
+    #[test]
+    fn custom_policy_evicts_cooling_down_session() {
+        let cache = SessionCache::new(3).with_eviction_policy(PreferCooldownPolicy);
+
+        let cold = Uuid::new_v4();
+        let cooling = Uuid::new_v4();
+        let hot = Uuid::new_v4();
+        cache.upsert(mk_session(cold, -100, 0));
+        let mut s = mk_session(cooling, 500, 10);
+        s.state.cooldown_until_ms = 60_000;
+        cache.upsert(s);
+        cache.upsert(mk_session(hot, 1_000, 20));
+
+        cache.upsert(mk_session(Uuid::new_v4(), 0, 0));
+        let keys = map_keys(&cache);
+        assert!(!keys.contains(&cooling));
+        assert!(keys.contains(&cold));
+
+        // No session cooling down: falls back to the coldest.
+        cache.upsert(mk_session(Uuid::new_v4(), 0, 0));
+        assert!(!map_keys(&cache).contains(&cold));
+        assert_eq!(cache.len_rr(), 3);
+    }
+
+    #[test]
+    fn default_policy_breaks_deficit_ties_by_last_served() {
+        let a = mk_session(Uuid::new_v4(), 5, 300);
+        let b = mk_session(Uuid::new_v4(), 5, 100);
+        let c = mk_session(Uuid::new_v4(), 9, 0);
+        let window = [(a.session_id, &a), (b.session_id, &b), (c.session_id, &c)];
+
+        assert_eq!(ColdDrrPolicy.pick_victim(&window), Some(b.session_id));
+        assert_eq!(ColdDrrPolicy.pick_victim(&[]), None);
+    }
+
     #[test]
     fn drr_math_overflow_safety() {
         let near_max = i128::MAX - 5;
@@ -1,5 +1,8 @@
 use uuid::Uuid;
 
+/// Milliseconds in a UTC day; `now_ms` is mapped onto `[0, MS_PER_DAY)` for window checks.
+pub const MS_PER_DAY: u64 = 86_400_000;
+
 /// Per-session execution constraints (Gate A + Gate B).
 /// Values are expressed in basis points (bps).
 #[derive(Clone, Debug)]
@@ -22,6 +25,27 @@ pub struct SessionIntent {
     pub preferred_chunk_bid: u128,
     /// Upper bound on volume this session should execute per tick.
     pub max_bid_per_tick: u128,
+
+    /// UTC time-of-day windows `(start_ms_of_day, end_ms_of_day)` in which the
+    /// session may execute. Start is inclusive, end exclusive; a window with
+    /// `start > end` spans midnight. Empty means always active.
+    pub active_windows: Vec<(u64, u64)>,
+
+    /// DRR tier multiplier: each scheduling pass adds
+    /// `quantum * quantum_weight` credit (1 = baseline, 0 is treated as 1).
+    pub quantum_weight: u32,
+
+    /// TWAP spacing: the session is not scheduled again until this many ms
+    /// have passed since `last_served_ms`, whatever its DRR credit (0 = off).
+    pub twap_interval_ms: u64,
+
+    /// Absolute expiry (ms since epoch); the session is no longer eligible
+    /// once `now_ms` passes it (0 = never expires).
+    pub expires_at_ms: u64,
+
+    /// Cooldown (ms) proposed after a failed chunk; `None` uses the
+    /// executor's `default_failure_cooldown_ms`.
+    pub failure_cooldown_ms: Option<u64>,
 }
 
 /// Runtime state for a session.
@@ -85,6 +109,36 @@ impl Session {
             && self.state.cooldown_until_ms <= now_ms
             && self.available_bid() > 0
             && self.available_chunks() > 0
+            && self.in_active_window(now_ms)
+            && !self.is_expired(now_ms)
+    }
+
+    /// True once `now_ms` is past a non-zero `expires_at_ms`.
+    pub fn is_expired(&self, now_ms: u64) -> bool {
+        self.intent.expires_at_ms != 0 && now_ms > self.intent.expires_at_ms
+    }
+
+    /// True if `now_ms` falls inside one of the session's UTC execution windows
+    /// (or no windows are configured).
+    pub fn in_active_window(&self, now_ms: u64) -> bool {
+        if self.intent.active_windows.is_empty() {
+            return true;
+        }
+
+        let tod = now_ms % MS_PER_DAY;
+        self.intent
+            .active_windows
+            .iter()
+            .any(|&(start, end)| match start.cmp(&end) {
+                std::cmp::Ordering::Less => start <= tod && tod < end,
+                std::cmp::Ordering::Greater => tod >= start || tod < end,
+                std::cmp::Ordering::Equal => false,
+            })
+    }
+
+    /// True once the TWAP spacing since the last service has elapsed.
+    pub fn twap_ready(&self, now_ms: u64) -> bool {
+        now_ms.saturating_sub(self.state.last_served_ms) >= self.intent.twap_interval_ms
     }
 
     /// DRR fairness check: does the session have enough accumulated credit?
@@ -118,6 +172,11 @@ mod tests {
                 },
                 preferred_chunk_bid: 100_000,
                 max_bid_per_tick: 1_000_000,
+                active_windows: Vec::new(),
+                quantum_weight: 1,
+                twap_interval_ms: 0,
+                expires_at_ms: 0,
+                failure_cooldown_ms: None,
             },
             state: SessionState {
                 remaining_bid,
@@ -261,6 +320,75 @@ mod tests {
         assert!(!eligible_basic(&s, 100));
     }
 
+    const HOUR_MS: u64 = 3_600_000;
+
+    #[test]
+    fn eligibility_true_inside_active_window() {
+        let mut s = mk_session(10_000, 0, 10, 0, 0, true);
+        s.intent.active_windows = vec![(9 * HOUR_MS, 17 * HOUR_MS)];
+
+        // Day 3, 12:00 UTC.
+        let now = 3 * MS_PER_DAY + 12 * HOUR_MS;
+        assert!(s.in_active_window(now));
+        assert!(s.is_eligible(now));
+
+        // Start inclusive, end exclusive.
+        assert!(s.is_eligible(3 * MS_PER_DAY + 9 * HOUR_MS));
+        assert!(!s.is_eligible(3 * MS_PER_DAY + 17 * HOUR_MS));
+    }
+
+    #[test]
+    fn eligibility_false_outside_active_window() {
+        let mut s = mk_session(10_000, 0, 10, 0, 0, true);
+        s.intent.active_windows = vec![(9 * HOUR_MS, 17 * HOUR_MS)];
+
+        let now = 3 * MS_PER_DAY + 20 * HOUR_MS;
+        assert!(!s.in_active_window(now));
+        assert!(!s.is_eligible(now));
+    }
+
+    #[test]
+    fn active_window_spanning_midnight() {
+        let mut s = mk_session(10_000, 0, 10, 0, 0, true);
+        s.intent.active_windows = vec![(22 * HOUR_MS, 2 * HOUR_MS)];
+
+        assert!(s.is_eligible(MS_PER_DAY + 23 * HOUR_MS));
+        assert!(s.is_eligible(2 * MS_PER_DAY + HOUR_MS));
+        assert!(s.is_eligible(2 * MS_PER_DAY));
+        assert!(!s.is_eligible(2 * MS_PER_DAY + 2 * HOUR_MS));
+        assert!(!s.is_eligible(2 * MS_PER_DAY + 12 * HOUR_MS));
+    }
+
+    #[test]
+    fn twap_spacing_counts_from_last_service() {
+        let mut s = mk_session(10_000, 0, 10, 0, 0, true);
+        assert!(s.twap_ready(0), "no spacing configured");
+
+        s.intent.twap_interval_ms = 10_000;
+        s.state.last_served_ms = 5_000;
+        assert!(!s.twap_ready(14_999));
+        assert!(s.twap_ready(15_000));
+    }
+
+    #[test]
+    fn empty_active_windows_means_always_active() {
+        let s = mk_session(10_000, 0, 10, 0, 0, true);
+        assert!(s.intent.active_windows.is_empty());
+
+        for h in 0..24 {
+            assert!(s.is_eligible(MS_PER_DAY + h * HOUR_MS));
+        }
+    }
+
+    #[test]
+    fn any_matching_window_is_sufficient() {
+        let mut s = mk_session(10_000, 0, 10, 0, 0, true);
+        s.intent.active_windows = vec![(HOUR_MS, 2 * HOUR_MS), (10 * HOUR_MS, 11 * HOUR_MS)];
+
+        assert!(s.is_eligible(10 * HOUR_MS + 30 * 60_000));
+        assert!(!s.is_eligible(5 * HOUR_MS));
+    }
+
     #[test]
     fn test_drr_credit_flow() {
         let mut s = mk_session(100_000, 0, 10, 0, 0, true);
@@ -305,6 +433,17 @@ mod tests {
         assert!(s.has_sufficient_credit());
     }
 
+    #[test]
+    fn expired_session_is_not_eligible() {
+        let mut s = mk_session(10_000, 0, 10, 0, 0, true);
+        assert!(!s.is_expired(u64::MAX), "0 never expires");
+
+        s.intent.expires_at_ms = 5_000;
+        assert!(s.is_eligible(5_000));
+        assert!(s.is_expired(5_001));
+        assert!(!s.is_eligible(5_001));
+    }
+
     #[test]
     fn test_eligibility_with_residual_dust() {
         // User wants 100k chunks, but only has 500 total left.
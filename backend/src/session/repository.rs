@@ -1,17 +1,48 @@
-use anyhow::Result;
 use async_trait::async_trait;
+use std::collections::HashMap;
 use uuid::Uuid;
 
-use crate::execution::types::{ReservedBatch, UserResult};
+use crate::error::{ReassignPairError, RepositoryError};
+use crate::execution::types::{
+    ChunkEvent, PendingChunk, RecoveryReport, ReservedBatch, UserResult,
+};
 use crate::planner::types::PlannedAllocation;
 use crate::session::model::Session;
 
+type Result<T, E = RepositoryError> = std::result::Result<T, E>;
+
 #[async_trait]
 pub trait SessionRepository: Send + Sync {
-    async fn fetch_page(&self, limit: usize, offset: usize) -> Result<Vec<Session>>;
+    /// Eligible sessions, restricted to `pair_id` when given (all pairs
+    /// when `None`).
+    async fn fetch_page(
+        &self,
+        limit: usize,
+        offset: usize,
+        pair_id: Option<&str>,
+    ) -> Result<Vec<Session>>;
+
+    /// Keyset pagination: eligible sessions ordered by `session_id`, strictly
+    /// after `after_session_id` (from the start when `None`). Unlike
+    /// `fetch_page`, cost does not grow with the position in the table.
+    /// `pair_id` filters as in `fetch_page`.
+    async fn fetch_page_after(
+        &self,
+        limit: usize,
+        after_session_id: Option<Uuid>,
+        pair_id: Option<&str>,
+    ) -> Result<Vec<Session>>;
 
     async fn fetch_by_id(&self, session_id: &Uuid) -> Result<Option<Session>>;
 
+    /// Batched `fetch_by_id`. Ids without a (well-formed) row are absent
+    /// from the returned map.
+    async fn fetch_by_ids(&self, session_ids: &[Uuid]) -> Result<HashMap<Uuid, Session>>;
+
+    /// Deactivates active sessions whose non-zero `expires_at_ms` is before
+    /// `now_ms`. Returns how many were expired.
+    async fn expire_due(&self, now_ms: u64) -> Result<u64>;
+
     async fn persist_fairness(
         &self,
         session_id: &Uuid,
@@ -24,11 +55,84 @@ pub trait SessionRepository: Send + Sync {
         pair_id: &str,
         now_ms: u64,
         allocations: &[PlannedAllocation],
-    ) -> anyhow::Result<Option<ReservedBatch>>;
+    ) -> Result<Option<ReservedBatch>>;
+
+    /// Claims a RESERVED batch for execution (RESERVED → EXECUTING), so that
+    /// `recover_stale` leaves it to the worker. Returns false if the batch
+    /// is no longer RESERVED (e.g. recovered or aborted meanwhile); such a
+    /// batch must not be executed.
+    async fn claim_batch(&self, batch_id: &Uuid) -> Result<bool>;
 
-    /// Finalizes a RESERVED batch based on executor results.
-    /// Must be atomic and idempotent.
+    /// Finalizes a RESERVED or EXECUTING batch based on executor results.
+    /// Must be atomic and idempotent: an already COMMITTED or ABORTED batch
+    /// is an `Ok(())` no-op, losing a concurrent finalization is `Conflict`.
+    ///
+    /// If any chunk is `ChunkStatus::Unknown`, the batch is not finalized:
+    /// the other outcomes are recorded on their items (and cooldowns
+    /// applied), the unknown item stays PENDING and the batch goes back to
+    /// RESERVED, and recovery settles everything once `query_status`
+    /// answers.
     async fn commit_batch(&self, batch: &ReservedBatch, results: &[UserResult]) -> Result<()>;
 
-    async fn recover_uncommitted(&self) -> anyhow::Result<()>;
+    /// Counts one failed `commit_batch` against a RESERVED or EXECUTING batch
+    /// (`commit_attempts`). No-op for finalized batches.
+    async fn record_commit_failure(&self, batch_id: &Uuid) -> Result<()>;
+
+    /// Parks a RESERVED or EXECUTING batch whose commit keeps failing: stores `results`
+    /// for manual reconciliation and marks the batch DEAD_LETTER so restart
+    /// recovery does not unwind chunks that may have executed on-chain.
+    /// In-flight accounting and session locks are left as they are.
+    /// No-op for COMMITTED or ABORTED batches.
+    async fn dead_letter_batch(
+        &self,
+        batch: &ReservedBatch,
+        results: &[UserResult],
+        error: &str,
+    ) -> Result<()>;
+
+    /// PENDING items of RESERVED and EXECUTING batches, i.e. chunks a crash
+    /// may have left executed on-chain without a recorded outcome.
+    async fn pending_chunks(&self) -> Result<Vec<PendingChunk>>;
+
+    /// Records a PENDING chunk as SUCCESS with `tx_id`, so that
+    /// `recover_uncommitted` settles it instead of unwinding it. Returns
+    /// false if the item is not PENDING in a RESERVED or EXECUTING batch.
+    async fn settle_landed_chunk(
+        &self,
+        batch_id: &Uuid,
+        chunk_id: &Uuid,
+        tx_id: &str,
+    ) -> Result<bool>;
+
+    /// Reconciles every RESERVED or EXECUTING batch left behind by a crash: items that
+    /// already carry an outcome get the same accounting `commit_batch`
+    /// applies, PENDING items are unwound. The batch ends COMMITTED if any
+    /// item had an outcome, ABORTED otherwise. Must be idempotent.
+    async fn recover_uncommitted(&self) -> Result<RecoveryReport>;
+
+    /// `recover_uncommitted` limited to RESERVED batches reserved before
+    /// `reserved_before_ms`, for reconciling stuck batches while workers run.
+    /// EXECUTING batches belong to a live worker and are left alone.
+    async fn recover_stale(&self, reserved_before_ms: u64) -> Result<RecoveryReport>;
+
+    /// Aborts a RESERVED batch that will never be executed, unwinding its
+    /// in-flight accounting. No-op for COMMITTED or ABORTED batches.
+    async fn abort_batch(&self, batch_id: &Uuid, reason: &str) -> Result<()>;
+
+    /// Settled chunk outcomes of a session, newest first, at most `limit`.
+    /// Every chunk appears once: the event is written in the same
+    /// transaction that settles it.
+    async fn fetch_chunk_history(&self, session_id: &Uuid, limit: usize)
+    -> Result<Vec<ChunkEvent>>;
+
+    /// Moves a session to `new_pair` (e.g. symbol migration).
+    ///
+    /// Refuses while the session has a pending batch or in-flight volume,
+    /// since the reserved chunks belong to the old pair's executor. Cached
+    /// copies are not updated; `SessionStore::reassign_pair` refreshes them.
+    async fn reassign_pair(
+        &self,
+        session_id: &Uuid,
+        new_pair: &str,
+    ) -> Result<(), ReassignPairError>;
 }
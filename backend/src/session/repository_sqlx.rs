@@ -1,36 +1,289 @@
-use anyhow::{Context, anyhow};
 use async_trait::async_trait;
 use sqlx::{AnyPool, Row};
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::execution::types::{ChunkStatus, ReservedBatch, UserResult};
-use crate::execution::{u32_to_i64, u128_to_i64};
+use crate::error::{ReassignPairError, RepositoryError};
+use crate::execution::types::{
+    ChunkEvent, ChunkResult, ChunkStatus, PendingChunk, RecoveryReport, ReservedBatch, UserResult,
+};
 use crate::planner::types::PlannedAllocation;
-use crate::session::model::{Session, SessionIntent, SessionState, UserConstraints};
+use crate::session::model::{MS_PER_DAY, Session, SessionIntent, SessionState, UserConstraints};
 use crate::session::repository::SessionRepository;
 use crate::time::now_ms;
 
+type Result<T, E = RepositoryError> = std::result::Result<T, E>;
+
+/// Max ids bound into one `IN (...)` list. Keeps `fetch_by_ids` well under
+/// SQLite's bind-parameter limit (999 on older builds).
+const MAX_IDS_PER_QUERY: usize = 500;
+
+/// Max `batch_items` rows per multi-row INSERT (4 bound parameters each),
+/// under the same bind-parameter limit.
+const MAX_ITEMS_PER_INSERT: usize = 200;
+
+/// SQL dialect of the primary database.
+///
+/// Queries are written with `?` placeholders (SQLite); on Postgres they are
+/// rewritten to `$n`, and `reserve_execution` locks each candidate row with
+/// `FOR UPDATE SKIP LOCKED` so concurrent schedulers skip rows another
+/// reserver holds instead of waiting on them.
+///
+/// Everything else is kept to the subset both engines share: `BOOLEAN`
+/// columns are compared against `TRUE`/`FALSE` (Postgres rejects `= 1`),
+/// read back through `CASE WHEN`/`CAST(.. AS INTEGER)`, and every statement
+/// goes through [`SqlDialect::sql`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SqlDialect {
+    #[default]
+    Sqlite,
+    Postgres,
+}
+
+impl SqlDialect {
+    /// Dialect for a connection URL (`postgres://`/`postgresql://` → Postgres).
+    pub fn from_url(url: &str) -> Self {
+        if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+            Self::Postgres
+        } else {
+            Self::Sqlite
+        }
+    }
+
+    /// Adapts a `?`-placeholder query to this dialect.
+    pub fn sql<'a>(&self, query: &'a str) -> Cow<'a, str> {
+        match self {
+            Self::Sqlite => Cow::Borrowed(query),
+            Self::Postgres => {
+                let mut out = String::with_capacity(query.len() + 8);
+                let mut n = 0;
+                for c in query.chars() {
+                    if c == '?' {
+                        n += 1;
+                        out.push('$');
+                        out.push_str(&n.to_string());
+                    } else {
+                        out.push(c);
+                    }
+                }
+                Cow::Owned(out)
+            }
+        }
+    }
+}
+
 /// SQLx-backed implementation of SessionRepository.
 /// Responsible only for persistence and row mapping.
+///
+/// Candidate reads (`fetch_page`, `fetch_page_after`, `fetch_by_id`,
+/// `fetch_by_ids`) go to `read_pool`, which is a replica when configured and
+/// the primary otherwise. Every mutation, and any read inside a mutating
+/// transaction, uses the primary.
 pub struct SqlxSessionRepository {
     pool: Arc<AnyPool>,
+    read_pool: Arc<AnyPool>,
+    dialect: SqlDialect,
 }
 
 impl SqlxSessionRepository {
     pub fn new(pool: Arc<AnyPool>) -> Self {
-        Self { pool }
+        Self {
+            read_pool: pool.clone(),
+            pool,
+            dialect: SqlDialect::Sqlite,
+        }
+    }
+
+    /// Routes candidate reads to `replica`, falling back to `primary` if `None`.
+    ///
+    /// Replica lag only delays visibility of sessions; reservation CAS and
+    /// commit always run against the primary, so stale reads cannot double-book.
+    pub fn with_read_pool(primary: Arc<AnyPool>, replica: Option<Arc<AnyPool>>) -> Self {
+        Self {
+            read_pool: replica.unwrap_or_else(|| primary.clone()),
+            pool: primary,
+            dialect: SqlDialect::Sqlite,
+        }
+    }
+
+    /// Sets the SQL dialect of the primary (SQLite by default).
+    pub fn with_dialect(mut self, dialect: SqlDialect) -> Self {
+        self.dialect = dialect;
+        self
     }
 
     pub fn pool(&self) -> &AnyPool {
         &self.pool
     }
+
+    pub fn read_pool(&self) -> &AnyPool {
+        &self.read_pool
+    }
+
+    /// Recovery of RESERVED batches created before `created_before_ms`, and
+    /// of EXECUTING ones too if `include_claimed`; see
+    /// `SessionRepository::recover_uncommitted`. Unwound items and the
+    /// finalized batch are tagged with `reason`.
+    async fn recover_reserved(
+        &self,
+        created_before_ms: i64,
+        include_claimed: bool,
+        reason: &str,
+    ) -> Result<RecoveryReport> {
+        let statuses = if include_claimed {
+            "'RESERVED', 'EXECUTING'"
+        } else {
+            "'RESERVED'"
+        };
+        let sql = format!(
+            "SELECT batch_id, status FROM batches WHERE status IN ({statuses}) AND created_ms < ?;"
+        );
+        let batches = sqlx::query(&self.dialect.sql(&sql))
+            .bind(created_before_ms)
+            .fetch_all(&*self.pool)
+            .await?;
+
+        let now_i64 = u64_to_i64(now_ms())?;
+        let mut report = RecoveryReport::default();
+
+        for b in batches {
+            let batch_id: String = b.get("batch_id");
+            let batch_status: String = b.get("status");
+
+            let mut tx = self.pool.begin().await?;
+
+            // Items that already carry an outcome: their row was written but
+            // the commit math never ran. Settle them exactly as commit_batch
+            // would have.
+            let settled = sqlx::query(&self.dialect.sql(
+                r#"
+SELECT session_id, chunk_id, bid, status, tx_id, error
+FROM batch_items
+WHERE batch_id = ? AND status != 'PENDING'
+ORDER BY session_id, chunk_id;
+"#,
+            ))
+            .bind(&batch_id)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            let mut touched_sessions = std::collections::BTreeSet::new();
+            let mut poisoned = false;
+
+            for it in &settled {
+                let session_id: String = it.get("session_id");
+                let Some(status) = item_status(
+                    &it.get::<String, _>("status"),
+                    it.get("tx_id"),
+                    it.get("error"),
+                ) else {
+                    poisoned = true;
+                    break;
+                };
+
+                settle_session(
+                    &mut tx,
+                    self.dialect,
+                    &session_id,
+                    it.get("bid"),
+                    &status,
+                    now_i64,
+                )
+                .await?;
+                record_chunk_event(
+                    &mut tx,
+                    self.dialect,
+                    &batch_id,
+                    &session_id,
+                    &it.get::<String, _>("chunk_id"),
+                    &status,
+                    now_i64,
+                )
+                .await?;
+                touched_sessions.insert(session_id);
+            }
+
+            if poisoned {
+                // Dropping `tx` rolls back; the batch stays RESERVED for an operator.
+                tracing::warn!(%batch_id, "unknown batch item status; batch not recovered");
+                continue;
+            }
+
+            // Chunks without an outcome were never (known to be) executed.
+            let (unwound, released) =
+                unwind_pending_items(&mut tx, self.dialect, &batch_id, reason).await?;
+
+            if settled.is_empty() && unwound == 0 {
+                // Nothing to reconcile; dropping `tx` rolls back.
+                continue;
+            }
+
+            for sid in touched_sessions {
+                sqlx::query(&self.dialect.sql(
+                    r#"
+UPDATE sessions
+SET has_pending_batch = FALSE
+WHERE session_id = ?;
+"#,
+                ))
+                .bind(sid)
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            // Any settled outcome makes this a (late) commit; otherwise nothing
+            // happened and the batch is aborted.
+            let status = if settled.is_empty() {
+                "ABORTED"
+            } else {
+                "COMMITTED"
+            };
+
+            let finalized = sqlx::query(&self.dialect.sql(
+                r#"
+UPDATE batches
+SET status=?, reason=?
+WHERE batch_id = ? AND status = ?;
+"#,
+            ))
+            .bind(status)
+            .bind(reason)
+            .bind(&batch_id)
+            .bind(&batch_status)
+            .execute(&mut *tx)
+            .await?;
+
+            if finalized.rows_affected() != 1 {
+                // Finalized or claimed concurrently; dropping `tx` rolls back.
+                continue;
+            }
+
+            tx.commit().await?;
+
+            if settled.is_empty() {
+                report.batches_aborted += 1;
+            } else {
+                report.batches_committed += 1;
+            }
+            report.chunks_unwound += unwound;
+            report.bid_released += released;
+        }
+
+        Ok(report)
+    }
 }
 
 #[async_trait]
 impl SessionRepository for SqlxSessionRepository {
-    async fn fetch_page(&self, limit: usize, offset: usize) -> anyhow::Result<Vec<Session>> {
-        let rows = sqlx::query(
+    async fn fetch_page(
+        &self,
+        limit: usize,
+        offset: usize,
+        pair_id: Option<&str>,
+    ) -> Result<Vec<Session>> {
+        let sql = format!(
             r#"
 SELECT
   session_id, pair_id, CASE WHEN active THEN 1 ELSE 0 END AS active_i64,
@@ -40,16 +293,27 @@ SELECT
   in_flight_bid, in_flight_chunks,
   cooldown_until_ms,
   quantum, deficit, last_served_ms,
-  CAST(has_pending_batch AS INTEGER) AS has_pending_batch
+  CAST(has_pending_batch AS INTEGER) AS has_pending_batch,
+  active_windows, quantum_weight, twap_interval_ms, expires_at_ms,
+  failure_cooldown_ms
 FROM sessions
 WHERE active = TRUE AND remaining_bid > 0 AND remaining_chunks > 0
+  {filter}
 LIMIT ? OFFSET ?;
 "#,
-        )
-        .bind(limit as i64)
-        .bind(offset as i64)
-        .fetch_all(&*self.pool)
-        .await?;
+            filter = pair_filter(pair_id),
+        );
+
+        let sql = self.dialect.sql(&sql);
+        let mut q = sqlx::query(&sql);
+        if let Some(pair_id) = pair_id {
+            q = q.bind(pair_id);
+        }
+        let rows = q
+            .bind(limit as i64)
+            .bind(offset as i64)
+            .fetch_all(&*self.read_pool)
+            .await?;
 
         let mut out = Vec::new();
         for r in rows {
@@ -65,8 +329,64 @@ LIMIT ? OFFSET ?;
         Ok(out)
     }
 
-    async fn fetch_by_id(&self, session_id: &Uuid) -> anyhow::Result<Option<Session>> {
-        let row = sqlx::query(
+    async fn fetch_page_after(
+        &self,
+        limit: usize,
+        after_session_id: Option<Uuid>,
+        pair_id: Option<&str>,
+    ) -> Result<Vec<Session>> {
+        // Every session_id sorts after the empty string, so `None` starts
+        // the scan from the beginning without a separate query.
+        let cursor = after_session_id
+            .map(|id| id.to_string())
+            .unwrap_or_default();
+
+        let sql = format!(
+            r#"
+SELECT
+  session_id, pair_id, CASE WHEN active THEN 1 ELSE 0 END AS active_i64,
+  max_spread_bps, max_trend_drop_bps, max_slippage_bps,
+  preferred_chunk_bid, max_bid_per_tick,
+  remaining_bid, remaining_chunks,
+  in_flight_bid, in_flight_chunks,
+  cooldown_until_ms,
+  quantum, deficit, last_served_ms,
+  CAST(has_pending_batch AS INTEGER) AS has_pending_batch,
+  active_windows, quantum_weight, twap_interval_ms, expires_at_ms,
+  failure_cooldown_ms
+FROM sessions
+WHERE active = TRUE AND remaining_bid > 0 AND remaining_chunks > 0
+  AND session_id > ?
+  {filter}
+ORDER BY session_id
+LIMIT ?;
+"#,
+            filter = pair_filter(pair_id),
+        );
+
+        let sql = self.dialect.sql(&sql);
+        let mut q = sqlx::query(&sql).bind(cursor);
+        if let Some(pair_id) = pair_id {
+            q = q.bind(pair_id);
+        }
+        let rows = q.bind(limit as i64).fetch_all(&*self.read_pool).await?;
+
+        let mut out = Vec::new();
+        for r in rows {
+            match row_to_session(&r) {
+                Ok(s) => out.push(s),
+                Err(e) => {
+                    // poison-row resilience: skip but don’t fail the batch
+                    tracing::warn!(error = %e, "skipping malformed session row");
+                }
+            }
+        }
+
+        Ok(out)
+    }
+
+    async fn fetch_by_id(&self, session_id: &Uuid) -> Result<Option<Session>> {
+        let row = sqlx::query(&self.dialect.sql(
             r#"
 SELECT
   session_id, pair_id, CASE WHEN active THEN 1 ELSE 0 END AS active_i64,
@@ -76,13 +396,15 @@ SELECT
   in_flight_bid, in_flight_chunks,
   cooldown_until_ms,
   quantum, deficit, last_served_ms, 
-  CAST(has_pending_batch AS INTEGER) AS has_pending_batch
+  CAST(has_pending_batch AS INTEGER) AS has_pending_batch,
+  active_windows, quantum_weight, twap_interval_ms, expires_at_ms,
+  failure_cooldown_ms
 FROM sessions
 WHERE session_id = ?;
 "#,
-        )
+        ))
         .bind(session_id.to_string())
-        .fetch_optional(&*self.pool)
+        .fetch_optional(&*self.read_pool)
         .await?;
 
         match row {
@@ -91,22 +413,85 @@ WHERE session_id = ?;
         }
     }
 
+    async fn fetch_by_ids(&self, session_ids: &[Uuid]) -> Result<HashMap<Uuid, Session>> {
+        let mut out = HashMap::with_capacity(session_ids.len());
+
+        for ids in session_ids.chunks(MAX_IDS_PER_QUERY) {
+            let placeholders = vec!["?"; ids.len()].join(", ");
+            let sql = format!(
+                r#"
+SELECT
+  session_id, pair_id, CASE WHEN active THEN 1 ELSE 0 END AS active_i64,
+  max_spread_bps, max_trend_drop_bps, max_slippage_bps,
+  preferred_chunk_bid, max_bid_per_tick,
+  remaining_bid, remaining_chunks,
+  in_flight_bid, in_flight_chunks,
+  cooldown_until_ms,
+  quantum, deficit, last_served_ms,
+  CAST(has_pending_batch AS INTEGER) AS has_pending_batch,
+  active_windows, quantum_weight, twap_interval_ms, expires_at_ms,
+  failure_cooldown_ms
+FROM sessions
+WHERE session_id IN ({placeholders});
+"#
+            );
+
+            let sql = self.dialect.sql(&sql);
+            let mut q = sqlx::query(&sql);
+            for id in ids {
+                q = q.bind(id.to_string());
+            }
+            let rows = q.fetch_all(&*self.read_pool).await?;
+
+            for r in rows {
+                match row_to_session(&r) {
+                    Ok(s) => {
+                        out.insert(s.session_id, s);
+                    }
+                    Err(e) => {
+                        // poison-row resilience: the id is reported as missing
+                        tracing::warn!(error = %e, "skipping malformed session row");
+                    }
+                }
+            }
+        }
+
+        Ok(out)
+    }
+
+    async fn expire_due(&self, now_ms: u64) -> Result<u64> {
+        let res = sqlx::query(&self.dialect.sql(
+            r#"
+UPDATE sessions
+SET active = FALSE
+WHERE active = TRUE
+  AND expires_at_ms > 0
+  AND expires_at_ms < ?;
+"#,
+        ))
+        .bind(u64_to_i64(now_ms)?)
+        .execute(&*self.pool)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
     async fn persist_fairness(
         &self,
         session_id: &Uuid,
         deficit: i128,
         last_served_ms: u64,
-    ) -> anyhow::Result<()> {
+    ) -> Result<()> {
         let deficit_i64 = i128_to_i64(deficit)?;
         let last_served_i64 = u64_to_i64(last_served_ms)?;
 
-        sqlx::query(
+        sqlx::query(&self.dialect.sql(
             r#"
 UPDATE sessions
 SET deficit = ?, last_served_ms = ?
 WHERE session_id = ?;
 "#,
-        )
+        ))
         .bind(deficit_i64)
         .bind(last_served_i64)
         .bind(session_id.to_string())
@@ -121,7 +506,7 @@ WHERE session_id = ?;
         pair_id: &str,
         now_ms: u64,
         allocations: &[PlannedAllocation],
-    ) -> anyhow::Result<Option<ReservedBatch>> {
+    ) -> Result<Option<ReservedBatch>> {
         use crate::execution::types::{ReservedBatch, ReservedChunk, ReservedUser};
 
         let mut tx = self.pool.begin().await?;
@@ -136,27 +521,52 @@ WHERE session_id = ?;
             let total_bid: u128 = a.chunks.iter().copied().sum();
             let total_chunks = a.chunks.len() as u32;
 
+            // Postgres: take the row lock up front, skipping rows another
+            // reserver holds. The CAS below still guards the reservation.
+            if self.dialect == SqlDialect::Postgres {
+                let locked = sqlx::query(&self.dialect.sql(
+                    r#"
+SELECT session_id FROM sessions
+WHERE session_id = ? AND pair_id = ?
+FOR UPDATE SKIP LOCKED;
+"#,
+                ))
+                .bind(a.session_id.to_string())
+                .bind(pair_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+
+                if locked.is_none() {
+                    tracing::debug!(
+                        session_id = %a.session_id,
+                        pair_id = %pair_id,
+                        "session row locked by another reserver; skipping"
+                    );
+                    continue;
+                }
+            }
+
             // Try to reserve this session.
-            let res = sqlx::query(
+            let res = sqlx::query(&self.dialect.sql(
                 r#"
 UPDATE sessions
 SET in_flight_bid     = in_flight_bid + ?,
     in_flight_chunks  = in_flight_chunks + ?,
-    has_pending_batch = 1
+    has_pending_batch = TRUE
 WHERE session_id = ?
   AND pair_id = ?
-  AND active = 1
-  AND has_pending_batch = 0
+  AND active = TRUE
+  AND has_pending_batch = FALSE
   AND (remaining_bid - in_flight_bid) >= ?
   AND (remaining_chunks - in_flight_chunks) >= ?;
 "#,
-            )
+            ))
             .bind(u128_to_i64(total_bid)?)
-            .bind(u32_to_i64(total_chunks)?)
+            .bind(i64::from(total_chunks))
             .bind(a.session_id.to_string())
             .bind(pair_id)
             .bind(u128_to_i64(total_bid)?)
-            .bind(u32_to_i64(total_chunks)?)
+            .bind(i64::from(total_chunks))
             .execute(&mut *tx)
             .await?;
 
@@ -173,12 +583,12 @@ WHERE session_id = ?
             }
 
             if !total_reserved_any {
-                sqlx::query(
+                sqlx::query(&self.dialect.sql(
                     r#"
 INSERT INTO batches(batch_id, pair_id, created_ms, status, reason)
 VALUES (?, ?, ?, 'RESERVED', '');
 "#,
-                )
+                ))
                 .bind(batch_id.to_string())
                 .bind(pair_id)
                 .bind(u64_to_i64(now_ms)?)
@@ -188,27 +598,33 @@ VALUES (?, ?, ?, 'RESERVED', '');
                 total_reserved_any = true;
             }
 
-            // Create batch items for this reserved session
-            let mut chunks_out = Vec::new();
-            for bid in &a.chunks {
-                let chunk_id = Uuid::new_v4();
-                sqlx::query(
-                    r#"
-INSERT INTO batch_items(chunk_id, batch_id, session_id, bid, status, tx_id, error)
-VALUES (?, ?, ?, ?, 'PENDING', '', '');
-"#,
-                )
-                .bind(chunk_id.to_string())
-                .bind(batch_id.to_string())
-                .bind(a.session_id.to_string())
-                .bind(u128_to_i64(*bid)?)
-                .execute(&mut *tx)
-                .await?;
-
-                chunks_out.push(ReservedChunk {
-                    chunk_id,
-                    bid: *bid,
-                });
+            // Create batch items for this reserved session, one multi-row
+            // INSERT per slice instead of a round-trip per chunk.
+            let chunks_out: Vec<ReservedChunk> = a
+                .chunks
+                .iter()
+                .map(|&bid| ReservedChunk {
+                    chunk_id: Uuid::new_v4(),
+                    bid,
+                })
+                .collect();
+
+            for rows in chunks_out.chunks(MAX_ITEMS_PER_INSERT) {
+                let values = vec!["(?, ?, ?, ?, 'PENDING', '', '')"; rows.len()].join(", ");
+                let sql = format!(
+                    "INSERT INTO batch_items(chunk_id, batch_id, session_id, bid, status, tx_id, error) VALUES {values};"
+                );
+                let sql = self.dialect.sql(&sql);
+
+                let mut q = sqlx::query(&sql);
+                for c in rows {
+                    q = q
+                        .bind(c.chunk_id.to_string())
+                        .bind(batch_id.to_string())
+                        .bind(a.session_id.to_string())
+                        .bind(u128_to_i64(c.bid)?);
+                }
+                q.execute(&mut *tx).await?;
             }
 
             users_out.push(ReservedUser {
@@ -233,35 +649,83 @@ VALUES (?, ?, ?, ?, 'PENDING', '', '');
         }))
     }
 
-    async fn commit_batch(
-        &self,
-        batch: &ReservedBatch,
-        results: &[UserResult],
-    ) -> anyhow::Result<()> {
+    async fn claim_batch(&self, batch_id: &Uuid) -> Result<bool> {
+        let claimed = sqlx::query(&self.dialect.sql(
+            r#"
+UPDATE batches
+SET status='EXECUTING'
+WHERE batch_id = ? AND status = 'RESERVED';
+"#,
+        ))
+        .bind(batch_id.to_string())
+        .execute(&*self.pool)
+        .await?;
+
+        Ok(claimed.rows_affected() == 1)
+    }
+
+    async fn commit_batch(&self, batch: &ReservedBatch, results: &[UserResult]) -> Result<()> {
         let mut tx = self.pool.begin().await?;
 
-        let row = sqlx::query("SELECT status FROM batches WHERE batch_id = ?")
-            .bind(batch.batch_id.to_string())
-            .fetch_one(&mut *tx)
-            .await?;
+        let row = sqlx::query(
+            &self
+                .dialect
+                .sql("SELECT status FROM batches WHERE batch_id = ?"),
+        )
+        .bind(batch.batch_id.to_string())
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| RepositoryError::NotFound(format!("batch {}", batch.batch_id)))?;
 
         let status: String = row.get(0);
         match status.as_str() {
-            "RESERVED" => {}
+            "RESERVED" | "EXECUTING" => {}
             "COMMITTED" | "ABORTED" => {
                 tx.commit().await?;
                 return Ok(());
             }
-            other => return Err(anyhow!("unexpected batch status: {}", other)),
+            other => return Err(RepositoryError::UnexpectedBatchStatus(other.to_string())),
         }
 
         let now = now_ms();
         let now_i64 = u64_to_i64(now)?;
 
-        use std::collections::HashSet;
-        let mut touched_sessions = HashSet::new();
-
-        for ur in results {
+        // One read for every item of the batch; only rows still PENDING are
+        // written below.
+        let items: HashMap<String, (i64, String)> = sqlx::query(&self.dialect.sql(
+            r#"
+SELECT chunk_id, bid, status
+FROM batch_items
+WHERE batch_id = ?;
+"#,
+        ))
+        .bind(batch.batch_id.to_string())
+        .fetch_all(&mut *tx)
+        .await?
+        .iter()
+        .map(|r| (r.get("chunk_id"), (r.get("bid"), r.get("status"))))
+        .collect();
+
+        // A chunk whose outcome is unknown hands the whole batch back to
+        // recovery as RESERVED: the known outcomes are recorded but settled
+        // later, together with the unknown chunk once `query_status` can tell
+        // whether it landed.
+        let unresolved = results
+            .iter()
+            .flat_map(|ur| &ur.chunk_results)
+            .any(|cr| matches!(cr.status, ChunkStatus::Unknown { .. }));
+
+        use std::collections::BTreeSet;
+        let mut touched_sessions = BTreeSet::new();
+
+        // Process sessions and chunks in a stable (session_id, chunk_id) order.
+        // Correctness does not depend on it, but a fixed statement order keeps
+        // row-lock acquisition consistent across concurrent commits (no
+        // cross-transaction deadlocks on Postgres) and makes traces reproducible.
+        let mut ordered: Vec<&UserResult> = results.iter().collect();
+        ordered.sort_by_key(|ur| ur.session_id);
+
+        for ur in ordered {
             touched_sessions.insert(ur.session_id);
 
             // Optional cooldown
@@ -269,14 +733,14 @@ VALUES (?, ?, ?, ?, 'PENDING', '', '');
                 let until = now.saturating_add(cd);
                 let until_i64 = u64_to_i64(until)?;
 
-                sqlx::query(
+                sqlx::query(&self.dialect.sql(
                     r#"
 UPDATE sessions
 SET cooldown_until_ms =
   CASE WHEN cooldown_until_ms > ? THEN cooldown_until_ms ELSE ? END
 WHERE session_id = ?;
 "#,
-                )
+                ))
                 .bind(until_i64)
                 .bind(until_i64)
                 .bind(ur.session_id.to_string())
@@ -284,223 +748,618 @@ WHERE session_id = ?;
                 .await?;
             }
 
-            for cr in &ur.chunk_results {
-                let row = sqlx::query(
-                    r#"
-SELECT bid, status
-FROM batch_items
-WHERE batch_id = ? AND chunk_id = ?;
-"#,
-                )
-                .bind(batch.batch_id.to_string())
-                .bind(cr.chunk_id.to_string())
-                .fetch_one(&mut *tx)
-                .await?;
+            let mut chunk_results: Vec<&ChunkResult> = ur.chunk_results.iter().collect();
+            chunk_results.sort_by_key(|cr| cr.chunk_id);
 
-                let bid: i64 = row.get(0);
-                let cur_status: String = row.get(1);
+            for cr in chunk_results {
+                let (bid, cur_status) = items.get(&cr.chunk_id.to_string()).ok_or_else(|| {
+                    RepositoryError::NotFound(format!(
+                        "chunk {} in batch {}",
+                        cr.chunk_id, batch.batch_id
+                    ))
+                })?;
+                let bid = *bid;
 
                 // Idempotency at chunk level
-                if cur_status != "PENDING" {
+                if cur_status != "PENDING" || matches!(cr.status, ChunkStatus::Unknown { .. }) {
                     continue;
                 }
 
-                match &cr.status {
-                    ChunkStatus::Success { tx_id } => {
-                        sqlx::query(
-                            r#"
-UPDATE batch_items
-SET status='SUCCESS', tx_id=?, error=''
-WHERE batch_id=? AND chunk_id=?;
-"#,
-                        )
-                        .bind(tx_id)
-                        .bind(batch.batch_id.to_string())
-                        .bind(cr.chunk_id.to_string())
-                        .execute(&mut *tx)
-                        .await?;
+                mark_item(
+                    &mut tx,
+                    self.dialect,
+                    &batch.batch_id.to_string(),
+                    &cr.chunk_id.to_string(),
+                    &cr.status,
+                )
+                .await?;
+                if unresolved {
+                    continue;
+                }
+                record_chunk_event(
+                    &mut tx,
+                    self.dialect,
+                    &batch.batch_id.to_string(),
+                    &ur.session_id.to_string(),
+                    &cr.chunk_id.to_string(),
+                    &cr.status,
+                    now_i64,
+                )
+                .await?;
+                settle_session(
+                    &mut tx,
+                    self.dialect,
+                    &ur.session_id.to_string(),
+                    bid,
+                    &cr.status,
+                    now_i64,
+                )
+                .await?;
+            }
+        }
 
-                        sqlx::query(
-                            r#"
-UPDATE sessions
-SET in_flight_bid    = in_flight_bid - ?,
-    in_flight_chunks = in_flight_chunks - 1,
-    remaining_bid    = remaining_bid - ?,
-    remaining_chunks = remaining_chunks - 1,
-    last_served_ms   = ?
-WHERE session_id = ?;
+        if unresolved {
+            sqlx::query(&self.dialect.sql(
+                r#"
+UPDATE batches
+SET status='RESERVED'
+WHERE batch_id = ? AND status = 'EXECUTING';
 "#,
-                        )
-                        .bind(bid)
-                        .bind(bid)
-                        .bind(now_i64)
-                        .bind(ur.session_id.to_string())
-                        .execute(&mut *tx)
-                        .await?;
-                    }
-
-                    ChunkStatus::Failed { reason } | ChunkStatus::Skipped { reason } => {
-                        let status = match cr.status {
-                            ChunkStatus::Failed { .. } => "FAILED",
-                            _ => "SKIPPED",
-                        };
+            ))
+            .bind(batch.batch_id.to_string())
+            .execute(&mut *tx)
+            .await?;
 
-                        sqlx::query(
-                            r#"
-UPDATE batch_items
-SET status=?, tx_id='', error=?
-WHERE batch_id=? AND chunk_id=?;
-"#,
-                        )
-                        .bind(status)
-                        .bind(reason)
-                        .bind(batch.batch_id.to_string())
-                        .bind(cr.chunk_id.to_string())
-                        .execute(&mut *tx)
-                        .await?;
-
-                        // Unwind in-flight only
-                        sqlx::query(
-                            r#"
-UPDATE sessions
-SET in_flight_bid    = in_flight_bid - ?,
-    in_flight_chunks = in_flight_chunks - 1
-WHERE session_id = ?;
-"#,
-                        )
-                        .bind(bid)
-                        .bind(ur.session_id.to_string())
-                        .execute(&mut *tx)
-                        .await?;
-                    }
-                }
-            }
+            tx.commit().await?;
+            return Ok(());
         }
 
         // Release per-session exclusive lock
         for sid in touched_sessions {
-            sqlx::query(
+            sqlx::query(&self.dialect.sql(
                 r#"
 UPDATE sessions
-SET has_pending_batch = 0
+SET has_pending_batch = FALSE
 WHERE session_id = ?;
 "#,
-            )
+            ))
             .bind(sid.to_string())
             .execute(&mut *tx)
             .await?;
         }
 
-        // Commit batch
-        sqlx::query(
+        // Commit batch (CAS: a concurrent abort/commit wins, this tx rolls back)
+        let committed = sqlx::query(&self.dialect.sql(
             r#"
 UPDATE batches
 SET status='COMMITTED', reason=''
-WHERE batch_id=?;
+WHERE batch_id=? AND status IN ('RESERVED', 'EXECUTING');
 "#,
-        )
+        ))
         .bind(batch.batch_id.to_string())
         .execute(&mut *tx)
         .await?;
 
+        if committed.rows_affected() != 1 {
+            return Err(RepositoryError::Conflict(format!(
+                "batch {} finalized concurrently",
+                batch.batch_id
+            )));
+        }
+
         tx.commit().await?;
         Ok(())
     }
 
-    async fn recover_uncommitted(&self) -> anyhow::Result<()> {
-        let batches = sqlx::query(r#"SELECT batch_id FROM batches WHERE status = 'RESERVED';"#)
-            .fetch_all(&*self.pool)
+    async fn record_commit_failure(&self, batch_id: &Uuid) -> Result<()> {
+        sqlx::query(&self.dialect.sql(
+            r#"
+UPDATE batches
+SET commit_attempts = commit_attempts + 1
+WHERE batch_id = ? AND status IN ('RESERVED', 'EXECUTING');
+"#,
+        ))
+        .bind(batch_id.to_string())
+        .execute(&*self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn dead_letter_batch(
+        &self,
+        batch: &ReservedBatch,
+        results: &[UserResult],
+        error: &str,
+    ) -> Result<()> {
+        let results_json = serde_json::to_string(results)
+            .map_err(|e| RepositoryError::InvalidRow(format!("dead letter results: {e}")))?;
+        let batch_id = batch.batch_id.to_string();
+
+        let mut tx = self.pool.begin().await?;
+
+        // CAS on status: a commit that succeeded despite reporting an error
+        // leaves nothing to reconcile.
+        let parked = sqlx::query(&self.dialect.sql(
+            r#"
+UPDATE batches
+SET status='DEAD_LETTER', reason='commit_failed'
+WHERE batch_id = ? AND status IN ('RESERVED', 'EXECUTING');
+"#,
+        ))
+        .bind(&batch_id)
+        .execute(&mut *tx)
+        .await?;
+
+        if parked.rows_affected() == 0 {
+            return Ok(());
+        }
+
+        sqlx::query(&self.dialect.sql(
+            r#"
+INSERT INTO dead_letter(batch_id, pair_id, dead_lettered_ms, commit_attempts, error, results_json)
+SELECT batch_id, pair_id, ?, commit_attempts, ?, ?
+FROM batches
+WHERE batch_id = ?;
+"#,
+        ))
+        .bind(u64_to_i64(now_ms())?)
+        .bind(error)
+        .bind(results_json)
+        .bind(&batch_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn pending_chunks(&self) -> Result<Vec<PendingChunk>> {
+        let rows = sqlx::query(&self.dialect.sql(
+            r#"
+SELECT i.batch_id, i.session_id, i.chunk_id, b.created_ms, b.status AS batch_status
+FROM batch_items i
+JOIN batches b ON b.batch_id = i.batch_id
+WHERE b.status IN ('RESERVED', 'EXECUTING') AND i.status = 'PENDING'
+ORDER BY i.batch_id, i.chunk_id;
+"#,
+        ))
+        .fetch_all(&*self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|r| {
+                Ok(PendingChunk {
+                    batch_id: parse_uuid("batch_id", r.get("batch_id"))?,
+                    session_id: parse_uuid("session_id", r.get("session_id"))?,
+                    chunk_id: parse_uuid("chunk_id", r.get("chunk_id"))?,
+                    created_ms: i64_to_u64(r.get("created_ms"))?,
+                    claimed: r.get::<String, _>("batch_status") == "EXECUTING",
+                })
+            })
+            .collect()
+    }
+
+    async fn settle_landed_chunk(
+        &self,
+        batch_id: &Uuid,
+        chunk_id: &Uuid,
+        tx_id: &str,
+    ) -> Result<bool> {
+        let res = sqlx::query(&self.dialect.sql(
+            r#"
+UPDATE batch_items
+SET status = 'SUCCESS', tx_id = ?
+WHERE batch_id = ? AND chunk_id = ? AND status = 'PENDING'
+  AND batch_id IN (
+    SELECT batch_id FROM batches WHERE status IN ('RESERVED', 'EXECUTING')
+  );
+"#,
+        ))
+        .bind(tx_id)
+        .bind(batch_id.to_string())
+        .bind(chunk_id.to_string())
+        .execute(&*self.pool)
+        .await?;
+
+        Ok(res.rows_affected() == 1)
+    }
+
+    async fn recover_uncommitted(&self) -> Result<RecoveryReport> {
+        self.recover_reserved(i64::MAX, true, "recovered_uncommitted")
+            .await
+    }
+
+    async fn recover_stale(&self, reserved_before_ms: u64) -> Result<RecoveryReport> {
+        self.recover_reserved(u64_to_i64(reserved_before_ms)?, false, "recovered_stale")
+            .await
+    }
+
+    async fn abort_batch(&self, batch_id: &Uuid, reason: &str) -> Result<()> {
+        let batch_id = batch_id.to_string();
+        let mut tx = self.pool.begin().await?;
+
+        // CAS on status: COMMITTED / ABORTED batches are left untouched.
+        let aborted = sqlx::query(&self.dialect.sql(
+            r#"
+UPDATE batches
+SET status='ABORTED', reason=?
+WHERE batch_id = ? AND status = 'RESERVED';
+"#,
+        ))
+        .bind(reason)
+        .bind(&batch_id)
+        .execute(&mut *tx)
+        .await?;
+
+        if aborted.rows_affected() == 0 {
+            return Ok(());
+        }
+
+        unwind_pending_items(&mut tx, self.dialect, &batch_id, reason).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn fetch_chunk_history(
+        &self,
+        session_id: &Uuid,
+        limit: usize,
+    ) -> Result<Vec<ChunkEvent>> {
+        let rows = sqlx::query(&self.dialect.sql(
+            r#"
+SELECT chunk_id, batch_id, session_id, outcome, reason, tx_id, ts_ms
+FROM chunk_events
+WHERE session_id = ?
+ORDER BY ts_ms DESC, chunk_id
+LIMIT ?;
+"#,
+        ))
+        .bind(session_id.to_string())
+        .bind(i64::try_from(limit).unwrap_or(i64::MAX))
+        .fetch_all(&*self.read_pool)
+        .await?;
+
+        rows.iter()
+            .map(|r| {
+                let outcome: String = r.get("outcome");
+                let status =
+                    item_status(&outcome, r.get("tx_id"), r.get("reason")).ok_or_else(|| {
+                        RepositoryError::InvalidRow(format!("chunk event outcome {outcome}"))
+                    })?;
+                Ok(ChunkEvent {
+                    chunk_id: parse_uuid("chunk_id", r.get("chunk_id"))?,
+                    batch_id: parse_uuid("batch_id", r.get("batch_id"))?,
+                    session_id: parse_uuid("session_id", r.get("session_id"))?,
+                    status,
+                    ts_ms: i64_to_u64(r.get("ts_ms"))?,
+                })
+            })
+            .collect()
+    }
+
+    /// The guard and the update are a single CAS statement; the follow-up
+    /// read only classifies a miss.
+    async fn reassign_pair(
+        &self,
+        session_id: &Uuid,
+        new_pair: &str,
+    ) -> Result<(), ReassignPairError> {
+        let mut tx = self.pool.begin().await?;
+
+        let res = sqlx::query(&self.dialect.sql(
+            r#"
+UPDATE sessions
+SET pair_id = ?
+WHERE session_id = ?
+  AND has_pending_batch = FALSE
+  AND in_flight_bid = 0;
+"#,
+        ))
+        .bind(new_pair)
+        .bind(session_id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+        if res.rows_affected() == 1 {
+            tx.commit().await?;
+            return Ok(());
+        }
+
+        let row = sqlx::query(&self.dialect.sql(
+            r#"
+SELECT CAST(has_pending_batch AS INTEGER) AS has_pending_batch, in_flight_bid
+FROM sessions
+WHERE session_id = ?;
+"#,
+        ))
+        .bind(session_id.to_string())
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        tx.rollback().await?;
+
+        let Some(row) = row else {
+            return Err(ReassignPairError::SessionNotFound(*session_id));
+        };
+
+        if row.get::<i64, _>("has_pending_batch") != 0 {
+            return Err(ReassignPairError::PendingBatch(*session_id));
+        }
+
+        Err(ReassignPairError::InFlight {
+            session_id: *session_id,
+            in_flight_bid: row.get("in_flight_bid"),
+        })
+    }
+}
+
+/// Records a chunk outcome on its PENDING `batch_items` row.
+async fn mark_item(
+    tx: &mut sqlx::Transaction<'_, sqlx::Any>,
+    dialect: SqlDialect,
+    batch_id: &str,
+    chunk_id: &str,
+    status: &ChunkStatus,
+) -> Result<()> {
+    let (item_status, tx_id, error) = status_columns(status);
+
+    sqlx::query(&dialect.sql(
+        r#"
+UPDATE batch_items
+SET status=?, tx_id=?, error=?
+WHERE batch_id=? AND chunk_id=?;
+"#,
+    ))
+    .bind(item_status)
+    .bind(tx_id)
+    .bind(error)
+    .bind(batch_id)
+    .bind(chunk_id)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Applies the commit math of one chunk outcome to its session.
+///
+/// Every outcome releases the chunk's in-flight reservation. A success also
+/// consumes `remaining_*`; a success or simulation counts as service.
+async fn settle_session(
+    tx: &mut sqlx::Transaction<'_, sqlx::Any>,
+    dialect: SqlDialect,
+    session_id: &str,
+    bid: i64,
+    status: &ChunkStatus,
+    now_i64: i64,
+) -> Result<()> {
+    match status {
+        ChunkStatus::Success { .. } => {
+            sqlx::query(&dialect.sql(
+                r#"
+UPDATE sessions
+SET in_flight_bid    = in_flight_bid - ?,
+    in_flight_chunks = in_flight_chunks - 1,
+    remaining_bid    = remaining_bid - ?,
+    remaining_chunks = remaining_chunks - 1,
+    last_served_ms   = ?
+WHERE session_id = ?;
+"#,
+            ))
+            .bind(bid)
+            .bind(bid)
+            .bind(now_i64)
+            .bind(session_id)
+            .execute(&mut **tx)
             .await?;
+        }
 
-        for b in batches {
-            let batch_id: String = b.get("batch_id");
+        ChunkStatus::Simulated => {
+            // Nothing was traded: unwind in-flight, keep remaining,
+            // but count it as service so fairness behaves as in production.
+            sqlx::query(&dialect.sql(
+                r#"
+UPDATE sessions
+SET in_flight_bid    = in_flight_bid - ?,
+    in_flight_chunks = in_flight_chunks - 1,
+    last_served_ms   = ?
+WHERE session_id = ?;
+"#,
+            ))
+            .bind(bid)
+            .bind(now_i64)
+            .bind(session_id)
+            .execute(&mut **tx)
+            .await?;
+        }
 
-            let items = sqlx::query(
+        ChunkStatus::Failed { .. } | ChunkStatus::Skipped { .. } => {
+            // Unwind in-flight only
+            sqlx::query(&dialect.sql(
                 r#"
-SELECT session_id, chunk_id, bid
-FROM batch_items
-WHERE batch_id = ? AND status = 'PENDING';
+UPDATE sessions
+SET in_flight_bid    = in_flight_bid - ?,
+    in_flight_chunks = in_flight_chunks - 1
+WHERE session_id = ?;
 "#,
-            )
-            .bind(batch_id.clone())
-            .fetch_all(&*self.pool)
+            ))
+            .bind(bid)
+            .bind(session_id)
+            .execute(&mut **tx)
             .await?;
+        }
 
-            if items.is_empty() {
-                continue;
-            }
+        // Still in flight: recovery settles it once its outcome is known.
+        ChunkStatus::Unknown { .. } => {}
+    }
 
-            let mut tx = self.pool.begin().await?;
+    Ok(())
+}
 
-            use std::collections::HashSet;
-            let mut touched_sessions = HashSet::new();
+/// Appends a chunk outcome to `chunk_events` inside `tx`.
+///
+/// Callers only pass chunks they are settling for the first time; the
+/// `chunk_id` primary key rejects a second event for the same chunk.
+async fn record_chunk_event(
+    tx: &mut sqlx::Transaction<'_, sqlx::Any>,
+    dialect: SqlDialect,
+    batch_id: &str,
+    session_id: &str,
+    chunk_id: &str,
+    status: &ChunkStatus,
+    ts_ms: i64,
+) -> Result<()> {
+    let (outcome, tx_id, reason) = status_columns(status);
+
+    sqlx::query(&dialect.sql(
+        r#"
+INSERT INTO chunk_events(chunk_id, batch_id, session_id, outcome, reason, tx_id, ts_ms)
+VALUES (?, ?, ?, ?, ?, ?, ?);
+"#,
+    ))
+    .bind(chunk_id)
+    .bind(batch_id)
+    .bind(session_id)
+    .bind(outcome)
+    .bind(reason)
+    .bind(tx_id)
+    .bind(ts_ms)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
 
-            for it in items {
-                let session_id: String = it.get("session_id");
-                let chunk_id: String = it.get("chunk_id");
-                let bid: i64 = it.get("bid");
+/// `(status, tx_id, error)` columns for a chunk outcome; the inverse of
+/// `item_status`.
+fn status_columns(status: &ChunkStatus) -> (&'static str, &str, &str) {
+    match status {
+        ChunkStatus::Success { tx_id } => ("SUCCESS", tx_id.as_str(), ""),
+        ChunkStatus::Simulated => ("SIMULATED", "", ""),
+        ChunkStatus::Failed { reason } => ("FAILED", "", reason.as_str()),
+        ChunkStatus::Skipped { reason } => ("SKIPPED", "", reason.as_str()),
+        // Not an outcome: the item is left as it was.
+        ChunkStatus::Unknown { reason } => ("PENDING", "", reason.as_str()),
+    }
+}
 
-                touched_sessions.insert(session_id.clone());
+/// Maps a stored (non-PENDING) item row back to its chunk outcome.
+/// `None` for statuses this version does not know.
+fn item_status(status: &str, tx_id: String, error: String) -> Option<ChunkStatus> {
+    Some(match status {
+        "SUCCESS" => ChunkStatus::Success { tx_id },
+        "SIMULATED" => ChunkStatus::Simulated,
+        "FAILED" => ChunkStatus::Failed { reason: error },
+        "SKIPPED" => ChunkStatus::Skipped { reason: error },
+        _ => return None,
+    })
+}
 
-                // Unwind in-flight safely
-                sqlx::query(
-                    r#"
+/// Unwinds every PENDING item of a batch inside `tx`.
+///
+/// Releases in-flight accounting, marks the items SKIPPED with `reason`,
+/// and clears the pending-batch lock of each touched session. The batch
+/// row itself is left to the caller. Returns the number of items unwound
+/// and the bid they released.
+async fn unwind_pending_items(
+    tx: &mut sqlx::Transaction<'_, sqlx::Any>,
+    dialect: SqlDialect,
+    batch_id: &str,
+    reason: &str,
+) -> Result<(usize, u128)> {
+    let items = sqlx::query(&dialect.sql(
+        r#"
+SELECT session_id, chunk_id, bid
+FROM batch_items
+WHERE batch_id = ? AND status = 'PENDING';
+"#,
+    ))
+    .bind(batch_id)
+    .fetch_all(&mut **tx)
+    .await?;
+
+    use std::collections::HashSet;
+    let mut touched_sessions = HashSet::new();
+    let unwound = items.len();
+    let now_i64 = u64_to_i64(now_ms())?;
+    let mut released: u128 = 0;
+
+    for it in items {
+        let session_id: String = it.get("session_id");
+        let chunk_id: String = it.get("chunk_id");
+        let bid: i64 = it.get("bid");
+
+        touched_sessions.insert(session_id.clone());
+        released += u128::from(i64_to_u64(bid)?);
+
+        // Unwind in-flight safely
+        sqlx::query(&dialect.sql(
+            r#"
 UPDATE sessions
 SET in_flight_bid    = CASE WHEN in_flight_bid >= ? THEN in_flight_bid - ? ELSE 0 END,
     in_flight_chunks = CASE WHEN in_flight_chunks >= 1 THEN in_flight_chunks - 1 ELSE 0 END
 WHERE session_id = ?;
 "#,
-                )
-                .bind(bid)
-                .bind(bid)
-                .bind(&session_id)
-                .execute(&mut *tx)
-                .await?;
+        ))
+        .bind(bid)
+        .bind(bid)
+        .bind(&session_id)
+        .execute(&mut **tx)
+        .await?;
 
-                // Mark chunk skipped
-                sqlx::query(
-                    r#"
+        // Mark chunk skipped
+        sqlx::query(&dialect.sql(
+            r#"
 UPDATE batch_items
-SET status='SKIPPED', error='recovered_uncommitted', tx_id=''
+SET status='SKIPPED', error=?, tx_id=''
 WHERE batch_id = ? AND chunk_id = ?;
 "#,
-                )
-                .bind(&batch_id)
-                .bind(&chunk_id)
-                .execute(&mut *tx)
-                .await?;
-            }
+        ))
+        .bind(reason)
+        .bind(batch_id)
+        .bind(&chunk_id)
+        .execute(&mut **tx)
+        .await?;
 
-            // 🔑 Release exclusive lock
-            for sid in touched_sessions {
-                sqlx::query(
-                    r#"
+        record_chunk_event(
+            tx,
+            dialect,
+            batch_id,
+            &session_id,
+            &chunk_id,
+            &ChunkStatus::Skipped {
+                reason: reason.to_string(),
+            },
+            now_i64,
+        )
+        .await?;
+    }
+
+    // 🔑 Release exclusive lock
+    for sid in touched_sessions {
+        sqlx::query(&dialect.sql(
+            r#"
 UPDATE sessions
-SET has_pending_batch = 0
+SET has_pending_batch = FALSE
 WHERE session_id = ?;
 "#,
-                )
-                .bind(sid)
-                .execute(&mut *tx)
-                .await?;
-            }
-
-            // Abort batch
-            sqlx::query(
-                r#"
-UPDATE batches
-SET status='ABORTED', reason='recovered_uncommitted'
-WHERE batch_id = ?;
-"#,
-            )
-            .bind(&batch_id)
-            .execute(&mut *tx)
-            .await?;
+        ))
+        .bind(sid)
+        .execute(&mut **tx)
+        .await?;
+    }
 
-            tx.commit().await?;
-        }
+    Ok((unwound, released))
+}
 
-        Ok(())
+/// Extra `WHERE` clause for candidate reads; binds one `pair_id` when set.
+fn pair_filter(pair_id: Option<&str>) -> &'static str {
+    if pair_id.is_some() {
+        "AND pair_id = ?"
+    } else {
+        ""
     }
 }
 
@@ -508,9 +1367,12 @@ WHERE batch_id = ?;
 Row mapping + conversions
 ========================= */
 
-fn row_to_session(r: &sqlx::any::AnyRow) -> anyhow::Result<Session> {
-    let id_str: String = r.get("session_id");
-    let session_id = Uuid::parse_str(&id_str).context("invalid session_id")?;
+fn parse_uuid(column: &str, s: String) -> Result<Uuid> {
+    Uuid::parse_str(&s).map_err(|e| RepositoryError::InvalidRow(format!("invalid {column}: {e}")))
+}
+
+fn row_to_session(r: &sqlx::any::AnyRow) -> Result<Session> {
+    let session_id = parse_uuid("session_id", r.get("session_id"))?;
 
     let active_i64: i64 = r.get("active_i64");
 
@@ -526,6 +1388,14 @@ fn row_to_session(r: &sqlx::any::AnyRow) -> anyhow::Result<Session> {
             },
             preferred_chunk_bid: i64_to_u128(r.get("preferred_chunk_bid"))?,
             max_bid_per_tick: i64_to_u128(r.get("max_bid_per_tick"))?,
+            active_windows: parse_active_windows(&r.get::<String, _>("active_windows"))?,
+            quantum_weight: i64_to_u32(r.get("quantum_weight"))?,
+            twap_interval_ms: i64_to_u64(r.get("twap_interval_ms"))?,
+            expires_at_ms: i64_to_u64(r.get("expires_at_ms"))?,
+            failure_cooldown_ms: r
+                .get::<Option<i64>, _>("failure_cooldown_ms")
+                .map(i64_to_u64)
+                .transpose()?,
         },
         state: SessionState {
             remaining_bid: i64_to_u128(r.get("remaining_bid"))?,
@@ -541,41 +1411,78 @@ fn row_to_session(r: &sqlx::any::AnyRow) -> anyhow::Result<Session> {
     })
 }
 
+/// Decodes the `active_windows` JSON column (`[[start_ms, end_ms], ...]`).
+/// Bounds must be valid times of day; anything else is treated as a poison row.
+fn parse_active_windows(raw: &str) -> Result<Vec<(u64, u64)>> {
+    let windows: Vec<(u64, u64)> = serde_json::from_str(raw)
+        .map_err(|e| RepositoryError::InvalidRow(format!("invalid active_windows json: {e}")))?;
+
+    if let Some(&(start, end)) = windows
+        .iter()
+        .find(|&&(start, end)| start >= MS_PER_DAY || end > MS_PER_DAY)
+    {
+        return Err(RepositoryError::InvalidRow(format!(
+            "active window out of range: ({start}, {end}) exceeds {MS_PER_DAY}ms"
+        )));
+    }
+
+    Ok(windows)
+}
+
 /* =========================
 Numeric safety helpers
 ========================= */
 
-fn i64_to_u128(v: i64) -> anyhow::Result<u128> {
+fn i64_to_u128(v: i64) -> Result<u128> {
     if v < 0 {
-        return Err(anyhow!("negative i64 where u128 expected: {v}"));
+        return Err(RepositoryError::Overflow(format!(
+            "negative i64 where u128 expected: {v}"
+        )));
     }
     Ok(v as u128)
 }
 
-fn i64_to_u32(v: i64) -> anyhow::Result<u32> {
+fn i64_to_u32(v: i64) -> Result<u32> {
     if v < 0 || v > u32::MAX as i64 {
-        return Err(anyhow!("out of range for u32: {v}"));
+        return Err(RepositoryError::Overflow(format!(
+            "out of range for u32: {v}"
+        )));
     }
     Ok(v as u32)
 }
 
-fn i64_to_u64(v: i64) -> anyhow::Result<u64> {
+fn i64_to_u64(v: i64) -> Result<u64> {
     if v < 0 {
-        return Err(anyhow!("negative i64 where u64 expected: {v}"));
+        return Err(RepositoryError::Overflow(format!(
+            "negative i64 where u64 expected: {v}"
+        )));
     }
     Ok(v as u64)
 }
 
-fn u64_to_i64(v: u64) -> anyhow::Result<i64> {
+fn u64_to_i64(v: u64) -> Result<i64> {
     if v > i64::MAX as u64 {
-        return Err(anyhow!("u64 too large for i64: {v}"));
+        return Err(RepositoryError::Overflow(format!(
+            "u64 too large for i64: {v}"
+        )));
     }
     Ok(v as i64)
 }
 
-fn i128_to_i64(v: i128) -> anyhow::Result<i64> {
+fn i128_to_i64(v: i128) -> Result<i64> {
     if v < i64::MIN as i128 || v > i64::MAX as i128 {
-        return Err(anyhow!("i128 out of range for i64: {v}"));
+        return Err(RepositoryError::Overflow(format!(
+            "i128 out of range for i64: {v}"
+        )));
+    }
+    Ok(v as i64)
+}
+
+fn u128_to_i64(v: u128) -> Result<i64> {
+    if v > i64::MAX as u128 {
+        return Err(RepositoryError::Overflow(format!(
+            "u128 too large for i64: {v}"
+        )));
     }
     Ok(v as i64)
 }
@@ -1,11 +1,14 @@
 use anyhow::{Context, Result};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
-use tracing::{debug, info, instrument};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, instrument, warn};
 use uuid::Uuid;
 
+use crate::error::ReassignPairError;
 use crate::logger::warn_if_slow;
-use crate::session::cache::SessionCache;
+use crate::session::cache::{CacheStats, SessionCache, SessionCacheMetrics};
 use crate::session::model::Session;
 use crate::session::repository::SessionRepository;
 
@@ -14,7 +17,9 @@ pub struct SessionStore {
     pub repo: Arc<dyn SessionRepository>,
     cache: SessionCache,
     page_size: usize,
-    last_offset: parking_lot::Mutex<usize>,
+    /// Pagination cursor per `pair_id` filter (`None` = all pairs); a
+    /// filter without an entry starts from the beginning of the table.
+    cursors: parking_lot::Mutex<HashMap<Option<String>, Uuid>>,
 }
 
 impl SessionStore {
@@ -23,30 +28,64 @@ impl SessionStore {
             repo,
             cache: SessionCache::new(5_000),
             page_size: 500,
-            last_offset: parking_lot::Mutex::new(0),
+            cursors: parking_lot::Mutex::new(HashMap::new()),
         }
     }
 
+    /// Count cache hits, misses, inserts, updates and evictions into
+    /// `metrics` (see `Counters::session_cache`).
+    pub fn with_cache_metrics(mut self, metrics: SessionCacheMetrics) -> Self {
+        self.cache = self.cache.with_metrics(metrics);
+        self
+    }
+
+    /// Drop cached sessions idle for longer than `max_idle_ms` (0 disables).
+    pub fn with_cache_max_idle_ms(mut self, max_idle_ms: u64) -> Self {
+        self.cache = self.cache.with_max_idle_ms(max_idle_ms);
+        self
+    }
+
     pub fn cache_len_rr(&self) -> usize {
         self.cache.len_rr()
     }
 
+    /// Cache hit/miss/eviction counters, to tell whether the scheduler is
+    /// falling back to `load_by_id`.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
     pub fn get_cached(&self, id: &Uuid) -> Option<Session> {
         self.cache.get(id)
     }
 
-    pub fn rotate_candidate(&self) -> Option<Uuid> {
-        self.cache.rotate()
+    /// Next round-robin candidate; idle sessions are dropped on the way.
+    pub fn rotate_candidate(&self, now_ms: u64) -> Option<Uuid> {
+        self.cache.rotate_at(now_ms)
     }
 
-    /// Ensures at least `min_needed` candidates exist in cache.
+    /// Ensures at least `min_needed` candidates exist in cache, loading pages
+    /// until it does or the whole table has been scanned. With `pair_id`,
+    /// only that pair's cached sessions count and only its sessions are
+    /// loaded; `None` counts and loads every pair. Each filter pages through
+    /// the table with its own cursor, so pairs sharing a store do not skip
+    /// each other's rows.
+    ///
+    /// A scan that starts mid-table may wrap once to reach the rows before
+    /// its starting point; the second wrap ends it, so a table smaller than
+    /// `min_needed` cannot loop forever.
     #[instrument(
         skip(self),
         target = "store",
         fields(current_len = self.cache.len_rr(), min_needed)
     )]
-    pub async fn ensure_candidates(&self, min_needed: usize) -> Result<()> {
-        let current = self.cache.len_rr();
+    pub async fn ensure_candidates(&self, min_needed: usize, pair_id: Option<&str>) -> Result<()> {
+        let candidates = || match pair_id {
+            Some(pair_id) => self.cache.len_pair(pair_id),
+            None => self.cache.len_rr(),
+        };
+
+        let current = candidates();
         if current >= min_needed {
             return Ok(());
         }
@@ -56,9 +95,16 @@ impl SessionStore {
             "cache below threshold; triggering database refill"
         );
 
-        self.load_next_page().await?;
+        let mut wraps_left = if self.cursor(pair_id).is_none() { 1 } else { 2 };
+        let mut pages = 0usize;
+        while candidates() < min_needed && wraps_left > 0 {
+            if self.load_next_page(pair_id).await? {
+                wraps_left -= 1;
+            }
+            pages += 1;
+        }
 
-        debug!(new_len = self.cache.len_rr(), "refill operation complete");
+        debug!(new_len = candidates(), pages, "refill operation complete");
         Ok(())
     }
 
@@ -76,6 +122,16 @@ impl SessionStore {
         }
     }
 
+    /// Loads many sessions in one repository call. Missing ids are absent
+    /// from the map rather than an error.
+    pub async fn load_by_ids(&self, ids: &[Uuid]) -> Result<HashMap<Uuid, Session>> {
+        warn_if_slow("db_fetch_by_ids", Duration::from_millis(200), async {
+            self.repo.fetch_by_ids(ids).await
+        })
+        .await
+        .context("repository batch fetch failed")
+    }
+
     #[instrument(skip(self), target = "store", fields(session_id = %session_id, deficit))]
     pub async fn persist_fairness(
         &self,
@@ -92,27 +148,89 @@ impl SessionStore {
         .context("failed to persist fairness state")
     }
 
+    /// Deactivates sessions whose expiry is before `now_ms`. Cached copies
+    /// are left alone: `Session::is_eligible` already rejects them.
+    pub async fn expire_due(&self, now_ms: u64) -> Result<u64> {
+        warn_if_slow("db_expire_due", Duration::from_millis(200), async {
+            self.repo.expire_due(now_ms).await
+        })
+        .await
+        .context("failed to expire sessions")
+    }
+
+    /// Moves a session to `new_pair` (see `SessionRepository::reassign_pair`)
+    /// and drops its cached copy, so the old pair's scheduler stops picking
+    /// it and the new pair's next refill loads it.
+    pub async fn reassign_pair(
+        &self,
+        session_id: &Uuid,
+        new_pair: &str,
+    ) -> std::result::Result<(), ReassignPairError> {
+        self.repo.reassign_pair(session_id, new_pair).await?;
+        self.cache.remove(session_id);
+        Ok(())
+    }
+
+    /// Runs `expire_due` every `interval` until `shutdown`.
+    pub async fn run_expiry_sweeper(
+        self: Arc<Self>,
+        interval: Duration,
+        shutdown: CancellationToken,
+    ) {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.cancelled() => break,
+            }
+
+            match self.expire_due(crate::time::now_ms()).await {
+                Ok(0) => {}
+                Ok(expired) => info!(target: "store", expired, "expired sessions deactivated"),
+                Err(e) => warn!(target: "store", error = ?e, "session expiry sweep failed"),
+            }
+        }
+
+        info!(target: "store", "session expiry sweeper stopped");
+    }
+
+    /// Pagination cursor of the `pair_id` filter.
+    fn cursor(&self, pair_id: Option<&str>) -> Option<Uuid> {
+        self.cursors
+            .lock()
+            .get(&pair_id.map(str::to_string))
+            .copied()
+    }
+
+    /// Loads the page after the `pair_id` filter's cursor. Returns true when
+    /// the end of the table was reached and the cursor wrapped back to the
+    /// start.
     #[instrument(skip(self), target = "store")]
-    async fn load_next_page(&self) -> Result<()> {
-        let offset = *self.last_offset.lock();
+    async fn load_next_page(&self, pair_id: Option<&str>) -> Result<bool> {
+        let cursor = self.cursor(pair_id);
+        let key = pair_id.map(str::to_string);
 
         let rows = warn_if_slow("db_load_next_page", Duration::from_millis(200), async {
-            self.repo.fetch_page(self.page_size, offset).await
+            self.repo
+                .fetch_page_after(self.page_size, cursor, pair_id)
+                .await
         })
         .await
         .context("failed to fetch page from repository")?;
 
-        if rows.is_empty() {
-            *self.last_offset.lock() = 0;
-            return Ok(());
-        }
+        let Some(last) = rows.last() else {
+            // End of the table: wrap around to the start.
+            self.cursors.lock().remove(&key);
+            return Ok(true);
+        };
 
-        *self.last_offset.lock() = offset + self.page_size;
+        self.cursors.lock().insert(key, last.session_id);
         for s in rows {
             self.cache.upsert(s);
         }
 
-        Ok(())
+        Ok(false)
     }
 
     pub fn upsert_cache(&self, s: Session) {
@@ -127,7 +245,11 @@ mod tests {
     use std::collections::HashMap;
     use tokio::task::JoinSet;
 
-    use crate::execution::types::{ReservedBatch, ReservedChunk, ReservedUser, UserResult};
+    use crate::error::{ReassignPairError, RepositoryError};
+    use crate::execution::types::{
+        ChunkEvent, PendingChunk, RecoveryReport, ReservedBatch, ReservedChunk, ReservedUser,
+        UserResult,
+    };
     use crate::planner::types::PlannedAllocation;
     use crate::session::model::{SessionIntent, SessionState, UserConstraints};
 
@@ -144,6 +266,11 @@ mod tests {
                 },
                 preferred_chunk_bid: 100_000,
                 max_bid_per_tick: 1_000_000,
+                active_windows: Vec::new(),
+                quantum_weight: 1,
+                twap_interval_ms: 0,
+                expires_at_ms: 0,
+                failure_cooldown_ms: None,
             },
             state: SessionState {
                 remaining_bid: 1_000_000,
@@ -169,27 +296,110 @@ mod tests {
 
     #[async_trait::async_trait]
     impl SessionRepository for MockSessionRepository {
-        async fn fetch_page(&self, limit: usize, offset: usize) -> anyhow::Result<Vec<Session>> {
+        async fn fetch_page(
+            &self,
+            limit: usize,
+            offset: usize,
+            _: Option<&str>,
+        ) -> Result<Vec<Session>, RepositoryError> {
             Ok(self.pages.get(offset / limit).cloned().unwrap_or_default())
         }
+        async fn fetch_page_after(
+            &self,
+            _: usize,
+            after: Option<Uuid>,
+            pair_id: Option<&str>,
+        ) -> Result<Vec<Session>, RepositoryError> {
+            // The page after the one holding `after`, restricted to `pair_id`.
+            let idx = match after {
+                None => 0,
+                Some(id) => match self
+                    .pages
+                    .iter()
+                    .position(|p| p.iter().any(|s| s.session_id == id))
+                {
+                    Some(i) => i + 1,
+                    None => return Ok(vec![]),
+                },
+            };
+            let page = self.pages.get(idx).cloned().unwrap_or_default();
+            Ok(page
+                .into_iter()
+                .filter(|s| pair_id.is_none_or(|p| s.pair_id == p))
+                .collect())
+        }
 
-        async fn fetch_by_id(&self, id: &Uuid) -> anyhow::Result<Option<Session>> {
+        async fn fetch_by_id(&self, id: &Uuid) -> Result<Option<Session>, RepositoryError> {
             Ok(self.by_id.get(id).cloned())
         }
 
+        async fn fetch_by_ids(
+            &self,
+            ids: &[Uuid],
+        ) -> Result<HashMap<Uuid, Session>, RepositoryError> {
+            Ok(ids
+                .iter()
+                .filter_map(|id| self.by_id.get(id).map(|s| (*id, s.clone())))
+                .collect())
+        }
+
+        async fn expire_due(&self, _: u64) -> Result<u64, RepositoryError> {
+            Ok(0)
+        }
+
         async fn persist_fairness(
             &self,
             id: &Uuid,
             deficit: i128,
             last_served_ms: u64,
-        ) -> anyhow::Result<()> {
+        ) -> Result<(), RepositoryError> {
             self.fairness_calls
                 .lock()
                 .push((*id, deficit, last_served_ms));
             Ok(())
         }
 
-        async fn recover_uncommitted(&self) -> anyhow::Result<()> {
+        async fn pending_chunks(&self) -> Result<Vec<PendingChunk>, RepositoryError> {
+            Ok(Vec::new())
+        }
+
+        async fn settle_landed_chunk(
+            &self,
+            _: &Uuid,
+            _: &Uuid,
+            _: &str,
+        ) -> Result<bool, RepositoryError> {
+            Ok(false)
+        }
+
+        async fn recover_uncommitted(&self) -> Result<RecoveryReport, RepositoryError> {
+            Ok(RecoveryReport::default())
+        }
+        async fn recover_stale(&self, _: u64) -> Result<RecoveryReport, RepositoryError> {
+            Ok(RecoveryReport::default())
+        }
+        async fn record_commit_failure(&self, _: &Uuid) -> Result<(), RepositoryError> {
+            Ok(())
+        }
+        async fn dead_letter_batch(
+            &self,
+            _: &ReservedBatch,
+            _: &[UserResult],
+            _: &str,
+        ) -> Result<(), RepositoryError> {
+            Ok(())
+        }
+        async fn abort_batch(&self, _: &Uuid, _: &str) -> Result<(), RepositoryError> {
+            Ok(())
+        }
+        async fn fetch_chunk_history(
+            &self,
+            _: &Uuid,
+            _: usize,
+        ) -> Result<Vec<ChunkEvent>, RepositoryError> {
+            Ok(Vec::new())
+        }
+        async fn reassign_pair(&self, _: &Uuid, _: &str) -> Result<(), ReassignPairError> {
             Ok(())
         }
 
@@ -198,7 +408,7 @@ mod tests {
             pair_id: &str,
             now_ms: u64,
             allocations: &[PlannedAllocation],
-        ) -> anyhow::Result<Option<ReservedBatch>> {
+        ) -> Result<Option<ReservedBatch>, RepositoryError> {
             let users = allocations
                 .iter()
                 .map(|a| ReservedUser {
@@ -225,11 +435,14 @@ mod tests {
             Ok(Some(batch))
         }
 
+        async fn claim_batch(&self, _: &Uuid) -> Result<bool, RepositoryError> {
+            Ok(true)
+        }
         async fn commit_batch(
             &self,
             batch: &ReservedBatch,
             _results: &[UserResult],
-        ) -> anyhow::Result<()> {
+        ) -> Result<(), RepositoryError> {
             self.commit_calls.lock().push(batch.batch_id);
             Ok(())
         }
@@ -249,7 +462,7 @@ mod tests {
         });
 
         let store = SessionStore::new(repo);
-        store.ensure_candidates(3).await.unwrap();
+        store.ensure_candidates(3, None).await.unwrap();
         assert!(store.cache_len_rr() >= 3);
     }
 
@@ -279,16 +492,88 @@ mod tests {
 
         #[async_trait::async_trait]
         impl SessionRepository for FailingRepo {
-            async fn fetch_page(&self, _: usize, _: usize) -> anyhow::Result<Vec<Session>> {
-                Err(anyhow::anyhow!("Database Offline"))
+            async fn fetch_page(
+                &self,
+                _: usize,
+                _: usize,
+                _: Option<&str>,
+            ) -> Result<Vec<Session>, RepositoryError> {
+                Err(RepositoryError::Db(sqlx::Error::Protocol(
+                    "Database Offline".into(),
+                )))
+            }
+            async fn fetch_page_after(
+                &self,
+                _: usize,
+                _: Option<Uuid>,
+                _: Option<&str>,
+            ) -> Result<Vec<Session>, RepositoryError> {
+                Err(RepositoryError::Db(sqlx::Error::Protocol(
+                    "Database Offline".into(),
+                )))
             }
-            async fn fetch_by_id(&self, _: &Uuid) -> anyhow::Result<Option<Session>> {
+            async fn fetch_by_id(&self, _: &Uuid) -> Result<Option<Session>, RepositoryError> {
                 Ok(None)
             }
-            async fn persist_fairness(&self, _: &Uuid, _: i128, _: u64) -> anyhow::Result<()> {
+            async fn fetch_by_ids(
+                &self,
+                _: &[Uuid],
+            ) -> Result<HashMap<Uuid, Session>, RepositoryError> {
+                Ok(HashMap::new())
+            }
+            async fn expire_due(&self, _: u64) -> Result<u64, RepositoryError> {
+                Ok(0)
+            }
+
+            async fn persist_fairness(
+                &self,
+                _: &Uuid,
+                _: i128,
+                _: u64,
+            ) -> Result<(), RepositoryError> {
                 Ok(())
             }
-            async fn recover_uncommitted(&self) -> anyhow::Result<()> {
+            async fn pending_chunks(&self) -> Result<Vec<PendingChunk>, RepositoryError> {
+                Ok(Vec::new())
+            }
+
+            async fn settle_landed_chunk(
+                &self,
+                _: &Uuid,
+                _: &Uuid,
+                _: &str,
+            ) -> Result<bool, RepositoryError> {
+                Ok(false)
+            }
+
+            async fn recover_uncommitted(&self) -> Result<RecoveryReport, RepositoryError> {
+                Ok(RecoveryReport::default())
+            }
+            async fn recover_stale(&self, _: u64) -> Result<RecoveryReport, RepositoryError> {
+                Ok(RecoveryReport::default())
+            }
+            async fn record_commit_failure(&self, _: &Uuid) -> Result<(), RepositoryError> {
+                Ok(())
+            }
+            async fn dead_letter_batch(
+                &self,
+                _: &ReservedBatch,
+                _: &[UserResult],
+                _: &str,
+            ) -> Result<(), RepositoryError> {
+                Ok(())
+            }
+            async fn abort_batch(&self, _: &Uuid, _: &str) -> Result<(), RepositoryError> {
+                Ok(())
+            }
+            async fn fetch_chunk_history(
+                &self,
+                _: &Uuid,
+                _: usize,
+            ) -> Result<Vec<ChunkEvent>, RepositoryError> {
+                Ok(Vec::new())
+            }
+            async fn reassign_pair(&self, _: &Uuid, _: &str) -> Result<(), ReassignPairError> {
                 Ok(())
             }
             async fn reserve_execution(
@@ -296,20 +581,27 @@ mod tests {
                 _: &str,
                 _: u64,
                 _: &[PlannedAllocation],
-            ) -> anyhow::Result<Option<ReservedBatch>> {
-                Err(anyhow::anyhow!("reserve not available"))
+            ) -> Result<Option<ReservedBatch>, RepositoryError> {
+                Err(RepositoryError::Db(sqlx::Error::Protocol(
+                    "reserve not available".into(),
+                )))
+            }
+            async fn claim_batch(&self, _: &Uuid) -> Result<bool, RepositoryError> {
+                Ok(true)
             }
             async fn commit_batch(
                 &self,
                 _: &ReservedBatch,
                 _: &[UserResult],
-            ) -> anyhow::Result<()> {
-                Err(anyhow::anyhow!("commit not available"))
+            ) -> Result<(), RepositoryError> {
+                Err(RepositoryError::Db(sqlx::Error::Protocol(
+                    "commit not available".into(),
+                )))
             }
         }
 
         let store = SessionStore::new(Arc::new(FailingRepo));
-        let result = store.ensure_candidates(1).await;
+        let result = store.ensure_candidates(1, None).await;
 
         assert!(result.is_err());
         let msg = format!("{:?}", result.unwrap_err());
@@ -336,7 +628,7 @@ mod tests {
 
         for _ in 0..20 {
             let s = store.clone();
-            set.spawn(async move { s.ensure_candidates(10).await });
+            set.spawn(async move { s.ensure_candidates(10, None).await });
         }
 
         while let Some(res) = set.join_next().await {
@@ -346,6 +638,93 @@ mod tests {
         assert!(store.cache_len_rr() >= 10);
     }
 
+    fn paged_repo(pages: Vec<Vec<Session>>) -> Arc<MockSessionRepository> {
+        Arc::new(MockSessionRepository {
+            pages,
+            by_id: HashMap::new(),
+            fairness_calls: Mutex::new(vec![]),
+            reservation_calls: Mutex::new(vec![]),
+            commit_calls: Mutex::new(vec![]),
+        })
+    }
+
+    #[tokio::test]
+    async fn ensure_candidates_loads_pages_until_the_minimum_is_met() {
+        let pages: Vec<Vec<Session>> = (0..5)
+            .map(|_| (0..2).map(|_| mk_session(Uuid::new_v4())).collect())
+            .collect();
+        let third_page_last = pages[2][1].session_id;
+
+        let store = SessionStore::new(paged_repo(pages));
+        store.ensure_candidates(5, None).await.unwrap();
+
+        // Three pages of two rows; the rest of the table is left unread.
+        assert_eq!(store.cache_len_rr(), 6);
+        assert_eq!(store.cursor(None), Some(third_page_last));
+    }
+
+    #[tokio::test]
+    async fn ensure_candidates_stops_after_a_full_scan_of_a_small_table() {
+        let pages: Vec<Vec<Session>> = (0..2)
+            .map(|_| (0..2).map(|_| mk_session(Uuid::new_v4())).collect())
+            .collect();
+        let store = SessionStore::new(paged_repo(pages));
+
+        tokio::time::timeout(Duration::from_secs(5), store.ensure_candidates(100, None))
+            .await
+            .expect("refill must terminate")
+            .unwrap();
+        assert_eq!(store.cache_len_rr(), 4);
+        assert_eq!(store.cursor(None), None);
+
+        // Starting mid-table it wraps once to read the head, then stops.
+        let store = SessionStore::new(paged_repo(vec![
+            vec![mk_session(Uuid::new_v4())],
+            vec![mk_session(Uuid::new_v4())],
+        ]));
+        store.load_next_page(None).await.unwrap();
+        store.cache.clear();
+        tokio::time::timeout(Duration::from_secs(5), store.ensure_candidates(100, None))
+            .await
+            .expect("refill must terminate")
+            .unwrap();
+        assert_eq!(store.cache_len_rr(), 2);
+
+        let empty = SessionStore::new(paged_repo(vec![]));
+        empty.ensure_candidates(1, None).await.unwrap();
+        assert_eq!(empty.cache_len_rr(), 0);
+    }
+
+    #[tokio::test]
+    async fn ensure_candidates_counts_and_pages_each_pair_separately() {
+        let eth = |id| Session {
+            pair_id: "ETH/USDT".to_string(),
+            ..mk_session(id)
+        };
+        let ton: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        let eth_ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        let pages = (0..3)
+            .map(|i| vec![mk_session(ton[i]), eth(eth_ids[i])])
+            .collect();
+        let store = SessionStore::new(paged_repo(pages));
+
+        store.ensure_candidates(2, Some("TON/USDT")).await.unwrap();
+        assert_eq!(store.cursor(Some("TON/USDT")), Some(ton[1]));
+
+        // Cached TON sessions do not satisfy an ETH refill, and ETH pages
+        // from the start of the table rather than from the TON cursor.
+        store.ensure_candidates(2, Some("ETH/USDT")).await.unwrap();
+        assert!(store.get_cached(&eth_ids[0]).is_some());
+        assert!(store.get_cached(&eth_ids[1]).is_some());
+        assert_eq!(store.cursor(Some("ETH/USDT")), Some(eth_ids[1]));
+
+        // TON resumes where it left off.
+        store.ensure_candidates(3, Some("TON/USDT")).await.unwrap();
+        assert!(store.get_cached(&ton[2]).is_some());
+        assert!(store.get_cached(&eth_ids[2]).is_none());
+        assert_eq!(store.cursor(Some("TON/USDT")), Some(ton[2]));
+    }
+
     /// Verifies that one malformed session in a DB page doesn't crash the cache refill.
     #[tokio::test]
     async fn test_poison_row_skipping() {
@@ -366,20 +745,21 @@ mod tests {
         });
 
         let store = SessionStore::new(repo);
-        store.ensure_candidates(1).await.unwrap();
+        store.ensure_candidates(1, None).await.unwrap();
 
         assert_eq!(store.cache_len_rr(), 1);
         assert!(store.get_cached(&good_id).is_some());
     }
 
-    /// Verifies that when the DB is exhausted, the offset resets to 0.
+    /// Verifies that when the DB is exhausted, the cursor resets to the start.
     #[tokio::test]
     async fn test_pagination_wrap_around() {
-        let page_1 = vec![mk_session(Uuid::new_v4())];
+        let id = Uuid::new_v4();
+        let page_1 = vec![mk_session(id)];
         // page_2 is empty (EOF)
 
         let repo = Arc::new(MockSessionRepository {
-            pages: vec![page_1], // index 0 has data, index 1 (offset 500) will be empty
+            pages: vec![page_1], // only one page; the page after `id` is empty
             by_id: HashMap::new(),
             fairness_calls: Mutex::new(vec![]),
             reservation_calls: Mutex::new(vec![]),
@@ -389,17 +769,17 @@ mod tests {
         let store = SessionStore::new(repo);
 
         // Load first page
-        store.ensure_candidates(1).await.unwrap();
-        assert_eq!(*store.last_offset.lock(), 500);
+        store.ensure_candidates(1, None).await.unwrap();
+        assert_eq!(store.cursor(None), Some(id));
 
         // Load next page (will be empty)
-        store.ensure_candidates(2).await.unwrap();
+        store.ensure_candidates(2, None).await.unwrap();
 
-        // Offset should have wrapped back to 0
+        // Cursor should have wrapped back to the start
         assert_eq!(
-            *store.last_offset.lock(),
-            0,
-            "Offset should reset to 0 after empty page"
+            store.cursor(None),
+            None,
+            "Cursor should reset after empty page"
         );
     }
 
@@ -0,0 +1,124 @@
+//! Repository scenarios shared by the SQLite and Postgres integration tests.
+
+use sqlx::{AnyPool, Row};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use backend::execution::types::{ChunkResult, ChunkStatus, UserResult};
+use backend::planner::types::PlannedAllocation;
+use backend::session::repository::SessionRepository;
+use backend::session::repository_sqlx::{SqlDialect, SqlxSessionRepository};
+
+/// Inserts an active, idle session with `remaining_bid` over `remaining_chunks`.
+pub async fn insert_session(
+    pool: &AnyPool,
+    dialect: SqlDialect,
+    session_id: Uuid,
+    pair_id: &str,
+    remaining_bid: i64,
+    remaining_chunks: i64,
+) {
+    sqlx::query(&dialect.sql(
+        r#"
+INSERT INTO sessions (
+  session_id, pair_id, active,
+  max_spread_bps, max_trend_drop_bps, max_slippage_bps,
+  preferred_chunk_bid, max_bid_per_tick,
+  remaining_bid, remaining_chunks, in_flight_bid, in_flight_chunks,
+  cooldown_until_ms, quantum, deficit, last_served_ms, has_pending_batch,
+  active_windows, quantum_weight, twap_interval_ms, expires_at_ms
+) VALUES (?, ?, TRUE, 50, 100, 75, 100, 1000, ?, ?, 0, 0, 0, 100, 0, 0, FALSE, '[]', 1, 0, 0);
+"#,
+    ))
+    .bind(session_id.to_string())
+    .bind(pair_id)
+    .bind(remaining_bid)
+    .bind(remaining_chunks)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+/// Reserves two sessions, commits a mixed outcome, and checks the session
+/// and batch rows. Finishes with a second reservation, which only succeeds
+/// if the commit released the pending-batch locks.
+pub async fn reserve_and_commit_round_trip(pool: Arc<AnyPool>, dialect: SqlDialect) {
+    let repo = SqlxSessionRepository::new(pool.clone()).with_dialect(dialect);
+    let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+    insert_session(&pool, dialect, a, "TON/USDT", 1000, 10).await;
+    insert_session(&pool, dialect, b, "TON/USDT", 1000, 10).await;
+
+    let allocations = [a, b].map(|session_id| PlannedAllocation {
+        session_id,
+        total_bid: 300,
+        chunks: vec![100, 200],
+    });
+    let batch = repo
+        .reserve_execution("TON/USDT", 1, &allocations)
+        .await
+        .unwrap()
+        .expect("both sessions are reservable");
+    assert_eq!(batch.users.len(), 2);
+
+    // Reserved sessions are locked against a second batch.
+    assert!(
+        repo.reserve_execution("TON/USDT", 2, &allocations)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    let results: Vec<UserResult> = batch
+        .users
+        .iter()
+        .map(|u| UserResult {
+            session_id: u.session_id,
+            cooldown_ms: (u.session_id == b).then_some(60_000),
+            chunk_results: u
+                .chunks
+                .iter()
+                .map(|c| ChunkResult {
+                    chunk_id: c.chunk_id,
+                    status: if u.session_id == a || c.bid == 100 {
+                        ChunkStatus::Success { tx_id: "tx".into() }
+                    } else {
+                        ChunkStatus::Failed {
+                            reason: "slippage".into(),
+                        }
+                    },
+                })
+                .collect(),
+        })
+        .collect();
+    repo.commit_batch(&batch, &results).await.unwrap();
+    // Replaying the commit is a no-op.
+    repo.commit_batch(&batch, &results).await.unwrap();
+
+    let sa = repo.fetch_by_id(&a).await.unwrap().unwrap();
+    assert_eq!(sa.state.remaining_bid, 700);
+    assert_eq!(sa.state.remaining_chunks, 8);
+    assert_eq!(sa.state.in_flight_bid, 0);
+    assert!(!sa.state.has_pending_batch);
+    assert!(sa.active);
+
+    let sb = repo.fetch_by_id(&b).await.unwrap().unwrap();
+    assert_eq!(sb.state.remaining_bid, 900);
+    assert_eq!(sb.state.remaining_chunks, 9);
+    assert_eq!(sb.state.in_flight_chunks, 0);
+    assert!(!sb.state.has_pending_batch);
+    assert!(sb.state.cooldown_until_ms > 0);
+
+    let row = sqlx::query(&dialect.sql("SELECT status FROM batches WHERE batch_id = ?"))
+        .bind(batch.batch_id.to_string())
+        .fetch_one(&*pool)
+        .await
+        .unwrap();
+    assert_eq!(row.get::<String, _>("status"), "COMMITTED");
+
+    assert!(
+        repo.reserve_execution("TON/USDT", 3, &allocations)
+            .await
+            .unwrap()
+            .is_some()
+    );
+}
@@ -0,0 +1,153 @@
+//! Repository tests against a live Postgres.
+//!
+//! Run with `TEST_POSTGRES_URL=postgres://... cargo test --features postgres`.
+#![cfg(feature = "postgres")]
+
+use sqlx::AnyPool;
+use sqlx::any::AnyPoolOptions;
+use std::collections::HashSet;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use backend::planner::types::PlannedAllocation;
+use backend::session::repository::SessionRepository;
+use backend::session::repository_sqlx::{SqlDialect, SqlxSessionRepository};
+
+mod common;
+
+/// Creates a fresh schema with the tables the repository touches.
+async fn setup_pg() -> (AnyPool, String) {
+    sqlx::any::install_default_drivers();
+
+    let url = std::env::var("TEST_POSTGRES_URL").expect("TEST_POSTGRES_URL must be set");
+    let schema = format!("kaskade_test_{}", Uuid::new_v4().simple());
+
+    let admin = AnyPoolOptions::new()
+        .max_connections(1)
+        .connect(&url)
+        .await
+        .unwrap();
+    sqlx::query(&format!("CREATE SCHEMA {schema}"))
+        .execute(&admin)
+        .await
+        .unwrap();
+
+    let sep = if url.contains('?') { '&' } else { '?' };
+    let pool = AnyPoolOptions::new()
+        .max_connections(8)
+        .connect(&format!("{url}{sep}options=-csearch_path%3D{schema}"))
+        .await
+        .unwrap();
+
+    for ddl in [
+        r#"
+CREATE TABLE sessions (
+  session_id TEXT PRIMARY KEY,
+  pair_id TEXT NOT NULL,
+  active BOOLEAN NOT NULL,
+  max_spread_bps DOUBLE PRECISION NOT NULL,
+  max_trend_drop_bps DOUBLE PRECISION NOT NULL,
+  max_slippage_bps DOUBLE PRECISION NOT NULL,
+  preferred_chunk_bid BIGINT NOT NULL,
+  max_bid_per_tick BIGINT NOT NULL,
+  remaining_bid BIGINT NOT NULL,
+  remaining_chunks BIGINT NOT NULL,
+  in_flight_bid BIGINT NOT NULL,
+  in_flight_chunks BIGINT NOT NULL,
+  cooldown_until_ms BIGINT NOT NULL,
+  quantum BIGINT NOT NULL,
+  deficit BIGINT NOT NULL,
+  last_served_ms BIGINT NOT NULL,
+  has_pending_batch BOOLEAN NOT NULL DEFAULT FALSE,
+  active_windows TEXT NOT NULL DEFAULT '[]',
+  quantum_weight BIGINT NOT NULL DEFAULT 1,
+  twap_interval_ms BIGINT NOT NULL DEFAULT 0,
+  expires_at_ms BIGINT NOT NULL DEFAULT 0,
+  failure_cooldown_ms BIGINT
+)"#,
+        r#"
+CREATE TABLE batches (
+  batch_id TEXT PRIMARY KEY,
+  pair_id TEXT NOT NULL,
+  created_ms BIGINT NOT NULL,
+  status TEXT NOT NULL,
+  reason TEXT NOT NULL,
+  commit_attempts BIGINT NOT NULL DEFAULT 0
+)"#,
+        r#"
+CREATE TABLE batch_items (
+  chunk_id TEXT PRIMARY KEY,
+  batch_id TEXT NOT NULL,
+  session_id TEXT NOT NULL,
+  bid BIGINT NOT NULL,
+  status TEXT NOT NULL,
+  tx_id TEXT NOT NULL,
+  error TEXT NOT NULL
+)"#,
+        r#"
+CREATE TABLE chunk_events (
+  chunk_id TEXT PRIMARY KEY,
+  batch_id TEXT NOT NULL,
+  session_id TEXT NOT NULL,
+  outcome TEXT NOT NULL,
+  reason TEXT NOT NULL,
+  tx_id TEXT NOT NULL,
+  ts_ms BIGINT NOT NULL
+)"#,
+    ] {
+        sqlx::query(ddl).execute(&pool).await.unwrap();
+    }
+
+    (pool, schema)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_reservers_never_double_reserve() {
+    let (pool, _schema) = setup_pg().await;
+    let pool = Arc::new(pool);
+
+    let ids: Vec<Uuid> = (0..20).map(|_| Uuid::new_v4()).collect();
+    for id in &ids {
+        common::insert_session(&pool, SqlDialect::Postgres, *id, "TON/USDT", 1000, 10).await;
+    }
+
+    let allocations: Vec<PlannedAllocation> = ids
+        .iter()
+        .map(|&session_id| PlannedAllocation {
+            session_id,
+            total_bid: 100,
+            chunks: vec![100],
+        })
+        .collect();
+
+    let mut tasks = Vec::new();
+    for _ in 0..4 {
+        let repo = SqlxSessionRepository::new(pool.clone()).with_dialect(SqlDialect::Postgres);
+        let allocations = allocations.clone();
+        tasks.push(tokio::spawn(async move {
+            repo.reserve_execution("TON/USDT", 0, &allocations)
+                .await
+                .unwrap()
+        }));
+    }
+
+    let mut reserved = HashSet::new();
+    for t in tasks {
+        if let Some(batch) = t.await.unwrap() {
+            for u in batch.users {
+                assert!(
+                    reserved.insert(u.session_id),
+                    "session {} reserved twice",
+                    u.session_id
+                );
+            }
+        }
+    }
+    assert_eq!(reserved.len(), ids.len());
+}
+
+#[tokio::test]
+async fn reserve_and_commit_round_trip_postgres() {
+    let (pool, _schema) = setup_pg().await;
+    common::reserve_and_commit_round_trip(Arc::new(pool), SqlDialect::Postgres).await;
+}
@@ -1,13 +1,29 @@
+use async_trait::async_trait;
 use sqlx::any::AnyPoolOptions;
 use sqlx::{AnyPool, Row};
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tokio::task::JoinSet;
 use uuid::Uuid;
 
-use backend::execution::types::{ChunkResult, ChunkStatus, UserResult};
+use backend::error::{ReassignPairError, RepositoryError, SwapError};
+use backend::execution::chain::DummySwapExecutor;
+use backend::execution::executor::{ExecutorWorker, RetryPolicy, SwapExecutor, WorkerConfig};
+use backend::execution::types::{
+    ChunkEvent, ChunkResult, ChunkStatus, PendingChunk, RecoveryReport, ReservedBatch,
+    ReservedChunk, ReservedUser, SwapCall, SwapReceipt, UserResult,
+};
+use backend::market::market_view_store::MarketViewStore;
+use backend::market::types::MarketMetricsView;
 use backend::planner::types::PlannedAllocation;
+use backend::session::model::Session;
 use backend::session::repository::SessionRepository;
-use backend::session::repository_sqlx::SqlxSessionRepository;
+use backend::session::repository_sqlx::{SqlDialect, SqlxSessionRepository};
+use backend::session::store::SessionStore;
+use backend::time::now_ms;
+
+mod common;
 
 /// Helper to setup an isolated, unique in-memory SQLite database.
 /// Using a unique name in the connection string prevents "Table already exists"
@@ -44,7 +60,12 @@ CREATE TABLE IF NOT EXISTS sessions (
   quantum BIGINT NOT NULL,
   deficit BIGINT NOT NULL,
   last_served_ms BIGINT NOT NULL,
-  has_pending_batch BOOLEAN NOT NULL DEFAULT 0
+  has_pending_batch BOOLEAN NOT NULL DEFAULT 0,
+  active_windows TEXT NOT NULL DEFAULT '[]',
+  quantum_weight BIGINT NOT NULL DEFAULT 1,
+  twap_interval_ms BIGINT NOT NULL DEFAULT 0,
+  expires_at_ms BIGINT NOT NULL DEFAULT 0,
+  failure_cooldown_ms BIGINT
 );
         "#,
     )
@@ -59,7 +80,17 @@ CREATE TABLE IF NOT EXISTS sessions (
         pair_id TEXT NOT NULL,
         created_ms BIGINT NOT NULL,
         status TEXT NOT NULL,
-        reason TEXT NOT NULL
+        reason TEXT NOT NULL,
+        commit_attempts BIGINT NOT NULL DEFAULT 0
+    );
+
+    CREATE TABLE IF NOT EXISTS dead_letter (
+        batch_id TEXT PRIMARY KEY,
+        pair_id TEXT NOT NULL,
+        dead_lettered_ms BIGINT NOT NULL,
+        commit_attempts BIGINT NOT NULL,
+        error TEXT NOT NULL,
+        results_json TEXT NOT NULL
     );
 
     CREATE TABLE IF NOT EXISTS batch_items (
@@ -71,6 +102,16 @@ CREATE TABLE IF NOT EXISTS sessions (
         tx_id TEXT NOT NULL,
         error TEXT NOT NULL
     );
+
+    CREATE TABLE IF NOT EXISTS chunk_events (
+        chunk_id TEXT PRIMARY KEY,
+        batch_id TEXT NOT NULL,
+        session_id TEXT NOT NULL,
+        outcome TEXT NOT NULL,
+        reason TEXT NOT NULL,
+        tx_id TEXT NOT NULL,
+        ts_ms BIGINT NOT NULL
+    );
     "#,
     )
     .execute(&pool)
@@ -80,6 +121,27 @@ CREATE TABLE IF NOT EXISTS sessions (
     pool
 }
 
+#[test]
+fn sql_dialect_rewrites_placeholders_for_postgres() {
+    let q = "UPDATE t SET a = ? WHERE id = ? AND s = 'x';";
+
+    assert_eq!(SqlDialect::Sqlite.sql(q), q);
+    assert_eq!(
+        SqlDialect::Postgres.sql(q),
+        "UPDATE t SET a = $1 WHERE id = $2 AND s = 'x';"
+    );
+
+    assert_eq!(
+        SqlDialect::from_url("postgres://u@h/db"),
+        SqlDialect::Postgres
+    );
+    assert_eq!(
+        SqlDialect::from_url("postgresql://u@h/db"),
+        SqlDialect::Postgres
+    );
+    assert_eq!(SqlDialect::from_url("sqlite::memory:"), SqlDialect::Sqlite);
+}
+
 #[tokio::test]
 async fn fetch_by_id_round_trip() {
     let pool = Arc::new(setup_db().await);
@@ -87,7 +149,7 @@ async fn fetch_by_id_round_trip() {
 
     let id = Uuid::new_v4();
     sqlx::query(
-        r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 42, 0, 0)"#,
+        r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 42, 0, 0, '[]', 1, 0, 0, NULL)"#,
     )
     .bind(id.to_string())
     .execute(&*pool)
@@ -97,6 +159,44 @@ async fn fetch_by_id_round_trip() {
     let s = repo.fetch_by_id(&id).await.unwrap().unwrap();
     assert_eq!(s.session_id, id);
     assert_eq!(s.state.deficit, 42);
+    assert_eq!(s.intent.failure_cooldown_ms, None);
+
+    let tuned = Uuid::new_v4();
+    sqlx::query(
+        r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 0, '[]', 1, 0, 0, 30000)"#,
+    )
+    .bind(tuned.to_string())
+    .execute(&*pool)
+    .await
+    .unwrap();
+
+    let s = repo.fetch_by_id(&tuned).await.unwrap().unwrap();
+    assert_eq!(s.intent.failure_cooldown_ms, Some(30_000));
+}
+
+#[tokio::test]
+async fn fetch_by_ids_spans_multiple_in_lists() {
+    let pool = Arc::new(setup_db().await);
+    let repo = SqlxSessionRepository::new(pool.clone());
+
+    // More ids than fit in a single IN list.
+    let mut ids = Vec::new();
+    for _ in 0..1200 {
+        let id = Uuid::new_v4();
+        sqlx::query(r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 0, '[]', 1, 0, 0, NULL)"#)
+            .bind(id.to_string())
+            .execute(&*pool).await.unwrap();
+        ids.push(id);
+    }
+    let missing = Uuid::new_v4();
+    ids.push(missing);
+
+    let found = repo.fetch_by_ids(&ids).await.unwrap();
+    assert_eq!(found.len(), 1200);
+    assert!(!found.contains_key(&missing));
+    assert!(found.iter().all(|(id, s)| *id == s.session_id));
+
+    assert!(repo.fetch_by_ids(&[]).await.unwrap().is_empty());
 }
 
 #[tokio::test]
@@ -106,7 +206,7 @@ async fn persist_fairness_updates_row() {
 
     let id = Uuid::new_v4();
     sqlx::query(
-        r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 0)"#,
+        r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 0, '[]', 1, 0, 0, NULL)"#,
     )
     .bind(id.to_string())
     .execute(&*pool)
@@ -132,7 +232,7 @@ async fn poison_rows_are_skipped() {
 
     // Insert invalid UUID string
     sqlx::query(
-        r#"INSERT INTO sessions VALUES ('bad-uuid', 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 0)"#,
+        r#"INSERT INTO sessions VALUES ('bad-uuid', 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 0, '[]', 1, 0, 0, NULL)"#,
     )
     .execute(&*pool)
     .await
@@ -140,7 +240,7 @@ async fn poison_rows_are_skipped() {
 
     let good_id = Uuid::new_v4();
     sqlx::query(
-        r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 0)"#,
+        r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 0, '[]', 1, 0, 0, NULL)"#,
     )
     .bind(good_id.to_string())
     .execute(&*pool)
@@ -148,7 +248,7 @@ async fn poison_rows_are_skipped() {
     .unwrap();
 
     // fetch_page should continue and return valid rows even if one row parsing fails
-    let page = repo.fetch_page(10, 0).await.unwrap();
+    let page = repo.fetch_page(10, 0, None).await.unwrap();
     assert_eq!(page.len(), 1);
     assert_eq!(page[0].session_id, good_id);
 }
@@ -161,7 +261,7 @@ async fn persist_fairness_numeric_overflow_error() {
     let id = Uuid::new_v4();
 
     sqlx::query(
-        r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 0)"#,
+        r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 0, '[]', 1, 0, 0, NULL)"#,
     )
     .bind(id.to_string())
     .execute(&*pool)
@@ -172,8 +272,70 @@ async fn persist_fairness_numeric_overflow_error() {
     // The repository should catch this and return a Result::Err instead of crashing.
     let result = repo.persist_fairness(&id, i128::MAX, 0).await;
     assert!(
-        result.is_err(),
-        "Repository must return Err on i128 -> i64 overflow"
+        matches!(result, Err(RepositoryError::Overflow(_))),
+        "Repository must return Overflow on i128 -> i64 overflow"
+    );
+}
+
+#[tokio::test]
+async fn drr_deficit_near_i64_max_always_persists() {
+    use backend::scheduler::drr;
+
+    let pool = Arc::new(setup_db().await);
+    let repo = SqlxSessionRepository::new(pool.clone());
+
+    let id = Uuid::new_v4();
+    sqlx::query(
+        r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 0, '[]', 1, 0, 0, NULL)"#,
+    )
+    .bind(id.to_string())
+    .execute(&*pool)
+    .await
+    .unwrap();
+
+    let mut s = repo.fetch_by_id(&id).await.unwrap().unwrap();
+    s.intent.preferred_chunk_bid = u128::MAX;
+    s.state.quantum = i64::MAX as u128 / 3;
+    s.state.deficit = i64::MAX as i128 - 5;
+
+    for _ in 0..10 {
+        drr::accumulate_credit(&mut s);
+        drr::apply_aging(&mut s, u64::MAX, 1);
+        repo.persist_fairness(&id, s.state.deficit, 0)
+            .await
+            .expect("bounded deficit must fit the BIGINT column");
+    }
+
+    let stored = repo.fetch_by_id(&id).await.unwrap().unwrap();
+    assert_eq!(stored.state.deficit, i64::MAX as i128);
+}
+
+#[tokio::test]
+async fn commit_batch_reports_typed_errors() {
+    let pool = Arc::new(setup_db().await);
+    let repo = SqlxSessionRepository::new(pool.clone());
+
+    let batch = ReservedBatch {
+        batch_id: Uuid::new_v4(),
+        pair_id: "TON/USDT".into(),
+        created_ms: 0,
+        users: vec![],
+    };
+
+    let missing = repo.commit_batch(&batch, &[]).await;
+    assert!(matches!(missing, Err(RepositoryError::NotFound(_))));
+
+    sqlx::query(
+        "INSERT INTO batches(batch_id, pair_id, created_ms, status, reason) VALUES (?, 'TON/USDT', 0, 'WEIRD', '')",
+    )
+    .bind(batch.batch_id.to_string())
+    .execute(&*pool)
+    .await
+    .unwrap();
+
+    let unexpected = repo.commit_batch(&batch, &[]).await;
+    assert!(
+        matches!(unexpected, Err(RepositoryError::UnexpectedBatchStatus(ref s)) if s == "WEIRD")
     );
 }
 
@@ -184,7 +346,7 @@ async fn test_concurrent_fairness_updates() {
     let id = Uuid::new_v4();
 
     sqlx::query(
-        r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 0)"#,
+        r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 0, '[]', 1, 0, 0, NULL)"#,
     )
     .bind(id.to_string())
     .execute(&*pool)
@@ -222,21 +384,57 @@ async fn fetch_page_empty_at_offset() {
     let repo = SqlxSessionRepository::new(pool.clone());
 
     // Requesting a page when the DB is empty
-    let page = repo.fetch_page(10, 0).await.unwrap();
+    let page = repo.fetch_page(10, 0, None).await.unwrap();
     assert!(page.is_empty(), "Page should be empty for empty table");
 
     // Seed 2 rows
     for _ in 0..2 {
-        sqlx::query(r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 0)"#)
+        sqlx::query(r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 0, '[]', 1, 0, 0, NULL)"#)
             .bind(Uuid::new_v4().to_string())
             .execute(&*pool).await.unwrap();
     }
 
     // Requesting offset exactly at total count
-    let page = repo.fetch_page(10, 2).await.unwrap();
+    let page = repo.fetch_page(10, 2, None).await.unwrap();
     assert!(page.is_empty());
 }
 
+#[tokio::test]
+async fn fetch_page_after_visits_each_active_session_once_per_cycle() {
+    let pool = Arc::new(setup_db().await);
+    let repo = SqlxSessionRepository::new(pool.clone());
+
+    let mut active = std::collections::HashSet::new();
+    for i in 0..1500 {
+        let id = Uuid::new_v4();
+        // Every tenth row is inactive and must never be returned.
+        let is_active = i % 10 != 0;
+        sqlx::query(r#"INSERT INTO sessions VALUES (?, 'TON/USDT', ?, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 0, '[]', 1, 0, 0, NULL)"#)
+            .bind(id.to_string())
+            .bind(is_active)
+            .execute(&*pool).await.unwrap();
+        if is_active {
+            active.insert(id);
+        }
+    }
+
+    for _cycle in 0..2 {
+        let mut seen = std::collections::HashSet::new();
+        let mut cursor = None;
+        loop {
+            let page = repo.fetch_page_after(500, cursor, None).await.unwrap();
+            let Some(last) = page.last() else {
+                break; // wrap around
+            };
+            cursor = Some(last.session_id);
+            for s in &page {
+                assert!(seen.insert(s.session_id), "session visited twice");
+            }
+        }
+        assert_eq!(seen, active);
+    }
+}
+
 #[tokio::test]
 async fn reserve_execution_happy_path() {
     let pool = Arc::new(setup_db().await);
@@ -251,7 +449,7 @@ async fn reserve_execution_happy_path() {
          1000, 10,
          0, 0,
          0, 100,
-         0, 0, 0)"#,
+         0, 0, 0, '[]', 1, 0, 0, NULL)"#,
     )
     .bind(session_id.to_string())
     .execute(&*pool)
@@ -285,6 +483,97 @@ async fn reserve_execution_happy_path() {
     assert_eq!(row.get::<i64, _>("in_flight_chunks"), 3);
 }
 
+#[tokio::test]
+async fn reserve_execution_writes_many_chunks_in_order() {
+    let pool = Arc::new(setup_db().await);
+    let repo = SqlxSessionRepository::new(pool.clone());
+
+    let session_id = Uuid::new_v4();
+    common::insert_session(
+        &pool,
+        SqlDialect::Sqlite,
+        session_id,
+        "TON/USDT",
+        1_000_000,
+        100,
+    )
+    .await;
+
+    let chunks: Vec<u128> = (1..=50).map(|i| i * 100).collect();
+    let alloc = PlannedAllocation {
+        session_id,
+        total_bid: chunks.iter().sum(),
+        chunks: chunks.clone(),
+    };
+
+    let batch = repo
+        .reserve_execution("TON/USDT", 0, &[alloc])
+        .await
+        .unwrap()
+        .unwrap();
+
+    let reserved = &batch.users[0].chunks;
+    assert_eq!(reserved.iter().map(|c| c.bid).collect::<Vec<_>>(), chunks);
+
+    let rows = sqlx::query(
+        "SELECT chunk_id, bid, session_id, status FROM batch_items WHERE batch_id = ? ORDER BY bid",
+    )
+    .bind(batch.batch_id.to_string())
+    .fetch_all(&*pool)
+    .await
+    .unwrap();
+    assert_eq!(rows.len(), 50);
+    for (row, c) in rows.iter().zip(reserved) {
+        assert_eq!(row.get::<String, _>("chunk_id"), c.chunk_id.to_string());
+        assert_eq!(row.get::<i64, _>("bid") as u128, c.bid);
+        assert_eq!(row.get::<String, _>("session_id"), session_id.to_string());
+        assert_eq!(row.get::<String, _>("status"), "PENDING");
+    }
+}
+
+#[tokio::test]
+async fn reserve_execution_rolls_back_when_item_insert_fails() {
+    let pool = Arc::new(setup_db().await);
+    let repo = SqlxSessionRepository::new(pool.clone());
+
+    let ids = [Uuid::new_v4(), Uuid::new_v4()];
+    for id in ids {
+        common::insert_session(&pool, SqlDialect::Sqlite, id, "TON/USDT", 1_000, 10).await;
+    }
+    sqlx::query("DROP TABLE batch_items")
+        .execute(&*pool)
+        .await
+        .unwrap();
+
+    let allocations = ids.map(|session_id| PlannedAllocation {
+        session_id,
+        total_bid: 300,
+        chunks: vec![100, 200],
+    });
+    assert!(
+        repo.reserve_execution("TON/USDT", 0, &allocations)
+            .await
+            .is_err()
+    );
+
+    let batches: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM batches")
+        .fetch_one(&*pool)
+        .await
+        .unwrap();
+    assert_eq!(batches, 0);
+    for id in ids {
+        let s = repo.fetch_by_id(&id).await.unwrap().unwrap();
+        assert_eq!(s.state.in_flight_bid, 0);
+        assert!(!s.state.has_pending_batch);
+    }
+}
+
+#[tokio::test]
+async fn reserve_and_commit_round_trip_sqlite() {
+    let pool = Arc::new(setup_db().await);
+    common::reserve_and_commit_round_trip(pool, SqlDialect::Sqlite).await;
+}
+
 #[tokio::test]
 async fn reserve_execution_fails_on_insufficient_bid() {
     let pool = Arc::new(setup_db().await);
@@ -298,7 +587,7 @@ async fn reserve_execution_fails_on_insufficient_bid() {
          200, 10,
          0, 0,
          0, 100,
-         0, 0, 0)"#,
+         0, 0, 0, '[]', 1, 0, 0, NULL)"#,
     )
     .bind(session_id.to_string())
     .execute(&*pool)
@@ -341,7 +630,7 @@ async fn reserve_execution_rolls_back_on_failure() {
          100, 1,
          0, 0,
          0, 100,
-         0, 0, 0)"#,
+         0, 0, 0, '[]', 1, 0, 0, NULL)"#,
     )
     .bind(session_id.to_string())
     .execute(&*pool)
@@ -381,7 +670,7 @@ async fn reserve_execution_concurrent_safety() {
          300, 3,
          0, 0,
          0, 100,
-         0, 0, 0)"#,
+         0, 0, 0, '[]', 1, 0, 0, NULL)"#,
     )
     .bind(session_id.to_string())
     .execute(&*pool)
@@ -427,7 +716,7 @@ async fn reserve_execution_pair_mismatch_fails() {
          1000, 10,
          0, 0,
          0, 100,
-         0, 0, 0)"#,
+         0, 0, 0, '[]', 1, 0, 0, NULL)"#,
     )
     .bind(session_id.to_string())
     .execute(&*pool)
@@ -461,7 +750,7 @@ async fn commit_batch_all_success() {
          1000, 10,
          0, 0,
          0, 100,
-         0, 0, 0)"#,
+         0, 0, 0, '[]', 1, 0, 0, NULL)"#,
     )
     .bind(session_id.to_string())
     .execute(&*pool)
@@ -526,7 +815,7 @@ async fn commit_batch_partial_failure() {
          1000, 10,
          0, 0,
          0, 100,
-         0, 0, 0)"#,
+         0, 0, 0, '[]', 1, 0, 0, NULL)"#,
     )
     .bind(session_id.to_string())
     .execute(&*pool)
@@ -592,7 +881,7 @@ async fn commit_batch_all_failed() {
          500, 10,
          0, 0,
          0, 100,
-         0, 0, 0)"#,
+         0, 0, 0, '[]', 1, 0, 0, NULL)"#,
     )
     .bind(session_id.to_string())
     .execute(&*pool)
@@ -651,7 +940,7 @@ async fn commit_batch_is_idempotent() {
          500, 10,
          0, 0,
          0, 100,
-         0, 0, 0)"#,
+         0, 0, 0, '[]', 1, 0, 0, NULL)"#,
     )
     .bind(session_id.to_string())
     .execute(&*pool)
@@ -699,6 +988,216 @@ async fn commit_batch_is_idempotent() {
     assert_eq!(row.get::<i64, _>("remaining_bid"), 300);
 }
 
+#[tokio::test]
+async fn commit_batch_skips_items_already_processed() {
+    let pool = Arc::new(setup_db().await);
+    let repo = SqlxSessionRepository::new(pool.clone());
+    let session_id = Uuid::new_v4();
+    common::insert_session(&pool, SqlDialect::Sqlite, session_id, "TON/USDT", 1_000, 10).await;
+
+    let batch = repo
+        .reserve_execution(
+            "TON/USDT",
+            0,
+            &[PlannedAllocation {
+                session_id,
+                total_bid: 600,
+                chunks: vec![100, 200, 300],
+            }],
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    let chunks = &batch.users[0].chunks;
+
+    // The first chunk's outcome and commit math were already applied.
+    sqlx::query("UPDATE batch_items SET status = 'SUCCESS', tx_id = 'tx-early' WHERE chunk_id = ?")
+        .bind(chunks[0].chunk_id.to_string())
+        .execute(&*pool)
+        .await
+        .unwrap();
+    sqlx::query(
+        r#"UPDATE sessions
+        SET in_flight_bid = in_flight_bid - 100, in_flight_chunks = in_flight_chunks - 1,
+            remaining_bid = remaining_bid - 100, remaining_chunks = remaining_chunks - 1
+        WHERE session_id = ?"#,
+    )
+    .bind(session_id.to_string())
+    .execute(&*pool)
+    .await
+    .unwrap();
+
+    let results = vec![UserResult {
+        session_id,
+        cooldown_ms: None,
+        chunk_results: chunks
+            .iter()
+            .map(|c| ChunkResult {
+                chunk_id: c.chunk_id,
+                status: ChunkStatus::Success {
+                    tx_id: "tx-late".into(),
+                },
+            })
+            .collect(),
+    }];
+    repo.commit_batch(&batch, &results).await.unwrap();
+
+    let mut tx_ids = Vec::new();
+    for c in chunks {
+        tx_ids.push(item_outcome(&pool, c.chunk_id).await.1);
+    }
+    assert_eq!(tx_ids, ["tx-early", "tx-late", "tx-late"]);
+
+    let s = repo.fetch_by_id(&session_id).await.unwrap().unwrap();
+    assert_eq!(s.state.remaining_bid, 400);
+    assert_eq!(s.state.remaining_chunks, 7);
+    assert_eq!(s.state.in_flight_bid, 0);
+    assert_eq!(s.state.in_flight_chunks, 0);
+    assert!(!s.state.has_pending_batch);
+}
+
+#[tokio::test]
+async fn commit_batch_records_each_chunk_outcome_once() {
+    let pool = Arc::new(setup_db().await);
+    let repo = SqlxSessionRepository::new(pool.clone());
+    let session_id = Uuid::new_v4();
+
+    sqlx::query(
+        r#"INSERT INTO sessions VALUES
+        (?, 'TON/USDT', 1, 50, 100, 75,
+         100, 1000,
+         1000, 10,
+         0, 0,
+         0, 100,
+         0, 0, 0, '[]', 1, 0, 0, NULL)"#,
+    )
+    .bind(session_id.to_string())
+    .execute(&*pool)
+    .await
+    .unwrap();
+
+    let batch = repo
+        .reserve_execution(
+            "TON/USDT",
+            0,
+            &[PlannedAllocation {
+                session_id,
+                total_bid: 300,
+                chunks: vec![100, 200],
+            }],
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    let chunks = &batch.users[0].chunks;
+
+    let results = vec![UserResult {
+        session_id,
+        cooldown_ms: None,
+        chunk_results: vec![
+            ChunkResult {
+                chunk_id: chunks[0].chunk_id,
+                status: ChunkStatus::Success {
+                    tx_id: "tx1".into(),
+                },
+            },
+            ChunkResult {
+                chunk_id: chunks[1].chunk_id,
+                status: ChunkStatus::Failed {
+                    reason: "MarketClosed".into(),
+                },
+            },
+        ],
+    }];
+
+    // Fail the session update so the commit transaction rolls back.
+    sqlx::query(
+        r#"
+    CREATE TRIGGER fail_commit BEFORE UPDATE OF remaining_bid ON sessions
+    BEGIN
+        SELECT RAISE(ABORT, 'boom');
+    END;
+    "#,
+    )
+    .execute(&*pool)
+    .await
+    .unwrap();
+
+    assert!(repo.commit_batch(&batch, &results).await.is_err());
+    assert!(
+        repo.fetch_chunk_history(&session_id, 10)
+            .await
+            .unwrap()
+            .is_empty(),
+        "events must roll back with the commit"
+    );
+
+    sqlx::query("DROP TRIGGER fail_commit")
+        .execute(&*pool)
+        .await
+        .unwrap();
+
+    repo.commit_batch(&batch, &results).await.unwrap();
+    repo.commit_batch(&batch, &results).await.unwrap();
+
+    let history = repo.fetch_chunk_history(&session_id, 10).await.unwrap();
+    assert_eq!(history.len(), 2, "re-commit must not duplicate events");
+
+    assert!(
+        history
+            .iter()
+            .all(|e| e.batch_id == batch.batch_id && e.session_id == session_id)
+    );
+    let status = |chunk_id: Uuid| {
+        &history
+            .iter()
+            .find(|e| e.chunk_id == chunk_id)
+            .unwrap()
+            .status
+    };
+    assert!(matches!(
+        status(chunks[0].chunk_id),
+        ChunkStatus::Success { tx_id } if tx_id == "tx1"
+    ));
+    assert!(matches!(
+        status(chunks[1].chunk_id),
+        ChunkStatus::Failed { reason } if reason == "MarketClosed"
+    ));
+
+    // A later aborted batch shows up first; `limit` caps the result.
+    let aborted = repo
+        .reserve_execution(
+            "TON/USDT",
+            0,
+            &[PlannedAllocation {
+                session_id,
+                total_bid: 100,
+                chunks: vec![100],
+            }],
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    repo.abort_batch(&aborted.batch_id, "operator")
+        .await
+        .unwrap();
+
+    let latest = repo.fetch_chunk_history(&session_id, 1).await.unwrap();
+    assert_eq!(latest.len(), 1);
+    assert_eq!(latest[0].chunk_id, aborted.users[0].chunks[0].chunk_id);
+    assert!(matches!(
+        &latest[0].status,
+        ChunkStatus::Skipped { reason } if reason == "operator"
+    ));
+    assert!(
+        repo.fetch_chunk_history(&Uuid::new_v4(), 10)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
 #[tokio::test]
 async fn test_extreme_timestamp_persistence() {
     let pool = Arc::new(setup_db().await);
@@ -706,7 +1205,7 @@ async fn test_extreme_timestamp_persistence() {
     let id = Uuid::new_v4();
 
     // Setup session
-    sqlx::query(r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100, 1000, 1000, 10, 0, 0, 0, 100, 0, 0, 0)"#)
+    sqlx::query(r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100, 1000, 1000, 10, 0, 0, 0, 100, 0, 0, 0, '[]', 1, 0, 0, NULL)"#)
             .bind(id.to_string()).execute(&*pool).await.unwrap();
 
     // Use a very large u64 timestamp (e.g., year 2262 approx)
@@ -730,7 +1229,7 @@ async fn test_commit_batch_unwinds_in_flight_on_failure() {
     let repo = SqlxSessionRepository::new(pool.clone());
     let session_id = Uuid::new_v4();
 
-    sqlx::query(r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100, 1000, 1000, 10, 0, 0, 0, 100, 0, 0, 0)"#)
+    sqlx::query(r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100, 1000, 1000, 10, 0, 0, 0, 100, 0, 0, 0, '[]', 1, 0, 0, NULL)"#)
             .bind(session_id.to_string()).execute(&*pool).await.unwrap();
 
     // Reserve 500 bid
@@ -782,7 +1281,7 @@ async fn test_commit_cooldown_idempotency() {
     let repo = SqlxSessionRepository::new(pool.clone());
     let session_id = Uuid::new_v4();
 
-    sqlx::query(r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100, 1000, 1000, 10, 0, 0, 0, 100, 0, 0, 0)"#)
+    sqlx::query(r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100, 1000, 1000, 10, 0, 0, 0, 100, 0, 0, 0, '[]', 1, 0, 0, NULL)"#)
             .bind(session_id.to_string()).execute(&*pool).await.unwrap();
 
     let alloc = PlannedAllocation {
@@ -851,7 +1350,12 @@ INSERT INTO sessions VALUES
  500, 1,
  0, 100,
  0, 0,
- 1                  -- has_pending_batch = true
+ 1,                 -- has_pending_batch = true
+ '[]',
+ 1,
+ 0,
+ 0,
+ NULL
 );
 "#,
     )
@@ -953,3 +1457,1532 @@ SELECT status FROM batches WHERE batch_id = ?;
     // Idempotency: running recovery again must be safe
     repo.recover_uncommitted().await.unwrap();
 }
+
+/// Seeds a session holding 600/3 in flight for a RESERVED batch whose items
+/// are `(bid, status, tx_id, error)`; returns `(session_id, batch_id, chunk_ids)`.
+async fn seed_reserved_batch(
+    pool: &AnyPool,
+    items: &[(i64, &str, &str, &str)],
+) -> (Uuid, Uuid, Vec<Uuid>) {
+    let session_id = Uuid::new_v4();
+    let batch_id = Uuid::new_v4();
+
+    sqlx::query(
+        r#"
+INSERT INTO sessions VALUES
+(?, 'TON/USDT', 1,
+ 50, 100, 75,
+ 100, 1000,
+ 1000, 10,
+ 600, 3,
+ 0, 100,
+ 0, 0,
+ 1,
+ '[]',
+ 1,
+ 0,
+ 0,
+ NULL
+);
+"#,
+    )
+    .bind(session_id.to_string())
+    .execute(pool)
+    .await
+    .unwrap();
+
+    sqlx::query(
+        r#"
+INSERT INTO batches (batch_id, pair_id, created_ms, status, reason)
+VALUES (?, 'TON/USDT', 0, 'RESERVED', '');
+"#,
+    )
+    .bind(batch_id.to_string())
+    .execute(pool)
+    .await
+    .unwrap();
+
+    let mut chunk_ids = Vec::new();
+    for (bid, status, tx_id, error) in items {
+        let chunk_id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+INSERT INTO batch_items
+(chunk_id, batch_id, session_id, bid, status, tx_id, error)
+VALUES (?, ?, ?, ?, ?, ?, ?);
+"#,
+        )
+        .bind(chunk_id.to_string())
+        .bind(batch_id.to_string())
+        .bind(session_id.to_string())
+        .bind(*bid)
+        .bind(*status)
+        .bind(*tx_id)
+        .bind(*error)
+        .execute(pool)
+        .await
+        .unwrap();
+        chunk_ids.push(chunk_id);
+    }
+
+    (session_id, batch_id, chunk_ids)
+}
+
+#[tokio::test]
+async fn recover_uncommitted_reports_what_it_unwound() {
+    let pool = Arc::new(setup_db().await);
+    let repo = SqlxSessionRepository::new(pool.clone());
+
+    seed_reserved_batch(&pool, &[(100, "PENDING", "", ""), (200, "PENDING", "", "")]).await;
+    seed_reserved_batch(
+        &pool,
+        &[(50, "SUCCESS", "tx-1", ""), (70, "PENDING", "", "")],
+    )
+    .await;
+    seed_reserved_batch(&pool, &[(40, "PENDING", "", "")]).await;
+
+    let report = repo.recover_uncommitted().await.unwrap();
+    assert_eq!(
+        report,
+        RecoveryReport {
+            batches_committed: 1,
+            batches_aborted: 2,
+            chunks_unwound: 4,
+            bid_released: 410,
+        }
+    );
+
+    let row = sqlx::query(
+        "SELECT COUNT(*) AS n, SUM(bid) AS bid FROM batch_items WHERE error = 'recovered_uncommitted'",
+    )
+    .fetch_one(&*pool)
+    .await
+    .unwrap();
+    assert_eq!(row.get::<i64, _>("n"), report.chunks_unwound as i64);
+    assert_eq!(row.get::<i64, _>("bid") as u128, report.bid_released);
+
+    let aborted: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM batches WHERE status = 'ABORTED'")
+        .fetch_one(&*pool)
+        .await
+        .unwrap();
+    assert_eq!(aborted as usize, report.batches_aborted);
+
+    // A second pass finds nothing left to do.
+    assert!(repo.recover_uncommitted().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn recover_uncommitted_settles_executed_items_and_unwinds_pending() {
+    let pool = Arc::new(setup_db().await);
+    let repo = SqlxSessionRepository::new(pool.clone());
+
+    // Item rows were written for the first two chunks, but the commit math
+    // never ran; the third chunk has no outcome.
+    let (session_id, batch_id, chunks) = seed_reserved_batch(
+        &pool,
+        &[
+            (100, "SUCCESS", "tx-1", ""),
+            (200, "FAILED", "", "Slippage"),
+            (300, "PENDING", "", ""),
+        ],
+    )
+    .await;
+
+    repo.recover_uncommitted().await.unwrap();
+
+    let session_row = || async {
+        sqlx::query(
+            r#"
+SELECT in_flight_bid, in_flight_chunks, remaining_bid, remaining_chunks, last_served_ms,
+CAST(has_pending_batch AS INTEGER) AS has_pending_batch
+FROM sessions WHERE session_id = ?;
+"#,
+        )
+        .bind(session_id.to_string())
+        .fetch_one(&*pool)
+        .await
+        .unwrap()
+    };
+
+    let row = session_row().await;
+    assert_eq!(row.get::<i64, _>("in_flight_bid"), 0);
+    assert_eq!(row.get::<i64, _>("in_flight_chunks"), 0);
+    // Only the executed chunk is consumed.
+    assert_eq!(row.get::<i64, _>("remaining_bid"), 900);
+    assert_eq!(row.get::<i64, _>("remaining_chunks"), 9);
+    assert!(row.get::<i64, _>("last_served_ms") > 0);
+    assert_eq!(row.get::<i64, _>("has_pending_batch"), 0);
+
+    let items: Vec<(String, String, String)> = {
+        let mut out = Vec::new();
+        for c in &chunks {
+            let r = sqlx::query("SELECT status, tx_id, error FROM batch_items WHERE chunk_id = ?")
+                .bind(c.to_string())
+                .fetch_one(&*pool)
+                .await
+                .unwrap();
+            out.push((r.get(0), r.get(1), r.get(2)));
+        }
+        out
+    };
+    assert_eq!(items[0], ("SUCCESS".into(), "tx-1".into(), "".into()));
+    assert_eq!(items[1], ("FAILED".into(), "".into(), "Slippage".into()));
+    assert_eq!(
+        items[2],
+        ("SKIPPED".into(), "".into(), "recovered_uncommitted".into())
+    );
+
+    let batch = sqlx::query("SELECT status, reason FROM batches WHERE batch_id = ?")
+        .bind(batch_id.to_string())
+        .fetch_one(&*pool)
+        .await
+        .unwrap();
+    assert_eq!(batch.get::<String, _>("status"), "COMMITTED");
+    assert_eq!(batch.get::<String, _>("reason"), "recovered_uncommitted");
+
+    // Idempotent: a second recovery (or a late commit) changes nothing.
+    repo.recover_uncommitted().await.unwrap();
+    let reserved = ReservedBatch {
+        batch_id,
+        pair_id: "TON/USDT".into(),
+        created_ms: 0,
+        users: vec![],
+    };
+    repo.commit_batch(&reserved, &[]).await.unwrap();
+
+    let again = session_row().await;
+    assert_eq!(again.get::<i64, _>("remaining_bid"), 900);
+    assert_eq!(again.get::<i64, _>("in_flight_bid"), 0);
+}
+
+#[tokio::test]
+async fn recover_uncommitted_leaves_unknown_item_status_reserved() {
+    let pool = Arc::new(setup_db().await);
+    let repo = SqlxSessionRepository::new(pool.clone());
+
+    let (session_id, batch_id, _) = seed_reserved_batch(
+        &pool,
+        &[(100, "SUCCESS", "tx-1", ""), (200, "WEIRD", "", "")],
+    )
+    .await;
+
+    repo.recover_uncommitted().await.unwrap();
+
+    let status: String = sqlx::query_scalar("SELECT status FROM batches WHERE batch_id = ?")
+        .bind(batch_id.to_string())
+        .fetch_one(&*pool)
+        .await
+        .unwrap();
+    assert_eq!(status, "RESERVED");
+
+    // Nothing was applied, not even for the well-formed item.
+    let row = sqlx::query("SELECT in_flight_bid, remaining_bid FROM sessions WHERE session_id = ?")
+        .bind(session_id.to_string())
+        .fetch_one(&*pool)
+        .await
+        .unwrap();
+    assert_eq!(row.get::<i64, _>("in_flight_bid"), 600);
+    assert_eq!(row.get::<i64, _>("remaining_bid"), 1000);
+}
+
+/// Answers `query_status` from `landed`; never executes anything.
+struct LandedExecutor {
+    landed: HashMap<Uuid, String>,
+    unreachable: bool,
+}
+
+#[async_trait]
+impl SwapExecutor for LandedExecutor {
+    async fn execute_swap(&self, _: SwapCall) -> Result<SwapReceipt, SwapError> {
+        panic!("recovery must not execute swaps");
+    }
+
+    async fn query_status(&self, chunk_id: Uuid) -> Result<Option<SwapReceipt>, SwapError> {
+        if self.unreachable {
+            return Err(SwapError::Timeout);
+        }
+        Ok(self.landed.get(&chunk_id).map(|tx_id| SwapReceipt {
+            tx_id: tx_id.clone(),
+            idempotency_key: None,
+        }))
+    }
+}
+
+async fn item_outcome(pool: &AnyPool, chunk_id: Uuid) -> (String, String, String) {
+    let r = sqlx::query("SELECT status, tx_id, error FROM batch_items WHERE chunk_id = ?")
+        .bind(chunk_id.to_string())
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    (r.get(0), r.get(1), r.get(2))
+}
+
+#[tokio::test]
+async fn recovery_settles_pending_chunks_that_already_landed() {
+    let pool = Arc::new(setup_db().await);
+    let repo = Arc::new(SqlxSessionRepository::new(pool.clone()));
+    let store = SessionStore::new(repo.clone());
+
+    // The worker crashed after the first swap landed but before commit.
+    let (session_id, batch_id, chunks) = seed_reserved_batch(
+        &pool,
+        &[
+            (100, "PENDING", "", ""),
+            (200, "PENDING", "", ""),
+            (300, "PENDING", "", ""),
+        ],
+    )
+    .await;
+    let mut pending: Vec<PendingChunk> = chunks
+        .iter()
+        .map(|&chunk_id| PendingChunk {
+            batch_id,
+            session_id,
+            chunk_id,
+            created_ms: 0,
+            claimed: false,
+        })
+        .collect();
+    pending.sort_by_key(|c| c.chunk_id.to_string());
+    assert_eq!(repo.pending_chunks().await.unwrap(), pending);
+
+    let exec = LandedExecutor {
+        landed: HashMap::from([(chunks[0], "tx-landed".to_string())]),
+        unreachable: false,
+    };
+    backend::execution::recover_uncommitted(&store, &exec)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        item_outcome(&pool, chunks[0]).await,
+        ("SUCCESS".into(), "tx-landed".into(), "".into())
+    );
+    for &chunk in &chunks[1..] {
+        assert_eq!(
+            item_outcome(&pool, chunk).await,
+            ("SKIPPED".into(), "".into(), "recovered_uncommitted".into())
+        );
+    }
+
+    let row = sqlx::query(
+        "SELECT in_flight_bid, remaining_bid, remaining_chunks FROM sessions WHERE session_id = ?",
+    )
+    .bind(session_id.to_string())
+    .fetch_one(&*pool)
+    .await
+    .unwrap();
+    assert_eq!(row.get::<i64, _>("in_flight_bid"), 0);
+    // Only the landed chunk is consumed.
+    assert_eq!(row.get::<i64, _>("remaining_bid"), 900);
+    assert_eq!(row.get::<i64, _>("remaining_chunks"), 9);
+
+    let status: String = sqlx::query_scalar("SELECT status FROM batches WHERE batch_id = ?")
+        .bind(batch_id.to_string())
+        .fetch_one(&*pool)
+        .await
+        .unwrap();
+    assert_eq!(status, "COMMITTED");
+    assert!(repo.pending_chunks().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn recovery_leaves_batch_reserved_when_chunk_status_is_unknown() {
+    let pool = Arc::new(setup_db().await);
+    let repo = Arc::new(SqlxSessionRepository::new(pool.clone()));
+    let store = SessionStore::new(repo.clone());
+
+    let (_, batch_id, chunks) = seed_reserved_batch(&pool, &[(100, "PENDING", "", "")]).await;
+
+    let exec = LandedExecutor {
+        landed: HashMap::new(),
+        unreachable: true,
+    };
+    assert!(
+        backend::execution::recover_uncommitted(&store, &exec)
+            .await
+            .is_err()
+    );
+
+    // Unwinding could double-execute a chunk that landed; nothing changes.
+    assert_eq!(item_outcome(&pool, chunks[0]).await.0, "PENDING");
+    let status: String = sqlx::query_scalar("SELECT status FROM batches WHERE batch_id = ?")
+        .bind(batch_id.to_string())
+        .fetch_one(&*pool)
+        .await
+        .unwrap();
+    assert_eq!(status, "RESERVED");
+}
+
+#[tokio::test]
+async fn commit_with_unknown_chunk_leaves_batch_for_recovery() {
+    let pool = Arc::new(setup_db().await);
+    let repo = Arc::new(SqlxSessionRepository::new(pool.clone()));
+    let store = SessionStore::new(repo.clone());
+
+    let (session_id, batch_id, chunks) = seed_reserved_batch(
+        &pool,
+        &[
+            (100, "PENDING", "", ""),
+            (200, "PENDING", "", ""),
+            (300, "PENDING", "", ""),
+        ],
+    )
+    .await;
+    let batch = ReservedBatch {
+        batch_id,
+        pair_id: "TON/USDT".into(),
+        created_ms: 0,
+        users: vec![ReservedUser {
+            session_id,
+            chunks: chunks
+                .iter()
+                .zip([100, 200, 300])
+                .map(|(&chunk_id, bid)| ReservedChunk { chunk_id, bid })
+                .collect(),
+        }],
+    };
+
+    // The second swap timed out and its status lookup failed; the third
+    // was never issued.
+    let results = vec![UserResult {
+        session_id,
+        chunk_results: vec![
+            ChunkResult {
+                chunk_id: chunks[0],
+                status: ChunkStatus::Success {
+                    tx_id: "tx-1".into(),
+                },
+            },
+            ChunkResult {
+                chunk_id: chunks[1],
+                status: ChunkStatus::Unknown {
+                    reason: "Timeout".into(),
+                },
+            },
+        ],
+        cooldown_ms: Some(60_000),
+    }];
+    assert!(repo.claim_batch(&batch_id).await.unwrap());
+    repo.commit_batch(&batch, &results).await.unwrap();
+
+    // Handed back to recovery.
+    assert_eq!(batch_status(&pool, batch_id).await.0, "RESERVED");
+    assert_eq!(
+        item_outcome(&pool, chunks[0]).await,
+        ("SUCCESS".into(), "tx-1".into(), "".into())
+    );
+    assert_eq!(item_outcome(&pool, chunks[1]).await.0, "PENDING");
+    assert_eq!(item_outcome(&pool, chunks[2]).await.0, "PENDING");
+
+    let session = || async {
+        sqlx::query(
+            r#"
+SELECT in_flight_bid, remaining_bid, remaining_chunks, cooldown_until_ms,
+CAST(has_pending_batch AS INTEGER) AS has_pending_batch
+FROM sessions WHERE session_id = ?;
+"#,
+        )
+        .bind(session_id.to_string())
+        .fetch_one(&*pool)
+        .await
+        .unwrap()
+    };
+    let row = session().await;
+    // Nothing settled yet: the reservation and the session lock are kept.
+    assert_eq!(row.get::<i64, _>("in_flight_bid"), 600);
+    assert_eq!(row.get::<i64, _>("remaining_bid"), 1000);
+    assert_eq!(row.get::<i64, _>("has_pending_batch"), 1);
+    assert!(row.get::<i64, _>("cooldown_until_ms") > 0);
+    assert!(
+        repo.fetch_chunk_history(&session_id, 10)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    // Re-delivering the same results changes nothing.
+    repo.commit_batch(&batch, &results).await.unwrap();
+    assert_eq!(batch_status(&pool, batch_id).await.0, "RESERVED");
+
+    // Recovery finds that the timed-out swap did land.
+    let exec = LandedExecutor {
+        landed: HashMap::from([(chunks[1], "tx-late".to_string())]),
+        unreachable: false,
+    };
+    backend::execution::recover_uncommitted(&store, &exec)
+        .await
+        .unwrap();
+
+    assert_eq!(batch_status(&pool, batch_id).await.0, "COMMITTED");
+    assert_eq!(
+        item_outcome(&pool, chunks[1]).await,
+        ("SUCCESS".into(), "tx-late".into(), "".into())
+    );
+    assert_eq!(item_outcome(&pool, chunks[2]).await.0, "SKIPPED");
+
+    let row = session().await;
+    assert_eq!(row.get::<i64, _>("in_flight_bid"), 0);
+    assert_eq!(row.get::<i64, _>("remaining_bid"), 700);
+    assert_eq!(row.get::<i64, _>("remaining_chunks"), 8);
+    assert_eq!(row.get::<i64, _>("has_pending_batch"), 0);
+    assert_eq!(
+        repo.fetch_chunk_history(&session_id, 10)
+            .await
+            .unwrap()
+            .len(),
+        3
+    );
+}
+
+async fn batch_status(pool: &AnyPool, batch_id: Uuid) -> (String, String) {
+    let r = sqlx::query("SELECT status, reason FROM batches WHERE batch_id = ?")
+        .bind(batch_id.to_string())
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    (r.get(0), r.get(1))
+}
+
+#[tokio::test]
+async fn stale_recovery_settles_landed_chunks_and_skips_fresh_batches() {
+    let pool = Arc::new(setup_db().await);
+    let repo = Arc::new(SqlxSessionRepository::new(pool.clone()));
+    let store = SessionStore::new(repo.clone());
+
+    // Seeded batches are reserved at 0; move one past the cutoff.
+    let (_, stale, stale_chunks) =
+        seed_reserved_batch(&pool, &[(100, "PENDING", "", ""), (200, "PENDING", "", "")]).await;
+    let (_, fresh, fresh_chunks) = seed_reserved_batch(&pool, &[(100, "PENDING", "", "")]).await;
+    sqlx::query("UPDATE batches SET created_ms = 5000 WHERE batch_id = ?")
+        .bind(fresh.to_string())
+        .execute(&*pool)
+        .await
+        .unwrap();
+
+    let exec = LandedExecutor {
+        landed: HashMap::from([
+            (stale_chunks[0], "tx-stale".to_string()),
+            (fresh_chunks[0], "tx-fresh".to_string()),
+        ]),
+        unreachable: false,
+    };
+    backend::execution::recover_stale(&store, &exec, 1_000)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        item_outcome(&pool, stale_chunks[0]).await,
+        ("SUCCESS".into(), "tx-stale".into(), "".into())
+    );
+    assert_eq!(
+        item_outcome(&pool, stale_chunks[1]).await,
+        ("SKIPPED".into(), "".into(), "recovered_stale".into())
+    );
+    assert_eq!(
+        batch_status(&pool, stale).await,
+        ("COMMITTED".into(), "recovered_stale".into())
+    );
+
+    // A worker may still be executing the fresh batch.
+    assert_eq!(item_outcome(&pool, fresh_chunks[0]).await.0, "PENDING");
+    assert_eq!(batch_status(&pool, fresh).await.0, "RESERVED");
+}
+
+#[tokio::test]
+async fn stale_recovery_unwinds_when_executor_cannot_look_up_swaps() {
+    let pool = Arc::new(setup_db().await);
+    let repo = Arc::new(SqlxSessionRepository::new(pool.clone()));
+    let store = SessionStore::new(repo.clone());
+
+    let (session_id, batch_id, chunks) =
+        seed_reserved_batch(&pool, &[(100, "PENDING", "", "")]).await;
+
+    // The default `query_status` knows of no landed swaps.
+    backend::execution::recover_stale(&store, &DummySwapExecutor, 1_000)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        item_outcome(&pool, chunks[0]).await,
+        ("SKIPPED".into(), "".into(), "recovered_stale".into())
+    );
+    assert_eq!(
+        batch_status(&pool, batch_id).await,
+        ("ABORTED".into(), "recovered_stale".into())
+    );
+    let row = sqlx::query("SELECT in_flight_bid, remaining_bid FROM sessions WHERE session_id = ?")
+        .bind(session_id.to_string())
+        .fetch_one(&*pool)
+        .await
+        .unwrap();
+    assert_eq!(row.get::<i64, _>("in_flight_bid"), 500);
+    assert_eq!(row.get::<i64, _>("remaining_bid"), 1000);
+}
+
+/// Holds every swap until `release` is notified.
+#[derive(Default)]
+struct GatedExecutor {
+    swaps: AtomicUsize,
+    started: tokio::sync::Notify,
+    release: tokio::sync::Notify,
+}
+
+#[async_trait]
+impl SwapExecutor for GatedExecutor {
+    async fn execute_swap(&self, call: SwapCall) -> Result<SwapReceipt, SwapError> {
+        self.swaps.fetch_add(1, Ordering::SeqCst);
+        self.started.notify_one();
+        self.release.notified().await;
+        Ok(SwapReceipt {
+            tx_id: format!("tx-{}", call.chunk_id),
+            idempotency_key: Some(call.idempotency_key),
+        })
+    }
+}
+
+/// Runs `batch` through an `ExecutorWorker` on `exec` in the background.
+async fn spawn_worker(
+    store: Arc<SessionStore>,
+    exec: Arc<GatedExecutor>,
+    batch: ReservedBatch,
+) -> tokio::task::JoinHandle<()> {
+    let market_view = MarketViewStore::new();
+    market_view
+        .set(
+            "TON/USDT",
+            MarketMetricsView {
+                ts_ms: now_ms(),
+                spread_bps: 5.0,
+                trend_drop_bps: 5.0,
+                max_depth: 1_000_000,
+                slippage_bps: None,
+            },
+        )
+        .await;
+
+    let worker = ExecutorWorker::new(
+        store,
+        market_view,
+        exec,
+        WorkerConfig::default(),
+        "TON/USDT".into(),
+    );
+    let (tx, rx) = tokio::sync::mpsc::channel(1);
+    tx.send(batch).await.unwrap();
+    tokio::spawn(worker.run(rx))
+}
+
+fn one_chunk_batch(session_id: Uuid, batch_id: Uuid, chunk_id: Uuid) -> ReservedBatch {
+    ReservedBatch {
+        batch_id,
+        pair_id: "TON/USDT".into(),
+        created_ms: 0,
+        users: vec![ReservedUser {
+            session_id,
+            chunks: vec![ReservedChunk { chunk_id, bid: 100 }],
+        }],
+    }
+}
+
+#[tokio::test]
+async fn stale_recovery_skips_batch_whose_swap_is_in_flight() {
+    let pool = Arc::new(setup_db().await);
+    let store = Arc::new(SessionStore::new(Arc::new(SqlxSessionRepository::new(
+        pool.clone(),
+    ))));
+
+    let (session_id, batch_id, chunks) =
+        seed_reserved_batch(&pool, &[(100, "PENDING", "", "")]).await;
+    let exec = Arc::new(GatedExecutor::default());
+    let worker = spawn_worker(
+        store.clone(),
+        exec.clone(),
+        one_chunk_batch(session_id, batch_id, chunks[0]),
+    )
+    .await;
+
+    // The recovery tick fires while the swap is still in flight; the batch
+    // is well past the cutoff but claimed by the worker.
+    exec.started.notified().await;
+    let report = backend::execution::recover_stale(&store, &*exec, now_ms())
+        .await
+        .unwrap();
+    assert!(report.is_empty());
+    assert_eq!(batch_status(&pool, batch_id).await.0, "EXECUTING");
+    assert_eq!(item_outcome(&pool, chunks[0]).await.0, "PENDING");
+
+    // The swap lands and the worker's commit records it.
+    exec.release.notify_one();
+    worker.await.unwrap();
+    assert_eq!(
+        item_outcome(&pool, chunks[0]).await,
+        ("SUCCESS".into(), format!("tx-{}", chunks[0]), "".into())
+    );
+    assert_eq!(batch_status(&pool, batch_id).await.0, "COMMITTED");
+}
+
+#[tokio::test]
+async fn worker_drops_batch_recovered_before_its_claim() {
+    let pool = Arc::new(setup_db().await);
+    let store = Arc::new(SessionStore::new(Arc::new(SqlxSessionRepository::new(
+        pool.clone(),
+    ))));
+
+    // Recovery unwinds the batch while it is still queued.
+    let (session_id, batch_id, chunks) =
+        seed_reserved_batch(&pool, &[(100, "PENDING", "", "")]).await;
+    backend::execution::recover_stale(&store, &DummySwapExecutor, 1_000)
+        .await
+        .unwrap();
+
+    let exec = Arc::new(GatedExecutor::default());
+    exec.release.notify_one();
+    spawn_worker(
+        store,
+        exec.clone(),
+        one_chunk_batch(session_id, batch_id, chunks[0]),
+    )
+    .await
+    .await
+    .unwrap();
+
+    assert_eq!(exec.swaps.load(Ordering::SeqCst), 0);
+    assert_eq!(
+        batch_status(&pool, batch_id).await,
+        ("ABORTED".into(), "recovered_stale".into())
+    );
+    assert_eq!(item_outcome(&pool, chunks[0]).await.0, "SKIPPED");
+}
+
+#[tokio::test]
+async fn commit_batch_applies_updates_in_sorted_order() {
+    let pool = Arc::new(setup_db().await);
+    let repo = SqlxSessionRepository::new(pool.clone());
+
+    // Record every batch_items update in statement order.
+    sqlx::query(
+        r#"
+    CREATE TABLE update_log (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        session_id TEXT NOT NULL,
+        chunk_id TEXT NOT NULL
+    );
+
+    CREATE TRIGGER log_batch_item_update AFTER UPDATE ON batch_items
+    BEGIN
+        INSERT INTO update_log(session_id, chunk_id) VALUES (NEW.session_id, NEW.chunk_id);
+    END;
+    "#,
+    )
+    .execute(&*pool)
+    .await
+    .unwrap();
+
+    let mut allocs = Vec::new();
+    for _ in 0..3 {
+        let session_id = Uuid::new_v4();
+        sqlx::query(
+            r#"INSERT INTO sessions VALUES
+            (?, 'TON/USDT', 1, 50, 100, 75,
+             100, 1000,
+             1000, 10,
+             0, 0,
+             0, 100,
+             0, 0, 0, '[]', 1, 0, 0, NULL)"#,
+        )
+        .bind(session_id.to_string())
+        .execute(&*pool)
+        .await
+        .unwrap();
+
+        allocs.push(PlannedAllocation {
+            session_id,
+            total_bid: 300,
+            chunks: vec![100, 100, 100],
+        });
+    }
+
+    let batch = repo
+        .reserve_execution("TON/USDT", 0, &allocs)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Hand results over in reverse order at both levels.
+    let results: Vec<UserResult> = batch
+        .users
+        .iter()
+        .rev()
+        .map(|u| UserResult {
+            session_id: u.session_id,
+            cooldown_ms: None,
+            chunk_results: u
+                .chunks
+                .iter()
+                .rev()
+                .map(|c| ChunkResult {
+                    chunk_id: c.chunk_id,
+                    status: ChunkStatus::Success { tx_id: "tx".into() },
+                })
+                .collect(),
+        })
+        .collect();
+
+    repo.commit_batch(&batch, &results).await.unwrap();
+
+    let mut expected: Vec<(Uuid, Uuid)> = batch
+        .users
+        .iter()
+        .flat_map(|u| u.chunks.iter().map(move |c| (u.session_id, c.chunk_id)))
+        .collect();
+    expected.sort();
+
+    let rows = sqlx::query("SELECT session_id, chunk_id FROM update_log ORDER BY seq")
+        .fetch_all(&*pool)
+        .await
+        .unwrap();
+
+    let actual: Vec<(Uuid, Uuid)> = rows
+        .iter()
+        .map(|r| {
+            (
+                Uuid::parse_str(&r.get::<String, _>("session_id")).unwrap(),
+                Uuid::parse_str(&r.get::<String, _>("chunk_id")).unwrap(),
+            )
+        })
+        .collect();
+
+    assert_eq!(actual, expected);
+}
+
+#[tokio::test]
+async fn fetch_by_id_decodes_active_windows() {
+    let pool = Arc::new(setup_db().await);
+    let repo = SqlxSessionRepository::new(pool.clone());
+
+    let id = Uuid::new_v4();
+    sqlx::query(
+        r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 0, '[[79200000, 7200000], [32400000, 61200000]]', 1, 0, 0, NULL)"#,
+    )
+    .bind(id.to_string())
+    .execute(&*pool)
+    .await
+    .unwrap();
+
+    let s = repo.fetch_by_id(&id).await.unwrap().unwrap();
+    assert_eq!(
+        s.intent.active_windows,
+        vec![(79_200_000, 7_200_000), (32_400_000, 61_200_000)]
+    );
+}
+
+#[tokio::test]
+async fn malformed_active_windows_rows_are_skipped() {
+    let pool = Arc::new(setup_db().await);
+    let repo = SqlxSessionRepository::new(pool.clone());
+
+    for windows in ["not-json", "[[0, 90000000]]"] {
+        sqlx::query(
+            r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 0, ?, 1, 0, 0, NULL)"#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(windows)
+        .execute(&*pool)
+        .await
+        .unwrap();
+    }
+
+    let page = repo.fetch_page(10, 0, None).await.unwrap();
+    assert!(page.is_empty());
+}
+
+#[tokio::test]
+async fn expire_due_deactivates_only_expired_sessions() {
+    let pool = Arc::new(setup_db().await);
+    let repo = SqlxSessionRepository::new(pool.clone());
+
+    let expired = Uuid::new_v4();
+    let future = Uuid::new_v4();
+    let never = Uuid::new_v4();
+
+    for (id, expires_at_ms) in [(expired, 1_000i64), (future, 10_000), (never, 0)] {
+        sqlx::query(
+            r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 0, '[]', 1, 0, ?, NULL)"#,
+        )
+        .bind(id.to_string())
+        .bind(expires_at_ms)
+        .execute(&*pool)
+        .await
+        .unwrap();
+    }
+
+    assert_eq!(repo.expire_due(5_000).await.unwrap(), 1);
+    assert_eq!(repo.expire_due(5_000).await.unwrap(), 0, "already inactive");
+
+    let s = repo.fetch_by_id(&expired).await.unwrap().unwrap();
+    assert!(!s.active);
+    assert!(repo.fetch_by_id(&future).await.unwrap().unwrap().active);
+    assert!(repo.fetch_by_id(&never).await.unwrap().unwrap().active);
+}
+
+#[tokio::test]
+async fn reassign_pair_moves_clean_session() {
+    let pool = Arc::new(setup_db().await);
+    let repo = SqlxSessionRepository::new(pool.clone());
+
+    let id = Uuid::new_v4();
+    sqlx::query(
+        r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 0, '[]', 1, 0, 0, NULL)"#,
+    )
+    .bind(id.to_string())
+    .execute(&*pool)
+    .await
+    .unwrap();
+
+    repo.reassign_pair(&id, "TON/STON").await.unwrap();
+
+    let s = repo.fetch_by_id(&id).await.unwrap().unwrap();
+    assert_eq!(s.pair_id, "TON/STON");
+}
+
+#[tokio::test]
+async fn store_reassign_pair_moves_cached_session_to_new_pair() {
+    let pool = Arc::new(setup_db().await);
+    let repo: Arc<dyn SessionRepository> = Arc::new(SqlxSessionRepository::new(pool.clone()));
+    let store = SessionStore::new(repo);
+
+    let id = Uuid::new_v4();
+    sqlx::query(
+        r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 0, '[]', 1, 0, 0, NULL)"#,
+    )
+    .bind(id.to_string())
+    .execute(&*pool)
+    .await
+    .unwrap();
+
+    store.ensure_candidates(10, Some("TON/USDT")).await.unwrap();
+    assert_eq!(store.get_cached(&id).unwrap().pair_id, "TON/USDT");
+
+    store.reassign_pair(&id, "TON/STON").await.unwrap();
+
+    // The old pair no longer sees it; the new pair's refill loads it moved.
+    assert!(store.get_cached(&id).is_none());
+    assert_eq!(store.cache_len_rr(), 0);
+    store.ensure_candidates(10, Some("TON/STON")).await.unwrap();
+    assert_eq!(store.get_cached(&id).unwrap().pair_id, "TON/STON");
+}
+
+#[tokio::test]
+async fn store_reassign_pair_keeps_cache_on_refusal() {
+    let pool = Arc::new(setup_db().await);
+    let repo: Arc<dyn SessionRepository> = Arc::new(SqlxSessionRepository::new(pool.clone()));
+    let store = SessionStore::new(repo);
+
+    let id = Uuid::new_v4();
+    sqlx::query(
+        r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 1, '[]', 1, 0, 0, NULL)"#,
+    )
+    .bind(id.to_string())
+    .execute(&*pool)
+    .await
+    .unwrap();
+    store.upsert_cache(store.load_by_id(&id).await.unwrap());
+
+    let err = store.reassign_pair(&id, "TON/STON").await.unwrap_err();
+    assert!(matches!(err, ReassignPairError::PendingBatch(_)));
+    assert_eq!(store.get_cached(&id).unwrap().pair_id, "TON/USDT");
+}
+
+#[tokio::test]
+async fn reassign_pair_refuses_in_flight_session() {
+    let pool = Arc::new(setup_db().await);
+    let repo = SqlxSessionRepository::new(pool.clone());
+
+    let in_flight = Uuid::new_v4();
+    let pending = Uuid::new_v4();
+
+    sqlx::query(
+        r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 300, 1, 0, 100000, 0, 0, 0, '[]', 1, 0, 0, NULL)"#,
+    )
+    .bind(in_flight.to_string())
+    .execute(&*pool)
+    .await
+    .unwrap();
+
+    sqlx::query(
+        r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100000, 1000000, 1000, 10, 0, 0, 0, 100000, 0, 0, 1, '[]', 1, 0, 0, NULL)"#,
+    )
+    .bind(pending.to_string())
+    .execute(&*pool)
+    .await
+    .unwrap();
+
+    let err = repo
+        .reassign_pair(&in_flight, "TON/STON")
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        ReassignPairError::InFlight {
+            in_flight_bid: 300,
+            ..
+        }
+    ));
+
+    let err = repo.reassign_pair(&pending, "TON/STON").await.unwrap_err();
+    assert!(matches!(err, ReassignPairError::PendingBatch(id) if id == pending));
+
+    let err = repo
+        .reassign_pair(&Uuid::new_v4(), "TON/STON")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ReassignPairError::SessionNotFound(_)));
+
+    // Neither session moved.
+    for id in [in_flight, pending] {
+        let s = repo.fetch_by_id(&id).await.unwrap().unwrap();
+        assert_eq!(s.pair_id, "TON/USDT");
+    }
+}
+
+#[tokio::test]
+async fn read_pool_serves_reads_and_primary_serves_writes() {
+    let primary = Arc::new(setup_db().await);
+    let replica = Arc::new(setup_db().await);
+    let repo = SqlxSessionRepository::with_read_pool(primary.clone(), Some(replica.clone()));
+
+    let id = Uuid::new_v4();
+    for (pool, deficit) in [(&primary, 1), (&replica, 2)] {
+        sqlx::query(
+            r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100, 1000, 1000, 10, 0, 0, 0, 100, ?, 0, 0, '[]', 1, 0, 0, NULL)"#,
+        )
+        .bind(id.to_string())
+        .bind(deficit)
+        .execute(&**pool)
+        .await
+        .unwrap();
+    }
+
+    // Reads come from the replica.
+    assert_eq!(
+        repo.fetch_by_id(&id).await.unwrap().unwrap().state.deficit,
+        2
+    );
+    assert_eq!(
+        repo.fetch_page(10, 0, None).await.unwrap()[0].state.deficit,
+        2
+    );
+
+    // Writes go to the primary only.
+    repo.persist_fairness(&id, 99, 7).await.unwrap();
+
+    let alloc = PlannedAllocation {
+        session_id: id,
+        total_bid: 100,
+        chunks: vec![100],
+    };
+    repo.reserve_execution("TON/USDT", 0, &[alloc])
+        .await
+        .unwrap()
+        .unwrap();
+
+    let deficit_of = |pool: Arc<AnyPool>| async move {
+        sqlx::query("SELECT deficit FROM sessions WHERE session_id = ?")
+            .bind(id.to_string())
+            .fetch_one(&*pool)
+            .await
+            .unwrap()
+            .get::<i64, _>("deficit")
+    };
+    assert_eq!(deficit_of(primary.clone()).await, 99);
+    assert_eq!(deficit_of(replica.clone()).await, 2);
+
+    let batches = |pool: Arc<AnyPool>| async move {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM batches")
+            .fetch_one(&*pool)
+            .await
+            .unwrap()
+    };
+    assert_eq!(batches(primary).await, 1);
+    assert_eq!(batches(replica).await, 0);
+}
+
+#[tokio::test]
+async fn read_pool_falls_back_to_primary() {
+    let primary = Arc::new(setup_db().await);
+    let repo = SqlxSessionRepository::with_read_pool(primary.clone(), None);
+
+    let id = Uuid::new_v4();
+    sqlx::query(
+        r#"INSERT INTO sessions VALUES (?, 'TON/USDT', 1, 50, 100, 75, 100, 1000, 1000, 10, 0, 0, 0, 100, 0, 0, 0, '[]', 1, 0, 0, NULL)"#,
+    )
+    .bind(id.to_string())
+    .execute(&*primary)
+    .await
+    .unwrap();
+
+    assert!(repo.fetch_by_id(&id).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn commit_batch_simulated_chunks_keep_remaining() {
+    let pool = Arc::new(setup_db().await);
+    let repo = SqlxSessionRepository::new(pool.clone());
+    let session_id = Uuid::new_v4();
+
+    sqlx::query(
+        r#"INSERT INTO sessions VALUES
+        (?, 'TON/USDT', 1, 50, 100, 75,
+         100, 1000,
+         500, 10,
+         0, 0,
+         0, 100,
+         0, 0, 0, '[]', 1, 0, 0, NULL)"#,
+    )
+    .bind(session_id.to_string())
+    .execute(&*pool)
+    .await
+    .unwrap();
+
+    let alloc = PlannedAllocation {
+        session_id,
+        total_bid: 200,
+        chunks: vec![100, 100],
+    };
+
+    let batch = repo
+        .reserve_execution("TON/USDT", 0, &[alloc])
+        .await
+        .unwrap()
+        .unwrap();
+
+    let results = vec![UserResult {
+        session_id,
+        cooldown_ms: None,
+        chunk_results: batch.users[0]
+            .chunks
+            .iter()
+            .map(|c| ChunkResult {
+                chunk_id: c.chunk_id,
+                status: ChunkStatus::Simulated,
+            })
+            .collect(),
+    }];
+
+    repo.commit_batch(&batch, &results).await.unwrap();
+
+    let row = sqlx::query(
+        "SELECT remaining_bid, in_flight_bid, in_flight_chunks, CAST(has_pending_batch AS INTEGER) AS has_pending_batch, last_served_ms FROM sessions WHERE session_id = ?",
+    )
+    .bind(session_id.to_string())
+    .fetch_one(&*pool)
+    .await
+    .unwrap();
+
+    assert_eq!(row.get::<i64, _>("remaining_bid"), 500);
+    assert_eq!(row.get::<i64, _>("in_flight_bid"), 0);
+    assert_eq!(row.get::<i64, _>("in_flight_chunks"), 0);
+    assert_eq!(row.get::<i64, _>("has_pending_batch"), 0);
+    assert!(row.get::<i64, _>("last_served_ms") > 0);
+
+    let simulated: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM batch_items WHERE batch_id = ? AND status = 'SIMULATED'",
+    )
+    .bind(batch.batch_id.to_string())
+    .fetch_one(&*pool)
+    .await
+    .unwrap();
+    assert_eq!(simulated, 2);
+}
+
+#[tokio::test]
+async fn abort_batch_unwinds_reserved_batch_and_is_idempotent() {
+    let pool = Arc::new(setup_db().await);
+    let repo = SqlxSessionRepository::new(pool.clone());
+
+    let session_id = Uuid::new_v4();
+    let batch_id = Uuid::new_v4();
+    let chunk_id = Uuid::new_v4();
+
+    sqlx::query(
+        r#"
+INSERT INTO sessions VALUES
+(?, 'TON/USDT', 1,
+ 50, 100, 75,
+ 100, 1000,
+ 1000, 10,
+ 500, 1,
+ 0, 100,
+ 0, 0,
+ 1,
+ '[]',
+ 1,
+ 0,
+ 0,
+ NULL
+);
+"#,
+    )
+    .bind(session_id.to_string())
+    .execute(&*pool)
+    .await
+    .unwrap();
+
+    sqlx::query(
+        r#"
+INSERT INTO batches (batch_id, pair_id, created_ms, status, reason)
+VALUES (?, 'TON/USDT', 0, 'RESERVED', '');
+"#,
+    )
+    .bind(batch_id.to_string())
+    .execute(&*pool)
+    .await
+    .unwrap();
+
+    sqlx::query(
+        r#"
+INSERT INTO batch_items
+(chunk_id, batch_id, session_id, bid, status, tx_id, error)
+VALUES (?, ?, ?, 500, 'PENDING', '', '');
+"#,
+    )
+    .bind(chunk_id.to_string())
+    .bind(batch_id.to_string())
+    .bind(session_id.to_string())
+    .execute(&*pool)
+    .await
+    .unwrap();
+
+    repo.abort_batch(&batch_id, "dead_letter").await.unwrap();
+
+    let row = sqlx::query(
+        r#"
+SELECT in_flight_bid, in_flight_chunks,
+CAST(has_pending_batch AS INTEGER) AS has_pending_batch
+FROM sessions WHERE session_id = ?;
+"#,
+    )
+    .bind(session_id.to_string())
+    .fetch_one(&*pool)
+    .await
+    .unwrap();
+
+    assert_eq!(row.get::<i64, _>("in_flight_bid"), 0);
+    assert_eq!(row.get::<i64, _>("in_flight_chunks"), 0);
+    assert_eq!(row.get::<i64, _>("has_pending_batch"), 0);
+
+    let item = sqlx::query("SELECT status, error FROM batch_items WHERE chunk_id = ?;")
+        .bind(chunk_id.to_string())
+        .fetch_one(&*pool)
+        .await
+        .unwrap();
+    assert_eq!(item.get::<String, _>("status"), "SKIPPED");
+    assert_eq!(item.get::<String, _>("error"), "dead_letter");
+
+    let batch = sqlx::query("SELECT status, reason FROM batches WHERE batch_id = ?;")
+        .bind(batch_id.to_string())
+        .fetch_one(&*pool)
+        .await
+        .unwrap();
+    assert_eq!(batch.get::<String, _>("status"), "ABORTED");
+    assert_eq!(batch.get::<String, _>("reason"), "dead_letter");
+
+    // Second abort (or a later recovery) must not touch the batch again.
+    repo.abort_batch(&batch_id, "other").await.unwrap();
+    repo.recover_uncommitted().await.unwrap();
+
+    let reason: String = sqlx::query_scalar("SELECT reason FROM batches WHERE batch_id = ?;")
+        .bind(batch_id.to_string())
+        .fetch_one(&*pool)
+        .await
+        .unwrap();
+    assert_eq!(reason, "dead_letter");
+}
+
+/// Delegates to the sqlx repository but fails the first `fail_commits`
+/// `commit_batch` calls, as a flaky primary would.
+struct FlakyCommitRepo {
+    inner: SqlxSessionRepository,
+    fail_commits: usize,
+    commits: AtomicUsize,
+}
+
+#[async_trait]
+impl SessionRepository for FlakyCommitRepo {
+    async fn fetch_page(
+        &self,
+        limit: usize,
+        offset: usize,
+        pair_id: Option<&str>,
+    ) -> Result<Vec<Session>, RepositoryError> {
+        self.inner.fetch_page(limit, offset, pair_id).await
+    }
+    async fn fetch_page_after(
+        &self,
+        limit: usize,
+        after: Option<Uuid>,
+        pair_id: Option<&str>,
+    ) -> Result<Vec<Session>, RepositoryError> {
+        self.inner.fetch_page_after(limit, after, pair_id).await
+    }
+    async fn fetch_by_id(&self, id: &Uuid) -> Result<Option<Session>, RepositoryError> {
+        self.inner.fetch_by_id(id).await
+    }
+    async fn fetch_by_ids(&self, ids: &[Uuid]) -> Result<HashMap<Uuid, Session>, RepositoryError> {
+        self.inner.fetch_by_ids(ids).await
+    }
+    async fn expire_due(&self, now_ms: u64) -> Result<u64, RepositoryError> {
+        self.inner.expire_due(now_ms).await
+    }
+    async fn persist_fairness(
+        &self,
+        id: &Uuid,
+        deficit: i128,
+        last_served_ms: u64,
+    ) -> Result<(), RepositoryError> {
+        self.inner
+            .persist_fairness(id, deficit, last_served_ms)
+            .await
+    }
+    async fn reserve_execution(
+        &self,
+        pair_id: &str,
+        now_ms: u64,
+        allocations: &[PlannedAllocation],
+    ) -> Result<Option<ReservedBatch>, RepositoryError> {
+        self.inner
+            .reserve_execution(pair_id, now_ms, allocations)
+            .await
+    }
+    async fn claim_batch(&self, batch_id: &Uuid) -> Result<bool, RepositoryError> {
+        self.inner.claim_batch(batch_id).await
+    }
+    async fn commit_batch(
+        &self,
+        batch: &ReservedBatch,
+        results: &[UserResult],
+    ) -> Result<(), RepositoryError> {
+        if self.commits.fetch_add(1, Ordering::SeqCst) < self.fail_commits {
+            return Err(RepositoryError::Db(sqlx::Error::PoolTimedOut));
+        }
+        self.inner.commit_batch(batch, results).await
+    }
+    async fn record_commit_failure(&self, batch_id: &Uuid) -> Result<(), RepositoryError> {
+        self.inner.record_commit_failure(batch_id).await
+    }
+    async fn dead_letter_batch(
+        &self,
+        batch: &ReservedBatch,
+        results: &[UserResult],
+        error: &str,
+    ) -> Result<(), RepositoryError> {
+        self.inner.dead_letter_batch(batch, results, error).await
+    }
+    async fn pending_chunks(&self) -> Result<Vec<PendingChunk>, RepositoryError> {
+        self.inner.pending_chunks().await
+    }
+    async fn settle_landed_chunk(
+        &self,
+        batch_id: &Uuid,
+        chunk_id: &Uuid,
+        tx_id: &str,
+    ) -> Result<bool, RepositoryError> {
+        self.inner
+            .settle_landed_chunk(batch_id, chunk_id, tx_id)
+            .await
+    }
+    async fn recover_uncommitted(&self) -> Result<RecoveryReport, RepositoryError> {
+        self.inner.recover_uncommitted().await
+    }
+    async fn recover_stale(
+        &self,
+        reserved_before_ms: u64,
+    ) -> Result<RecoveryReport, RepositoryError> {
+        self.inner.recover_stale(reserved_before_ms).await
+    }
+    async fn abort_batch(&self, batch_id: &Uuid, reason: &str) -> Result<(), RepositoryError> {
+        self.inner.abort_batch(batch_id, reason).await
+    }
+    async fn fetch_chunk_history(
+        &self,
+        session_id: &Uuid,
+        limit: usize,
+    ) -> Result<Vec<ChunkEvent>, RepositoryError> {
+        self.inner.fetch_chunk_history(session_id, limit).await
+    }
+    async fn reassign_pair(
+        &self,
+        session_id: &Uuid,
+        new_pair: &str,
+    ) -> Result<(), ReassignPairError> {
+        self.inner.reassign_pair(session_id, new_pair).await
+    }
+}
+
+struct OkExecutor;
+
+#[async_trait]
+impl SwapExecutor for OkExecutor {
+    async fn execute_swap(&self, call: SwapCall) -> Result<SwapReceipt, SwapError> {
+        Ok(SwapReceipt {
+            tx_id: format!("tx-{}", call.chunk_id),
+            idempotency_key: Some(call.idempotency_key),
+        })
+    }
+}
+
+/// Reserves a two-chunk batch for a fresh session and executes it through
+/// an `ExecutorWorker` whose commits fail `fail_commits` times.
+async fn execute_with_flaky_commit(
+    fail_commits: usize,
+) -> (Arc<AnyPool>, Arc<FlakyCommitRepo>, ReservedBatch) {
+    let pool = Arc::new(setup_db().await);
+    let repo = Arc::new(FlakyCommitRepo {
+        inner: SqlxSessionRepository::new(pool.clone()),
+        fail_commits,
+        commits: AtomicUsize::new(0),
+    });
+
+    let session_id = Uuid::new_v4();
+    sqlx::query(
+        r#"INSERT INTO sessions VALUES
+        (?, 'TON/USDT', 1, 50, 100, 75,
+         100, 1000,
+         1000, 10,
+         0, 0,
+         0, 100,
+         0, 0, 0, '[]', 1, 0, 0, NULL)"#,
+    )
+    .bind(session_id.to_string())
+    .execute(&*pool)
+    .await
+    .unwrap();
+
+    let batch = repo
+        .reserve_execution(
+            "TON/USDT",
+            0,
+            &[PlannedAllocation {
+                session_id,
+                total_bid: 300,
+                chunks: vec![100, 200],
+            }],
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    let market_view = MarketViewStore::new();
+    market_view
+        .set(
+            "TON/USDT",
+            MarketMetricsView {
+                ts_ms: now_ms(),
+                spread_bps: 5.0,
+                trend_drop_bps: 5.0,
+                max_depth: 1_000_000,
+                slippage_bps: None,
+            },
+        )
+        .await;
+
+    let worker = ExecutorWorker::new(
+        Arc::new(SessionStore::new(repo.clone())),
+        market_view,
+        Arc::new(OkExecutor),
+        WorkerConfig {
+            max_commit_attempts: 3,
+            retry: RetryPolicy {
+                max_attempts: 3,
+                base_backoff_ms: 1,
+            },
+            ..Default::default()
+        },
+        "TON/USDT".into(),
+    );
+
+    let (tx, rx) = tokio::sync::mpsc::channel(1);
+    tx.send(batch.clone()).await.unwrap();
+    drop(tx);
+    worker.run(rx).await;
+
+    (pool, repo, batch)
+}
+
+#[tokio::test]
+async fn flaky_commit_is_retried_until_it_succeeds() {
+    // Fails twice, succeeds on the third attempt.
+    let (pool, repo, batch) = execute_with_flaky_commit(2).await;
+
+    assert_eq!(repo.commits.load(Ordering::SeqCst), 3);
+
+    let row = sqlx::query("SELECT status, commit_attempts FROM batches WHERE batch_id = ?")
+        .bind(batch.batch_id.to_string())
+        .fetch_one(&*pool)
+        .await
+        .unwrap();
+    assert_eq!(row.get::<String, _>("status"), "COMMITTED");
+    assert_eq!(row.get::<i64, _>("commit_attempts"), 2);
+
+    let remaining_bid: i64 = sqlx::query_scalar("SELECT remaining_bid FROM sessions")
+        .fetch_one(&*pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining_bid, 700);
+
+    let dead: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM dead_letter")
+        .fetch_one(&*pool)
+        .await
+        .unwrap();
+    assert_eq!(dead, 0);
+}
+
+#[tokio::test]
+async fn commit_is_dead_lettered_after_max_attempts() {
+    let (pool, repo, batch) = execute_with_flaky_commit(usize::MAX).await;
+
+    assert_eq!(repo.commits.load(Ordering::SeqCst), 3);
+
+    let row = sqlx::query("SELECT status, commit_attempts FROM batches WHERE batch_id = ?")
+        .bind(batch.batch_id.to_string())
+        .fetch_one(&*pool)
+        .await
+        .unwrap();
+    assert_eq!(row.get::<String, _>("status"), "DEAD_LETTER");
+    assert_eq!(row.get::<i64, _>("commit_attempts"), 3);
+
+    let dl =
+        sqlx::query("SELECT commit_attempts, results_json FROM dead_letter WHERE batch_id = ?")
+            .bind(batch.batch_id.to_string())
+            .fetch_one(&*pool)
+            .await
+            .unwrap();
+    assert_eq!(dl.get::<i64, _>("commit_attempts"), 3);
+
+    // Full results are kept for reconciliation: both chunks executed.
+    let results: serde_json::Value =
+        serde_json::from_str(&dl.get::<String, _>("results_json")).unwrap();
+    let chunks = results[0]["chunk_results"].as_array().unwrap();
+    assert_eq!(chunks.len(), 2);
+    assert!(chunks.iter().all(|c| c["status"].get("Success").is_some()));
+    assert_eq!(
+        results[0]["session_id"],
+        batch.users[0].session_id.to_string()
+    );
+
+    // Restart recovery must not unwind chunks that already executed.
+    repo.recover_uncommitted().await.unwrap();
+    let s = sqlx::query("SELECT in_flight_bid, remaining_bid FROM sessions")
+        .fetch_one(&*pool)
+        .await
+        .unwrap();
+    assert_eq!(s.get::<i64, _>("in_flight_bid"), 300);
+    assert_eq!(s.get::<i64, _>("remaining_bid"), 1000);
+    let status: String = sqlx::query_scalar("SELECT status FROM batches WHERE batch_id = ?")
+        .bind(batch.batch_id.to_string())
+        .fetch_one(&*pool)
+        .await
+        .unwrap();
+    assert_eq!(status, "DEAD_LETTER");
+}
@@ -1,16 +1,37 @@
+use async_trait::async_trait;
 use sqlx::AnyPool;
 use sqlx::any::AnyPoolOptions;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
 use backend::{
-    execution::types::{ChunkResult, ChunkStatus, ExecutionEvent, ReservedBatch, UserResult},
-    market::types::MarketMetricsView,
+    error::{ReassignPairError, RepositoryError, SwapError},
+    execution::executor::{ExecutorBacklog, PairExecutorRouter, SwapExecutor, WorkerConfig},
+    execution::types::{
+        ChunkEvent, ChunkResult, ChunkStatus, ExecutionEvent, PendingChunk, RecoveryReport,
+        ReservedBatch, SwapCall, SwapReceipt, UserResult,
+    },
+    market::{
+        market_view_store::MarketViewStore,
+        pulses::{MarketPulse, TrendConfirmation, TrendMonitor},
+        types::{MarketMetricsView, PoolSnapshot},
+    },
     metrics::counters::Counters,
-    scheduler::scheduler::Scheduler,
+    planner::types::{PlannedAllocation, PlannedBatch},
+    scheduler::{
+        control::PairControl,
+        scheduler::Scheduler,
+        trace::{CandidateOutcome, SchedulerTrace},
+    },
     session::{
-        repository::SessionRepository, repository_sqlx::SqlxSessionRepository, store::SessionStore,
+        model::{MS_PER_DAY, Session},
+        repository::SessionRepository,
+        repository_sqlx::SqlxSessionRepository,
+        store::SessionStore,
     },
     time::now_ms,
 };
@@ -56,7 +77,12 @@ CREATE TABLE IF NOT EXISTS sessions (
   quantum BIGINT NOT NULL,
   deficit BIGINT NOT NULL,
   last_served_ms BIGINT NOT NULL,
-  has_pending_batch INTEGER NOT NULL DEFAULT 0
+  has_pending_batch INTEGER NOT NULL DEFAULT 0,
+  active_windows TEXT NOT NULL DEFAULT '[]',
+  quantum_weight BIGINT NOT NULL DEFAULT 1,
+  twap_interval_ms BIGINT NOT NULL DEFAULT 0,
+  expires_at_ms BIGINT NOT NULL DEFAULT 0,
+  failure_cooldown_ms BIGINT
 );
 "#,
     )
@@ -71,7 +97,17 @@ CREATE TABLE IF NOT EXISTS batches (
   pair_id TEXT NOT NULL,
   created_ms BIGINT NOT NULL,
   status TEXT NOT NULL,
-  reason TEXT NOT NULL
+  reason TEXT NOT NULL,
+  commit_attempts BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS dead_letter (
+  batch_id TEXT PRIMARY KEY,
+  pair_id TEXT NOT NULL,
+  dead_lettered_ms BIGINT NOT NULL,
+  commit_attempts BIGINT NOT NULL,
+  error TEXT NOT NULL,
+  results_json TEXT NOT NULL
 );
 
 CREATE TABLE IF NOT EXISTS batch_items (
@@ -83,6 +119,16 @@ CREATE TABLE IF NOT EXISTS batch_items (
   tx_id TEXT NOT NULL,
   error TEXT NOT NULL
 );
+
+CREATE TABLE IF NOT EXISTS chunk_events (
+  chunk_id TEXT PRIMARY KEY,
+  batch_id TEXT NOT NULL,
+  session_id TEXT NOT NULL,
+  outcome TEXT NOT NULL,
+  reason TEXT NOT NULL,
+  tx_id TEXT NOT NULL,
+  ts_ms BIGINT NOT NULL
+);
 "#,
     )
     .execute(&pool)
@@ -98,6 +144,7 @@ fn good_market() -> MarketMetricsView {
         spread_bps: 10.0,
         trend_drop_bps: 5.0,
         max_depth: 1_000_000_000,
+        slippage_bps: None,
     }
 }
 
@@ -112,7 +159,7 @@ INSERT INTO sessions VALUES
  1000000, 10,
  0, 0,
  0,
- ?, ?, 0, 0)
+ ?, ?, 0, 0, '[]', 1, 0, 0, NULL)
 "#,
     )
     .bind(id.to_string())
@@ -133,7 +180,7 @@ INSERT INTO sessions VALUES
  1000000, 10,
  0, 0,
  0,
- 100000, 0, 0, 0)
+ 100000, 0, 0, 0, '[]', 1, 0, 0, NULL)
 "#,
     )
     .bind(id.to_string())
@@ -206,7 +253,7 @@ async fn schedules_only_eligible_sessions_single_tick() {
     insert_inactive_session(&pool, bad).await;
 
     store
-        .ensure_candidates(10)
+        .ensure_candidates(10, None)
         .await
         .expect("ensure candidates");
 
@@ -219,13 +266,95 @@ async fn schedules_only_eligible_sessions_single_tick() {
         .expect("on_tick");
 
     let ev = rx.recv().await.expect("expected reserved event");
-    let ExecutionEvent::Reserved(batch) = ev;
+    let ExecutionEvent::Reserved(batch) = ev else {
+        panic!("expected reserved event");
+    };
 
     assert_eq!(batch.pair_id, PAIR);
     assert_eq!(batch.users.len(), 1);
     assert_eq!(batch.users[0].session_id, good);
 }
 
+#[tokio::test]
+async fn candidate_refill_is_filtered_by_pair() {
+    let (pool, _repo, store, sched) = setup_scheduler().await;
+
+    let ton = Uuid::new_v4();
+    let eth = Uuid::new_v4();
+    insert_active_session(&pool, ton, 100_000, 0).await;
+    insert_active_session(&pool, eth, 100_000, 0).await;
+    sqlx::query("UPDATE sessions SET pair_id = 'ETH/USDT' WHERE session_id = ?")
+        .bind(eth.to_string())
+        .execute(&*pool)
+        .await
+        .unwrap();
+
+    // The tick's own refill asks for TON/USDT only.
+    let (tx, _rx) = mpsc::channel(8);
+    sched
+        .on_tick(PAIR, good_market(), tx, now_ms())
+        .await
+        .expect("on_tick");
+    store.ensure_candidates(10, Some(PAIR)).await.unwrap();
+
+    assert!(store.get_cached(&ton).is_some());
+    assert!(store.get_cached(&eth).is_none());
+    assert_eq!(store.cache_len_rr(), 1);
+
+    // Multi-pair mode still loads everything.
+    store.ensure_candidates(10, None).await.unwrap();
+    assert!(store.get_cached(&eth).is_some());
+}
+
+#[tokio::test]
+async fn tick_over_mixed_pair_cache_picks_and_charges_only_its_pair() {
+    let (pool, _repo, store, sched) = setup_scheduler().await;
+
+    let ton = Uuid::new_v4();
+    let eth_ready = Uuid::new_v4();
+    let eth_short = Uuid::new_v4();
+    insert_active_session(&pool, ton, 100_000, 0).await;
+    insert_active_session(&pool, eth_ready, 100_000, 0).await;
+    // Under one quantum of credit: a tick that visited it would bank credit.
+    insert_active_session(&pool, eth_short, 50_000, 0).await;
+    for eth in [eth_ready, eth_short] {
+        sqlx::query("UPDATE sessions SET pair_id = 'ETH/USDT' WHERE session_id = ?")
+            .bind(eth.to_string())
+            .execute(&*pool)
+            .await
+            .unwrap();
+    }
+
+    // Multi-pair refill: every pair's sessions share the cache.
+    store.ensure_candidates(10, None).await.unwrap();
+    assert_eq!(store.cache_len_rr(), 3);
+
+    let (tx, mut rx) = mpsc::channel(8);
+    sched
+        .on_tick(PAIR, good_market(), tx, now_ms())
+        .await
+        .expect("on_tick");
+
+    let Some(ExecutionEvent::Reserved(batch)) = rx.recv().await else {
+        panic!("expected reserved event");
+    };
+    let picked: Vec<Uuid> = batch.users.iter().map(|u| u.session_id).collect();
+    assert_eq!(picked, vec![ton]);
+
+    for eth in [eth_ready, eth_short] {
+        let cached = store.get_cached(&eth).expect("still cached");
+        assert_eq!(fairness_state(&cached), (0, 0, 0, false));
+
+        let (deficit, last_served): (i64, i64) =
+            sqlx::query_as("SELECT deficit, last_served_ms FROM sessions WHERE session_id = ?")
+                .bind(eth.to_string())
+                .fetch_one(&*pool)
+                .await
+                .unwrap();
+        assert_eq!((deficit, last_served), (0, 0));
+    }
+}
+
 #[tokio::test]
 async fn drr_prevents_starvation_when_batches_complete() {
     let (pool, repo, store, sched) = setup_scheduler().await;
@@ -238,7 +367,10 @@ async fn drr_prevents_starvation_when_batches_complete() {
     insert_active_session(&pool, slow, 20_000, 0).await; // needs multiple rounds to reach want
     insert_active_session(&pool, fast, 200_000, 0).await; // served quickly
 
-    store.ensure_candidates(2).await.expect("ensure candidates");
+    store
+        .ensure_candidates(2, None)
+        .await
+        .expect("ensure candidates");
 
     let (tx, mut rx) = mpsc::channel(64);
 
@@ -280,6 +412,199 @@ async fn drr_prevents_starvation_when_batches_complete() {
     );
 }
 
+#[tokio::test]
+async fn weighted_session_is_served_proportionally_more_often() {
+    let (pool, repo, store, sched) = setup_scheduler().await;
+
+    let baseline = Uuid::new_v4();
+    let premium = Uuid::new_v4();
+
+    // quantum = half a preferred chunk: baseline needs two passes per serve.
+    insert_active_session(&pool, baseline, 50_000, 0).await;
+    insert_active_session(&pool, premium, 50_000, 0).await;
+
+    sqlx::query(
+        "UPDATE sessions SET remaining_bid = 100000000, remaining_chunks = 1000, \
+         quantum_weight = CASE WHEN session_id = ? THEN 2 ELSE 1 END",
+    )
+    .bind(premium.to_string())
+    .execute(&*pool)
+    .await
+    .unwrap();
+
+    store
+        .ensure_candidates(2, None)
+        .await
+        .expect("ensure candidates");
+
+    let (tx, mut rx) = mpsc::channel(64);
+    let (mut baseline_served, mut premium_served) = (0u32, 0u32);
+
+    for i in 0..40u64 {
+        sched
+            .on_tick(PAIR, good_market(), tx.clone(), now_ms() + i)
+            .await
+            .expect("on_tick");
+
+        if let Ok(ExecutionEvent::Reserved(batch)) = rx.try_recv() {
+            for u in &batch.users {
+                if u.session_id == baseline {
+                    baseline_served += 1;
+                }
+                if u.session_id == premium {
+                    premium_served += 1;
+                }
+            }
+            commit_all_success(repo.as_ref(), &batch).await;
+        }
+    }
+
+    assert!(baseline_served > 0);
+    let ratio = premium_served as f64 / baseline_served as f64;
+    assert!(
+        (1.5..=2.5).contains(&ratio),
+        "premium={premium_served} baseline={baseline_served}"
+    );
+}
+
+/// Ticks 200ms apart until `id` is reserved; returns the tick index, if any.
+async fn ticks_until_served(
+    sched: &Scheduler,
+    repo: &dyn SessionRepository,
+    id: Uuid,
+    start_ms: u64,
+    max_ticks: u64,
+) -> Option<u64> {
+    let (tx, mut rx) = mpsc::channel(64);
+
+    for i in 0..max_ticks {
+        sched
+            .on_tick(PAIR, good_market(), tx.clone(), start_ms + i * 200)
+            .await
+            .expect("on_tick");
+
+        if let Ok(ExecutionEvent::Reserved(batch)) = rx.try_recv() {
+            commit_all_success(repo, &batch).await;
+            if batch.users.iter().any(|u| u.session_id == id) {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+#[tokio::test]
+async fn starvation_watchdog_serves_under_quantum_session() {
+    let (pool, repo, store, _) = setup_scheduler().await;
+
+    let counters = Counters::default();
+    let sched =
+        Scheduler::new(store.clone(), 10, 1_000, 16, counters.clone()).with_starvation_ms(1_000);
+
+    // quantum = 1 against a 100_000 chunk: plain DRR would need 100k passes.
+    let starved = Uuid::new_v4();
+    insert_active_session(&pool, starved, 1, 0).await;
+
+    let start = now_ms();
+    sqlx::query("UPDATE sessions SET last_served_ms = ? WHERE session_id = ?")
+        .bind(start as i64)
+        .bind(starved.to_string())
+        .execute(&*pool)
+        .await
+        .unwrap();
+
+    store.ensure_candidates(1, None).await.unwrap();
+
+    let served_at = ticks_until_served(&sched, repo.as_ref(), starved, start, 20)
+        .await
+        .expect("starved session must be scheduled");
+
+    // Boost kicks in on the first tick past 1_000ms of waiting.
+    assert_eq!(served_at, 6);
+    assert!(counters.sched_starvation_boosts.load(Ordering::Relaxed) >= 1);
+}
+
+#[tokio::test]
+async fn without_watchdog_under_quantum_session_waits() {
+    let (pool, repo, store, sched) = setup_scheduler().await;
+
+    let starved = Uuid::new_v4();
+    insert_active_session(&pool, starved, 1, 0).await;
+    store.ensure_candidates(1, None).await.unwrap();
+
+    assert_eq!(
+        ticks_until_served(&sched, repo.as_ref(), starved, now_ms(), 20).await,
+        None
+    );
+}
+
+#[tokio::test]
+async fn twap_session_is_not_served_twice_within_interval() {
+    let (pool, repo, store, sched) = setup_scheduler().await;
+
+    // Ample credit and budget: only the TWAP spacing can hold it back.
+    let id = Uuid::new_v4();
+    insert_active_session(&pool, id, 1_000_000, 1_000_000).await;
+    sqlx::query("UPDATE sessions SET twap_interval_ms = 10000 WHERE session_id = ?")
+        .bind(id.to_string())
+        .execute(&*pool)
+        .await
+        .unwrap();
+    store.ensure_candidates(1, None).await.unwrap();
+
+    let (tx, mut rx) = mpsc::channel(8);
+    let start = now_ms();
+
+    let mut served_at = Vec::new();
+    for t in [0, 1_000, 5_000, 9_000, 11_000] {
+        let market = MarketMetricsView {
+            ts_ms: start + t,
+            ..good_market()
+        };
+        sched
+            .on_tick(PAIR, market, tx.clone(), start + t)
+            .await
+            .unwrap();
+        if let Ok(ExecutionEvent::Reserved(batch)) = rx.try_recv() {
+            commit_all_success(repo.as_ref(), &batch).await;
+            served_at.push(t);
+        }
+    }
+
+    // Spacing is measured from the commit (wall clock), just after `start`.
+    assert_eq!(served_at, vec![0, 11_000]);
+}
+
+#[tokio::test]
+async fn tick_budget_caps_total_selected_bid() {
+    let (pool, _repo, store, sched) = setup_scheduler().await;
+    let sched = sched.with_max_total_bid_per_tick(250_000);
+
+    // Five eligible sessions wanting 100_000 each.
+    for _ in 0..5 {
+        insert_active_session(&pool, Uuid::new_v4(), 100_000, 100_000).await;
+    }
+    store.ensure_candidates(5, None).await.unwrap();
+
+    let (tx, mut rx) = mpsc::channel(8);
+    sched
+        .on_tick(PAIR, good_market(), tx, now_ms())
+        .await
+        .unwrap();
+
+    let Ok(ExecutionEvent::Reserved(batch)) = rx.try_recv() else {
+        panic!("expected reserved event");
+    };
+
+    let total: u128 = batch
+        .users
+        .iter()
+        .flat_map(|u| u.chunks.iter().map(|c| c.bid))
+        .sum();
+    assert!(total <= 250_000, "total reserved bid {total} exceeds cap");
+    assert_eq!(batch.users.len(), 2);
+}
+
 #[tokio::test]
 async fn enqueue_failure_does_not_corrupt_state_single_tick() {
     let (pool, _repo, store, sched) = setup_scheduler().await;
@@ -287,7 +612,10 @@ async fn enqueue_failure_does_not_corrupt_state_single_tick() {
     let id = Uuid::new_v4();
     insert_active_session(&pool, id, 100_000, 0).await;
 
-    store.ensure_candidates(1).await.expect("ensure candidates");
+    store
+        .ensure_candidates(1, None)
+        .await
+        .expect("ensure candidates");
 
     // Channel with dropped receiver -> enqueue fails, but reservation must still be persisted as RESERVED.
     let (tx, rx) = mpsc::channel(1);
@@ -313,7 +641,7 @@ async fn enqueue_failure_does_not_corrupt_state_single_tick() {
 //     let id = Uuid::new_v4();
 
 //     insert_active_session(&pool, id, 50_000, 0).await;
-//     store.ensure_candidates(1).await.expect("ensure candidates");
+//     store.ensure_candidates(1, None).await.expect("ensure candidates");
 
 //     let (tx, mut rx) = mpsc::channel(4);
 
@@ -378,7 +706,7 @@ async fn skips_sessions_with_pending_batch() {
         .unwrap();
 
     // Reload into cache
-    store.ensure_candidates(1).await.unwrap();
+    store.ensure_candidates(1, None).await.unwrap();
 
     let (tx, mut rx) = mpsc::channel(8);
 
@@ -419,7 +747,7 @@ async fn recognizes_reserved_batches_as_in_flight_bid() {
     .unwrap();
 
     store
-        .ensure_candidates(1)
+        .ensure_candidates(1, None)
         .await
         .expect("load state from DB");
 
@@ -452,7 +780,7 @@ async fn gate_a_strict_boundary_checks() {
         .await
         .unwrap();
 
-    store.ensure_candidates(1).await.unwrap();
+    store.ensure_candidates(1, None).await.unwrap();
 
     let (tx, mut rx) = mpsc::channel(8);
 
@@ -476,3 +804,697 @@ async fn gate_a_strict_boundary_checks() {
         "market exceeding boundary must be rejected"
     );
 }
+
+#[tokio::test]
+async fn skips_sessions_outside_active_window() {
+    let (pool, _repo, store, sched) = setup_scheduler().await;
+
+    let inside = Uuid::new_v4();
+    let outside = Uuid::new_v4();
+
+    insert_active_session(&pool, inside, 100_000, 100_000).await;
+    insert_active_session(&pool, outside, 100_000, 100_000).await;
+
+    const HOUR_MS: u64 = 3_600_000;
+
+    // inside: 22:00 -> 02:00 (spans midnight), outside: 09:00 -> 17:00
+    sqlx::query("UPDATE sessions SET active_windows = ? WHERE session_id = ?")
+        .bind(format!("[[{}, {}]]", 22 * HOUR_MS, 2 * HOUR_MS))
+        .bind(inside.to_string())
+        .execute(&*pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE sessions SET active_windows = ? WHERE session_id = ?")
+        .bind(format!("[[{}, {}]]", 9 * HOUR_MS, 17 * HOUR_MS))
+        .bind(outside.to_string())
+        .execute(&*pool)
+        .await
+        .unwrap();
+
+    store.ensure_candidates(2, None).await.unwrap();
+
+    let (tx, mut rx) = mpsc::channel(8);
+
+    // 01:00 UTC on some day.
+    let now = (now_ms() / MS_PER_DAY) * MS_PER_DAY + HOUR_MS;
+
+    sched.on_tick(PAIR, good_market(), tx, now).await.unwrap();
+
+    let Some(ExecutionEvent::Reserved(batch)) = rx.recv().await else {
+        panic!("expected reserved event");
+    };
+
+    assert_eq!(batch.users.len(), 1);
+    assert_eq!(batch.users[0].session_id, inside);
+}
+
+#[tokio::test]
+async fn gate_a_rejects_stale_market_snapshot() {
+    let (pool, _repo, store, sched) = setup_scheduler().await;
+    let sched = sched.with_max_snapshot_age_ms(5_000);
+
+    let id = Uuid::new_v4();
+    insert_active_session(&pool, id, 100_000, 100_000).await;
+    store.ensure_candidates(1, None).await.unwrap();
+
+    let (tx, mut rx) = mpsc::channel(8);
+
+    let mut market = good_market();
+    market.ts_ms = 1_000_000;
+
+    // One millisecond past the threshold: treated as missing.
+    sched
+        .on_tick(PAIR, market.clone(), tx.clone(), 1_005_001)
+        .await
+        .unwrap();
+    assert!(
+        rx.try_recv().is_err(),
+        "stale snapshot must fail Gate A closed"
+    );
+
+    // Exactly at the threshold: still accepted.
+    sched.on_tick(PAIR, market, tx, 1_005_000).await.unwrap();
+    assert!(
+        rx.try_recv().is_ok(),
+        "snapshot at max age must pass Gate A"
+    );
+}
+
+#[tokio::test]
+async fn stale_market_skips_tick_before_selection() {
+    let (pool, _repo, store, _) = setup_scheduler().await;
+
+    let counters = Counters::default();
+    let sched = Scheduler::new(store.clone(), 10, 1_000, 16, counters.clone())
+        .with_max_snapshot_age_ms(5_000);
+
+    insert_active_session(&pool, Uuid::new_v4(), 100_000, 100_000).await;
+    store.ensure_candidates(1, None).await.unwrap();
+
+    let (tx, mut rx) = mpsc::channel(8);
+    let now = now_ms();
+
+    // Feed stalled 60s ago: nothing is reserved and no session is even scanned.
+    let stale = MarketMetricsView {
+        ts_ms: now - 60_000,
+        ..good_market()
+    };
+    sched.on_tick(PAIR, stale, tx.clone(), now).await.unwrap();
+    assert!(rx.try_recv().is_err());
+    assert_eq!(count_batches(&pool).await, 0);
+    assert_eq!(counters.sched_stale_market.load(Ordering::Relaxed), 1);
+    assert_eq!(counters.sched_skip_constraints.load(Ordering::Relaxed), 0);
+
+    // Fresh snapshot: scheduling proceeds normally.
+    let fresh = MarketMetricsView {
+        ts_ms: now,
+        ..good_market()
+    };
+    sched.on_tick(PAIR, fresh, tx, now).await.unwrap();
+    assert!(matches!(rx.try_recv(), Ok(ExecutionEvent::Reserved(_))));
+    assert_eq!(counters.sched_stale_market.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn gate_a_rejects_slippage_above_session_limit() {
+    let (pool, _repo, store, sched) = setup_scheduler().await;
+
+    let id = Uuid::new_v4();
+    // max_slippage_bps = 100
+    insert_active_session(&pool, id, 100_000, 100_000).await;
+    store.ensure_candidates(1, None).await.unwrap();
+
+    let (tx, mut rx) = mpsc::channel(8);
+
+    let mut market = good_market();
+    market.slippage_bps = Some(100.1);
+    sched
+        .on_tick(PAIR, market.clone(), tx.clone(), now_ms())
+        .await
+        .unwrap();
+    assert!(
+        rx.try_recv().is_err(),
+        "slippage above limit must fail Gate A"
+    );
+
+    market.slippage_bps = Some(100.0);
+    sched.on_tick(PAIR, market, tx, now_ms()).await.unwrap();
+    assert!(
+        rx.try_recv().is_ok(),
+        "slippage at the limit must pass Gate A"
+    );
+}
+
+#[tokio::test]
+async fn gate_a_rejects_only_confirmed_trend_drops() {
+    let (pool, _repo, store, sched) = setup_scheduler().await;
+
+    let id = Uuid::new_v4();
+    insert_active_session(&pool, id, 100_000, 100_000).await;
+    sqlx::query("UPDATE sessions SET max_trend_drop_bps = 3 WHERE session_id = ?")
+        .bind(id.to_string())
+        .execute(&*pool)
+        .await
+        .unwrap();
+    store.ensure_candidates(1, None).await.unwrap();
+
+    let trend_drop = |end_r1: u128| {
+        let mut m = TrendMonitor::new(10, 1_000).with_confirmation(TrendConfirmation {
+            min_drop_bps: 20.0,
+            min_slope_bps_per_min: 5.0,
+        });
+        for (r1, ts_ms) in [(100_000, 0), (end_r1, 60_000)] {
+            m.update(PoolSnapshot {
+                reserve0: 100_000,
+                reserve1: r1,
+                ts_ms,
+                protocol_fee: 0,
+                lp_fee: 0,
+            });
+        }
+        m.compute().trend_drop_bps
+    };
+
+    let (tx, mut rx) = mpsc::channel(8);
+
+    // A sustained ~300 bps drop is confirmed and exceeds the 3 bps limit.
+    let mut market = good_market();
+    market.trend_drop_bps = trend_drop(97_000);
+    sched
+        .on_tick(PAIR, market.clone(), tx.clone(), now_ms())
+        .await
+        .unwrap();
+    assert!(
+        rx.try_recv().is_err(),
+        "confirmed trend drop must fail Gate A"
+    );
+
+    // A ~5 bps dip is noise: unconfirmed, so it reports no drop.
+    market.trend_drop_bps = trend_drop(99_950);
+    sched.on_tick(PAIR, market, tx, now_ms()).await.unwrap();
+    assert!(rx.try_recv().is_ok(), "unconfirmed dip must pass Gate A");
+}
+
+/// Backlog probe with a manually controlled depth.
+struct FixedBacklog(AtomicUsize);
+
+#[async_trait::async_trait]
+impl ExecutorBacklog for FixedBacklog {
+    async fn queue_depth(&self, _pair_id: &str) -> Option<usize> {
+        Some(self.0.load(Ordering::SeqCst))
+    }
+}
+
+async fn count_batches(pool: &AnyPool) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM batches")
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn backpressure_holds_reservations_until_queue_drains() {
+    let (pool, _repo, store, _) = setup_scheduler().await;
+
+    let counters = Counters::default();
+    let backlog = Arc::new(FixedBacklog(AtomicUsize::new(2)));
+    let sched = Scheduler::new(store.clone(), 10, 1_000, 16, counters.clone())
+        .with_backpressure(backlog.clone(), 2);
+
+    insert_active_session(&pool, Uuid::new_v4(), 100_000, 100_000).await;
+    store.ensure_candidates(1, None).await.unwrap();
+
+    let (tx, mut rx) = mpsc::channel(8);
+
+    // Queue saturated: nothing is reserved, however many ticks run.
+    for _ in 0..3 {
+        sched
+            .on_tick(PAIR, good_market(), tx.clone(), now_ms())
+            .await
+            .unwrap();
+    }
+    assert!(rx.try_recv().is_err());
+    assert_eq!(count_batches(&pool).await, 0);
+    assert_eq!(counters.sched_backpressure.load(Ordering::Relaxed), 3);
+
+    // Worker drained one batch: scheduling resumes.
+    backlog.0.store(1, Ordering::SeqCst);
+    sched
+        .on_tick(PAIR, good_market(), tx, now_ms())
+        .await
+        .unwrap();
+
+    assert!(matches!(rx.try_recv(), Ok(ExecutionEvent::Reserved(_))));
+    assert_eq!(count_batches(&pool).await, 1);
+    assert_eq!(counters.sched_backpressure.load(Ordering::Relaxed), 3);
+}
+
+#[tokio::test]
+async fn paused_pair_reserves_nothing_until_resumed() {
+    let (pool, _repo, store, _) = setup_scheduler().await;
+
+    let counters = Counters::default();
+    let control = PairControl::new();
+    let sched = Scheduler::new(store.clone(), 10, 1_000, 16, counters.clone())
+        .with_pair_control(control.clone());
+
+    insert_active_session(&pool, Uuid::new_v4(), 100_000, 100_000).await;
+    store.ensure_candidates(1, None).await.unwrap();
+
+    let (tx, mut rx) = mpsc::channel(8);
+
+    control.pause(PAIR);
+    for _ in 0..2 {
+        sched
+            .on_tick(PAIR, good_market(), tx.clone(), now_ms())
+            .await
+            .unwrap();
+    }
+    assert!(rx.try_recv().is_err());
+    assert_eq!(count_batches(&pool).await, 0);
+    assert_eq!(counters.sched_paused.load(Ordering::Relaxed), 2);
+
+    // Pausing another pair does not affect this one.
+    control.resume(PAIR);
+    control.pause("STON/TON");
+    sched
+        .on_tick(PAIR, good_market(), tx, now_ms())
+        .await
+        .unwrap();
+
+    assert!(matches!(rx.try_recv(), Ok(ExecutionEvent::Reserved(_))));
+    assert_eq!(count_batches(&pool).await, 1);
+    assert_eq!(counters.sched_paused.load(Ordering::Relaxed), 2);
+    // Paused ticks never reach the reservation.
+    assert_eq!(counters.reserve_execution_latency.count(), 1);
+}
+
+#[tokio::test]
+async fn trace_explains_skipped_cooldown_session() {
+    let (pool, _repo, store, _) = setup_scheduler().await;
+
+    let trace = SchedulerTrace::new();
+    let sched =
+        Scheduler::new(store.clone(), 10, 1_000, 16, Counters::default()).with_trace(trace.clone());
+
+    let (ready, cooling) = (Uuid::new_v4(), Uuid::new_v4());
+    insert_active_session(&pool, ready, 100_000, 100_000).await;
+    insert_active_session(&pool, cooling, 100_000, 100_000).await;
+    sqlx::query("UPDATE sessions SET cooldown_until_ms = ? WHERE session_id = ?")
+        .bind((now_ms() + 60_000) as i64)
+        .bind(cooling.to_string())
+        .execute(&*pool)
+        .await
+        .unwrap();
+    store.ensure_candidates(2, None).await.unwrap();
+
+    let (tx, _rx) = mpsc::channel(8);
+    sched
+        .on_tick(PAIR, good_market(), tx, now_ms())
+        .await
+        .unwrap();
+
+    let tick = trace.last_tick(PAIR).expect("tick was traced");
+    assert_eq!(tick.outcome(&cooling), Some(CandidateOutcome::Cooldown));
+    assert_eq!(tick.outcome(&ready), Some(CandidateOutcome::Selected));
+}
+
+/// Repository that counts the writes a scheduler tick can make.
+struct WriteCountingRepo {
+    inner: SqlxSessionRepository,
+    reserves: AtomicUsize,
+    fairness_writes: AtomicUsize,
+}
+
+#[async_trait]
+impl SessionRepository for WriteCountingRepo {
+    async fn fetch_page(
+        &self,
+        limit: usize,
+        offset: usize,
+        pair_id: Option<&str>,
+    ) -> Result<Vec<Session>, RepositoryError> {
+        self.inner.fetch_page(limit, offset, pair_id).await
+    }
+    async fn fetch_page_after(
+        &self,
+        limit: usize,
+        after: Option<Uuid>,
+        pair_id: Option<&str>,
+    ) -> Result<Vec<Session>, RepositoryError> {
+        self.inner.fetch_page_after(limit, after, pair_id).await
+    }
+    async fn fetch_by_id(&self, id: &Uuid) -> Result<Option<Session>, RepositoryError> {
+        self.inner.fetch_by_id(id).await
+    }
+    async fn fetch_by_ids(&self, ids: &[Uuid]) -> Result<HashMap<Uuid, Session>, RepositoryError> {
+        self.inner.fetch_by_ids(ids).await
+    }
+    async fn expire_due(&self, now_ms: u64) -> Result<u64, RepositoryError> {
+        self.inner.expire_due(now_ms).await
+    }
+    async fn persist_fairness(
+        &self,
+        id: &Uuid,
+        deficit: i128,
+        last_served_ms: u64,
+    ) -> Result<(), RepositoryError> {
+        self.fairness_writes.fetch_add(1, Ordering::SeqCst);
+        self.inner
+            .persist_fairness(id, deficit, last_served_ms)
+            .await
+    }
+    async fn reserve_execution(
+        &self,
+        pair_id: &str,
+        now_ms: u64,
+        allocations: &[PlannedAllocation],
+    ) -> Result<Option<ReservedBatch>, RepositoryError> {
+        self.reserves.fetch_add(1, Ordering::SeqCst);
+        self.inner
+            .reserve_execution(pair_id, now_ms, allocations)
+            .await
+    }
+    async fn claim_batch(&self, batch_id: &Uuid) -> Result<bool, RepositoryError> {
+        self.inner.claim_batch(batch_id).await
+    }
+    async fn commit_batch(
+        &self,
+        batch: &ReservedBatch,
+        results: &[UserResult],
+    ) -> Result<(), RepositoryError> {
+        self.inner.commit_batch(batch, results).await
+    }
+    async fn record_commit_failure(&self, batch_id: &Uuid) -> Result<(), RepositoryError> {
+        self.inner.record_commit_failure(batch_id).await
+    }
+    async fn dead_letter_batch(
+        &self,
+        batch: &ReservedBatch,
+        results: &[UserResult],
+        error: &str,
+    ) -> Result<(), RepositoryError> {
+        self.inner.dead_letter_batch(batch, results, error).await
+    }
+    async fn pending_chunks(&self) -> Result<Vec<PendingChunk>, RepositoryError> {
+        self.inner.pending_chunks().await
+    }
+    async fn settle_landed_chunk(
+        &self,
+        batch_id: &Uuid,
+        chunk_id: &Uuid,
+        tx_id: &str,
+    ) -> Result<bool, RepositoryError> {
+        self.inner
+            .settle_landed_chunk(batch_id, chunk_id, tx_id)
+            .await
+    }
+    async fn recover_uncommitted(&self) -> Result<RecoveryReport, RepositoryError> {
+        self.inner.recover_uncommitted().await
+    }
+    async fn recover_stale(
+        &self,
+        reserved_before_ms: u64,
+    ) -> Result<RecoveryReport, RepositoryError> {
+        self.inner.recover_stale(reserved_before_ms).await
+    }
+    async fn abort_batch(&self, batch_id: &Uuid, reason: &str) -> Result<(), RepositoryError> {
+        self.inner.abort_batch(batch_id, reason).await
+    }
+    async fn fetch_chunk_history(
+        &self,
+        session_id: &Uuid,
+        limit: usize,
+    ) -> Result<Vec<ChunkEvent>, RepositoryError> {
+        self.inner.fetch_chunk_history(session_id, limit).await
+    }
+    async fn reassign_pair(
+        &self,
+        session_id: &Uuid,
+        new_pair: &str,
+    ) -> Result<(), ReassignPairError> {
+        self.inner.reassign_pair(session_id, new_pair).await
+    }
+}
+
+/// DRR and reservation state of a session, as cached or stored.
+fn fairness_state(s: &Session) -> (i128, u64, u128, bool) {
+    (
+        s.state.deficit,
+        s.state.last_served_ms,
+        s.state.in_flight_bid,
+        s.state.has_pending_batch,
+    )
+}
+
+#[tokio::test]
+async fn dry_run_plans_without_reserving_or_writing_state() {
+    let pool = Arc::new(setup_db().await);
+    let repo = Arc::new(WriteCountingRepo {
+        inner: SqlxSessionRepository::new(pool.clone()),
+        reserves: AtomicUsize::new(0),
+        fairness_writes: AtomicUsize::new(0),
+    });
+    let store = Arc::new(SessionStore::new(repo.clone()));
+
+    let ids = [Uuid::new_v4(), Uuid::new_v4()];
+    for id in ids {
+        insert_active_session(&pool, id, 100_000, 100_000).await;
+    }
+    store.ensure_candidates(2, None).await.unwrap();
+    let before: Vec<_> = ids
+        .iter()
+        .map(|id| fairness_state(&store.get_cached(id).unwrap()))
+        .collect();
+
+    let counters = Counters::default();
+    let (preview_tx, mut preview_rx) = mpsc::channel::<PlannedBatch>(8);
+    let sched = Scheduler::new(store.clone(), 10, 1_000, 16, counters.clone())
+        .with_dry_run(Some(preview_tx));
+    let (tx, mut rx) = mpsc::channel(8);
+
+    for _ in 0..2 {
+        sched
+            .on_tick(PAIR, good_market(), tx.clone(), now_ms())
+            .await
+            .unwrap();
+    }
+
+    // Both ticks plan the same batch: no fairness state carried over.
+    let first = preview_rx.try_recv().expect("first plan");
+    let second = preview_rx.try_recv().expect("second plan");
+    assert_eq!(first.pair_id, PAIR);
+    let mut planned: Vec<Uuid> = first.allocations.iter().map(|a| a.session_id).collect();
+    planned.sort();
+    let mut expected = ids.to_vec();
+    expected.sort();
+    assert_eq!(planned, expected);
+    assert_eq!(
+        second.allocations.iter().map(|a| a.total_bid).sum::<u128>(),
+        first.allocations.iter().map(|a| a.total_bid).sum::<u128>()
+    );
+    assert_eq!(counters.sched_dry_run_plans.load(Ordering::Relaxed), 2);
+    assert_eq!(counters.sched_batches.load(Ordering::Relaxed), 0);
+
+    // Nothing reserved, enqueued or written.
+    assert!(rx.try_recv().is_err());
+    assert_eq!(repo.reserves.load(Ordering::SeqCst), 0);
+    assert_eq!(repo.fairness_writes.load(Ordering::SeqCst), 0);
+    assert_eq!(count_batches(&pool).await, 0);
+    for (id, before) in ids.iter().zip(&before) {
+        assert_eq!(&fairness_state(&store.get_cached(id).unwrap()), before);
+        let stored = repo.fetch_by_id(id).await.unwrap().unwrap();
+        assert_eq!(&fairness_state(&stored), before);
+    }
+}
+
+/// Executor whose swaps wait for a permit, standing in for a stalled chain.
+struct StalledExecutor(tokio::sync::Semaphore);
+
+#[async_trait::async_trait]
+impl SwapExecutor for StalledExecutor {
+    async fn execute_swap(&self, call: SwapCall) -> Result<SwapReceipt, SwapError> {
+        self.0.acquire().await.unwrap().forget();
+        Ok(SwapReceipt {
+            tx_id: format!("tx-{}", call.chunk_id),
+            idempotency_key: Some(call.idempotency_key),
+        })
+    }
+}
+
+/// Polls the router until `pair`'s worker queue holds exactly `depth` batches.
+async fn wait_for_queue_depth<E: SwapExecutor + ?Sized>(
+    router: &PairExecutorRouter<E>,
+    depth: usize,
+) {
+    tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        while router.queue_depth(PAIR).await != Some(depth) {
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("queue depth never reached {depth}"));
+}
+
+#[tokio::test]
+async fn saturated_executor_stops_reservations() {
+    let (pool, _repo, store, _) = setup_scheduler().await;
+
+    let market_view = MarketViewStore::new();
+    market_view.set(PAIR, good_market()).await;
+    let exec = Arc::new(StalledExecutor(tokio::sync::Semaphore::new(0)));
+    let router = Arc::new(PairExecutorRouter::new(
+        store.clone(),
+        market_view,
+        exec.clone(),
+        WorkerConfig::default(),
+        8,
+    ));
+    let (tx, rx) = mpsc::channel(8);
+    tokio::spawn(router.clone().run(rx));
+
+    // One session per batch, so every tick can reserve a new batch.
+    let counters = Counters::default();
+    let sched = Scheduler::new(store.clone(), 10, 1_000, 1, counters.clone())
+        .with_backpressure(router.clone(), 1);
+
+    for _ in 0..4 {
+        insert_active_session(&pool, Uuid::new_v4(), 100_000, 100_000).await;
+    }
+    store.ensure_candidates(4, None).await.unwrap();
+
+    // The worker takes the first batch and stalls inside the executor;
+    // the second one waits in its queue.
+    sched
+        .on_tick(PAIR, good_market(), tx.clone(), now_ms())
+        .await
+        .unwrap();
+    wait_for_queue_depth(&router, 0).await;
+    sched
+        .on_tick(PAIR, good_market(), tx.clone(), now_ms())
+        .await
+        .unwrap();
+    wait_for_queue_depth(&router, 1).await;
+    assert_eq!(count_batches(&pool).await, 2);
+
+    // Saturated: eligible sessions remain, but nothing more is reserved.
+    for _ in 0..3 {
+        sched
+            .on_tick(PAIR, good_market(), tx.clone(), now_ms())
+            .await
+            .unwrap();
+    }
+    assert_eq!(count_batches(&pool).await, 2);
+    assert_eq!(counters.sched_backpressure.load(Ordering::Relaxed), 3);
+
+    // The executor catches up and the worker drains its queue.
+    exec.0.add_permits(64);
+    wait_for_queue_depth(&router, 0).await;
+    sched
+        .on_tick(PAIR, good_market(), tx, now_ms())
+        .await
+        .unwrap();
+    assert_eq!(count_batches(&pool).await, 3);
+}
+
+#[tokio::test]
+async fn run_stops_on_cancellation_after_enqueueing_every_reservation() {
+    let (pool, _repo, store, sched) = setup_scheduler().await;
+
+    for _ in 0..3 {
+        insert_active_session(&pool, Uuid::new_v4(), 100_000, 100_000).await;
+    }
+    store.ensure_candidates(3, None).await.unwrap();
+
+    let market_view = MarketViewStore::new();
+    market_view.set(PAIR, good_market()).await;
+
+    let (tx, mut rx) = mpsc::channel(64);
+    let shutdown = CancellationToken::new();
+    let task = tokio::spawn(sched.run(
+        PAIR.to_string(),
+        market_view,
+        tx,
+        std::time::Duration::from_millis(5),
+        shutdown.clone(),
+    ));
+
+    // Wait for at least one reservation, then stop.
+    let first = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv())
+        .await
+        .unwrap();
+    assert!(matches!(first, Some(ExecutionEvent::Reserved(_))));
+
+    shutdown.cancel();
+    tokio::time::timeout(std::time::Duration::from_secs(5), task)
+        .await
+        .expect("scheduler did not stop")
+        .unwrap();
+
+    // The sender was dropped on exit; every reserved batch was enqueued.
+    let mut enqueued = 1;
+    while let Some(ev) = rx.recv().await {
+        assert!(matches!(ev, ExecutionEvent::Reserved(_)));
+        enqueued += 1;
+    }
+    assert_eq!(count_batches(&pool).await, enqueued);
+}
+
+/// Runs `ticks` scheduling rounds where the tick budget fits exactly one user,
+/// committing each batch, and returns the session served on each tick.
+async fn first_served_per_tick(rotate: bool, ticks: u64) -> (Vec<Uuid>, Vec<Uuid>) {
+    let (pool, repo, store, sched) = setup_scheduler().await;
+    let sched = sched.with_plan_rotation(rotate);
+
+    // 4 users; 1_000 RR attempts per tick is a multiple of 4, so the intent
+    // order coming out of pick_intents is identical on every tick.
+    let mut ids = Vec::new();
+    for _ in 0..4 {
+        let id = Uuid::new_v4();
+        insert_active_session(&pool, id, 100_000, 100_000).await;
+        ids.push(id);
+    }
+    store.ensure_candidates(4, None).await.unwrap();
+
+    // depth 400_000 * 0.25 utilization = 100_000 budget = one user's chunk.
+    let mut market = good_market();
+    market.max_depth = 400_000;
+
+    let (tx, mut rx) = mpsc::channel(8);
+    let mut served = Vec::new();
+
+    for i in 0..ticks {
+        sched
+            .on_tick(PAIR, market.clone(), tx.clone(), now_ms() + i)
+            .await
+            .unwrap();
+
+        let Ok(ExecutionEvent::Reserved(batch)) = rx.try_recv() else {
+            panic!("batch each tick");
+        };
+        assert_eq!(batch.users.len(), 1);
+        served.push(batch.users[0].session_id);
+
+        commit_all_success(repo.as_ref(), &batch).await;
+    }
+
+    (served, ids)
+}
+
+#[tokio::test]
+async fn plan_rotation_cycles_first_fit_across_users() {
+    let (served, ids) = first_served_per_tick(true, 8).await;
+
+    // Every user gets the first-fit slot, in a stable cycle.
+    for id in &ids {
+        assert_eq!(served.iter().filter(|s| *s == id).count(), 2);
+    }
+    assert_eq!(served[..4], served[4..]);
+}
+
+#[tokio::test]
+async fn without_plan_rotation_first_fit_sticks() {
+    let (served, _) = first_served_per_tick(false, 8).await;
+
+    assert!(served.iter().all(|s| *s == served[0]));
+}
@@ -0,0 +1,42 @@
+-- Base schema: sessions, reserved batches and their per-chunk items.
+-- IF NOT EXISTS lets databases created before migrations were tracked adopt
+-- this version without changes.
+CREATE TABLE IF NOT EXISTS sessions (
+  session_id TEXT PRIMARY KEY,
+  pair_id TEXT NOT NULL,
+  active BOOLEAN NOT NULL,
+  max_spread_bps DOUBLE PRECISION NOT NULL,
+  max_trend_drop_bps DOUBLE PRECISION NOT NULL,
+  max_slippage_bps DOUBLE PRECISION NOT NULL,
+  preferred_chunk_bid BIGINT NOT NULL,
+  max_bid_per_tick BIGINT NOT NULL,
+  remaining_bid BIGINT NOT NULL,
+  remaining_chunks BIGINT NOT NULL,
+  in_flight_bid BIGINT NOT NULL DEFAULT 0,
+  in_flight_chunks BIGINT NOT NULL DEFAULT 0,
+  cooldown_until_ms BIGINT NOT NULL DEFAULT 0,
+  quantum BIGINT NOT NULL,
+  deficit BIGINT NOT NULL DEFAULT 0,
+  last_served_ms BIGINT NOT NULL DEFAULT 0,
+  has_pending_batch BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE TABLE IF NOT EXISTS batches (
+  batch_id TEXT PRIMARY KEY,
+  pair_id TEXT NOT NULL,
+  created_ms BIGINT NOT NULL,
+  status TEXT NOT NULL,
+  reason TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS batch_items (
+  chunk_id TEXT PRIMARY KEY,
+  batch_id TEXT NOT NULL,
+  session_id TEXT NOT NULL,
+  bid BIGINT NOT NULL,
+  status TEXT NOT NULL,
+  tx_id TEXT NOT NULL,
+  error TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS batch_items_batch_id ON batch_items (batch_id);
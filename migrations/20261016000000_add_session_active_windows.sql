@@ -0,0 +1,3 @@
+-- Per-session UTC execution windows, stored as JSON: [[start_ms_of_day, end_ms_of_day], ...].
+-- Empty array means the session is always active.
+ALTER TABLE sessions ADD COLUMN active_windows TEXT NOT NULL DEFAULT '[]';
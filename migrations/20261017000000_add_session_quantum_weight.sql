@@ -0,0 +1,3 @@
+-- DRR tier multiplier: credit added per scheduling pass is quantum * quantum_weight.
+-- 1 = baseline tier.
+ALTER TABLE sessions ADD COLUMN quantum_weight BIGINT NOT NULL DEFAULT 1;
@@ -0,0 +1,3 @@
+-- Minimum spacing (ms) between two scheduled batches of the same session,
+-- independent of DRR credit. 0 = no spacing.
+ALTER TABLE sessions ADD COLUMN twap_interval_ms BIGINT NOT NULL DEFAULT 0;
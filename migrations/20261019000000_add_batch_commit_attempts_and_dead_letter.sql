@@ -0,0 +1,14 @@
+-- Failed commit_batch calls per batch. Chunks have already executed when a
+-- commit fails, so the worker retries before giving up.
+ALTER TABLE batches ADD COLUMN commit_attempts BIGINT NOT NULL DEFAULT 0;
+
+-- Batches whose commit never succeeded, kept for manual reconciliation.
+-- results_json holds the executor's UserResults verbatim.
+CREATE TABLE IF NOT EXISTS dead_letter (
+  batch_id TEXT PRIMARY KEY,
+  pair_id TEXT NOT NULL,
+  dead_lettered_ms BIGINT NOT NULL,
+  commit_attempts BIGINT NOT NULL,
+  error TEXT NOT NULL,
+  results_json TEXT NOT NULL
+);
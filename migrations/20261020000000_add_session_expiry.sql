@@ -0,0 +1,3 @@
+-- Absolute expiry (ms since epoch) after which a session is deactivated by
+-- the expiry sweeper. 0 = never expires.
+ALTER TABLE sessions ADD COLUMN expires_at_ms BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,13 @@
+-- Append-only outcome trail: one row per settled chunk, written in the same
+-- transaction that settles it, so it never disagrees with batch_items.
+CREATE TABLE IF NOT EXISTS chunk_events (
+  chunk_id TEXT PRIMARY KEY,
+  batch_id TEXT NOT NULL,
+  session_id TEXT NOT NULL,
+  outcome TEXT NOT NULL,
+  reason TEXT NOT NULL,
+  tx_id TEXT NOT NULL,
+  ts_ms BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS chunk_events_session_ts ON chunk_events (session_id, ts_ms);
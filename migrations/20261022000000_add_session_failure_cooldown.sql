@@ -0,0 +1,3 @@
+-- Cooldown (ms) applied to a session after a failed chunk. NULL = use the
+-- executor's default_failure_cooldown_ms.
+ALTER TABLE sessions ADD COLUMN failure_cooldown_ms BIGINT;